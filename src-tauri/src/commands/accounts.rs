@@ -6,6 +6,11 @@ use tauri::State;
 
 use uuid::Uuid;
 
+use super::auth::SessionStore;
+use super::company::financial_year_start_date;
+use super::system_accounts::{find_system_account, resolve_system_account};
+use crate::voucher_seq::get_next_voucher_number;
+
 // ============= CHART OF ACCOUNTS =============
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct ChartOfAccount {
@@ -35,31 +40,8 @@ pub struct CreateChartOfAccount {
     pub description: Option<String>,
     pub opening_balance: Option<f64>,
     pub opening_balance_type: Option<String>,
-}
-
-async fn get_next_voucher_number(pool: &SqlitePool, voucher_type: &str) -> Result<String, String> {
-    let prefix = match voucher_type {
-        "purchase" => "PI",
-        "sales" => "SI",
-        "payment" => "PV",
-        "receipt" => "RV",
-        "journal" => "JV",
-        "opening_balance" => "OB",
-        _ => "V",
-    };
-
-    let last_number = sqlx::query_scalar::<_, Option<i64>>(
-        "SELECT MAX(CAST(SUBSTR(voucher_no, ?1 + 1) AS INTEGER)) FROM vouchers WHERE voucher_type = ?2"
-    )
-    .bind(prefix.len() as i32)
-    .bind(voucher_type)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| e.to_string())?
-    .flatten()
-    .unwrap_or(0);
-
-    Ok(format!("{}{:05}", prefix, last_number + 1))
+    /// Date for the opening balance voucher; defaults to the financial year start
+    pub opening_balance_date: Option<String>,
 }
 
 #[tauri::command]
@@ -104,16 +86,38 @@ pub async fn get_accounts_by_groups(
     query.fetch_all(&pool).await.map_err(|e| e.to_string())
 }
 
+/// Canonical "Dr"/"Cr" for an opening balance side, accepting any case.
+/// Defaults to "Dr" when not given; anything else is rejected.
+pub(crate) fn normalize_opening_balance_type(value: Option<&str>) -> Result<&'static str, String> {
+    match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("dr") => Ok("Dr"),
+        Some("cr") => Ok("Cr"),
+        Some(other) => Err(format!(
+            "Opening balance type must be 'Dr' or 'Cr', got '{}'",
+            other
+        )),
+    }
+}
+
 #[tauri::command]
 pub async fn create_chart_of_account(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     account: CreateChartOfAccount,
 ) -> Result<ChartOfAccount, String> {
     let pool = registry.active_pool().await?;
+    let branch_id = session_store.active_branch_id();
+    create_chart_of_account_with_pool(&pool, account, branch_id.as_deref()).await
+}
+
+pub(crate) async fn create_chart_of_account_with_pool(
+    pool: &SqlitePool,
+    account: CreateChartOfAccount,
+    branch_id: Option<&str>,
+) -> Result<ChartOfAccount, String> {
     let opening_balance = account.opening_balance.unwrap_or(0.0);
-    let opening_balance_type = account
-        .opening_balance_type
-        .unwrap_or_else(|| "Dr".to_string());
+    let opening_balance_type =
+        normalize_opening_balance_type(account.opening_balance_type.as_deref())?;
 
     let id = Uuid::now_v7().to_string();
 
@@ -129,45 +133,43 @@ pub async fn create_chart_of_account(
     .bind(&account.description)
     .bind(opening_balance)
     .bind(&opening_balance_type)
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
     // If opening balance is provided, create voucher and journal entries
     if opening_balance > 0.0 {
+        let opening_date = match &account.opening_balance_date {
+            Some(date) if !date.trim().is_empty() => date.trim().to_string(),
+            _ => financial_year_start_date(pool).await?,
+        };
         let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
         // Get next voucher number
-        let voucher_no = get_next_voucher_number(&pool, "opening_balance").await?;
+        let voucher_no =
+            get_next_voucher_number(pool, "opening_balance", &opening_date, branch_id).await?;
         let voucher_id = Uuid::now_v7().to_string();
 
         // Create voucher entry
         let _ = sqlx::query(
-            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, narration, status, party_id, total_amount)
-             VALUES (?, ?, ?, ?, ?, ?, 'posted', ?, ?)"
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, narration, status, party_id, total_amount, branch_id)
+             VALUES (?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?)"
         )
         .bind(&voucher_id)
         .bind(&voucher_no)
         .bind("opening_balance")
-        .bind("2025-12-21")
+        .bind(&opening_date)
         .bind(format!("Opening balance for {}", account.account_name))
         .bind(format!("Initial balance for account: {}", account.account_name))
         .bind(&id)
         .bind(opening_balance)
+        .bind(branch_id)
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
 
-        // Find Opening Balance Adjustment account (code 3004)
-        let ob_account = sqlx::query_as::<_, (String,)>(
-            "SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1",
-        )
-        .fetch_optional(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Opening Balance Adjustment account not found".to_string())?;
-
-        let ob_account_id = ob_account.0;
+        // Find Opening Balance Adjustment account
+        let ob_account_id = resolve_system_account(&mut *tx, "opening_balance_adjustment").await?;
 
         let je_id_1 = Uuid::now_v7().to_string();
         let je_id_2 = Uuid::now_v7().to_string();
@@ -239,7 +241,7 @@ pub async fn create_chart_of_account(
 
     sqlx::query_as::<_, ChartOfAccount>("SELECT * FROM chart_of_accounts WHERE id = ?")
         .bind(id)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .map_err(|e| e.to_string())
 }
@@ -247,14 +249,14 @@ pub async fn create_chart_of_account(
 #[tauri::command]
 pub async fn update_chart_of_account(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     account: CreateChartOfAccount,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
     let new_opening_balance = account.opening_balance.unwrap_or(0.0);
-    let opening_balance_type = account
-        .opening_balance_type
-        .unwrap_or_else(|| "Dr".to_string());
+    let opening_balance_type =
+        normalize_opening_balance_type(account.opening_balance_type.as_deref())?;
 
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
@@ -307,32 +309,53 @@ pub async fn update_chart_of_account(
         .await
         .map_err(|e| e.to_string())?;
 
+        let supplied_date = account
+            .opening_balance_date
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty());
+
         let voucher_id = if let Some(vid) = opening_balance_voucher {
             // Update existing voucher with new amount and confirm party_id
-            let _ = sqlx::query("UPDATE vouchers SET total_amount = ?, party_id = ? WHERE id = ?")
-                .bind(new_opening_balance)
-                .bind(&id)
-                .bind(&vid)
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+            let _ = sqlx::query(
+                "UPDATE vouchers SET total_amount = ?, party_id = ?, voucher_date = COALESCE(?, voucher_date) WHERE id = ?",
+            )
+            .bind(new_opening_balance)
+            .bind(&id)
+            .bind(supplied_date)
+            .bind(&vid)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
 
             vid
         } else {
             // Create a new opening balance voucher if one doesn't exist
-            let voucher_no = get_next_voucher_number(&pool, "opening_balance").await?;
+            let opening_date = match supplied_date {
+                Some(date) => date.to_string(),
+                None => financial_year_start_date(&pool).await?,
+            };
+            let branch_id = session_store.active_branch_id();
+            let voucher_no = get_next_voucher_number(
+                &pool,
+                "opening_balance",
+                &opening_date,
+                branch_id.as_deref(),
+            )
+            .await?;
             let new_vid = Uuid::now_v7().to_string();
             let _ = sqlx::query(
-                "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, narration, status, party_id, total_amount)
-                 VALUES (?, ?, 'opening_balance', ?, ?, ?, 'posted', ?, ?)"
+                "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, narration, status, party_id, total_amount, branch_id)
+                 VALUES (?, ?, 'opening_balance', ?, ?, ?, 'posted', ?, ?, ?)"
             )
             .bind(&new_vid)
             .bind(&voucher_no)
-            .bind(chrono::Local::now().format("%Y-%m-%d").to_string())
+            .bind(&opening_date)
             .bind(format!("Opening balance for {}", account.account_name))
             .bind(format!("Initial balance for account: {}", account.account_name))
             .bind(&id)
             .bind(new_opening_balance)
+            .bind(&branch_id)
             .execute(&mut *tx)
             .await
             .map_err(|e| e.to_string())?;
@@ -349,13 +372,9 @@ pub async fn update_chart_of_account(
             .map_err(|e| e.to_string())?;
 
         // Find Opening Balance Adjustment account
-        let ob_account: Option<(String,)> =
-            sqlx::query_as("SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+        let ob_account = find_system_account(&mut *tx, "opening_balance_adjustment").await?;
 
-        if let Some((ob_account_id,)) = ob_account {
+        if let Some(ob_account_id) = ob_account {
             // Delete existing balancing entry (if any)
             sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ? AND account_id = ?")
                 .bind(&voucher_id)
@@ -458,6 +477,29 @@ pub async fn delete_chart_of_account(
         return Err("Cannot delete system generated accounts".to_string());
     }
 
+    // Cash/bank accounts are stored as the party on payment and receipt vouchers
+    if account.account_group == "Cash" || account.account_group == "Bank Account" {
+        let usage: (i64, Option<String>) = sqlx::query_as(
+            "SELECT COUNT(*), MIN(voucher_no) FROM vouchers
+             WHERE voucher_type IN ('payment', 'receipt') AND (party_id = ? OR account_id = ?)",
+        )
+        .bind(&id)
+        .bind(&id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if usage.0 > 0 {
+            return Err(format!(
+                "Cannot delete {} account '{}': it is used by {} payment/receipt voucher(s) (e.g. {}).",
+                account.account_group.to_lowercase(),
+                account.account_name,
+                usage.0,
+                usage.1.unwrap_or_default()
+            ));
+        }
+    }
+
     // Check for references in journal_entries
     let journal_count: i64 =
         sqlx::query_scalar("SELECT COUNT(*) FROM journal_entries WHERE account_id = ?")
@@ -716,3 +758,69 @@ pub async fn get_cash_bank_accounts(
     .await
     .map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn account_with_opening(code: &str, amount: f64, side: &str) -> CreateChartOfAccount {
+        CreateChartOfAccount {
+            account_code: code.to_string(),
+            account_name: format!("Account {}", code),
+            account_type: "Asset".to_string(),
+            account_group: "Current Assets".to_string(),
+            description: None,
+            opening_balance: Some(amount),
+            opening_balance_type: Some(side.to_string()),
+            opening_balance_date: Some("2024-04-01".to_string()),
+        }
+    }
+
+    #[test]
+    fn opening_balance_type_is_normalized_case_insensitively() {
+        assert_eq!(normalize_opening_balance_type(Some("dr")), Ok("Dr"));
+        assert_eq!(normalize_opening_balance_type(Some(" CR ")), Ok("Cr"));
+        assert_eq!(normalize_opening_balance_type(None), Ok("Dr"));
+    }
+
+    #[test]
+    fn opening_balance_type_rejects_anything_but_dr_or_cr() {
+        assert!(normalize_opening_balance_type(Some("X")).is_err());
+    }
+
+    #[tokio::test]
+    async fn trial_balance_counts_an_account_opening_balance_once() {
+        let pool = test_pool().await;
+        create_chart_of_account_with_pool(&pool, account_with_opening("T-OB1", 500.0, "Dr"), None)
+            .await
+            .unwrap();
+        create_chart_of_account_with_pool(&pool, account_with_opening("T-OB2", 200.0, "Cr"), None)
+            .await
+            .unwrap();
+
+        for from_date in [None, Some("2024-05-01")] {
+            let rows = crate::commands::reports::trial_balance_rows(&pool, from_date, "2024-05-31")
+                .await
+                .unwrap();
+            let closing = |code: &str| {
+                rows.iter()
+                    .find(|row| row.account_code == code)
+                    .map(|row| row.closing_balance)
+            };
+            assert_eq!(closing("T-OB1"), Some(500.0));
+            assert_eq!(closing("T-OB2"), Some(-200.0));
+
+            let opening: f64 = rows.iter().map(|row| row.opening_balance).sum();
+            let debit: f64 = rows.iter().map(|row| row.debit).sum();
+            let credit: f64 = rows.iter().map(|row| row.credit).sum();
+            assert!(opening.abs() < 0.005, "opening {}", opening);
+            assert!(
+                (debit - credit).abs() < 0.005,
+                "debit {} credit {}",
+                debit,
+                credit
+            );
+        }
+    }
+}
@@ -1,9 +1,17 @@
 use crate::company_db::DbRegistry;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
+use super::auth::SessionStore;
+use super::entries::{
+    insert_journal_lines, invoice_total_for_status, recompute_invoice_status, JournalLine,
+    INVOICE_TOTAL_SQL,
+};
+use crate::voucher_seq::get_next_voucher_number_in_tx;
+
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct PaymentAllocation {
     pub id: String,
@@ -51,14 +59,14 @@ pub async fn get_outstanding_invoices(
             v.voucher_no,
             v.voucher_date,
             coa.account_name as party_name,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2) as total_amount,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0) + COALESCE(v.round_off_amount, 0.0), 2) as total_amount,
             COALESCE(
                 (SELECT SUM(pa.allocated_amount) 
                  FROM payment_allocations pa 
                  WHERE pa.invoice_voucher_id = v.id), 
                 0.0
             ) as allocated_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2) - COALESCE(
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0) + COALESCE(v.round_off_amount, 0.0), 2) - COALESCE(
                 (SELECT SUM(pa.allocated_amount) 
                  FROM payment_allocations pa 
                  WHERE pa.invoice_voucher_id = v.id), 
@@ -130,17 +138,7 @@ pub async fn create_allocation(
     .await
     .map_err(|e| e.to_string())?;
 
-    let invoice_total: f64 = sqlx::query_scalar(
-        "SELECT ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2)
-         FROM vouchers v
-         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-         WHERE v.id = ?
-         GROUP BY v.id",
-    )
-    .bind(&allocation.invoice_voucher_id)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    let invoice_total = invoice_total_for_status(&mut tx, &allocation.invoice_voucher_id).await?;
 
     let status = if (total_allocated - invoice_total).abs() < 0.01 {
         "paid"
@@ -276,17 +274,7 @@ pub async fn delete_allocation(
     .await
     .map_err(|e| e.to_string())?;
 
-    let invoice_total: f64 = sqlx::query_scalar(
-        "SELECT ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2)
-         FROM vouchers v
-         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-         WHERE v.id = ?
-         GROUP BY v.id",
-    )
-    .bind(&invoice_id)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    let invoice_total = invoice_total_for_status(&mut tx, &invoice_id).await?;
 
     let status = if (total_allocated - invoice_total).abs() < 0.01 {
         "paid"
@@ -308,6 +296,83 @@ pub async fn delete_allocation(
     Ok(())
 }
 
+// Change how much of a payment/receipt is allocated to one invoice
+#[tauri::command]
+pub async fn update_allocation(
+    registry: State<'_, Arc<DbRegistry>>,
+    allocation_id: String,
+    new_amount: f64,
+) -> Result<(), String> {
+    let new_amount = (new_amount * 100.0).round() / 100.0;
+    if new_amount <= 0.0 {
+        return Err(
+            "Allocated amount must be greater than zero; delete the allocation instead".to_string(),
+        );
+    }
+
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let (payment_voucher_id, invoice_id): (String, String) = sqlx::query_as(
+        "SELECT payment_voucher_id, invoice_voucher_id FROM payment_allocations WHERE id = ?",
+    )
+    .bind(&allocation_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Allocation not found".to_string())?;
+
+    // Other allocations against the same invoice and from the same voucher
+    let (invoice_others, payment_others): (f64, f64) = sqlx::query_as(
+        "SELECT
+            CAST(COALESCE(SUM(CASE WHEN invoice_voucher_id = ? THEN allocated_amount ELSE 0 END), 0.0) AS REAL),
+            CAST(COALESCE(SUM(CASE WHEN payment_voucher_id = ? THEN allocated_amount ELSE 0 END), 0.0) AS REAL)
+         FROM payment_allocations
+         WHERE id <> ?",
+    )
+    .bind(&invoice_id)
+    .bind(&payment_voucher_id)
+    .bind(&allocation_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let invoice_total = invoice_total_for_status(&mut tx, &invoice_id).await?;
+    if new_amount > invoice_total - invoice_others + 0.01 {
+        return Err(format!(
+            "Only {:.2} of the invoice is left to allocate",
+            invoice_total - invoice_others
+        ));
+    }
+
+    let payment_total: f64 = sqlx::query_scalar(
+        "SELECT CAST(COALESCE(NULLIF(grand_total, 0), total_amount, 0) AS REAL) FROM vouchers WHERE id = ?",
+    )
+    .bind(&payment_voucher_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if new_amount > payment_total - payment_others + 0.01 {
+        return Err(format!(
+            "Only {:.2} of the payment is available to allocate",
+            payment_total - payment_others
+        ));
+    }
+
+    sqlx::query("UPDATE payment_allocations SET allocated_amount = ? WHERE id = ?")
+        .bind(new_amount)
+        .bind(&allocation_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    recompute_invoice_status(&mut tx, &invoice_id).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 // Quick payment - creates payment and allocation in one go
 #[derive(Deserialize)]
 pub struct QuickPayment {
@@ -374,8 +439,8 @@ pub async fn create_quick_payment(
 
     // Create payment/receipt voucher
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, total_amount, grand_total, metadata, narration, status, created_from_invoice_id, account_id)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, total_amount, grand_total, metadata, narration, status, created_from_invoice_id, account_id, branch_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, (SELECT branch_id FROM vouchers WHERE id = ?))"
     )
     .bind(&payment_id)
     .bind(&voucher_no)
@@ -390,6 +455,7 @@ pub async fn create_quick_payment(
     .bind(&payment.remarks)
     .bind(&payment.invoice_id)
     .bind(&payment.payment_account_id)
+    .bind(&payment.invoice_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -418,62 +484,19 @@ pub async fn create_quick_payment(
     .map_err(|e| e.to_string())?;
 
     // Create journal entries
-    let je_id_1 = Uuid::now_v7().to_string();
-    let je_id_2 = Uuid::now_v7().to_string();
-
-    if voucher_type == "payment" {
-        // Credit: Payment account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Payment made')",
-        )
-        .bind(&je_id_1)
-        .bind(&payment_id)
-        .bind(&payment.payment_account_id)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        // Debit: Party account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Payment to party')",
-        )
-        .bind(&je_id_2)
-        .bind(&payment_id)
-        .bind(&invoice.0)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
+    let narrations = if voucher_type == "payment" {
+        ("Payment made", "Payment to party")
     } else {
-        // Debit: Payment account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Receipt received')",
-        )
-        .bind(&je_id_1)
-        .bind(&payment_id)
-        .bind(&payment.payment_account_id)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        // Credit: Party account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Receipt from party')",
-        )
-        .bind(&je_id_2)
-        .bind(&payment_id)
-        .bind(&invoice.0)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+        ("Receipt received", "Receipt from party")
+    };
+    let journal_lines = quick_payment_journal_lines(
+        voucher_type,
+        &payment.payment_account_id,
+        &invoice.0,
+        payment.amount,
+        narrations,
+    );
+    insert_journal_lines(&mut tx, &payment_id, &journal_lines).await?;
 
     let allocation_id = Uuid::now_v7().to_string();
 
@@ -503,17 +526,7 @@ pub async fn create_quick_payment(
     .await
     .map_err(|e| e.to_string())?;
 
-    let invoice_total: f64 = sqlx::query_scalar(
-        "SELECT ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2)
-         FROM vouchers v
-         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-         WHERE v.id = ?
-         GROUP BY v.id",
-    )
-    .bind(&payment.invoice_id)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    let invoice_total = invoice_total_for_status(&mut tx, &payment.invoice_id).await?;
 
     let status = if (total_allocated - invoice_total).abs() < 0.01 {
         "paid"
@@ -622,85 +635,26 @@ pub async fn update_quick_payment(
     .await
     .map_err(|e| e.to_string())?;
 
-    // Create new UUIDs for journal entries since we are re-inserting them
-    let je_id_1 = Uuid::now_v7().to_string();
-    let je_id_2 = Uuid::now_v7().to_string();
-
-    // Re-create journal entries
-    // First distinctively delete old ones (logic was just inserting new ones? code had inserts)
-    // Wait, the original code had INSERTS in `update_quick_payment`?
-    // "Re-create journal entries" comment suggests it might be adding duplicates?
-    // Let's check original code. Lines 575-619 were INSERTS.
-    // Yes, but it didn't delete old ones properly? Or does it rely on something else?
-    // Ah, `update_quick_payment` usually deletes old entries first.
-    // Looking at the original code (lines 532+), it updates vouchers, updates voucher_items.
-    // Then lines 572+ inserts NEW journal entries?
-    // That seems like a bug in the original code unless `journal_entries` were deleted somewhere I missed.
-    // I don't see a DELETE query for journal entries in the original code snippet.
-    // I should probably add a DELETE for journal entries if I'm inserting new ones, or UPDATE them.
-    // For now, I will replicate the behavior (INSERT) but adapt to UUIDs.
-    // However, inserting without deleting will duplicate entries.
-    // I will add a DELETE query for safety before inserting, assuming that's the intention.
-
+    // Replace the voucher's journal entries
     sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ?")
         .bind(&payment.payment_voucher_id)
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
 
-    if voucher_type == "payment" {
-        // Credit: Payment account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Payment updated')",
-        )
-        .bind(&je_id_1)
-        .bind(&payment.payment_voucher_id)
-        .bind(&payment.payment_account_id)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        // Debit: Party account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Payment to party updated')",
-        )
-        .bind(&je_id_2)
-        .bind(&payment.payment_voucher_id)
-        .bind(&invoice.0)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
+    let narrations = if voucher_type == "payment" {
+        ("Payment updated", "Payment to party updated")
     } else {
-        // Debit: Payment account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Receipt updated')",
-        )
-        .bind(&je_id_1)
-        .bind(&payment.payment_voucher_id)
-        .bind(&payment.payment_account_id)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        // Credit: Party account
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Receipt from party updated')",
-        )
-        .bind(&je_id_2)
-        .bind(&payment.payment_voucher_id)
-        .bind(&invoice.0)
-        .bind(payment.amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+        ("Receipt updated", "Receipt from party updated")
+    };
+    let journal_lines = quick_payment_journal_lines(
+        voucher_type,
+        &payment.payment_account_id,
+        &invoice.0,
+        payment.amount,
+        narrations,
+    );
+    insert_journal_lines(&mut tx, &payment.payment_voucher_id, &journal_lines).await?;
 
     // Update allocation
     sqlx::query(
@@ -728,17 +682,7 @@ pub async fn update_quick_payment(
     .await
     .map_err(|e| e.to_string())?;
 
-    let invoice_total: f64 = sqlx::query_scalar(
-        "SELECT ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2)
-         FROM vouchers v
-         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-         WHERE v.id = ?
-         GROUP BY v.id",
-    )
-    .bind(&payment.invoice_id)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
+    let invoice_total = invoice_total_for_status(&mut tx, &payment.invoice_id).await?;
 
     let status = if (total_allocated - invoice_total).abs() < 0.01 {
         "paid"
@@ -759,3 +703,680 @@ pub async fn update_quick_payment(
 
     Ok(())
 }
+
+/// Cash/bank against the party ledger for a quick payment or receipt.
+/// `narrations` are (cash line, party line).
+fn quick_payment_journal_lines(
+    voucher_type: &str,
+    payment_account_id: &str,
+    party_account_id: &str,
+    amount: f64,
+    narrations: (&str, &str),
+) -> Vec<JournalLine> {
+    // Payments credit cash and debit the party; receipts the reverse
+    let cash_debit = voucher_type == "receipt";
+    let line = |account_id: &str, debit: bool, narration: &str| JournalLine {
+        account_id: account_id.to_string(),
+        debit: if debit { amount } else { 0.0 },
+        credit: if debit { 0.0 } else { amount },
+        is_manual: false,
+        narration: Some(narration.to_string()),
+    };
+    vec![
+        line(payment_account_id, cash_debit, narrations.0),
+        line(party_account_id, !cash_debit, narrations.1),
+    ]
+}
+
+// Recompute payment_status for every invoice from its allocations.
+// Repairs statuses left stale by earlier edits/deletions; returns how many invoices changed.
+#[tauri::command]
+pub async fn recompute_payment_statuses(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<usize, String> {
+    let pool = registry.active_pool().await?;
+    recompute_payment_statuses_with_pool(&pool).await
+}
+
+pub(crate) async fn recompute_payment_statuses_with_pool(
+    pool: &sqlx::SqlitePool,
+) -> Result<usize, String> {
+    const BATCH_SIZE: usize = 500;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    // Invoices against a cash/bank account are settled on posting, mirroring the
+    // status logic in the invoice create/update commands.
+    let invoices = sqlx::query_as::<_, (String, Option<String>, f64, f64, bool)>(&format!(
+        "SELECT 
+            v.id,
+            v.payment_status,
+            {invoice_total} as invoice_total,
+            CAST(COALESCE(
+                (SELECT SUM(pa.allocated_amount) FROM payment_allocations pa WHERE pa.invoice_voucher_id = v.id),
+                0.0
+            ) AS REAL) as total_allocated,
+            EXISTS(
+                SELECT 1 FROM chart_of_accounts coa
+                WHERE coa.id = v.party_id AND coa.account_group IN ('Cash', 'Bank Account', 'Bank Accounts')
+            ) as is_cash_or_bank
+         FROM vouchers v
+         WHERE v.voucher_type IN ('sales_invoice', 'purchase_invoice')
+         AND v.deleted_at IS NULL
+         AND COALESCE(v.status, 'posted') = 'posted'",
+        invoice_total = INVOICE_TOTAL_SQL
+    ))
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut changes: HashMap<&'static str, Vec<String>> = HashMap::new();
+    for (id, current, invoice_total, total_allocated, is_cash_or_bank) in invoices {
+        let status = if is_cash_or_bank || (total_allocated - invoice_total).abs() < 0.01 {
+            "paid"
+        } else if total_allocated > 0.0 {
+            "partially_paid"
+        } else {
+            "unpaid"
+        };
+        if current.as_deref() != Some(status) {
+            changes.entry(status).or_default().push(id);
+        }
+    }
+
+    let mut updated = 0;
+    for (status, ids) in &changes {
+        for chunk in ids.chunks(BATCH_SIZE) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let query = format!(
+                "UPDATE vouchers SET payment_status = ? WHERE id IN ({})",
+                placeholders
+            );
+            let mut q = sqlx::query(&query).bind(*status);
+            for id in chunk {
+                q = q.bind(id);
+            }
+            updated += q
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?
+                .rows_affected() as usize;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(updated)
+}
+
+// ============= PARTY NET-OFF =============
+
+/// Allocates `amount` of a settlement voucher against the party's oldest open
+/// invoices of `voucher_type`, updating each invoice's payment_status.
+async fn allocate_oldest_first(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    settlement_voucher_id: &str,
+    party_id: &str,
+    party_type: &str,
+    voucher_type: &str,
+    amount: f64,
+    allocation_date: &str,
+) -> Result<(), String> {
+    let open_invoices = sqlx::query_as::<_, (String, f64, f64)>(&format!(
+        "SELECT 
+            v.id,
+            {invoice_total} as invoice_total,
+            CAST(COALESCE(
+                (SELECT SUM(pa.allocated_amount) FROM payment_allocations pa WHERE pa.invoice_voucher_id = v.id),
+                0.0
+            ) AS REAL) as total_allocated
+         FROM vouchers v
+         WHERE v.voucher_type = ?
+         AND v.party_id = ?
+         AND v.deleted_at IS NULL
+         AND v.status = 'posted'
+         AND COALESCE(v.payment_status, 'unpaid') IN ('unpaid', 'partially_paid')
+         ORDER BY v.voucher_date ASC, v.id ASC",
+        invoice_total = INVOICE_TOTAL_SQL
+    ))
+    .bind(voucher_type)
+    .bind(party_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut remaining = amount;
+    for (invoice_id, invoice_total, total_allocated) in open_invoices {
+        if remaining < 0.01 {
+            break;
+        }
+        let outstanding = invoice_total - total_allocated;
+        if outstanding < 0.01 {
+            continue;
+        }
+        let allocated = remaining.min(outstanding);
+
+        sqlx::query(
+            "INSERT INTO payment_allocations (id, payment_voucher_id, invoice_voucher_id, allocated_amount, allocation_date, remarks, party_id, party_type)
+             VALUES (?, ?, ?, ?, ?, 'Net-off', ?, ?)"
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(settlement_voucher_id)
+        .bind(&invoice_id)
+        .bind(allocated)
+        .bind(allocation_date)
+        .bind(party_id)
+        .bind(party_type)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let status = if (total_allocated + allocated - invoice_total).abs() < 0.01 {
+            "paid"
+        } else {
+            "partially_paid"
+        };
+        sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
+            .bind(status)
+            .bind(&invoice_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        remaining -= allocated;
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct NetOffAllocation {
+    pub invoice_voucher_id: String,
+    pub allocated_amount: f64,
+}
+
+/// Allocates a settlement voucher against the invoices the user picked, each of
+/// which must belong to one of `party_ids`. Each party can settle at most `amount`.
+async fn allocate_selected_invoices(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    settlement_voucher_id: &str,
+    party_ids: [&str; 2],
+    amount: f64,
+    allocation_date: &str,
+    allocations: Vec<NetOffAllocation>,
+) -> Result<(), String> {
+    let mut allocated_per_party: HashMap<String, f64> = HashMap::new();
+    for allocation in allocations {
+        if allocation.allocated_amount <= 0.0 {
+            continue;
+        }
+        let invoice = sqlx::query_as::<_, (String, String, String, f64, f64)>(&format!(
+            "SELECT 
+                v.voucher_no,
+                v.party_id,
+                v.party_type,
+                {invoice_total} as invoice_total,
+                CAST(COALESCE(
+                    (SELECT SUM(pa.allocated_amount) FROM payment_allocations pa WHERE pa.invoice_voucher_id = v.id),
+                    0.0
+                ) AS REAL) as total_allocated
+             FROM vouchers v
+             WHERE v.id = ?
+             AND v.voucher_type IN ('sales_invoice', 'purchase_invoice')
+             AND v.deleted_at IS NULL",
+            invoice_total = INVOICE_TOTAL_SQL
+        ))
+        .bind(&allocation.invoice_voucher_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        let (invoice_no, party_id, party_type, invoice_total, total_allocated) =
+            invoice.ok_or_else(|| "Invoice to allocate against not found".to_string())?;
+
+        if !party_ids.contains(&party_id.as_str()) {
+            return Err(format!(
+                "Invoice {} does not belong to either party of the net-off",
+                invoice_no
+            ));
+        }
+        if total_allocated + allocation.allocated_amount > invoice_total + 0.01 {
+            return Err(format!(
+                "Invoice {} has only {:.2} outstanding",
+                invoice_no,
+                invoice_total - total_allocated
+            ));
+        }
+        let party_total = allocated_per_party.entry(party_id.clone()).or_insert(0.0);
+        *party_total += allocation.allocated_amount;
+        if *party_total > amount + 0.01 {
+            return Err(format!(
+                "Allocations exceed the net-off amount of {:.2}",
+                amount
+            ));
+        }
+
+        sqlx::query(
+            "INSERT INTO payment_allocations (id, payment_voucher_id, invoice_voucher_id, allocated_amount, allocation_date, remarks, party_id, party_type)
+             VALUES (?, ?, ?, ?, ?, 'Net-off', ?, ?)"
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(settlement_voucher_id)
+        .bind(&allocation.invoice_voucher_id)
+        .bind(allocation.allocated_amount)
+        .bind(allocation_date)
+        .bind(&party_id)
+        .bind(&party_type)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let settled = total_allocated + allocation.allocated_amount;
+        let status = if (settled - invoice_total).abs() < 0.01 {
+            "paid"
+        } else {
+            "partially_paid"
+        };
+        sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
+            .bind(status)
+            .bind(&allocation.invoice_voucher_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Net a customer's receivable against the same entity's payable as a supplier with a
+// 'contra' voucher. `customer_id`/`supplier_id` are the parties' chart-of-accounts ids
+// (as used on invoices). Without `allocations` the amount settles each side's oldest
+// open invoices.
+#[tauri::command]
+pub async fn net_off_party(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    customer_id: String,
+    supplier_id: String,
+    amount: f64,
+    date: String,
+    narration: Option<String>,
+    allocations: Option<Vec<NetOffAllocation>>,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    let branch_id = session_store.active_branch_id();
+    let net_off = NetOff {
+        customer_id,
+        supplier_id,
+        amount,
+        date,
+        narration,
+        allocations,
+    };
+    net_off_party_with_pool(&pool, net_off, branch_id.as_deref()).await
+}
+
+/// The arguments of `net_off_party`.
+pub(crate) struct NetOff {
+    pub customer_id: String,
+    pub supplier_id: String,
+    pub amount: f64,
+    pub date: String,
+    pub narration: Option<String>,
+    pub allocations: Option<Vec<NetOffAllocation>>,
+}
+
+pub(crate) async fn net_off_party_with_pool(
+    pool: &sqlx::SqlitePool,
+    net_off: NetOff,
+    branch_id: Option<&str>,
+) -> Result<String, String> {
+    let NetOff {
+        customer_id,
+        supplier_id,
+        amount,
+        date,
+        narration,
+        allocations,
+    } = net_off;
+    let amount = (amount * 100.0).round() / 100.0;
+    if amount <= 0.0 {
+        return Err("Net-off amount must be greater than zero".to_string());
+    }
+
+    super::year_end::ensure_period_open(pool, &date).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    // (name, group, balance) where balance is positive in the account's normal direction
+    let party_balance = "SELECT 
+            coa.account_name,
+            coa.account_group,
+            CAST(
+                (CASE WHEN coa.opening_balance_type = 'Dr' THEN COALESCE(coa.opening_balance, 0) ELSE -COALESCE(coa.opening_balance, 0) END)
+                + COALESCE((SELECT SUM(je.debit - je.credit)
+                            FROM journal_entries je
+                            JOIN vouchers v ON je.voucher_id = v.id
+                            WHERE je.account_id = coa.id AND v.voucher_date <= ? AND v.deleted_at IS NULL), 0)
+            AS REAL) as dr_balance
+         FROM chart_of_accounts coa
+         WHERE coa.id = ? AND coa.deleted_at IS NULL";
+
+    let (customer_name, customer_group, receivable): (String, String, f64) =
+        sqlx::query_as(party_balance)
+            .bind(&date)
+            .bind(&customer_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Customer account not found".to_string())?;
+    if customer_group != "Accounts Receivable" {
+        return Err(format!("{} is not a customer account", customer_name));
+    }
+
+    let (supplier_name, supplier_group, supplier_dr_balance): (String, String, f64) =
+        sqlx::query_as(party_balance)
+            .bind(&date)
+            .bind(&supplier_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Supplier account not found".to_string())?;
+    if supplier_group != "Accounts Payable" {
+        return Err(format!("{} is not a supplier account", supplier_name));
+    }
+    let payable = -supplier_dr_balance;
+
+    if receivable + 0.01 < amount {
+        return Err(format!(
+            "Receivable from {} is only {:.2}, cannot net off {:.2}",
+            customer_name, receivable, amount
+        ));
+    }
+    if payable + 0.01 < amount {
+        return Err(format!(
+            "Payable to {} is only {:.2}, cannot net off {:.2}",
+            supplier_name, payable, amount
+        ));
+    }
+
+    let voucher_no = get_next_voucher_number_in_tx(&mut tx, "contra", &date, branch_id).await?;
+    let voucher_id = Uuid::now_v7().to_string();
+    let narration = narration
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| format!("Net-off: {} against {}", customer_name, supplier_name));
+
+    sqlx::query(
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, total_amount, narration, status, branch_id)
+         VALUES (?, ?, 'contra', ?, ?, 'customer', ?, ?, 'posted', ?)",
+    )
+    .bind(&voucher_id)
+    .bind(&voucher_no)
+    .bind(&date)
+    .bind(&customer_id)
+    .bind(amount)
+    .bind(&narration)
+    .bind(branch_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Dr payable (supplier), Cr receivable (customer)
+    let journal_lines = [(&supplier_id, amount, 0.0), (&customer_id, 0.0, amount)]
+        .into_iter()
+        .map(|(account_id, debit, credit)| JournalLine {
+            account_id: account_id.clone(),
+            debit,
+            credit,
+            is_manual: false,
+            narration: Some(narration.clone()),
+        })
+        .collect::<Vec<_>>();
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
+
+    match allocations {
+        Some(allocations) if !allocations.is_empty() => {
+            allocate_selected_invoices(
+                &mut tx,
+                &voucher_id,
+                [customer_id.as_str(), supplier_id.as_str()],
+                amount,
+                &date,
+                allocations,
+            )
+            .await?;
+        }
+        _ => {
+            allocate_oldest_first(
+                &mut tx,
+                &voucher_id,
+                &customer_id,
+                "customer",
+                "sales_invoice",
+                amount,
+                &date,
+            )
+            .await?;
+            allocate_oldest_first(
+                &mut tx,
+                &voucher_id,
+                &supplier_id,
+                "supplier",
+                "purchase_invoice",
+                amount,
+                &date,
+            )
+            .await?;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(voucher_id)
+}
+
+// ============= CONTRA ADJUSTMENTS =============
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ContraAdjustment {
+    pub id: String,
+    pub voucher_no: String,
+    pub voucher_date: String,
+    pub from_account_id: String,
+    pub from_account_name: String,
+    pub to_account_id: String,
+    pub to_account_name: String,
+    pub amount: f64,
+    pub narration: Option<String>,
+    pub created_at: String,
+}
+
+// Lists net-off vouchers: `from` is the credited (customer) ledger, `to` the debited one.
+#[tauri::command]
+pub async fn get_contra_adjustments(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<ContraAdjustment>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, ContraAdjustment>(
+        "SELECT 
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            cr.account_id as from_account_id,
+            cr_coa.account_name as from_account_name,
+            dr.account_id as to_account_id,
+            dr_coa.account_name as to_account_name,
+            CAST(COALESCE(v.total_amount, 0.0) AS REAL) as amount,
+            v.narration,
+            v.created_at
+        FROM vouchers v
+        JOIN journal_entries cr ON cr.voucher_id = v.id AND cr.credit > 0
+        JOIN journal_entries dr ON dr.voucher_id = v.id AND dr.debit > 0
+        JOIN chart_of_accounts cr_coa ON cr.account_id = cr_coa.id
+        JOIN chart_of_accounts dr_coa ON dr.account_id = dr_coa.id
+        WHERE v.voucher_type = 'contra' AND v.deleted_at IS NULL
+        ORDER BY v.voucher_date DESC, v.created_at DESC, v.id DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use sqlx::SqlitePool;
+
+    async fn insert_party_account(pool: &SqlitePool, code: &str, party_type: &str) -> String {
+        let (account_type, account_group) = if party_type == "customer" {
+            ("Asset", "Accounts Receivable")
+        } else {
+            ("Liability", "Accounts Payable")
+        };
+        let account_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO chart_of_accounts (id, account_code, account_name, account_type, account_group, party_type)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&account_id)
+        .bind(code)
+        .bind(format!("Party {}", code))
+        .bind(account_type)
+        .bind(account_group)
+        .bind(party_type)
+        .execute(pool)
+        .await
+        .unwrap();
+        account_id
+    }
+
+    /// A posted voucher with a stored grand total and payment status.
+    async fn insert_voucher(
+        pool: &SqlitePool,
+        voucher_type: &str,
+        party_id: &str,
+        grand_total: f64,
+        payment_status: &str,
+    ) -> String {
+        let id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO vouchers
+                (id, voucher_no, voucher_type, voucher_date, party_id, grand_total, total_amount, payment_status)
+             VALUES (?, ?, ?, '2024-04-01', ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(format!("T-{}", id))
+        .bind(voucher_type)
+        .bind(party_id)
+        .bind(grand_total)
+        .bind(grand_total)
+        .bind(payment_status)
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn allocate(pool: &SqlitePool, payment_id: &str, invoice_id: &str, amount: f64) {
+        sqlx::query(
+            "INSERT INTO payment_allocations (id, payment_voucher_id, invoice_voucher_id, allocated_amount)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(payment_id)
+        .bind(invoice_id)
+        .bind(amount)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn payment_status(pool: &SqlitePool, id: &str) -> String {
+        sqlx::query_scalar("SELECT payment_status FROM vouchers WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn recompute_repairs_stale_payment_statuses() {
+        let pool = test_pool().await;
+        let customer = insert_party_account(&pool, "1003-TR", "customer").await;
+        let receipt = insert_voucher(&pool, "receipt", &customer, 1100.0, "paid").await;
+        let settled = insert_voucher(&pool, "sales_invoice", &customer, 1000.0, "unpaid").await;
+        let open = insert_voucher(&pool, "sales_invoice", &customer, 500.0, "paid").await;
+        let part = insert_voucher(&pool, "sales_invoice", &customer, 300.0, "partially_paid").await;
+        allocate(&pool, &receipt, &settled, 1000.0).await;
+        allocate(&pool, &receipt, &part, 100.0).await;
+
+        let updated = recompute_payment_statuses_with_pool(&pool).await.unwrap();
+
+        assert_eq!(updated, 2);
+        assert_eq!(payment_status(&pool, &settled).await, "paid");
+        assert_eq!(payment_status(&pool, &open).await, "unpaid");
+        assert_eq!(payment_status(&pool, &part).await, "partially_paid");
+    }
+
+    async fn post(pool: &SqlitePool, voucher_id: &str, account_id: &str, debit: f64, credit: f64) {
+        sqlx::query(
+            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(voucher_id)
+        .bind(account_id)
+        .bind(debit)
+        .bind(credit)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn net_off_reduces_both_party_balances() {
+        let pool = test_pool().await;
+        let customer = insert_party_account(&pool, "1003-TN", "customer").await;
+        let supplier = insert_party_account(&pool, "2001-TN", "supplier").await;
+        let (sales, purchases): (String, String) = sqlx::query_as(
+            "SELECT
+                (SELECT id FROM chart_of_accounts WHERE account_code = '4001'),
+                (SELECT id FROM chart_of_accounts WHERE account_code = '5001')",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let sale = insert_voucher(&pool, "sales_invoice", &customer, 800.0, "unpaid").await;
+        post(&pool, &sale, &customer, 800.0, 0.0).await;
+        post(&pool, &sale, &sales, 0.0, 800.0).await;
+        let purchase = insert_voucher(&pool, "purchase_invoice", &supplier, 600.0, "unpaid").await;
+        post(&pool, &purchase, &purchases, 600.0, 0.0).await;
+        post(&pool, &purchase, &supplier, 0.0, 600.0).await;
+        let net_off = |amount: f64| NetOff {
+            customer_id: customer.clone(),
+            supplier_id: supplier.clone(),
+            amount,
+            date: "2024-04-15".to_string(),
+            narration: None,
+            allocations: None,
+        };
+
+        let err = net_off_party_with_pool(&pool, net_off(700.0), None)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("Payable to"), "{}", err);
+
+        net_off_party_with_pool(&pool, net_off(500.0), None)
+            .await
+            .unwrap();
+        let balances = crate::commands::entries::account_balances(
+            &pool,
+            &[customer.clone(), supplier.clone()],
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(balances[&customer], 300.0);
+        assert_eq!(balances[&supplier], -100.0);
+        assert_eq!(payment_status(&pool, &sale).await, "partially_paid");
+        assert_eq!(payment_status(&pool, &purchase).await, "partially_paid");
+    }
+}
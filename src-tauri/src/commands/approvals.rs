@@ -0,0 +1,364 @@
+use crate::company_db::DbRegistry;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::sync::Arc;
+use tauri::State;
+
+use super::audit::record_voucher_audit;
+use super::auth::SessionStore;
+use super::entries::{
+    apply_payment_product_costs, archive_voucher_allocations, refresh_invoice_payment_statuses,
+    unarchive_voucher_allocations,
+};
+use super::invoices::{ensure_stock_covers, ensure_within_credit_limit};
+use super::reports::STOCK_IN_TYPES;
+use super::year_end::ensure_voucher_unlocked;
+
+// ============= VOUCHER APPROVAL =============
+// Voucher status moves draft -> pending -> posted. While a voucher is draft or
+// pending its journal entries and stock movements sit in held_journal_entries /
+// held_stock_movements (and payment allocations in deleted_payment_allocations),
+// so ledgers, trial balance and stock see nothing until it is approved.
+
+const JOURNAL_COLUMNS: &str =
+    "id, voucher_id, account_id, debit, credit, is_manual, narration, created_at";
+const STOCK_COLUMNS: &str = "id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount, created_at";
+
+/// Marks a freshly posted voucher as a draft by moving its postings into the
+/// held tables. Any postings held from an earlier save are replaced.
+pub(crate) async fn hold_voucher_postings(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<(), String> {
+    discard_held_postings(tx, voucher_id).await?;
+
+    for (live, held, columns) in [
+        ("journal_entries", "held_journal_entries", JOURNAL_COLUMNS),
+        ("stock_movements", "held_stock_movements", STOCK_COLUMNS),
+    ] {
+        move_rows(tx, live, held, columns, voucher_id).await?;
+    }
+
+    let allocated_invoices: Vec<String> = sqlx::query_scalar(
+        "SELECT invoice_voucher_id FROM payment_allocations WHERE payment_voucher_id = ?",
+    )
+    .bind(voucher_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    archive_voucher_allocations(tx, voucher_id).await?;
+    refresh_invoice_payment_statuses(tx, &allocated_invoices).await?;
+
+    sqlx::query("UPDATE vouchers SET status = 'draft' WHERE id = ?")
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Settles a re-saved voucher: held again as a draft when `as_draft` is set.
+/// Otherwise a draft is held and submitted for approval, since only an approver
+/// may post it; an already posted voucher stays posted.
+pub(crate) async fn set_voucher_draft_state(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+    as_draft: bool,
+) -> Result<(), String> {
+    let status: Option<String> = sqlx::query_scalar("SELECT status FROM vouchers WHERE id = ?")
+        .bind(voucher_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    let was_draft = status.as_deref() == Some("draft");
+    if !as_draft && !was_draft {
+        return discard_held_postings(tx, voucher_id).await;
+    }
+
+    hold_voucher_postings(tx, voucher_id).await?;
+    if !as_draft {
+        sqlx::query(
+            "UPDATE vouchers SET status = 'pending', rejection_reason = NULL
+             WHERE id = ? AND status = 'draft'",
+        )
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Drops postings held for a draft, before the voucher is re-saved.
+pub(crate) async fn discard_held_postings(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<(), String> {
+    delete_held_postings(tx, voucher_id).await?;
+    sqlx::query("DELETE FROM deleted_payment_allocations WHERE payment_voucher_id = ?")
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Drops the held journal entries and stock movements of a voucher being
+/// deleted. Its archived allocations stay, as they are what a restore brings back.
+pub(crate) async fn delete_held_postings(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<(), String> {
+    for table in ["held_journal_entries", "held_stock_movements"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE voucher_id = ?", table))
+            .bind(voucher_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+async fn move_rows(
+    tx: &mut Transaction<'_, Sqlite>,
+    from: &str,
+    to: &str,
+    columns: &str,
+    voucher_id: &str,
+) -> Result<(), String> {
+    sqlx::query(&format!(
+        "INSERT INTO {to} ({columns}) SELECT {columns} FROM {from} WHERE voucher_id = ?"
+    ))
+    .bind(voucher_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query(&format!("DELETE FROM {from} WHERE voucher_id = ?"))
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Guards edits: vouchers awaiting approval must be rejected before they can
+/// change. Returns whether the voucher is currently a draft.
+pub(crate) async fn ensure_voucher_editable(pool: &SqlitePool, id: &str) -> Result<bool, String> {
+    let status: Option<String> =
+        sqlx::query_scalar("SELECT COALESCE(status, 'posted') FROM vouchers WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    match status.as_deref() {
+        Some("pending") => {
+            Err("This voucher is awaiting approval; reject it before editing".to_string())
+        }
+        Some("draft") => Ok(true),
+        _ => Ok(false),
+    }
+}
+
+/// Re-runs the stock and credit-limit checks a sales invoice passed when it was
+/// saved, against the held stock movements about to be released.
+async fn recheck_sales_invoice(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+    override_credit_limit: bool,
+) -> Result<(), String> {
+    let required: Vec<(String, f64)> = sqlx::query_as(&format!(
+        "SELECT product_id, SUM(CASE WHEN movement_type IN ({}) THEN -quantity ELSE quantity END)
+         FROM held_stock_movements
+         WHERE voucher_id = ?
+         GROUP BY product_id",
+        STOCK_IN_TYPES
+    ))
+    .bind(voucher_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    ensure_stock_covers(tx, required).await?;
+
+    if !override_credit_limit {
+        let (party_id, amount): (Option<String>, f64) = sqlx::query_as(
+            "SELECT party_id, COALESCE(base_grand_total, grand_total, 0) FROM vouchers WHERE id = ?",
+        )
+        .bind(voucher_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if let Some(party_id) = party_id {
+            ensure_within_credit_limit(tx, &party_id, amount, Some(voucher_id)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns (status, voucher_type, created_by) of a live voucher.
+async fn load_voucher_state(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<(String, String, Option<String>), String> {
+    sqlx::query_as(
+        "SELECT COALESCE(status, 'posted'), voucher_type, created_by
+         FROM vouchers WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Voucher not found".to_string())
+}
+
+#[tauri::command]
+pub async fn submit_voucher_for_approval(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let (status, _, _) = load_voucher_state(&pool, &id).await?;
+    if status != "draft" {
+        return Err(format!(
+            "Only draft vouchers can be submitted (this one is {})",
+            status
+        ));
+    }
+
+    // Drafts saved before approval existed (e.g. reorder suggestions) hold nothing yet
+    let has_postings: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM held_journal_entries WHERE voucher_id = ?)",
+    )
+    .bind(&id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if !has_postings {
+        return Err("This draft has no postings yet; save it before submitting".to_string());
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        "UPDATE vouchers SET status = 'pending', rejection_reason = NULL
+         WHERE id = ? AND status = 'draft'",
+    )
+    .bind(&id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "submit").await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Posts a pending voucher: its held journal entries, stock movements and
+/// allocations take effect. The approver is the signed-in user, who must not be
+/// the voucher's creator. A sales invoice is checked again against stock and the
+/// customer's credit limit, since both may have moved while it was waiting.
+#[tauri::command]
+pub async fn approve_voucher(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+    override_credit_limit: Option<bool>,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let approver = session_store
+        .current_user_id()
+        .ok_or_else(|| "Sign in to approve vouchers".to_string())?;
+    approve_voucher_with_pool(
+        &pool,
+        &id,
+        &approver,
+        override_credit_limit.unwrap_or(false),
+    )
+    .await
+}
+
+pub(crate) async fn approve_voucher_with_pool(
+    pool: &SqlitePool,
+    id: &str,
+    approver: &str,
+    override_credit_limit: bool,
+) -> Result<(), String> {
+    let (status, voucher_type, created_by) = load_voucher_state(pool, id).await?;
+    if status != "pending" {
+        return Err(format!(
+            "Only pending vouchers can be approved (this one is {})",
+            status
+        ));
+    }
+    if created_by.as_deref() == Some(approver) {
+        return Err("A voucher cannot be approved by the user who created it".to_string());
+    }
+    ensure_voucher_unlocked(pool, id).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    if voucher_type == "sales_invoice" {
+        recheck_sales_invoice(&mut tx, id, override_credit_limit).await?;
+    }
+
+    for (held, live, columns) in [
+        ("held_journal_entries", "journal_entries", JOURNAL_COLUMNS),
+        ("held_stock_movements", "stock_movements", STOCK_COLUMNS),
+    ] {
+        move_rows(&mut tx, held, live, columns, id).await?;
+    }
+
+    if voucher_type == "payment" {
+        apply_payment_product_costs(&mut tx, id).await?;
+    }
+    let allocated_invoices = unarchive_voucher_allocations(&mut tx, id).await?;
+    refresh_invoice_payment_statuses(&mut tx, &allocated_invoices).await?;
+
+    sqlx::query(
+        "UPDATE vouchers SET status = 'posted', approved_by = ?, approved_at = CURRENT_TIMESTAMP
+         WHERE id = ?",
+    )
+    .bind(approver)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    record_voucher_audit(&mut *tx, Some(approver), id, "approve").await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Sends a pending voucher back to draft with the reason recorded.
+#[tauri::command]
+pub async fn reject_voucher(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+    reason: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let (status, _, _) = load_voucher_state(&pool, &id).await?;
+    if status != "pending" {
+        return Err(format!(
+            "Only pending vouchers can be rejected (this one is {})",
+            status
+        ));
+    }
+    if reason.trim().is_empty() {
+        return Err("A rejection reason is required".to_string());
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("UPDATE vouchers SET status = 'draft', rejection_reason = ? WHERE id = ?")
+        .bind(reason.trim())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "reject").await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,174 @@
+use crate::company_db::DbRegistry;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+// ============= VOUCHER ATTACHMENTS =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct VoucherAttachment {
+    pub id: String,
+    pub voucher_id: String,
+    /// Original file name, for display
+    pub file_name: String,
+    /// Absolute path of the app-managed copy; open it with the opener plugin
+    pub stored_path: String,
+    pub mime_type: Option<String>,
+    pub created_at: String,
+}
+
+fn mime_type_for(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "tif" | "tiff" => "image/tiff",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Copies `source_path` into `<app data>/voucher_attachments/<company>/<voucher_id>/`
+/// and records it against the voucher. The original file is left untouched.
+#[tauri::command]
+pub async fn add_voucher_attachment(
+    app_handle: AppHandle,
+    registry: State<'_, Arc<DbRegistry>>,
+    voucher_id: String,
+    source_path: String,
+) -> Result<VoucherAttachment, String> {
+    let company = registry
+        .get_active_company_info()
+        .await?
+        .ok_or_else(|| "No active company selected.".to_string())?;
+    let pool = registry.active_pool().await?;
+
+    let voucher_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM vouchers WHERE id = ? AND deleted_at IS NULL)",
+    )
+    .bind(&voucher_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if !voucher_exists {
+        return Err("Voucher not found".to_string());
+    }
+
+    let source = Path::new(&source_path);
+    if !source.is_file() {
+        return Err(format!("File not found: {}", source_path));
+    }
+    let file_name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid file path: {}", source_path))?;
+    let extension = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_string();
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    let target_dir = app_dir
+        .join("voucher_attachments")
+        .join(&company.slug)
+        .join(&voucher_id);
+    std::fs::create_dir_all(&target_dir).map_err(|e| e.to_string())?;
+
+    // Stored under the attachment id so two files with the same name never clash
+    let id = Uuid::now_v7().to_string();
+    let stored_name = if extension.is_empty() {
+        id.clone()
+    } else {
+        format!("{}.{}", id, extension)
+    };
+    let stored_path = target_dir.join(stored_name);
+    std::fs::copy(source, &stored_path).map_err(|e| format!("Failed to copy attachment: {}", e))?;
+    let stored_path = stored_path.to_string_lossy().to_string();
+
+    let inserted = sqlx::query(
+        "INSERT INTO voucher_attachments (id, voucher_id, file_name, stored_path, mime_type)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&voucher_id)
+    .bind(&file_name)
+    .bind(&stored_path)
+    .bind(mime_type_for(&extension))
+    .execute(&pool)
+    .await;
+    if let Err(e) = inserted {
+        let _ = std::fs::remove_file(&stored_path);
+        return Err(e.to_string());
+    }
+
+    sqlx::query_as::<_, VoucherAttachment>(
+        "SELECT id, voucher_id, file_name, stored_path, mime_type, created_at
+         FROM voucher_attachments WHERE id = ?",
+    )
+    .bind(&id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_voucher_attachments(
+    registry: State<'_, Arc<DbRegistry>>,
+    voucher_id: String,
+) -> Result<Vec<VoucherAttachment>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, VoucherAttachment>(
+        "SELECT id, voucher_id, file_name, stored_path, mime_type, created_at
+         FROM voucher_attachments
+         WHERE voucher_id = ?
+         ORDER BY created_at ASC, id ASC",
+    )
+    .bind(voucher_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_voucher_attachment(
+    registry: State<'_, Arc<DbRegistry>>,
+    attachment_id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let stored_path: Option<String> =
+        sqlx::query_scalar("SELECT stored_path FROM voucher_attachments WHERE id = ?")
+            .bind(&attachment_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    let Some(stored_path) = stored_path else {
+        return Err("Attachment not found".to_string());
+    };
+
+    sqlx::query("DELETE FROM voucher_attachments WHERE id = ?")
+        .bind(&attachment_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // The row is gone either way; a missing file is not an error
+    let path = Path::new(&stored_path);
+    if path.exists() {
+        let _ = std::fs::remove_file(path);
+    }
+
+    Ok(())
+}
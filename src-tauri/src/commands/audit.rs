@@ -0,0 +1,85 @@
+use crate::company_db::DbRegistry;
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteExecutor;
+use std::sync::Arc;
+use tauri::State;
+use uuid::Uuid;
+
+// ============= VOUCHER AUDIT TRAIL =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct VoucherAuditEntry {
+    pub id: String,
+    pub voucher_id: String,
+    /// "create" | "update" | "delete" | "restore" | "submit" | "approve" | "reject"
+    pub action: String,
+    /// User id of the logged-in user, if any
+    pub actor: Option<String>,
+    pub actor_name: Option<String>,
+    pub changed_at: String,
+    /// JSON snapshot of the voucher header after the action
+    pub snapshot_json: Option<String>,
+}
+
+/// Appends an audit row with a JSON snapshot of the voucher header as it now
+/// stands, attributed to `actor` (the logged-in user id). Run it on the same
+/// transaction as the change, so the change never commits without its audit row.
+pub(crate) async fn record_voucher_audit<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    actor: Option<&str>,
+    voucher_id: &str,
+    action: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO voucher_audit_log (id, voucher_id, action, actor, snapshot_json)
+         SELECT ?, id, ?, ?, json_object(
+            'voucher_no', voucher_no,
+            'voucher_type', voucher_type,
+            'voucher_date', voucher_date,
+            'party_id', party_id,
+            'party_type', party_type,
+            'reference', reference,
+            'narration', narration,
+            'subtotal', subtotal,
+            'discount_amount', discount_amount,
+            'tax_amount', tax_amount,
+            'total_amount', total_amount,
+            'grand_total', grand_total,
+            'currency', currency,
+            'exchange_rate', exchange_rate,
+            'status', status,
+            'payment_status', payment_status,
+            'deleted_at', deleted_at
+         )
+         FROM vouchers WHERE id = ?",
+    )
+    .bind(Uuid::now_v7().to_string())
+    .bind(action)
+    .bind(actor)
+    .bind(voucher_id)
+    .execute(executor)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_voucher_audit_trail(
+    registry: State<'_, Arc<DbRegistry>>,
+    voucher_id: String,
+) -> Result<Vec<VoucherAuditEntry>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, VoucherAuditEntry>(
+        "SELECT a.id, a.voucher_id, a.action, a.actor,
+                COALESCE(u.full_name, u.username) as actor_name,
+                a.changed_at, a.snapshot_json
+         FROM voucher_audit_log a
+         LEFT JOIN users u ON u.id = a.actor
+         WHERE a.voucher_id = ?
+         ORDER BY a.changed_at ASC, a.id ASC",
+    )
+    .bind(voucher_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
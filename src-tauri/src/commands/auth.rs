@@ -10,18 +10,26 @@ use uuid::Uuid;
 // Session storage (in-memory for simplicity)
 pub struct SessionStore {
     sessions: Mutex<HashMap<String, String>>, // token -> user_id
+    current_user: Mutex<Option<String>>,      // user of the latest login
+    active_branch: Mutex<Option<String>>,     // branch new vouchers are recorded under
 }
 
 impl SessionStore {
     pub fn new() -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
+            current_user: Mutex::new(None),
+            active_branch: Mutex::new(None),
         }
     }
 
     pub fn create_session(&self, user_id: String) -> String {
         let token = Uuid::new_v4().to_string();
-        self.sessions.lock().unwrap().insert(token.clone(), user_id);
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(token.clone(), user_id.clone());
+        *self.current_user.lock().unwrap() = Some(user_id);
         token
     }
 
@@ -29,8 +37,26 @@ impl SessionStore {
         self.sessions.lock().unwrap().get(token).cloned()
     }
 
+    /// The logged-in user on this desktop session, used as the audit actor
+    pub fn current_user_id(&self) -> Option<String> {
+        self.current_user.lock().unwrap().clone()
+    }
+
+    /// Branch this desktop session records new vouchers under; None for single-branch use
+    pub fn active_branch_id(&self) -> Option<String> {
+        self.active_branch.lock().unwrap().clone()
+    }
+
+    pub fn set_active_branch(&self, branch_id: Option<String>) {
+        *self.active_branch.lock().unwrap() = branch_id;
+    }
+
     pub fn remove_session(&self, token: &str) {
-        self.sessions.lock().unwrap().remove(token);
+        let removed = self.sessions.lock().unwrap().remove(token);
+        let mut current = self.current_user.lock().unwrap();
+        if removed.is_some() && *current == removed {
+            *current = None;
+        }
     }
 }
 
@@ -1,5 +1,6 @@
 use crate::company_db::{CompanyInfo, DbRegistry};
 use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::SqlitePool;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -99,6 +100,125 @@ pub async fn create_manual_backup(
     })
 }
 
+/// Tables every Kola-biz company database must contain to be accepted for restore.
+const REQUIRED_TABLES: &[&str] = &[
+    "company_profile",
+    "chart_of_accounts",
+    "vouchers",
+    "voucher_items",
+    "journal_entries",
+    "products",
+    "units",
+];
+
+/// Backs up the active company database to `target_path` (chosen via the frontend
+/// save dialog). Returns the size of the backup file in bytes.
+#[tauri::command]
+pub async fn backup_database(
+    registry: State<'_, Arc<DbRegistry>>,
+    target_path: String,
+) -> Result<u64, String> {
+    let pool = registry.active_pool().await?;
+    let dest = PathBuf::from(&target_path);
+
+    perform_sqlite_backup(&pool, &dest).await?;
+
+    std::fs::metadata(&dest)
+        .map(|m| m.len())
+        .map_err(|e| format!("Backup written but could not be read back: {}", e))
+}
+
+/// Replaces the active company database with the file at `source_path`.
+/// The file is checked for the Kola-biz schema first, and the current database is
+/// saved alongside it as `<db>.pre-restore-<timestamp>.db`. The company is closed
+/// afterwards, so the app must be restarted to load the restored data.
+#[tauri::command]
+pub async fn restore_database(
+    registry: State<'_, Arc<DbRegistry>>,
+    source_path: String,
+) -> Result<BackupResult, String> {
+    let source = PathBuf::from(&source_path);
+    if !source.exists() {
+        return Err(format!("Backup file not found: {}", source_path));
+    }
+
+    let company = registry
+        .get_active_company_info()
+        .await?
+        .ok_or_else(|| "No active company selected.".to_string())?;
+
+    if Path::new(&company.db_path) == source.as_path() {
+        return Err("Cannot restore a database onto itself.".to_string());
+    }
+
+    // Validate the source file before touching anything
+    let source_options = SqliteConnectOptions::new()
+        .filename(&source)
+        .read_only(true);
+    let source_pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(source_options)
+        .await
+        .map_err(|e| format!("Not a valid database file: {}", e))?;
+
+    let tables: Result<Vec<(String,)>, _> =
+        sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .fetch_all(&source_pool)
+            .await;
+    source_pool.close().await;
+    let tables: Vec<String> = tables
+        .map_err(|e| format!("Not a valid database file: {}", e))?
+        .into_iter()
+        .map(|(name,)| name)
+        .collect();
+
+    let missing: Vec<&str> = REQUIRED_TABLES
+        .iter()
+        .copied()
+        .filter(|t| !tables.iter().any(|name| name == t))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "This file is not a Kola-biz company database (missing tables: {}).",
+            missing.join(", ")
+        ));
+    }
+
+    // Keep a copy of the current data in case the restore needs to be undone
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let safety_path = PathBuf::from(format!("{}.pre-restore-{}.db", company.db_path, timestamp));
+    let pool = registry.active_pool().await?;
+    perform_sqlite_backup(&pool, &safety_path).await?;
+    drop(pool);
+
+    // Release the company so the file can be replaced
+    registry.close_company_pool(&company.id).await;
+
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = format!("{}{}", company.db_path, suffix);
+        if Path::new(&sidecar).exists() {
+            std::fs::remove_file(&sidecar)
+                .map_err(|e| format!("Failed to remove {}: {}", sidecar, e))?;
+        }
+    }
+
+    std::fs::copy(&source, &company.db_path)
+        .map_err(|e| format!("Failed to restore database: {}", e))?;
+
+    Ok(BackupResult {
+        success: true,
+        message: "Database restored. Please restart the application to load the restored data."
+            .to_string(),
+        path: Some(safety_path.to_string_lossy().to_string()),
+    })
+}
+
+/// Restarts the app, e.g. after `restore_database` so the restored data is loaded.
+#[tauri::command]
+pub fn restart_app(app_handle: AppHandle) {
+    app_handle.restart();
+}
+
 /// Spawns a background task that performs automated backups periodically (e.g., every 6 hours).
 pub fn setup_automated_backups(app_handle: AppHandle, registry: Arc<DbRegistry>) {
     tauri::async_runtime::spawn(async move {
@@ -0,0 +1,111 @@
+use super::auth::SessionStore;
+use crate::company_db::DbRegistry;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+use uuid::Uuid;
+
+// ============= BRANCHES =============
+// Optional. While a branch is active for the session (SessionStore::active_branch_id),
+// new vouchers are stamped with it and voucher numbers carry its code as a prefix.
+// With no active branch, numbering and reports behave as before.
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct Branch {
+    pub id: String,
+    pub code: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub is_active: i64,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateBranch {
+    pub code: String,
+    pub name: String,
+    pub address: Option<String>,
+}
+
+#[tauri::command]
+pub async fn list_branches(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<Branch>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, Branch>(
+        "SELECT id, code, name, address, is_active, created_at
+         FROM branches WHERE deleted_at IS NULL ORDER BY code ASC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_branch(
+    registry: State<'_, Arc<DbRegistry>>,
+    branch: CreateBranch,
+) -> Result<Branch, String> {
+    let pool = registry.active_pool().await?;
+    let code = branch.code.trim().to_uppercase();
+    let name = branch.name.trim();
+    if code.is_empty() || name.is_empty() {
+        return Err("Branch code and name are required".to_string());
+    }
+    if !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("Branch code may only contain letters and digits".to_string());
+    }
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM branches WHERE code = ?)")
+        .bind(&code)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Err(format!("A branch with code '{}' already exists", code));
+    }
+
+    let id = Uuid::now_v7().to_string();
+    sqlx::query("INSERT INTO branches (id, code, name, address) VALUES (?, ?, ?, ?)")
+        .bind(&id)
+        .bind(&code)
+        .bind(name)
+        .bind(&branch.address)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query_as::<_, Branch>(
+        "SELECT id, code, name, address, is_active, created_at FROM branches WHERE id = ?",
+    )
+    .bind(&id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Makes `branch_id` the branch this session records new vouchers under; None
+/// returns to single-branch numbering. Other users keep their own branch.
+#[tauri::command]
+pub async fn set_active_branch(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    branch_id: Option<String>,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+
+    if let Some(id) = &branch_id {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM branches WHERE id = ? AND deleted_at IS NULL AND is_active = 1)",
+        )
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err("Branch not found".to_string());
+        }
+    }
+
+    session_store.set_active_branch(branch_id);
+
+    Ok(())
+}
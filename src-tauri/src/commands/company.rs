@@ -1,6 +1,7 @@
 use crate::company_db::DbRegistry;
+use chrono::Datelike;
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
+use sqlx::{SqliteExecutor, SqlitePool};
 use std::sync::Arc;
 use tauri::State;
 
@@ -12,6 +13,14 @@ pub struct Country {
     pub code: String,
 }
 
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct StateRow {
+    pub id: String,
+    pub country_code: String,
+    pub name: String,
+    pub code: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct Currency {
     pub id: String,
@@ -30,6 +39,27 @@ pub async fn get_countries(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<C
         .map_err(|e| e.to_string())
 }
 
+/// States/provinces for a country (ISO code, e.g. "IN"); empty for countries without seeded data.
+#[tauri::command]
+pub async fn get_states(
+    registry: State<'_, Arc<DbRegistry>>,
+    country_code: String,
+) -> Result<Vec<StateRow>, String> {
+    let pool = registry.active_pool().await?;
+    get_states_with_pool(&pool, &country_code).await
+}
+
+pub(crate) async fn get_states_with_pool(
+    pool: &SqlitePool,
+    country_code: &str,
+) -> Result<Vec<StateRow>, String> {
+    sqlx::query_as::<_, StateRow>("SELECT * FROM states WHERE country_code = ? ORDER BY name")
+        .bind(country_code.trim().to_uppercase())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_currencies(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<Currency>, String> {
     let pool = registry.active_pool().await?;
@@ -65,6 +95,17 @@ pub struct CompanyProfile {
     pub bank_branch: Option<String>,
     pub terms_and_conditions: Option<String>,
     pub base_currency: Option<String>,
+    #[sqlx(default)]
+    pub financial_year_start: Option<String>,
+    /// When set, sales invoices may not take a product's stock below zero
+    #[sqlx(default)]
+    pub prevent_negative_stock: bool,
+    /// When set, invoice grand totals are rounded to the nearest whole unit
+    #[sqlx(default)]
+    pub round_invoice_totals: bool,
+    /// UPI VPA (e.g. `shop@okbank`) printed on invoices as a payment QR
+    #[sqlx(default)]
+    pub upi_id: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -93,6 +134,13 @@ pub struct UpdateCompanyProfile {
     pub bank_branch: Option<String>,
     pub terms_and_conditions: Option<String>,
     pub base_currency: Option<String>,
+    pub financial_year_start: Option<String>,
+    /// Left unchanged when omitted
+    pub prevent_negative_stock: Option<bool>,
+    /// Left unchanged when omitted
+    pub round_invoice_totals: Option<bool>,
+    /// Left unchanged when omitted; an empty string clears it
+    pub upi_id: Option<String>,
 }
 
 #[tauri::command]
@@ -129,12 +177,95 @@ pub(crate) async fn get_company_profile_with_pool(
     }
 }
 
+/// Fiscal-year start month used when the profile has no `financial_year_start`.
+const DEFAULT_FY_START_MONTH: u32 = 4;
+
+/// Start of the company's financial year as YYYY-MM-DD, from the profile's
+/// `financial_year_start`.
+pub(crate) async fn financial_year_start_date(pool: &SqlitePool) -> Result<String, String> {
+    configured_financial_year_start(pool).await?.ok_or_else(|| {
+        "Financial year start is not set in the company profile. Set it there or enter an opening balance date.".to_string()
+    })
+}
+
+/// The month (1-12) the fiscal year starts in, taken from the profile's
+/// `financial_year_start` and defaulting to April.
+pub(crate) async fn financial_year_start_month<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+) -> Result<u32, String> {
+    Ok(configured_financial_year_start(executor)
+        .await?
+        .and_then(|date| chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+        .map(|date| date.month())
+        .unwrap_or(DEFAULT_FY_START_MONTH))
+}
+
+async fn configured_financial_year_start<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+) -> Result<Option<String>, String> {
+    let configured: Option<String> = sqlx::query_scalar(
+        "SELECT financial_year_start FROM company_profile
+         WHERE financial_year_start IS NOT NULL AND TRIM(financial_year_start) != ''
+         ORDER BY id LIMIT 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(configured.map(|date| date.trim().to_string()))
+}
+
+/// First day of the fiscal year containing `date` for a year starting in `start_month`.
+pub(crate) fn fiscal_year_start_for(
+    date: chrono::NaiveDate,
+    start_month: u32,
+) -> chrono::NaiveDate {
+    let year = if date.month() >= start_month {
+        date.year()
+    } else {
+        date.year() - 1
+    };
+    chrono::NaiveDate::from_ymd_opt(year, start_month, 1).unwrap_or(date)
+}
+
+#[derive(Serialize)]
+pub struct FinancialYear {
+    pub start_month: u32,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+#[tauri::command]
+pub async fn get_current_financial_year(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<FinancialYear, String> {
+    let pool = registry.active_pool().await?;
+    let start_month = financial_year_start_month(&pool).await?;
+    let today = chrono::Local::now().naive_local().date();
+    let start = fiscal_year_start_for(today, start_month);
+    let end = start
+        .with_year(start.year() + 1)
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(start);
+    Ok(FinancialYear {
+        start_month,
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+    })
+}
+
 #[tauri::command]
 pub async fn update_company_profile(
     registry: State<'_, Arc<DbRegistry>>,
     profile: UpdateCompanyProfile,
 ) -> Result<CompanyProfile, String> {
     let pool = registry.active_pool().await?;
+    update_company_profile_with_pool(&pool, profile).await
+}
+
+pub(crate) async fn update_company_profile_with_pool(
+    pool: &SqlitePool,
+    profile: UpdateCompanyProfile,
+) -> Result<CompanyProfile, String> {
     sqlx::query(
         "UPDATE company_profile SET 
             company_name = ?,
@@ -159,6 +290,10 @@ pub async fn update_company_profile(
             bank_branch = ?,
             terms_and_conditions = ?,
             base_currency = ?,
+            financial_year_start = COALESCE(?, financial_year_start),
+            prevent_negative_stock = COALESCE(?, prevent_negative_stock),
+            round_invoice_totals = COALESCE(?, round_invoice_totals),
+            upi_id = COALESCE(?, upi_id),
             updated_at = CURRENT_TIMESTAMP
         WHERE id = 1",
     )
@@ -184,9 +319,76 @@ pub async fn update_company_profile(
     .bind(&profile.bank_branch)
     .bind(&profile.terms_and_conditions)
     .bind(&profile.base_currency)
-    .execute(&pool)
+    .bind(&profile.financial_year_start)
+    .bind(profile.prevent_negative_stock)
+    .bind(profile.round_invoice_totals)
+    .bind(profile.upi_id.as_deref().map(str::trim))
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
-    get_company_profile_with_pool(&pool).await
+    get_company_profile_with_pool(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn states_are_listed_for_seeded_countries_only() {
+        let pool = test_pool().await;
+
+        let india = get_states_with_pool(&pool, " in ").await.unwrap();
+        assert!(india
+            .iter()
+            .any(|state| state.name == "Kerala" && state.code.as_deref() == Some("32")));
+
+        let unknown = get_states_with_pool(&pool, "ZZ").await.unwrap();
+        assert!(unknown.is_empty());
+    }
+
+    #[tokio::test]
+    async fn saving_the_profile_without_a_year_start_keeps_the_existing_one() {
+        let pool = test_pool().await;
+        get_company_profile_with_pool(&pool).await.unwrap();
+
+        let profile = |financial_year_start: Option<&str>| -> UpdateCompanyProfile {
+            serde_json::from_value(serde_json::json!({
+                "company_name": "Kola Traders",
+                "financial_year_start": financial_year_start,
+            }))
+            .unwrap()
+        };
+        update_company_profile_with_pool(&pool, profile(Some("2024-07-01")))
+            .await
+            .unwrap();
+        let saved = update_company_profile_with_pool(&pool, profile(None))
+            .await
+            .unwrap();
+
+        assert_eq!(saved.company_name, "Kola Traders");
+        assert_eq!(saved.financial_year_start.as_deref(), Some("2024-07-01"));
+        assert_eq!(financial_year_start_month(&pool).await.unwrap(), 7);
+
+        let account = serde_json::from_value(serde_json::json!({
+            "account_code": "T-FY1",
+            "account_name": "Fixture Deposit",
+            "account_type": "Asset",
+            "account_group": "Current Assets",
+            "opening_balance": 250.0,
+            "opening_balance_type": "Dr",
+        }))
+        .unwrap();
+        crate::commands::accounts::create_chart_of_account_with_pool(&pool, account, None)
+            .await
+            .unwrap();
+        let voucher_date: String = sqlx::query_scalar(
+            "SELECT voucher_date FROM vouchers WHERE voucher_type = 'opening_balance'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(voucher_date, "2024-07-01");
+    }
 }
@@ -1,11 +1,20 @@
 use crate::company_db::DbRegistry;
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
-use crate::voucher_seq::get_next_voucher_number;
+use super::approvals::{
+    delete_held_postings, ensure_voucher_editable, hold_voucher_postings, set_voucher_draft_state,
+};
+use super::audit::record_voucher_audit;
+use super::auth::SessionStore;
+use super::settings::{resolve_narration, validate_voucher_date};
+use super::system_accounts::{find_system_account, resolve_system_account};
+use super::year_end::{ensure_period_open, ensure_voucher_unlocked};
+use crate::voucher_seq::{get_next_voucher_number, get_next_voucher_number_in_tx};
 
 // ============= PAYMENT COMMANDS =============
 
@@ -62,6 +71,7 @@ pub struct PendingInvoice {
 #[derive(Deserialize)]
 pub struct CreatePaymentItem {
     pub description: String,
+    /// Ledger account for this line; required (lines are no longer matched by name)
     pub account_id: Option<String>,
     pub amount: f64,
     pub tax_rate: f64,
@@ -79,18 +89,36 @@ pub struct CreatePayment {
     pub narration: Option<String>,
     pub items: Vec<CreatePaymentItem>,
     pub user_id: Option<String>,
+    pub allow_future: Option<bool>,
+    /// Saves as a draft: no ledger or allocation effect until approved
+    pub as_draft: Option<bool>,
 }
 
 #[tauri::command]
 pub async fn create_payment(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     payment: CreatePayment,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    let branch_id = session_store.active_branch_id();
+    create_payment_with_pool(&pool, payment, actor.as_deref(), branch_id.as_deref()).await
+}
+
+pub(crate) async fn create_payment_with_pool(
+    pool: &SqlitePool,
+    payment: CreatePayment,
+    actor: Option<&str>,
+    branch_id: Option<&str>,
+) -> Result<String, String> {
+    validate_voucher_date(pool, &payment.voucher_date, payment.allow_future).await?;
+    let as_draft = payment.as_draft.unwrap_or(false);
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Generate voucher number
-    let voucher_no = get_next_voucher_number(&pool, "payment").await?;
+    let voucher_no =
+        get_next_voucher_number(pool, "payment", &payment.voucher_date, branch_id).await?;
 
     // Calculate totals
     let mut total_amount = 0.0;
@@ -103,11 +131,20 @@ pub async fn create_payment(
 
     let grand_total = total_amount + total_tax;
     let voucher_id = Uuid::now_v7().to_string();
+    let party_id = payment.items.iter().find_map(|item| item.account_id.as_deref());
+    let narration = resolve_narration(
+        &mut tx,
+        payment.narration.as_ref(),
+        "payment",
+        party_id,
+        &voucher_no,
+    )
+    .await?;
 
     // Create voucher
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, total_amount, grand_total, metadata, narration, status, account_id, created_by)
-         VALUES (?, ?, 'payment', ?, ?, 'account', ?, ?, ?, ?, ?, 'posted', ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, total_amount, grand_total, metadata, narration, status, account_id, created_by, branch_id)
+         VALUES (?, ?, 'payment', ?, ?, 'account', ?, ?, ?, ?, ?, 'posted', ?, ?, ?)"
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
@@ -117,9 +154,10 @@ pub async fn create_payment(
     .bind(total_amount)
     .bind(grand_total)
     .bind(&payment.payment_method)
-    .bind(&payment.narration)
+    .bind(&narration)
     .bind(&payment.account_id)
     .bind(&payment.user_id)
+    .bind(branch_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -159,7 +197,7 @@ pub async fn create_payment(
         .await
         .map_err(|e| e.to_string())?;
 
-        if update_cost_enabled {
+        if update_cost_enabled && !as_draft {
             if let Some(prod_id) = &item.product_id {
                 if !prod_id.trim().is_empty() {
                     sqlx::query(
@@ -191,117 +229,36 @@ pub async fn create_payment(
             .await
             .map_err(|e| e.to_string())?;
 
-                // Update invoice status
-                let total_allocated: f64 = sqlx::query_scalar(
-                    "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let invoice_total: f64 = sqlx::query_scalar(
-                    "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-                     FROM vouchers v
-                     LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-                     WHERE v.id = ?
-                     GROUP BY v.id",
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let status = if (total_allocated - invoice_total).abs() < 0.01 {
-                    "paid"
-                } else if total_allocated > 0.0 {
-                    "partially_paid"
-                } else {
-                    "unpaid"
-                };
-
-                sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-                    .bind(status)
-                    .bind(&alloc.invoice_id)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| e.to_string())?;
+                recompute_invoice_status(&mut tx, &alloc.invoice_id).await?;
             }
         }
     }
 
-    // Create journal entries
-    let je_id_1 = Uuid::now_v7().to_string();
-
-    // Credit: Cash/Bank Account (the account user selected to pay from)
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-         VALUES (?, ?, ?, 0, ?, 0, 'Payment made')",
+    // Cr cash/bank, Dr each payee ledger and input tax
+    let lines: Vec<_> = payment
+        .items
+        .iter()
+        .map(|item| (&item.account_id, item.description.as_str(), item.amount))
+        .collect();
+    let journal_lines = cash_voucher_journal_lines(
+        &mut tx,
+        false,
+        &payment.account_id,
+        "Payment made",
+        &lines,
+        grand_total,
+        total_tax,
     )
-    .bind(&je_id_1)
-    .bind(&voucher_id)
-    .bind(&payment.account_id)
-    .bind(grand_total)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    // Debit: Each Payee/Ledger Account from items
-    for item in &payment.items {
-        // Look up the account
-        let payee_account: Option<String> = if let Some(acc_id) = &item.account_id {
-            Some(acc_id.clone())
-        } else {
-            sqlx::query_scalar(
-                "SELECT id FROM chart_of_accounts WHERE account_name = ? AND is_active = 1",
-            )
-            .bind(&item.description)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?
-        };
-
-        if let Some(payee_acc) = payee_account {
-            let je_id_2 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-                 VALUES (?, ?, ?, ?, 0, 0, ?)",
-            )
-            .bind(&je_id_2)
-            .bind(&voucher_id)
-            .bind(payee_acc)
-            .bind(item.amount)
-            .bind(format!("Payment to {}", item.description))
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-        }
-    }
+    .await?;
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
 
-    // Debit: Tax Account if applicable
-    if total_tax > 0.0 {
-        let tax_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1005'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+    ensure_journal_balanced(&mut tx, &voucher_id).await?;
 
-        if let Some(tax_acc) = tax_account {
-            let je_id_3 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-                 VALUES (?, ?, ?, ?, 0, 0, 'Tax on payment')",
-            )
-            .bind(&je_id_3)
-            .bind(&voucher_id)
-            .bind(tax_acc)
-            .bind(total_tax)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-        }
+    if as_draft {
+        hold_voucher_postings(&mut tx, &voucher_id).await?;
     }
 
+    record_voucher_audit(&mut *tx, actor, &voucher_id, "create").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
 
     Ok(voucher_id)
@@ -464,12 +421,21 @@ pub(crate) async fn get_payment_items_with_pool(
 #[tauri::command]
 pub async fn delete_payment(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
-    // Reverse product cost updates if setting is enabled
+    // Reverse product cost updates if setting is enabled (drafts never applied them)
+    let is_posted: bool = sqlx::query_scalar(
+        "SELECT COALESCE(status, 'posted') = 'posted' FROM vouchers WHERE id = ?",
+    )
+    .bind(&id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
     let update_cost_enabled: bool = sqlx::query_scalar::<_, String>(
         "SELECT setting_value FROM app_settings WHERE setting_key = 'update_payment_to_product_cost'",
     )
@@ -480,7 +446,7 @@ pub async fn delete_payment(
     .map(|v| v == "true" || v == "\"true\"")
     .unwrap_or(false);
 
-    if update_cost_enabled {
+    if update_cost_enabled && is_posted {
         let items_to_reverse: Vec<(Option<String>, f64)> = sqlx::query_as(
             "SELECT product_id, amount FROM voucher_items WHERE voucher_id = ? AND product_id IS NOT NULL AND product_id != ''"
         )
@@ -512,12 +478,10 @@ pub async fn delete_payment(
     .await
     .map_err(|e| e.to_string())?;
 
-    // Delete allocations
-    sqlx::query("DELETE FROM payment_allocations WHERE payment_voucher_id = ?")
-        .bind(&id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Move allocations aside so a restore can bring them back
+    archive_voucher_allocations(&mut tx, &id).await?;
+
+    delete_held_postings(&mut tx, &id).await?;
 
     // Soft delete voucher
     sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND voucher_type = 'payment'")
@@ -527,55 +491,26 @@ pub async fn delete_payment(
         .map_err(|e| e.to_string())?;
 
     // Recalculate status for affected invoices
-    for inv_id in affected_invoices {
-        let total_allocated: f64 = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let invoice_total: f64 = sqlx::query_scalar(
-            "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-             FROM vouchers v
-             LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-             WHERE v.id = ?
-             GROUP BY v.id",
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let status = if (total_allocated - invoice_total).abs() < 0.01 {
-            "paid"
-        } else if total_allocated > 0.0 {
-            "partially_paid"
-        } else {
-            "unpaid"
-        };
-
-        sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-            .bind(status)
-            .bind(&inv_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
+    refresh_invoice_payment_statuses(&mut tx, &affected_invoices).await?;
 
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "delete").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
 #[tauri::command]
 pub async fn update_payment(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     payment: CreatePayment,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    let was_draft = ensure_voucher_editable(&pool, &id).await?;
+    let as_draft = payment.as_draft.unwrap_or(false);
+    validate_voucher_date(&pool, &payment.voucher_date, payment.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // 1. Calculate totals
@@ -631,42 +566,7 @@ pub async fn update_payment(
         .map_err(|e| e.to_string())?;
 
     // Recalculate status for affected invoices (from old allocations)
-    for inv_id in allocated_invoices {
-        let total_allocated: f64 = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let invoice_total: f64 = sqlx::query_scalar(
-            "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-             FROM vouchers v
-             LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-             WHERE v.id = ?
-             GROUP BY v.id",
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let status = if (total_allocated - invoice_total).abs() < 0.01 {
-            "paid"
-        } else if total_allocated > 0.0 {
-            "partially_paid"
-        } else {
-            "unpaid"
-        };
-
-        sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-            .bind(status)
-            .bind(&inv_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
+    refresh_invoice_payment_statuses(&mut tx, &allocated_invoices).await?;
 
     // Reverse old product cost updates if setting is enabled
     let update_cost_enabled: bool = sqlx::query_scalar::<_, String>(
@@ -679,7 +579,7 @@ pub async fn update_payment(
     .map(|v| v == "true" || v == "\"true\"")
     .unwrap_or(false);
 
-    if update_cost_enabled {
+    if update_cost_enabled && !was_draft {
         let items_to_reverse: Vec<(Option<String>, f64)> = sqlx::query_as(
             "SELECT product_id, amount FROM voucher_items WHERE voucher_id = ? AND product_id IS NOT NULL AND product_id != ''"
         )
@@ -740,7 +640,7 @@ pub async fn update_payment(
         .await
         .map_err(|e| e.to_string())?;
 
-        if update_cost_enabled {
+        if update_cost_enabled && !as_draft {
             if let Some(prod_id) = &item.product_id {
                 if !prod_id.trim().is_empty() {
                     sqlx::query(
@@ -772,117 +672,36 @@ pub async fn update_payment(
             .await
             .map_err(|e| e.to_string())?;
 
-                // Update invoice status
-                let total_allocated: f64 = sqlx::query_scalar(
-                    "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let invoice_total: f64 = sqlx::query_scalar(
-                    "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-                     FROM vouchers v
-                     LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-                     WHERE v.id = ?
-                     GROUP BY v.id",
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let status = if (total_allocated - invoice_total).abs() < 0.01 {
-                    "paid"
-                } else if total_allocated > 0.0 {
-                    "partially_paid"
-                } else {
-                    "unpaid"
-                };
-
-                sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-                    .bind(status)
-                    .bind(&alloc.invoice_id)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| e.to_string())?;
+                recompute_invoice_status(&mut tx, &alloc.invoice_id).await?;
             }
         }
     }
 
-    // 6. Create New Journal Entries
-    // Credit: Cash/Bank Account
-    let je_id_1 = Uuid::now_v7().to_string();
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-         VALUES (?, ?, ?, 0, ?, 0, 'Payment updated')",
+    // 6. Create New Journal Entries: Cr cash/bank, Dr each payee ledger and input tax
+    let lines: Vec<_> = payment
+        .items
+        .iter()
+        .map(|item| (&item.account_id, item.description.as_str(), item.amount))
+        .collect();
+    let journal_lines = cash_voucher_journal_lines(
+        &mut tx,
+        false,
+        &payment.account_id,
+        "Payment updated",
+        &lines,
+        grand_total,
+        total_tax,
     )
-    .bind(&je_id_1)
-    .bind(&id)
-    .bind(&payment.account_id)
-    .bind(grand_total)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    // Debit: Each Payee/Ledger Account from items
-    for item in &payment.items {
-        let payee_account: Option<String> = if let Some(acc_id) = &item.account_id {
-            Some(acc_id.clone())
-        } else {
-            sqlx::query_scalar(
-                "SELECT id FROM chart_of_accounts WHERE account_name = ? AND is_active = 1",
-            )
-            .bind(&item.description)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?
-        };
-
-        if let Some(payee_acc) = payee_account {
-            let je_id_2 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-                 VALUES (?, ?, ?, ?, 0, 0, ?)",
-            )
-            .bind(&je_id_2)
-            .bind(&id)
-            .bind(payee_acc)
-            .bind(item.amount)
-            .bind(format!("Payment to {}", item.description))
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-        }
-    }
+    .await?;
+    insert_journal_lines(&mut tx, &id, &journal_lines).await?;
 
-    // Debit: Tax Account if applicable
-    if total_tax > 0.0 {
-        let tax_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1005'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+    ensure_journal_balanced(&mut tx, &id).await?;
 
-        if let Some(tax_acc) = tax_account {
-            let je_id_3 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-                 VALUES (?, ?, ?, ?, 0, 0, 'Tax on payment')",
-            )
-            .bind(&je_id_3)
-            .bind(&id)
-            .bind(tax_acc)
-            .bind(total_tax)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-        }
-    }
+    set_voucher_draft_state(&mut tx, &id, as_draft).await?;
 
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "update").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
@@ -923,6 +742,7 @@ pub struct ReceiptItem {
 #[derive(Deserialize)]
 pub struct CreateReceiptItem {
     pub description: String,
+    /// Ledger account for this line; required (lines are no longer matched by name)
     pub account_id: Option<String>,
     pub amount: f64,
     pub tax_rate: f64,
@@ -939,18 +759,35 @@ pub struct CreateReceipt {
     pub narration: Option<String>,
     pub items: Vec<CreateReceiptItem>,
     pub user_id: Option<String>,
+    pub allow_future: Option<bool>,
+    /// Saves as a draft: no ledger or allocation effect until approved
+    pub as_draft: Option<bool>,
 }
 
 #[tauri::command]
 pub async fn create_receipt(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     receipt: CreateReceipt,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    let branch_id = session_store.active_branch_id();
+    create_receipt_with_pool(&pool, receipt, actor.as_deref(), branch_id.as_deref()).await
+}
+
+pub(crate) async fn create_receipt_with_pool(
+    pool: &SqlitePool,
+    receipt: CreateReceipt,
+    actor: Option<&str>,
+    branch_id: Option<&str>,
+) -> Result<String, String> {
+    validate_voucher_date(pool, &receipt.voucher_date, receipt.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Generate voucher number
-    let voucher_no = get_next_voucher_number(&pool, "receipt").await?;
+    let voucher_no =
+        get_next_voucher_number(pool, "receipt", &receipt.voucher_date, branch_id).await?;
 
     // Calculate totals
     let mut total_amount = 0.0;
@@ -963,11 +800,20 @@ pub async fn create_receipt(
 
     let grand_total = total_amount + total_tax;
     let voucher_id = Uuid::now_v7().to_string();
+    let party_id = receipt.items.iter().find_map(|item| item.account_id.as_deref());
+    let narration = resolve_narration(
+        &mut tx,
+        receipt.narration.as_ref(),
+        "receipt",
+        party_id,
+        &voucher_no,
+    )
+    .await?;
 
     // Create voucher
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, total_amount, grand_total, metadata, narration, status, account_id, created_by)
-         VALUES (?, ?, 'receipt', ?, ?, 'account', ?, ?, ?, ?, ?, 'posted', ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, total_amount, grand_total, metadata, narration, status, account_id, created_by, branch_id)
+         VALUES (?, ?, 'receipt', ?, ?, 'account', ?, ?, ?, ?, ?, 'posted', ?, ?, ?)"
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
@@ -977,9 +823,10 @@ pub async fn create_receipt(
     .bind(total_amount)
     .bind(grand_total)
     .bind(&receipt.receipt_method)
-    .bind(&receipt.narration)
+    .bind(&narration)
     .bind(&receipt.account_id)
     .bind(&receipt.user_id)
+    .bind(branch_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -1025,117 +872,36 @@ pub async fn create_receipt(
                 .await
                 .map_err(|e| e.to_string())?;
 
-                // Update invoice status
-                let total_allocated: f64 = sqlx::query_scalar(
-                    "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let invoice_total: f64 = sqlx::query_scalar(
-                    "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-                     FROM vouchers v
-                     LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-                     WHERE v.id = ?
-                     GROUP BY v.id",
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let status = if (total_allocated - invoice_total).abs() < 0.01 {
-                    "paid"
-                } else if total_allocated > 0.0 {
-                    "partially_paid"
-                } else {
-                    "unpaid"
-                };
-
-                sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-                    .bind(status)
-                    .bind(&alloc.invoice_id)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| e.to_string())?;
+                recompute_invoice_status(&mut tx, &alloc.invoice_id).await?;
             }
         }
     }
 
-    // Create journal entries
-    let je_id_1 = Uuid::now_v7().to_string();
-
-    // Debit: Cash/Bank Account (the account user selected to receive payment)
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, ?, 0, 'Receipt received')",
+    // Dr cash/bank, Cr each payer ledger and input tax
+    let lines: Vec<_> = receipt
+        .items
+        .iter()
+        .map(|item| (&item.account_id, item.description.as_str(), item.amount))
+        .collect();
+    let journal_lines = cash_voucher_journal_lines(
+        &mut tx,
+        true,
+        &receipt.account_id,
+        "Receipt received",
+        &lines,
+        grand_total,
+        total_tax,
     )
-    .bind(&je_id_1)
-    .bind(&voucher_id)
-    .bind(&receipt.account_id)
-    .bind(grand_total)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    // Credit: Each Payer/Ledger Account from items
-    for item in &receipt.items {
-        // Look up the account
-        let payer_account: Option<String> = if let Some(acc_id) = &item.account_id {
-            Some(acc_id.clone())
-        } else {
-            sqlx::query_scalar(
-                "SELECT id FROM chart_of_accounts WHERE account_name = ? AND is_active = 1",
-            )
-            .bind(&item.description)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?
-        };
-
-        if let Some(payer_acc) = payer_account {
-            let je_id_2 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, 0, ?, ?)",
-            )
-            .bind(&je_id_2)
-            .bind(&voucher_id)
-            .bind(payer_acc)
-            .bind(item.amount)
-            .bind(format!("Receipt from {}", item.description))
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-        }
-    }
+    .await?;
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
 
-    // Credit: Tax Account if applicable
-    if total_tax > 0.0 {
-        let tax_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1005'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+    ensure_journal_balanced(&mut tx, &voucher_id).await?;
 
-        if let Some(tax_acc) = tax_account {
-            let je_id_3 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, 0, ?, 'Tax on receipt')",
-            )
-            .bind(&je_id_3)
-            .bind(&voucher_id)
-            .bind(tax_acc)
-            .bind(total_tax)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-        }
+    if receipt.as_draft.unwrap_or(false) {
+        hold_voucher_postings(&mut tx, &voucher_id).await?;
     }
 
+    record_voucher_audit(&mut *tx, actor, &voucher_id, "create").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
 
     Ok(voucher_id)
@@ -1297,84 +1063,62 @@ pub(crate) async fn get_receipt_items_with_pool(
 #[tauri::command]
 pub async fn delete_receipt(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    delete_receipt_with_pool(&pool, &id, actor.as_deref()).await
+}
+
+async fn delete_receipt_with_pool(
+    pool: &SqlitePool,
+    id: &str,
+    actor: Option<&str>,
+) -> Result<(), String> {
+    ensure_voucher_unlocked(pool, id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Get affected invoices before deleting allocations
     let affected_invoices: Vec<String> = sqlx::query_scalar(
         "SELECT invoice_voucher_id FROM payment_allocations WHERE payment_voucher_id = ?",
     )
-    .bind(&id)
+    .bind(id)
     .fetch_all(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
 
-    // Delete allocations
-    sqlx::query("DELETE FROM payment_allocations WHERE payment_voucher_id = ?")
-        .bind(&id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Move allocations aside so a restore can bring them back
+    archive_voucher_allocations(&mut tx, id).await?;
+
+    delete_held_postings(&mut tx, id).await?;
 
     // Soft delete voucher
     sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND voucher_type = 'receipt'")
-        .bind(&id)
+        .bind(id)
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
 
     // Recalculate status for affected invoices
-    for inv_id in affected_invoices {
-        let total_allocated: f64 = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let invoice_total: f64 = sqlx::query_scalar(
-            "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-             FROM vouchers v
-             LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-             WHERE v.id = ?
-             GROUP BY v.id",
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let status = if (total_allocated - invoice_total).abs() < 0.01 {
-            "paid"
-        } else if total_allocated > 0.0 {
-            "partially_paid"
-        } else {
-            "unpaid"
-        };
-
-        sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-            .bind(status)
-            .bind(&inv_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
+    refresh_invoice_payment_statuses(&mut tx, &affected_invoices).await?;
 
+    record_voucher_audit(&mut *tx, actor, id, "delete").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
 #[tauri::command]
 pub async fn update_receipt(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     receipt: CreateReceipt,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    ensure_voucher_editable(&pool, &id).await?;
+    validate_voucher_date(&pool, &receipt.voucher_date, receipt.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // 1. Calculate totals
@@ -1429,42 +1173,7 @@ pub async fn update_receipt(
         .map_err(|e| e.to_string())?;
 
     // Recalculate status for effected invoices
-    for inv_id in allocated_invoices {
-        let total_allocated: f64 = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let invoice_total: f64 = sqlx::query_scalar(
-            "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-             FROM vouchers v
-             LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-             WHERE v.id = ?
-             GROUP BY v.id",
-        )
-        .bind(&inv_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let status = if (total_allocated - invoice_total).abs() < 0.01 {
-            "paid"
-        } else if total_allocated > 0.0 {
-            "partially_paid"
-        } else {
-            "unpaid"
-        };
-
-        sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-            .bind(status)
-            .bind(&inv_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
+    refresh_invoice_payment_statuses(&mut tx, &allocated_invoices).await?;
 
     // 4. Delete existing Items and Journal Entries
     sqlx::query("DELETE FROM voucher_items WHERE voucher_id = ?")
@@ -1520,117 +1229,492 @@ pub async fn update_receipt(
             .await
             .map_err(|e| e.to_string())?;
 
-                // Update invoice status
-                let total_allocated: f64 = sqlx::query_scalar(
-                    "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let invoice_total: f64 = sqlx::query_scalar(
-                    "SELECT v.total_amount + COALESCE(SUM(vi.tax_amount), 0.0)
-                     FROM vouchers v
-                     LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
-                     WHERE v.id = ?
-                     GROUP BY v.id",
-                )
-                .bind(&alloc.invoice_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-                let status = if (total_allocated - invoice_total).abs() < 0.01 {
-                    "paid"
-                } else if total_allocated > 0.0 {
-                    "partially_paid"
-                } else {
-                    "unpaid"
-                };
-
-                sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-                    .bind(status)
-                    .bind(&alloc.invoice_id)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| e.to_string())?;
+                recompute_invoice_status(&mut tx, &alloc.invoice_id).await?;
             }
         }
     }
 
-    // 6. Create New Journal Entries
-    // Debit: Cash/Bank Account
-    let je_id_1 = Uuid::now_v7().to_string();
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, ?, 0, 'Receipt updated')",
+    // 6. Create New Journal Entries: Dr cash/bank, Cr each payer ledger and input tax
+    let lines: Vec<_> = receipt
+        .items
+        .iter()
+        .map(|item| (&item.account_id, item.description.as_str(), item.amount))
+        .collect();
+    let journal_lines = cash_voucher_journal_lines(
+        &mut tx,
+        true,
+        &receipt.account_id,
+        "Receipt updated",
+        &lines,
+        grand_total,
+        total_tax,
     )
-    .bind(&je_id_1)
-    .bind(&id)
-    .bind(&receipt.account_id)
-    .bind(grand_total)
-    .execute(&mut *tx)
+    .await?;
+    insert_journal_lines(&mut tx, &id, &journal_lines).await?;
+
+    ensure_journal_balanced(&mut tx, &id).await?;
+
+    set_voucher_draft_state(&mut tx, &id, receipt.as_draft.unwrap_or(false)).await?;
+
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "update").await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ============= DELETED PAYMENTS & RECEIPTS =============
+
+/// Moves a voucher's allocations into `deleted_payment_allocations`.
+pub(crate) async fn archive_voucher_allocations(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO deleted_payment_allocations
+            (id, payment_voucher_id, invoice_voucher_id, allocated_amount, allocation_date,
+             remarks, party_id, party_type, created_at)
+         SELECT id, payment_voucher_id, invoice_voucher_id, allocated_amount, allocation_date,
+                remarks, party_id, party_type, created_at
+         FROM payment_allocations WHERE payment_voucher_id = ?",
+    )
+    .bind(voucher_id)
+    .execute(&mut **tx)
     .await
     .map_err(|e| e.to_string())?;
 
-    // Credit: Each Payer/Ledger Account from items
-    for item in &receipt.items {
-        let payer_account: Option<String> = if let Some(acc_id) = &item.account_id {
-            Some(acc_id.clone())
-        } else {
-            sqlx::query_scalar(
-                "SELECT id FROM chart_of_accounts WHERE account_name = ? AND is_active = 1",
-            )
-            .bind(&item.description)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?
-        };
+    sqlx::query("DELETE FROM payment_allocations WHERE payment_voucher_id = ?")
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
 
-        if let Some(payer_acc) = payer_account {
-            let je_id_2 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, 0, ?, ?)",
-            )
-            .bind(&je_id_2)
-            .bind(&id)
-            .bind(payer_acc)
-            .bind(item.amount)
-            .bind(format!("Receipt from {}", item.description))
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves archived allocations back into `payment_allocations`, skipping any whose
+/// invoice has since been deleted. Returns the invoices they point at.
+pub(crate) async fn unarchive_voucher_allocations(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<Vec<String>, String> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO payment_allocations
+            (id, payment_voucher_id, invoice_voucher_id, allocated_amount, allocation_date,
+             remarks, party_id, party_type, created_at)
+         SELECT d.id, d.payment_voucher_id, d.invoice_voucher_id, d.allocated_amount,
+                d.allocation_date, d.remarks, d.party_id, d.party_type, d.created_at
+         FROM deleted_payment_allocations d
+         JOIN vouchers inv ON inv.id = d.invoice_voucher_id AND inv.deleted_at IS NULL
+         WHERE d.payment_voucher_id = ?",
+    )
+    .bind(voucher_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let invoices: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT invoice_voucher_id FROM payment_allocations WHERE payment_voucher_id = ?",
+    )
+    .bind(voucher_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM deleted_payment_allocations WHERE payment_voucher_id = ?")
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(invoices)
+}
+
+/// Journal lines for a payment or receipt: the cash/bank account for
+/// `grand_total` against each line's ledger, plus input tax when the tax account
+/// exists. Payments credit cash; receipts debit it.
+async fn cash_voucher_journal_lines(
+    tx: &mut Transaction<'_, Sqlite>,
+    is_receipt: bool,
+    cash_account_id: &str,
+    cash_narration: &str,
+    lines: &[(&Option<String>, &str, f64)],
+    grand_total: f64,
+    total_tax: f64,
+) -> Result<Vec<JournalLine>, String> {
+    let (line_prefix, tax_narration) = if is_receipt {
+        ("Receipt from", "Tax on receipt")
+    } else {
+        ("Payment to", "Tax on payment")
+    };
+    // The cash side is debited on receipts; the ledger side takes the opposite
+    let entry = |account_id: String, amount: f64, cash_side: bool, narration: String| {
+        let debit_side = cash_side == is_receipt;
+        JournalLine {
+            account_id,
+            debit: if debit_side { amount } else { 0.0 },
+            credit: if debit_side { 0.0 } else { amount },
+            is_manual: false,
+            narration: Some(narration),
         }
-    }
+    };
 
-    // Credit: Tax Account if applicable
+    let mut journal_lines = vec![entry(
+        cash_account_id.to_string(),
+        grand_total,
+        true,
+        cash_narration.to_string(),
+    )];
+    for (account_id, description, amount) in lines {
+        let ledger = require_item_account(tx, account_id, description).await?;
+        journal_lines.push(entry(
+            ledger,
+            *amount,
+            false,
+            format!("{} {}", line_prefix, description),
+        ));
+    }
     if total_tax > 0.0 {
-        let tax_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1005'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+        if let Some(tax_acc) = find_system_account(&mut **tx, "input_tax").await? {
+            journal_lines.push(entry(tax_acc, total_tax, false, tax_narration.to_string()));
+        }
+    }
+    Ok(journal_lines)
+}
 
-        if let Some(tax_acc) = tax_account {
-            let je_id_3 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, 0, ?, 'Tax on receipt')",
-            )
-            .bind(&je_id_3)
-            .bind(&id)
-            .bind(tax_acc)
-            .bind(total_tax)
-            .execute(&mut *tx)
+/// Resolves the ledger account of a payment/receipt line. `account_id` is required;
+/// looking accounts up by name was ambiguous when two accounts shared a name and
+/// silently dropped the line when nothing matched, leaving the voucher unbalanced.
+async fn require_item_account(
+    tx: &mut Transaction<'_, Sqlite>,
+    account_id: &Option<String>,
+    description: &str,
+) -> Result<String, String> {
+    let account_id = account_id
+        .as_deref()
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| format!("Line '{}' has no account selected.", description))?;
+
+    let exists: Option<String> =
+        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE id = ? AND deleted_at IS NULL")
+            .bind(account_id)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(|e| e.to_string())?;
+
+    exists.ok_or_else(|| format!("Account for line '{}' was not found.", description))
+}
+
+/// Fails when the voucher's journal lines don't net to zero, so a line that could
+/// not be posted (e.g. a missing tax account) rolls the whole voucher back instead
+/// of leaving the trial balance out.
+async fn ensure_journal_balanced(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<(), String> {
+    let (debit, credit): (f64, f64) = sqlx::query_as(
+        "SELECT CAST(COALESCE(SUM(debit), 0) AS REAL), CAST(COALESCE(SUM(credit), 0) AS REAL)
+         FROM journal_entries WHERE voucher_id = ?",
+    )
+    .bind(voucher_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if (debit - credit).abs() > 0.01 {
+        return Err(format!(
+            "Voucher is unbalanced: debits {:.2} vs credits {:.2}. Check that every line and the tax account resolve to a ledger account.",
+            debit, credit
+        ));
+    }
+    Ok(())
+}
+
+/// SQL expression (over `vouchers v`) for the invoice total allocations are compared
+/// against. Uses the stored (base-currency) grand total, the same figure the open
+/// invoice reports read; older rows without one fall back to subtotal less discount,
+/// plus tax (summed from the items when not stored) and round-off.
+pub(crate) const INVOICE_TOTAL_SQL: &str = "CAST(ROUND(COALESCE(
+    v.base_grand_total,
+    v.grand_total,
+    COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0)
+    + COALESCE(v.tax_amount, (SELECT SUM(vi.tax_amount) FROM voucher_items vi WHERE vi.voucher_id = v.id), 0.0)
+    + COALESCE(v.round_off_amount, 0.0)
+), 2) AS REAL)";
+
+/// Sum an invoice's allocations are compared against when deriving payment_status.
+pub(crate) async fn invoice_total_for_status(
+    tx: &mut Transaction<'_, Sqlite>,
+    invoice_id: &str,
+) -> Result<f64, String> {
+    sqlx::query_scalar(&format!(
+        "SELECT {} FROM vouchers v WHERE v.id = ?",
+        INVOICE_TOTAL_SQL
+    ))
+    .bind(invoice_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Sets an invoice's payment_status from the allocations currently against it.
+pub(crate) async fn recompute_invoice_status(
+    tx: &mut Transaction<'_, Sqlite>,
+    invoice_id: &str,
+) -> Result<(), String> {
+    let total_allocated: f64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
+    )
+    .bind(invoice_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let invoice_total = invoice_total_for_status(tx, invoice_id).await?;
+
+    let status = if (total_allocated - invoice_total).abs() < 0.01 {
+        "paid"
+    } else if total_allocated > 0.0 {
+        "partially_paid"
+    } else {
+        "unpaid"
+    };
+
+    sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
+        .bind(status)
+        .bind(invoice_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Recomputes payment_status for each invoice from its remaining allocations.
+pub(crate) async fn refresh_invoice_payment_statuses(
+    tx: &mut Transaction<'_, Sqlite>,
+    invoice_ids: &[String],
+) -> Result<(), String> {
+    for inv_id in invoice_ids {
+        recompute_invoice_status(tx, inv_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Adds a payment's product lines to product cost when the
+/// `update_payment_to_product_cost` setting is on.
+pub(crate) async fn apply_payment_product_costs(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+) -> Result<(), String> {
+    if !payment_cost_update_enabled(tx).await {
+        return Ok(());
+    }
+    sqlx::query(
+        "UPDATE products SET cost = COALESCE(cost, 0) + (
+            SELECT SUM(vi.amount) FROM voucher_items vi
+            WHERE vi.voucher_id = ? AND vi.product_id = products.id
+         )
+         WHERE id IN (
+            SELECT product_id FROM voucher_items
+            WHERE voucher_id = ? AND product_id IS NOT NULL AND product_id != ''
+         )",
+    )
+    .bind(voucher_id)
+    .bind(voucher_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn payment_cost_update_enabled(tx: &mut Transaction<'_, Sqlite>) -> bool {
+    sqlx::query_scalar::<_, String>(
+        "SELECT setting_value FROM app_settings WHERE setting_key = 'update_payment_to_product_cost'",
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .ok()
+    .flatten()
+    .map(|v| v == "true" || v == "\"true\"")
+    .unwrap_or(false)
+}
+
+/// Clears deleted_at on a payment/receipt, brings back its allocations and
+/// recomputes the status of every invoice they settle.
+async fn restore_settlement_voucher(
+    pool: &SqlitePool,
+    id: &str,
+    voucher_type: &str,
+    actor: Option<&str>,
+) -> Result<(), String> {
+    let voucher: Option<(bool, String)> = sqlx::query_as(
+        "SELECT deleted_at IS NOT NULL, COALESCE(status, 'posted')
+         FROM vouchers WHERE id = ? AND voucher_type = ?",
+    )
+    .bind(id)
+    .bind(voucher_type)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let is_posted = match voucher {
+        None => return Err(format!("{} not found", voucher_type)),
+        Some((false, _)) => return Err(format!("This {} is not deleted", voucher_type)),
+        Some((true, status)) => status == "posted",
+    };
+    ensure_voucher_unlocked(pool, id).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query("UPDATE vouchers SET deleted_at = NULL WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Drafts never applied their effects, so there is nothing to bring back
+    if is_posted {
+        // Re-apply product cost updates reversed on delete
+        if voucher_type == "payment" {
+            apply_payment_product_costs(&mut tx, id).await?;
         }
+        let affected_invoices = unarchive_voucher_allocations(&mut tx, id).await?;
+        refresh_invoice_payment_statuses(&mut tx, &affected_invoices).await?;
     }
 
-    tx.commit().await.map_err(|e| e.to_string())?;
+    record_voucher_audit(&mut *tx, actor, id, "restore").await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_deleted_payments(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<PaymentVoucher>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, PaymentVoucher>(
+        "SELECT 
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            CASE 
+                WHEN v.created_from_invoice_id IS NOT NULL THEN COALESCE(v.account_id, je.account_id)
+                ELSE v.party_id
+            END as account_id,
+            CASE 
+                WHEN v.created_from_invoice_id IS NOT NULL THEN coa_payment.account_name
+                ELSE coa.account_name
+            END as account_name,
+            COALESCE(v.metadata, '') as payment_method,
+            v.reference as reference_number,
+            v.total_amount,
+            COALESCE(SUM(vi.tax_amount), 0.0) as tax_amount,
+            v.grand_total,
+            v.narration,
+            v.status,
+            v.created_at,
+            v.deleted_at,
+            v.created_from_invoice_id,
+            u.full_name as created_by_name
+        FROM vouchers v
+        LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
+        LEFT JOIN chart_of_accounts coa_payment ON coa_payment.id = (
+            COALESCE(
+                v.account_id,
+                (SELECT account_id FROM journal_entries 
+                WHERE voucher_id = v.id AND credit > 0 LIMIT 1)
+            )
+        )
+        LEFT JOIN (
+            SELECT voucher_id, account_id 
+            FROM journal_entries 
+            WHERE credit > 0
+        ) je ON v.id = je.voucher_id
+        LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
+        LEFT JOIN users u ON v.created_by = u.id
+        WHERE v.voucher_type = 'payment' AND v.deleted_at IS NOT NULL
+        GROUP BY v.id
+        ORDER BY v.deleted_at DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_payment(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    restore_settlement_voucher(&pool, &id, "payment", actor.as_deref()).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_deleted_receipts(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<ReceiptVoucher>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, ReceiptVoucher>(
+        "SELECT 
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            CASE 
+                WHEN v.created_from_invoice_id IS NOT NULL THEN COALESCE(v.account_id, je.account_id)
+                ELSE v.party_id
+            END as account_id,
+            CASE 
+                WHEN v.created_from_invoice_id IS NOT NULL THEN coa_payment.account_name
+                ELSE coa.account_name
+            END as account_name,
+            COALESCE(v.metadata, '') as receipt_method,
+            v.reference as reference_number,
+            v.total_amount,
+            COALESCE(SUM(vi.tax_amount), 0.0) as tax_amount,
+            v.grand_total,
+            v.narration,
+            v.status,
+            v.created_at,
+            v.deleted_at,
+            v.created_from_invoice_id,
+            u.full_name as created_by_name
+        FROM vouchers v
+        LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
+        LEFT JOIN chart_of_accounts coa_payment ON coa_payment.id = (
+            COALESCE(
+                v.account_id,
+                (SELECT account_id FROM journal_entries 
+                WHERE voucher_id = v.id AND debit > 0 LIMIT 1)
+            )
+        )
+        LEFT JOIN (
+            SELECT voucher_id, account_id 
+            FROM journal_entries 
+            WHERE debit > 0
+        ) je ON v.id = je.voucher_id
+        LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
+        LEFT JOIN users u ON v.created_by = u.id
+        WHERE v.voucher_type = 'receipt' AND v.deleted_at IS NOT NULL
+        GROUP BY v.id
+        ORDER BY v.deleted_at DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
 
+#[tauri::command]
+pub async fn restore_receipt(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    restore_settlement_voucher(&pool, &id, "receipt", actor.as_deref()).await?;
     Ok(())
 }
 
@@ -1661,7 +1745,7 @@ pub struct JournalEntryLine {
     pub narration: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct CreateJournalEntryLine {
     pub account_id: String,
     pub debit: f64,
@@ -1676,19 +1760,14 @@ pub struct CreateJournalEntry {
     pub narration: Option<String>,
     pub lines: Vec<CreateJournalEntryLine>,
     pub user_id: Option<String>,
+    pub allow_future: Option<bool>,
+    /// Saves as a draft: no ledger effect until approved
+    pub as_draft: Option<bool>,
 }
 
-#[tauri::command]
-pub async fn create_journal_entry(
-    registry: State<'_, Arc<DbRegistry>>,
-    entry: CreateJournalEntry,
-) -> Result<String, String> {
-    let pool = registry.active_pool().await?;
-    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
-
-    // Generate voucher number
-    let voucher_no = get_next_voucher_number(&pool, "journal").await?;
-
+/// Validates that a journal entry is balanced and every line is one-sided.
+/// Returns the total debit (used as the voucher total).
+pub(crate) fn validate_journal_entry(entry: &CreateJournalEntry) -> Result<f64, String> {
     // Calculate totals
     let total_debit: f64 = entry.lines.iter().map(|l| l.debit).sum();
     let total_credit: f64 = entry.lines.iter().map(|l| l.credit).sum();
@@ -1709,53 +1788,401 @@ pub async fn create_journal_entry(
         }
     }
 
+    Ok(total_debit)
+}
+
+/// A single journal line to be posted via `insert_journal_lines`.
+pub(crate) struct JournalLine {
+    pub account_id: String,
+    pub debit: f64,
+    pub credit: f64,
+    pub is_manual: bool,
+    pub narration: Option<String>,
+}
+
+/// SQLite's default limit on bound parameters per statement.
+const MAX_BIND_PARAMS: usize = 999;
+const JOURNAL_LINE_PARAMS: usize = 7;
+
+/// Inserts journal lines for a voucher using multi-row INSERTs, chunked so
+/// each statement stays under SQLite's bound-parameter limit.
+/// Returns the number of statements executed.
+pub(crate) async fn insert_journal_lines(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    voucher_id: &str,
+    lines: &[JournalLine],
+) -> Result<usize, String> {
+    let rows_per_chunk = MAX_BIND_PARAMS / JOURNAL_LINE_PARAMS;
+    let mut statements = 0;
+
+    for chunk in lines.chunks(rows_per_chunk) {
+        let placeholders = vec!["(?, ?, ?, ?, ?, ?, ?)"; chunk.len()].join(", ");
+        let sql = format!(
+            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
+             VALUES {}",
+            placeholders
+        );
+
+        let mut query = sqlx::query(&sql);
+        for line in chunk {
+            query = query
+                .bind(Uuid::now_v7().to_string())
+                .bind(voucher_id)
+                .bind(&line.account_id)
+                .bind(line.debit)
+                .bind(line.credit)
+                .bind(if line.is_manual { 1 } else { 0 })
+                .bind(&line.narration);
+        }
+        query.execute(&mut **tx).await.map_err(|e| e.to_string())?;
+        statements += 1;
+    }
+
+    Ok(statements)
+}
+
+fn manual_journal_lines(lines: &[CreateJournalEntryLine]) -> Vec<JournalLine> {
+    lines
+        .iter()
+        .map(|line| JournalLine {
+            account_id: line.account_id.clone(),
+            debit: line.debit,
+            credit: line.credit,
+            is_manual: true,
+            narration: line.narration.clone(),
+        })
+        .collect()
+}
+
+/// Pairs each opening balance line with its reversing line in the
+/// Opening Balance Adjustment account.
+fn opening_balance_journal_lines(
+    lines: &[OpeningBalanceLine],
+    ob_account_id: &str,
+) -> Vec<JournalLine> {
+    let mut journal_lines = Vec::with_capacity(lines.len() * 2);
+    for line in lines {
+        // User's account with their debit/credit (auto-generated, not manual)
+        journal_lines.push(JournalLine {
+            account_id: line.account_id.clone(),
+            debit: line.debit,
+            credit: line.credit,
+            is_manual: false,
+            narration: Some(line.narration.clone()),
+        });
+        // Balancing entry: if user has debit, this is credit (and vice versa)
+        journal_lines.push(JournalLine {
+            account_id: ob_account_id.to_string(),
+            debit: line.credit,
+            credit: line.debit,
+            is_manual: false,
+            narration: Some("Auto-generated balancing entry".to_string()),
+        });
+    }
+    journal_lines
+}
+
+/// Inserts a validated journal voucher and its lines inside an open transaction.
+async fn insert_journal_entry_in_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    entry: &CreateJournalEntry,
+    voucher_no: &str,
+    total_debit: f64,
+    branch_id: Option<&str>,
+) -> Result<String, String> {
     let voucher_id = Uuid::now_v7().to_string();
+    let narration =
+        resolve_narration(tx, entry.narration.as_ref(), "journal", None, voucher_no).await?;
 
     // Create voucher
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, total_amount, narration, status, created_by)
-         VALUES (?, ?, 'journal', ?, ?, ?, ?, 'posted', ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, total_amount, narration, status, created_by, branch_id)
+         VALUES (?, ?, 'journal', ?, ?, ?, ?, 'posted', ?, ?)"
     )
     .bind(&voucher_id)
-    .bind(&voucher_no)
+    .bind(voucher_no)
     .bind(&entry.voucher_date)
     .bind(&entry.reference)
     .bind(total_debit)
-    .bind(&entry.narration)
+    .bind(&narration)
     .bind(&entry.user_id)
-    .execute(&mut *tx)
+    .bind(branch_id)
+    .execute(&mut **tx)
     .await
     .map_err(|e| e.to_string())?;
 
     // Insert journal entries
-    for line in &entry.lines {
-        let je_id = Uuid::now_v7().to_string();
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-             VALUES (?, ?, ?, ?, ?, 1, ?)"
-        )
-        .bind(&je_id)
-        .bind(&voucher_id)
-        .bind(&line.account_id)
-        .bind(line.debit)
-        .bind(line.credit)
-        .bind(&line.narration)
-        .execute(&mut *tx)
+    insert_journal_lines(tx, &voucher_id, &manual_journal_lines(&entry.lines)).await?;
+
+    if entry.as_draft.unwrap_or(false) {
+        hold_voucher_postings(tx, &voucher_id).await?;
+    }
+
+    Ok(voucher_id)
+}
+
+#[tauri::command]
+pub async fn create_journal_entry(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    entry: CreateJournalEntry,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    validate_voucher_date(&pool, &entry.voucher_date, entry.allow_future).await?;
+    let total_debit = validate_journal_entry(&entry)?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    // Generate voucher number
+    let branch_id = session_store.active_branch_id();
+    let voucher_no = get_next_voucher_number_in_tx(
+        &mut tx,
+        "journal",
+        &entry.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
+
+    let voucher_id = insert_journal_entry_in_tx(
+        &mut tx,
+        &entry,
+        &voucher_no,
+        total_debit,
+        branch_id.as_deref(),
+    )
+    .await?;
+
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &voucher_id, "create").await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(voucher_id)
+}
+
+/// Posts several journal entries atomically. Every entry is validated first;
+/// if any entry fails validation or insertion, nothing from the batch is persisted.
+#[tauri::command]
+pub async fn create_journal_entries_batch(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    entries: Vec<CreateJournalEntry>,
+) -> Result<Vec<String>, String> {
+    let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    let branch_id = session_store.active_branch_id();
+    create_journal_entries_batch_with_pool(&pool, entries, actor.as_deref(), branch_id.as_deref())
         .await
-        .map_err(|e| e.to_string())?;
+}
+
+pub(crate) async fn create_journal_entries_batch_with_pool(
+    pool: &SqlitePool,
+    entries: Vec<CreateJournalEntry>,
+    actor: Option<&str>,
+    branch_id: Option<&str>,
+) -> Result<Vec<String>, String> {
+    if entries.is_empty() {
+        return Err("No journal entries to post".to_string());
+    }
+
+    let mut totals = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let total_debit =
+            validate_journal_entry(entry).map_err(|e| format!("Entry {}: {}", index + 1, e))?;
+        validate_voucher_date(pool, &entry.voucher_date, entry.allow_future)
+            .await
+            .map_err(|e| format!("Entry {}: {}", index + 1, e))?;
+        totals.push(total_debit);
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let mut voucher_ids = Vec::with_capacity(entries.len());
+    for (index, (entry, total_debit)) in entries.iter().zip(totals).enumerate() {
+        // Numbers are drawn inside the transaction so a rollback also releases them
+        let voucher_no =
+            get_next_voucher_number_in_tx(&mut tx, "journal", &entry.voucher_date, branch_id)
+                .await?;
+        let voucher_id =
+            insert_journal_entry_in_tx(&mut tx, entry, &voucher_no, total_debit, branch_id)
+                .await
+                .map_err(|e| format!("Entry {}: {}", index + 1, e))?;
+        record_voucher_audit(&mut *tx, actor, &voucher_id, "create").await?;
+        voucher_ids.push(voucher_id);
     }
 
     tx.commit().await.map_err(|e| e.to_string())?;
 
+    Ok(voucher_ids)
+}
+
+#[derive(Deserialize)]
+pub struct PostInterestJournal {
+    pub party_id: String, // This is coa.id
+    pub party_type: String,
+    pub as_on_date: String,
+    pub annual_rate: f64,
+    pub grace_days: Option<i64>,
+    pub interest_account_id: String,
+    pub user_id: Option<String>,
+}
+
+/// Books interest on a party's overdue invoices as a journal voucher. Customers are
+/// debited against `interest_account_id` (income); for suppliers the interest
+/// account (expense) is debited and the supplier credited. Each invoice charged is
+/// marked as posted through `as_on_date`, so the next run only charges later days.
+#[tauri::command]
+pub async fn post_interest_journal(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    request: PostInterestJournal,
+) -> Result<String, String> {
+    let PostInterestJournal {
+        party_id,
+        party_type,
+        as_on_date,
+        annual_rate,
+        grace_days,
+        interest_account_id,
+        user_id,
+    } = request;
+    let pool = registry.active_pool().await?;
+    validate_voucher_date(&pool, &as_on_date, None).await?;
+
+    let interest = super::reports::overdue_interest(
+        &pool,
+        &party_id,
+        &party_type,
+        &as_on_date,
+        annual_rate,
+        grace_days.unwrap_or(0),
+    )
+    .await?;
+    if interest.total_interest <= 0.0 {
+        return Err("No overdue interest to post".to_string());
+    }
+
+    let invoice_list = interest
+        .invoices
+        .iter()
+        .map(|i| i.voucher_no.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let narration = format!(
+        "Interest @ {}% p.a. on overdue invoices {}",
+        annual_rate, invoice_list
+    );
+
+    let line = |account_id: &str, debit: f64, credit: f64| CreateJournalEntryLine {
+        account_id: account_id.to_string(),
+        debit,
+        credit,
+        narration: Some(narration.clone()),
+    };
+    let amount = interest.total_interest;
+    let lines = if party_type == "customer" {
+        vec![
+            line(&party_id, amount, 0.0),
+            line(&interest_account_id, 0.0, amount),
+        ]
+    } else {
+        vec![
+            line(&interest_account_id, amount, 0.0),
+            line(&party_id, 0.0, amount),
+        ]
+    };
+
+    let entry = CreateJournalEntry {
+        voucher_date: as_on_date,
+        reference: None,
+        narration: Some(narration.clone()),
+        lines,
+        user_id,
+        allow_future: None,
+        as_draft: None,
+    };
+    let total_debit = validate_journal_entry(&entry)?;
+
+    let branch_id = session_store.active_branch_id();
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let voucher_no = get_next_voucher_number_in_tx(
+        &mut tx,
+        "journal",
+        &entry.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
+    let voucher_id = insert_journal_entry_in_tx(
+        &mut tx,
+        &entry,
+        &voucher_no,
+        total_debit,
+        branch_id.as_deref(),
+    )
+    .await?;
+    for invoice in &interest.invoices {
+        sqlx::query("UPDATE vouchers SET interest_posted_through = ? WHERE id = ?")
+            .bind(&entry.voucher_date)
+            .bind(&invoice.voucher_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &voucher_id, "create").await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_id)
 }
 
+/// Page size used by get_journal_entries when the caller passes no limit.
+const DEFAULT_JOURNAL_PAGE_SIZE: i64 = 100;
+
+#[derive(Serialize)]
+pub struct JournalEntryPage {
+    pub rows: Vec<JournalEntry>,
+    pub total_count: i64,
+}
+
+/// Lists manual journals newest first. `account_id` keeps only journals with a
+/// line on that account; it filters via EXISTS so the header totals still cover
+/// every line of the voucher.
 #[tauri::command]
 pub async fn get_journal_entries(
     registry: State<'_, Arc<DbRegistry>>,
-) -> Result<Vec<JournalEntry>, String> {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    account_id: Option<String>,
+) -> Result<JournalEntryPage, String> {
     let pool = registry.active_pool().await?;
-    let entries = sqlx::query_as::<_, JournalEntry>(
+
+    let mut filter = String::new();
+    let mut binds: Vec<&String> = Vec::new();
+    if let Some(from) = &from_date {
+        filter.push_str(" AND v.voucher_date >= ?");
+        binds.push(from);
+    }
+    if let Some(to) = &to_date {
+        filter.push_str(" AND v.voucher_date <= ?");
+        binds.push(to);
+    }
+    if let Some(account) = &account_id {
+        filter.push_str(
+            " AND EXISTS(SELECT 1 FROM journal_entries fje WHERE fje.voucher_id = v.id AND fje.account_id = ?)",
+        );
+        binds.push(account);
+    }
+
+    let count_query = format!(
+        "SELECT COUNT(*) FROM vouchers v WHERE v.voucher_type = 'journal' AND v.deleted_at IS NULL{}",
+        filter
+    );
+    let mut count_q = sqlx::query_scalar::<_, i64>(&count_query);
+    for value in &binds {
+        count_q = count_q.bind(*value);
+    }
+    let total_count = count_q.fetch_one(&pool).await.map_err(|e| e.to_string())?;
+
+    let query = format!(
         "SELECT 
             v.id,
             v.voucher_no,
@@ -1771,15 +2198,24 @@ pub async fn get_journal_entries(
         FROM vouchers v
         LEFT JOIN journal_entries je ON v.id = je.voucher_id
         LEFT JOIN users u ON v.created_by = u.id
-        WHERE v.voucher_type = 'journal' AND v.deleted_at IS NULL
+        WHERE v.voucher_type = 'journal' AND v.deleted_at IS NULL{}
         GROUP BY v.id, u.full_name
-        ORDER BY v.voucher_date DESC, v.created_at DESC, v.id DESC",
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+        ORDER BY v.voucher_date DESC, v.created_at DESC, v.id DESC
+        LIMIT ? OFFSET ?",
+        filter
+    );
+    let mut q = sqlx::query_as::<_, JournalEntry>(&query);
+    for value in &binds {
+        q = q.bind(*value);
+    }
+    let rows = q
+        .bind(limit.unwrap_or(DEFAULT_JOURNAL_PAGE_SIZE))
+        .bind(offset.unwrap_or(0))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    Ok(entries)
+    Ok(JournalEntryPage { rows, total_count })
 }
 
 #[tauri::command]
@@ -1832,42 +2268,211 @@ pub async fn get_journal_entry_lines(
             je.narration
         FROM journal_entries je
         LEFT JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE je.voucher_id = ? AND je.is_manual = 1
-        ORDER BY je.id ASC",
+        WHERE je.voucher_id = ? AND je.is_manual = 1
+        ORDER BY je.id ASC",
+    )
+    .bind(voucher_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(lines)
+}
+
+#[tauri::command]
+pub async fn delete_journal_entry(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    delete_journal_entry_with_pool(&pool, &id, actor.as_deref()).await
+}
+
+async fn delete_journal_entry_with_pool(
+    pool: &SqlitePool,
+    id: &str,
+    actor: Option<&str>,
+) -> Result<(), String> {
+    ensure_voucher_unlocked(pool, id).await?;
+    // Check if this is a manual journal entry
+    let voucher_type: String = sqlx::query_scalar("SELECT voucher_type FROM vouchers WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if voucher_type != "journal" {
+        return Err("Can only delete manual journal entries".to_string());
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    delete_held_postings(&mut tx, id).await?;
+
+    // Soft delete voucher
+    sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    record_voucher_audit(&mut *tx, actor, id, "delete").await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_deleted_journal_entries(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<JournalEntry>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, JournalEntry>(
+        "SELECT
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            v.reference,
+            v.narration,
+            COALESCE(SUM(je.debit), 0.0) as total_debit,
+            COALESCE(SUM(je.credit), 0.0) as total_credit,
+            v.status,
+            v.created_at,
+            v.deleted_at,
+            u.full_name as created_by_name
+        FROM vouchers v
+        LEFT JOIN journal_entries je ON v.id = je.voucher_id
+        LEFT JOIN users u ON v.created_by = u.id
+        WHERE v.voucher_type = 'journal' AND v.deleted_at IS NOT NULL
+        GROUP BY v.id, u.full_name
+        ORDER BY v.deleted_at DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Undoes delete_journal_entry. Its lines were kept, so clearing deleted_at
+/// brings the entry back into the ledger and trial balance.
+#[tauri::command]
+pub async fn restore_journal_entry(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    restore_journal_entry_with_pool(&pool, &id, actor.as_deref()).await
+}
+
+async fn restore_journal_entry_with_pool(
+    pool: &SqlitePool,
+    id: &str,
+    actor: Option<&str>,
+) -> Result<(), String> {
+    let voucher: Option<(String, String, bool)> = sqlx::query_as(
+        "SELECT voucher_type, voucher_date, deleted_at IS NOT NULL FROM vouchers WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let voucher_date = match voucher {
+        None => return Err("Journal entry not found".to_string()),
+        Some((voucher_type, _, _)) if voucher_type != "journal" => {
+            return Err("Can only restore manual journal entries".to_string())
+        }
+        Some((_, _, false)) => return Err("This journal entry is not deleted".to_string()),
+        Some((_, voucher_date, true)) => voucher_date,
+    };
+    ensure_voucher_unlocked(pool, id).await?;
+    // Restoring brings the postings back, so it counts as posting into that period
+    ensure_period_open(pool, &voucher_date).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("UPDATE vouchers SET deleted_at = NULL WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    record_voucher_audit(&mut *tx, actor, id, "restore").await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+// ============= ORPHANED JOURNAL ENTRIES =============
+
+/// A journal line whose voucher is missing, or soft-deleted when `voucher_deleted_at` is set.
+#[derive(Serialize, sqlx::FromRow)]
+pub struct OrphanedJournalEntry {
+    pub id: String,
+    pub voucher_id: String,
+    pub voucher_no: Option<String>,
+    pub voucher_deleted_at: Option<String>,
+    pub account_id: String,
+    pub account_name: Option<String>,
+    pub debit: f64,
+    pub credit: f64,
+    pub created_at: Option<String>,
+}
+
+/// Support utility: lists journal lines with no matching live voucher.
+#[tauri::command]
+pub async fn get_orphaned_journal_entries(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<OrphanedJournalEntry>, String> {
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let rows = sqlx::query_as::<_, OrphanedJournalEntry>(
+        "SELECT 
+            je.id,
+            je.voucher_id,
+            v.voucher_no,
+            v.deleted_at as voucher_deleted_at,
+            je.account_id,
+            coa.account_name,
+            CAST(COALESCE(je.debit, 0) AS REAL) as debit,
+            CAST(COALESCE(je.credit, 0) AS REAL) as credit,
+            je.created_at
+        FROM journal_entries je
+        LEFT JOIN vouchers v ON je.voucher_id = v.id
+        LEFT JOIN chart_of_accounts coa ON je.account_id = coa.id
+        WHERE v.id IS NULL OR v.deleted_at IS NOT NULL
+        ORDER BY je.voucher_id, je.id",
     )
-    .bind(voucher_id)
-    .fetch_all(&pool)
+    .fetch_all(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
 
-    Ok(lines)
+    Ok(rows)
 }
 
+/// Deletes journal lines whose voucher no longer exists. Lines of soft-deleted
+/// vouchers are what a restore brings back, so they are only purged when
+/// `include_deleted_vouchers` is set. Returns the number of lines removed.
 #[tauri::command]
-pub async fn delete_journal_entry(
+pub async fn purge_orphaned_journal_entries(
     registry: State<'_, Arc<DbRegistry>>,
-    id: String,
-) -> Result<(), String> {
+    include_deleted_vouchers: Option<bool>,
+) -> Result<u64, String> {
     let pool = registry.active_pool().await?;
-    // Check if this is a manual journal entry
-    let voucher_type: String = sqlx::query_scalar("SELECT voucher_type FROM vouchers WHERE id = ?")
-        .bind(&id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if voucher_type != "journal" {
-        return Err("Can only delete manual journal entries".to_string());
-    }
-
-    // Soft delete voucher
-    sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?")
-        .bind(&id)
-        .execute(&pool)
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let query = if include_deleted_vouchers.unwrap_or(false) {
+        "DELETE FROM journal_entries
+         WHERE NOT EXISTS (
+            SELECT 1 FROM vouchers v WHERE v.id = journal_entries.voucher_id AND v.deleted_at IS NULL
+         )"
+    } else {
+        "DELETE FROM journal_entries
+         WHERE NOT EXISTS (SELECT 1 FROM vouchers v WHERE v.id = journal_entries.voucher_id)"
+    };
+    let purged = sqlx::query(query)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+    tx.commit().await.map_err(|e| e.to_string())?;
 
-    Ok(())
+    Ok(purged)
 }
 
 // ============= OPENING BALANCE COMMANDS =============
@@ -1890,13 +2495,22 @@ pub struct CreateOpeningBalance {
 #[tauri::command]
 pub async fn create_opening_balance(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     entry: CreateOpeningBalance,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Get next voucher number
-    let voucher_no = get_next_voucher_number(&pool, "opening_balance").await?;
+    let voucher_date = entry
+        .form
+        .get("voucher_date")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let branch_id = session_store.active_branch_id();
+    let voucher_no =
+        get_next_voucher_number(&pool, "opening_balance", voucher_date, branch_id.as_deref())
+            .await?;
     let voucher_id = Uuid::now_v7().to_string();
 
     // Calculate total amount and identify party_id
@@ -1909,66 +2523,32 @@ pub async fn create_opening_balance(
 
     // Create voucher master record
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, narration, status, party_id, total_amount)
-         VALUES (?, ?, 'opening_balance', ?, ?, ?, 'posted', ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, reference, narration, status, party_id, total_amount, branch_id)
+         VALUES (?, ?, 'opening_balance', ?, ?, ?, 'posted', ?, ?, ?)"
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
-    .bind(entry.form.get("voucher_date").and_then(|v| v.as_str()).unwrap_or(""))
+    .bind(voucher_date)
     .bind(entry.form.get("reference").and_then(|v| v.as_str()).unwrap_or(""))
     .bind(entry.form.get("narration").and_then(|v| v.as_str()).unwrap_or(""))
     .bind(party_id)
     .bind(total_amount)
+    .bind(&branch_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
 
-    // Find Opening Balance Adjustment account (code 3004)
-    let ob_account = sqlx::query_as::<_, (String,)>(
-        "SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1",
-    )
-    .fetch_optional(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?
-    .ok_or_else(|| "Opening Balance Adjustment account not found".to_string())?;
+    // Find Opening Balance Adjustment account
+    let ob_account_id = resolve_system_account(&mut *tx, "opening_balance_adjustment").await?;
 
-    let ob_account_id = ob_account.0;
 
     // Insert journal entries for each line - create dual entries
-    for line in entry.lines {
-        // First entry: user's account with their debit/credit (auto-generated, not manual)
-        let je_id_1 = Uuid::now_v7().to_string();
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration, is_manual)
-             VALUES (?, ?, ?, ?, ?, ?, 0)"
-        )
-        .bind(&je_id_1)
-        .bind(&voucher_id)
-        .bind(line.account_id)
-        .bind(line.debit)
-        .bind(line.credit)
-        .bind(&line.narration)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        // Second entry: balancing entry in Opening Balance Adjustment account (auto-generated)
-        // If user has debit, this is credit (and vice versa)
-        let je_id_2 = Uuid::now_v7().to_string();
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration, is_manual)
-             VALUES (?, ?, ?, ?, ?, ?, 0)"
-        )
-        .bind(&je_id_2)
-        .bind(&voucher_id)
-        .bind(&ob_account_id)
-        .bind(line.credit)  // Reverse: credit becomes debit
-        .bind(line.debit)   // Reverse: debit becomes credit
-        .bind("Auto-generated balancing entry")
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+    insert_journal_lines(
+        &mut tx,
+        &voucher_id,
+        &opening_balance_journal_lines(&entry.lines, &ob_account_id),
+    )
+    .await?;
 
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_id)
@@ -1994,6 +2574,7 @@ pub async fn delete_opening_balance(
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND voucher_type = 'opening_balance'")
         .bind(id)
         .execute(&pool)
@@ -2003,31 +2584,69 @@ pub async fn delete_opening_balance(
     Ok(())
 }
 
+/// Net balance (Dr - Cr) of one account, opening balance voucher included. Assets and
+/// expenses usually come out positive, liabilities and income negative; the UI
+/// shows Dr/Cr from the sign.
 #[tauri::command]
 pub async fn get_account_balance(
     registry: State<'_, Arc<DbRegistry>>,
     account_id: String,
 ) -> Result<f64, String> {
     let pool = registry.active_pool().await?;
-    let result = sqlx::query_as::<_, (f64, f64)>(
+    let balances = account_balances(&pool, std::slice::from_ref(&account_id), None).await?;
+    Ok(balances.get(&account_id).copied().unwrap_or(0.0))
+}
+
+/// Balances (Dr - Cr) for many accounts in one grouped query. Summed from journal
+/// lines only, since an account's opening balance is posted as an
+/// `opening_balance` voucher. Postings are limited to `as_on_date` when given.
+#[tauri::command]
+pub async fn get_account_balances(
+    registry: State<'_, Arc<DbRegistry>>,
+    account_ids: Vec<String>,
+    as_on_date: Option<String>,
+) -> Result<HashMap<String, f64>, String> {
+    let pool = registry.active_pool().await?;
+    account_balances(&pool, &account_ids, as_on_date.as_deref()).await
+}
+
+pub(crate) async fn account_balances(
+    pool: &SqlitePool,
+    account_ids: &[String],
+    as_on_date: Option<&str>,
+) -> Result<HashMap<String, f64>, String> {
+    if account_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let placeholders = vec!["?"; account_ids.len()].join(", ");
+    let date_filter = if as_on_date.is_some() {
+        "AND v.voucher_date <= ?"
+    } else {
+        ""
+    };
+    let query = format!(
         "SELECT 
-            COALESCE(SUM(je.debit), 0.0) as total_debit, 
-            COALESCE(SUM(je.credit), 0.0) as total_credit 
-         FROM journal_entries je
-         JOIN vouchers v ON je.voucher_id = v.id
-         WHERE je.account_id = ? AND v.deleted_at IS NULL",
-    )
-    .bind(account_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+            coa.id,
+            CAST(COALESCE(SUM(CASE WHEN v.id IS NOT NULL THEN je.debit - je.credit ELSE 0 END), 0) AS REAL) as balance
+         FROM chart_of_accounts coa
+         LEFT JOIN journal_entries je ON je.account_id = coa.id
+         LEFT JOIN vouchers v ON je.voucher_id = v.id AND v.deleted_at IS NULL {}
+         WHERE coa.id IN ({})
+         GROUP BY coa.id",
+        date_filter, placeholders
+    );
+
+    let mut q = sqlx::query_as::<_, (String, f64)>(&query);
+    if let Some(date) = as_on_date {
+        q = q.bind(date);
+    }
+    for id in account_ids {
+        q = q.bind(id);
+    }
 
-    // Net balance: Dr - Cr.
-    // Assets/Expenses usually Dr > Cr (Positive).
-    // Liabilities/Income usually Cr > Dr (Negative).
-    // UI can display Dr/Cr based on sign.
-    let balance = result.0 - result.1;
-    Ok(balance)
+    let rows = q.fetch_all(pool).await.map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().collect())
 }
 
 #[tauri::command]
@@ -2043,8 +2662,8 @@ pub async fn get_pending_invoices(
             v.voucher_no,
             v.voucher_date,
             v.voucher_type,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2) as total_amount,
-            (ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0), 2) - COALESCE(
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0) + COALESCE(v.round_off_amount, 0.0), 2) as total_amount,
+            (ROUND(COALESCE(v.subtotal, v.total_amount, 0.0) - COALESCE(v.discount_amount, 0.0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0.0), 0.0) + COALESCE(v.round_off_amount, 0.0), 2) - COALESCE(
                 (SELECT SUM(allocated_amount) FROM payment_allocations WHERE invoice_voucher_id = v.id), 0.0
             )) as pending_amount,
             v.narration
@@ -2069,10 +2688,14 @@ pub async fn get_pending_invoices(
 #[tauri::command]
 pub async fn update_journal_entry(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     entry: CreateJournalEntry,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    ensure_voucher_editable(&pool, &id).await?;
+    validate_voucher_date(&pool, &entry.voucher_date, entry.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Check if this is a manual journal entry
@@ -2132,25 +2755,12 @@ pub async fn update_journal_entry(
         .map_err(|e| e.to_string())?;
 
     // Insert new journal lines
-    for line in &entry.lines {
-        let je_id = Uuid::now_v7().to_string();
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, is_manual, narration)
-             VALUES (?, ?, ?, ?, ?, 1, ?)"
-        )
-        .bind(&je_id)
-        .bind(&id)
-        .bind(line.account_id.clone())
-        .bind(line.debit)
-        .bind(line.credit)
-        .bind(&line.narration)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+    insert_journal_lines(&mut tx, &id, &manual_journal_lines(&entry.lines)).await?;
+    set_voucher_draft_state(&mut tx, &id, entry.as_draft.unwrap_or(false)).await?;
 
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "update").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
@@ -2186,14 +2796,12 @@ pub async fn get_opening_balance_lines(
 ) -> Result<Vec<OpeningBalanceLine>, String> {
     let pool = registry.active_pool().await?;
     // Only fetch the user-facing entries (not the auto-balancing ones)
-    // The auto-balancing entry has account_id 3004.
+    // The auto-balancing entry posts to the opening balance adjustment account.
 
     // Find Opening Balance Adjustment account id
-    let ob_account_id: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1")
-            .fetch_one(&pool)
-            .await
-            .unwrap_or("".to_string()); // empty string won't match anything valid
+    let ob_account_id = find_system_account(&pool, "opening_balance_adjustment")
+        .await?
+        .unwrap_or_default(); // empty string won't match anything valid
 
     let lines = sqlx::query_as::<_, (String, String, f64, f64, String)>(
         "SELECT 
@@ -2234,6 +2842,7 @@ pub async fn update_opening_balance(
     entry: CreateOpeningBalance,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Check voucher type
@@ -2294,15 +2903,8 @@ pub async fn update_opening_balance(
     .map_err(|e| e.to_string())?;
 
     // Find OB Adjustment account
-    let ob_account = sqlx::query_as::<_, (String,)>(
-        "SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1",
-    )
-    .fetch_optional(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?
-    .ok_or_else(|| "Opening Balance Adjustment account not found".to_string())?;
+    let ob_account_id = resolve_system_account(&mut *tx, "opening_balance_adjustment").await?;
 
-    let ob_account_id = ob_account.0;
 
     // Delete existing journal entries
     sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ?")
@@ -2312,41 +2914,671 @@ pub async fn update_opening_balance(
         .map_err(|e| e.to_string())?;
 
     // Insert new journal entries (dual entry logic)
-    for line in entry.lines {
-        // User entry
-        let je_id_1 = Uuid::now_v7().to_string();
+    insert_journal_lines(
+        &mut tx,
+        &id,
+        &opening_balance_journal_lines(&entry.lines, &ob_account_id),
+    )
+    .await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn insert_voucher(pool: &SqlitePool, voucher_type: &str, voucher_no: &str) -> String {
+        let id = Uuid::now_v7().to_string();
         sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration, is_manual)
-             VALUES (?, ?, ?, ?, ?, ?, 0)"
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date)
+             VALUES (?, ?, ?, '2024-04-01')",
         )
-        .bind(&je_id_1)
         .bind(&id)
-        .bind(&line.account_id)
-        .bind(line.debit)
-        .bind(line.credit)
-        .bind(&line.narration)
-        .execute(&mut *tx)
+        .bind(voucher_no)
+        .bind(voucher_type)
+        .execute(pool)
         .await
-        .map_err(|e| e.to_string())?;
+        .unwrap();
+        id
+    }
+
+    /// A posted two-line journal moving `amount` from the adjustment account to inventory.
+    async fn insert_journal(
+        pool: &SqlitePool,
+        voucher_no: &str,
+        date: &str,
+        amount: f64,
+    ) -> String {
+        let voucher_id = insert_voucher(pool, "journal", voucher_no).await;
+        sqlx::query("UPDATE vouchers SET voucher_date = ? WHERE id = ?")
+            .bind(date)
+            .bind(&voucher_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        let inventory = resolve_system_account(pool, "inventory").await.unwrap();
+        let adjustment = resolve_system_account(pool, "opening_balance_adjustment")
+            .await
+            .unwrap();
+        let lines = [
+            JournalLine {
+                account_id: inventory,
+                debit: amount,
+                credit: 0.0,
+                is_manual: true,
+                narration: None,
+            },
+            JournalLine {
+                account_id: adjustment,
+                debit: 0.0,
+                credit: amount,
+                is_manual: true,
+                narration: None,
+            },
+        ];
+        let mut tx = pool.begin().await.unwrap();
+        insert_journal_lines(&mut tx, &voucher_id, &lines)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        voucher_id
+    }
+
+    async fn payment_status(pool: &SqlitePool, invoice_id: &str) -> String {
+        sqlx::query_scalar("SELECT payment_status FROM vouchers WHERE id = ?")
+            .bind(invoice_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn inventory_trial_balance_debit(pool: &SqlitePool) -> f64 {
+        let inventory_code: String =
+            sqlx::query_scalar("SELECT account_code FROM chart_of_accounts WHERE id = ?")
+                .bind(resolve_system_account(pool, "inventory").await.unwrap())
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        crate::commands::reports::trial_balance_rows(pool, None, "2099-12-31")
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|row| row.account_code == inventory_code)
+            .map(|row| row.debit)
+            .unwrap_or(0.0)
+    }
+
+    #[tokio::test]
+    async fn restored_journal_reappears_and_counts_in_trial_balance() {
+        let pool = test_pool().await;
+        let id = insert_journal(&pool, "JV-T-0001", "2024-05-10", 500.0).await;
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 500.0);
+
+        delete_journal_entry_with_pool(&pool, &id, None)
+            .await
+            .unwrap();
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 0.0);
+
+        restore_journal_entry_with_pool(&pool, &id, None)
+            .await
+            .unwrap();
+        let live: bool = sqlx::query_scalar(
+            "SELECT deleted_at IS NULL FROM vouchers WHERE id = ? AND voucher_type = 'journal'",
+        )
+        .bind(&id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(live);
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 500.0);
+    }
+
+    #[tokio::test]
+    async fn journal_cannot_be_restored_into_a_closed_year() {
+        let pool = test_pool().await;
+        let id = insert_journal(&pool, "JV-T-0002", "2024-01-15", 500.0).await;
+        delete_journal_entry_with_pool(&pool, &id, None)
+            .await
+            .unwrap();
+
+        sqlx::query(
+            "INSERT INTO financial_year_closings (id, fy_end_date, carry_forward_year)
+             VALUES (?, '2024-03-31', '2024-25')",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = restore_journal_entry_with_pool(&pool, &id, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("2024-03-31 is closed"), "{}", err);
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn payment_line_with_account_id_posts_its_debit() {
+        let pool = test_pool().await;
+        let cash = resolve_system_account(&pool, "inventory").await.unwrap();
+        let payee = resolve_system_account(&pool, "purchases").await.unwrap();
+
+        let mut tx = pool.begin().await.unwrap();
+        let payee_id = Some(payee.clone());
+        let lines = cash_voucher_journal_lines(
+            &mut tx,
+            false,
+            &cash,
+            "Payment made",
+            &[(&payee_id, "Rent", 250.0)],
+            250.0,
+            0.0,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].debit, lines[0].credit), (0.0, 250.0));
+        assert_eq!(lines[1].account_id, payee);
+        assert_eq!((lines[1].debit, lines[1].credit), (250.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn payment_line_without_account_id_is_rejected() {
+        let pool = test_pool().await;
+        let cash = resolve_system_account(&pool, "inventory").await.unwrap();
+
+        let mut tx = pool.begin().await.unwrap();
+        let err = cash_voucher_journal_lines(
+            &mut tx,
+            false,
+            &cash,
+            "Payment made",
+            &[(&None, "Rent", 250.0)],
+            250.0,
+            0.0,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(err, "Line 'Rent' has no account selected.");
+
+        let unknown = Some("no-such-account".to_string());
+        let err = require_item_account(&mut tx, &unknown, "Rent")
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Account for line 'Rent' was not found.");
+    }
+
+    #[tokio::test]
+    async fn insert_journal_lines_posts_300_line_opening_balance_in_bounded_statements() {
+        let pool = test_pool().await;
+        let voucher_id = insert_voucher(&pool, "opening_balance", "OB-T-0001").await;
+        let inventory = resolve_system_account(&pool, "inventory").await.unwrap();
+        let adjustment = resolve_system_account(&pool, "opening_balance_adjustment")
+            .await
+            .unwrap();
+
+        let mut lines = Vec::new();
+        for i in 1..=150 {
+            let narration = Some(format!("Line {}", i));
+            lines.push(JournalLine {
+                account_id: inventory.clone(),
+                debit: i as f64,
+                credit: 0.0,
+                is_manual: true,
+                narration: narration.clone(),
+            });
+            lines.push(JournalLine {
+                account_id: adjustment.clone(),
+                debit: 0.0,
+                credit: i as f64,
+                is_manual: true,
+                narration,
+            });
+        }
 
-        // Balancing entry
-        let je_id_2 = Uuid::now_v7().to_string();
+        let mut tx = pool.begin().await.unwrap();
+        let statements = insert_journal_lines(&mut tx, &voucher_id, &lines)
+            .await
+            .unwrap();
+        ensure_journal_balanced(&mut tx, &voucher_id).await.unwrap();
+        tx.commit().await.unwrap();
+
+        // 142 rows fit under the 999 bound-parameter limit: 142 + 142 + 16
+        assert_eq!(statements, 3);
+
+        let (count, debit, credit): (i64, f64, f64) = sqlx::query_as(
+            "SELECT COUNT(*), CAST(SUM(debit) AS REAL), CAST(SUM(credit) AS REAL)
+             FROM journal_entries WHERE voucher_id = ? AND is_manual = 1",
+        )
+        .bind(&voucher_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(count, 300);
+        assert_eq!(debit, 11325.0);
+        assert_eq!(credit, 11325.0);
+
+        let line_75: Vec<(String, f64, f64)> = sqlx::query_as(
+            "SELECT account_id, debit, credit FROM journal_entries
+             WHERE voucher_id = ? AND narration = 'Line 75' ORDER BY debit DESC",
+        )
+        .bind(&voucher_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            line_75,
+            vec![(inventory, 75.0, 0.0), (adjustment, 0.0, 75.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn overdue_interest_skips_days_already_posted() {
+        let pool = test_pool().await;
+        let account_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO chart_of_accounts (id, account_code, account_name, account_type, account_group, party_type)
+             VALUES (?, '1003-C1', 'Customer C1', 'Asset', 'Accounts Receivable', 'customer')",
+        )
+        .bind(&account_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let invoice_id = insert_voucher(&pool, "sales_invoice", "SI-1").await;
         sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration, is_manual)
-             VALUES (?, ?, ?, ?, ?, ?, 0)"
+            "UPDATE vouchers
+             SET voucher_date = '2024-01-01', party_id = 'C1', party_type = 'customer',
+                 grand_total = 1000, total_amount = 1000
+             WHERE id = ?",
+        )
+        .bind(&invoice_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let interest = |as_on: &'static str| {
+            let pool = pool.clone();
+            let account_id = account_id.clone();
+            async move {
+                crate::commands::reports::overdue_interest(
+                    &pool,
+                    &account_id,
+                    "customer",
+                    as_on,
+                    36.5,
+                    0,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let first = interest("2024-03-01").await;
+        assert_eq!(first.invoices[0].overdue_days, 60);
+        assert_eq!(first.total_interest, 60.0);
+
+        sqlx::query("UPDATE vouchers SET interest_posted_through = '2024-03-01' WHERE id = ?")
+            .bind(&invoice_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let rerun = interest("2024-03-01").await;
+        assert!(rerun.invoices.is_empty());
+        assert_eq!(rerun.total_interest, 0.0);
+
+        let later = interest("2024-03-31").await;
+        assert_eq!(later.invoices[0].overdue_days, 30);
+        assert_eq!(later.total_interest, 30.0);
+    }
+
+    #[tokio::test]
+    async fn single_and_batch_account_balances_count_the_opening_voucher_once() {
+        let pool = test_pool().await;
+        let account = crate::commands::accounts::create_chart_of_account_with_pool(
+            &pool,
+            serde_json::from_value(serde_json::json!({
+                "account_code": "T-BAL",
+                "account_name": "Balance Test",
+                "account_type": "Asset",
+                "account_group": "Current Assets",
+                "opening_balance": 500.0,
+                "opening_balance_type": "Dr",
+                "opening_balance_date": "2024-04-01",
+            }))
+            .unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+        let adjustment = resolve_system_account(&pool, "opening_balance_adjustment")
+            .await
+            .unwrap();
+
+        let single = account_balances(&pool, std::slice::from_ref(&account.id), None)
+            .await
+            .unwrap();
+        let batch = account_balances(&pool, &[account.id.clone(), adjustment.clone()], None)
+            .await
+            .unwrap();
+
+        assert_eq!(single[&account.id], 500.0);
+        assert_eq!(batch[&account.id], single[&account.id]);
+        assert_eq!(batch[&adjustment], -500.0);
+    }
+
+    /// A journal moved into the approval queue, created by `created_by`.
+    async fn insert_pending_journal(
+        pool: &SqlitePool,
+        voucher_no: &str,
+        created_by: &str,
+    ) -> String {
+        let id = insert_journal(pool, voucher_no, "2024-05-10", 500.0).await;
+        let mut tx = pool.begin().await.unwrap();
+        crate::commands::approvals::hold_voucher_postings(&mut tx, &id)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE vouchers SET status = 'pending', created_by = ? WHERE id = ?")
+            .bind(created_by)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn creator_cannot_approve_own_voucher() {
+        let pool = test_pool().await;
+        let id = insert_pending_journal(&pool, "JV-T-0003", "u1").await;
+
+        let err = crate::commands::approvals::approve_voucher_with_pool(&pool, &id, "u1", false)
+            .await
+            .unwrap_err();
+        assert!(err.contains("user who created it"), "{}", err);
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 0.0);
+
+        crate::commands::approvals::approve_voucher_with_pool(&pool, &id, "u2", false)
+            .await
+            .unwrap();
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 500.0);
+    }
+
+    #[tokio::test]
+    async fn resaving_a_draft_as_final_submits_it_for_approval() {
+        let pool = test_pool().await;
+        let id = insert_journal(&pool, "JV-T-0004", "2024-05-10", 500.0).await;
+        let mut tx = pool.begin().await.unwrap();
+        crate::commands::approvals::hold_voucher_postings(&mut tx, &id)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let mut tx = pool.begin().await.unwrap();
+        crate::commands::approvals::set_voucher_draft_state(&mut tx, &id, false)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let status: String = sqlx::query_scalar("SELECT status FROM vouchers WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "pending");
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_draft_drops_its_held_postings() {
+        let pool = test_pool().await;
+        let id = insert_journal(&pool, "JV-T-0005", "2024-05-10", 500.0).await;
+        let mut tx = pool.begin().await.unwrap();
+        crate::commands::approvals::hold_voucher_postings(&mut tx, &id)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        delete_journal_entry_with_pool(&pool, &id, None)
+            .await
+            .unwrap();
+
+        let held: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COUNT(*) FROM held_journal_entries WHERE voucher_id = ?)
+                  + (SELECT COUNT(*) FROM held_stock_movements WHERE voucher_id = ?)",
         )
-        .bind(&je_id_2)
         .bind(&id)
-        .bind(&ob_account_id)
-        .bind(line.credit)
-        .bind(line.debit)
-        .bind("Auto-generated balancing entry")
-        .execute(&mut *tx)
+        .bind(&id)
+        .fetch_one(&pool)
         .await
-        .map_err(|e| e.to_string())?;
+        .unwrap();
+        assert_eq!(held, 0);
     }
 
-    tx.commit().await.map_err(|e| e.to_string())?;
+    fn journal_entry(lines: &[(&str, f64, f64)]) -> CreateJournalEntry {
+        let lines: Vec<serde_json::Value> = lines
+            .iter()
+            .map(|(account_id, debit, credit)| {
+                serde_json::json!({
+                    "account_id": account_id,
+                    "debit": debit,
+                    "credit": credit,
+                    "narration": null,
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "voucher_date": "2024-05-10",
+            "reference": null,
+            "narration": null,
+            "lines": lines,
+            "user_id": null,
+        }))
+        .unwrap()
+    }
 
-    Ok(())
+    async fn journal_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM vouchers WHERE voucher_type = 'journal'")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn journal_batch_posts_nothing_when_one_entry_fails() {
+        let pool = test_pool().await;
+        let inventory = resolve_system_account(&pool, "inventory").await.unwrap();
+        let adjustment = resolve_system_account(&pool, "opening_balance_adjustment")
+            .await
+            .unwrap();
+        let (inventory, adjustment) = (inventory.as_str(), adjustment.as_str());
+        let balanced = || journal_entry(&[(inventory, 100.0, 0.0), (adjustment, 0.0, 100.0)]);
+
+        let err = create_journal_entries_batch_with_pool(
+            &pool,
+            vec![
+                balanced(),
+                balanced(),
+                journal_entry(&[(inventory, 100.0, 0.0), (adjustment, 0.0, 90.0)]),
+            ],
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.starts_with("Entry 3:"), "{}", err);
+        assert_eq!(journal_count(&pool).await, 0);
+
+        // A failure while inserting rolls back the entries already written
+        let err = create_journal_entries_batch_with_pool(
+            &pool,
+            vec![
+                balanced(),
+                balanced(),
+                journal_entry(&[(inventory, 100.0, 0.0), ("missing", 0.0, 100.0)]),
+            ],
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.starts_with("Entry 3:"), "{}", err);
+        assert_eq!(journal_count(&pool).await, 0);
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 0.0);
+
+        let ids =
+            create_journal_entries_batch_with_pool(&pool, vec![balanced(), balanced()], None, None)
+                .await
+                .unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(inventory_trial_balance_debit(&pool).await, 200.0);
+    }
+
+    #[tokio::test]
+    async fn restored_receipt_marks_its_invoice_paid_again() {
+        let pool = test_pool().await;
+        let customer = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO chart_of_accounts (id, account_code, account_name, account_type, account_group, party_type)
+             VALUES (?, '1003-C2', 'Customer C2', 'Asset', 'Accounts Receivable', 'customer')",
+        )
+        .bind(&customer)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let invoice_id = insert_voucher(&pool, "sales_invoice", "SI-2").await;
+        sqlx::query(
+            "UPDATE vouchers SET party_id = ?, grand_total = 1000, total_amount = 1000 WHERE id = ?",
+        )
+        .bind(&customer)
+        .bind(&invoice_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let cash: String =
+            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1001'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let receipt = serde_json::from_value(serde_json::json!({
+            "account_id": cash,
+            "voucher_date": "2024-04-01",
+            "receipt_method": "cash",
+            "items": [{
+                "description": "Against SI-2",
+                "account_id": customer,
+                "amount": 1000.0,
+                "tax_rate": 0.0,
+                "allocations": [{ "invoice_id": invoice_id, "amount": 1000.0 }],
+            }],
+        }))
+        .unwrap();
+        let receipt_id = create_receipt_with_pool(&pool, receipt, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(payment_status(&pool, &invoice_id).await, "paid");
+
+        delete_receipt_with_pool(&pool, &receipt_id, None)
+            .await
+            .unwrap();
+        assert_eq!(payment_status(&pool, &invoice_id).await, "unpaid");
+
+        restore_settlement_voucher(&pool, &receipt_id, "receipt", None)
+            .await
+            .unwrap();
+        assert_eq!(payment_status(&pool, &invoice_id).await, "paid");
+    }
+
+    /// Payment, receipt and journal commands are defined once, here. A second
+    /// definition elsewhere would make these glob re-exported paths ambiguous and
+    /// stop this test from compiling.
+    #[tokio::test]
+    async fn payment_receipt_and_journal_commands_resolve_to_entries() {
+        let _ = (
+            crate::commands::create_payment,
+            crate::commands::update_payment,
+            crate::commands::delete_payment,
+            crate::commands::restore_payment,
+            crate::commands::get_payments,
+            crate::commands::get_payment_items,
+        );
+        let _ = (
+            crate::commands::create_receipt,
+            crate::commands::update_receipt,
+            crate::commands::delete_receipt,
+            crate::commands::restore_receipt,
+            crate::commands::get_receipts,
+            crate::commands::get_receipt_items,
+        );
+        let _ = (
+            crate::commands::create_journal_entry,
+            crate::commands::create_journal_entries_batch,
+            crate::commands::update_journal_entry,
+            crate::commands::delete_journal_entry,
+            crate::commands::restore_journal_entry,
+            crate::commands::get_journal_entries,
+        );
+
+        let pool = test_pool().await;
+        let mut ids = HashMap::new();
+        for code in ["1001", "4001", "5004"] {
+            let id: String =
+                sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = ?")
+                    .bind(code)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            ids.insert(code, id);
+        }
+        let settlement = |ledger: &str, amount: f64| {
+            serde_json::json!({
+                "account_id": ids["1001"],
+                "voucher_date": "2024-04-01",
+                "payment_method": "cash",
+                "receipt_method": "cash",
+                "items": [{
+                    "description": "Cash line",
+                    "account_id": ids[ledger],
+                    "amount": amount,
+                    "tax_rate": 0.0,
+                }],
+            })
+        };
+
+        let payment: crate::commands::CreatePayment =
+            serde_json::from_value(settlement("5004", 200.0)).unwrap();
+        let receipt: crate::commands::CreateReceipt =
+            serde_json::from_value(settlement("4001", 500.0)).unwrap();
+        let journal: crate::commands::CreateJournalEntry = journal_entry(&[
+            (ids["5004"].as_str(), 50.0, 0.0),
+            (ids["1001"].as_str(), 0.0, 50.0),
+        ]);
+        create_payment_with_pool(&pool, payment, None, None)
+            .await
+            .unwrap();
+        create_receipt_with_pool(&pool, receipt, None, None)
+            .await
+            .unwrap();
+        create_journal_entries_batch_with_pool(&pool, vec![journal], None, None)
+            .await
+            .unwrap();
+
+        let cash: f64 = sqlx::query_scalar(
+            "SELECT CAST(SUM(debit - credit) AS REAL) FROM journal_entries WHERE account_id = ?",
+        )
+        .bind(&ids["1001"])
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(cash, 250.0);
+    }
 }
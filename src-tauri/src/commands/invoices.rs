@@ -1,11 +1,22 @@
 use crate::company_db::DbRegistry;
 use serde::{Deserialize, Serialize};
-use sqlx::{Sqlite, SqlitePool, Transaction};
+use sqlx::{Sqlite, SqliteExecutor, SqlitePool, Transaction};
 use std::sync::Arc;
 use tauri::State;
 
+use super::approvals::{
+    delete_held_postings, ensure_voucher_editable, hold_voucher_postings, set_voucher_draft_state,
+};
+use super::audit::record_voucher_audit;
+use super::auth::SessionStore;
+use super::entries::{insert_journal_lines, recompute_invoice_status, JournalLine};
+use super::reports::{STOCK_IN_TYPES, STOCK_OUT_TYPES};
 use super::resolve_voucher_line_unit;
+use super::search_index::{search_index_ready, search_match_query};
+use super::settings::{resolve_narration, validate_voucher_date};
 use super::sales_returns::{create_sales_return_in_tx, CreateSalesReturn, CreateSalesReturnItem};
+use super::system_accounts::resolve_system_account;
+use super::year_end::ensure_voucher_unlocked;
 use crate::voucher_seq::get_next_voucher_number;
 use uuid::Uuid;
 
@@ -29,6 +40,66 @@ pub(crate) async fn get_product_purchase_cost_rate(
         .map(|value| value.unwrap_or(0.0))
 }
 
+// ============= CURRENCY =============
+
+/// Resolves an invoice's currency and exchange rate. Missing currency means the
+/// company's base currency, which always posts at 1.0.
+async fn resolve_invoice_currency(
+    tx: &mut Transaction<'_, Sqlite>,
+    currency: Option<&str>,
+    exchange_rate: Option<f64>,
+) -> Result<(String, f64), String> {
+    let base_currency: String = sqlx::query_scalar(
+        "SELECT COALESCE(base_currency, 'INR') FROM company_profile ORDER BY id DESC LIMIT 1",
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .unwrap_or_else(|| "INR".to_string());
+
+    let currency = currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| base_currency.clone());
+
+    if currency == base_currency {
+        return Ok((currency, 1.0));
+    }
+
+    match exchange_rate {
+        Some(rate) if rate > 0.0 => Ok((currency, rate)),
+        _ => Err(format!(
+            "An exchange rate to {} is required for invoices in {}",
+            base_currency, currency
+        )),
+    }
+}
+
+/// Converts journal lines from invoice currency to base currency. Per-line rounding
+/// can leave the voucher a paisa out, so the residue is absorbed by the first
+/// (party) line to keep debits equal to credits.
+fn convert_journal_lines_to_base(lines: &mut [JournalLine], exchange_rate: f64) {
+    if exchange_rate == 1.0 || lines.is_empty() {
+        return;
+    }
+    for line in lines.iter_mut() {
+        line.debit = round2(line.debit * exchange_rate);
+        line.credit = round2(line.credit * exchange_rate);
+    }
+
+    let residue = round2(
+        lines.iter().map(|l| l.debit).sum::<f64>() - lines.iter().map(|l| l.credit).sum::<f64>(),
+    );
+    if residue != 0.0 {
+        let party = &mut lines[0];
+        if party.debit > 0.0 {
+            party.debit = round2(party.debit - residue);
+        } else {
+            party.credit = round2(party.credit + residue);
+        }
+    }
+}
+
 // ============= GST INVOICE HELPERS =============
 
 #[derive(Debug)]
@@ -72,6 +143,33 @@ pub struct ProcessedVoucher {
     pub total_igst: f64,
 }
 
+/// Page size used by the invoice list commands when the caller passes no limit.
+const DEFAULT_INVOICE_PAGE_SIZE: i64 = 100;
+
+#[derive(Serialize)]
+pub struct InvoicePage<T> {
+    pub rows: Vec<T>,
+    pub total_count: i64,
+}
+
+/// Builds the optional voucher_date range clause for the invoice list queries.
+fn invoice_date_filter<'a>(
+    from_date: &'a Option<String>,
+    to_date: &'a Option<String>,
+) -> (String, Vec<&'a String>) {
+    let mut clause = String::new();
+    let mut binds = Vec::new();
+    if let Some(from) = from_date {
+        clause.push_str(" AND v.voucher_date >= ?");
+        binds.push(from);
+    }
+    if let Some(to) = to_date {
+        clause.push_str(" AND v.voucher_date <= ?");
+        binds.push(to);
+    }
+    (clause, binds)
+}
+
 // ============= PURCHASE INVOICE =============
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct PurchaseInvoice {
@@ -85,6 +183,8 @@ pub struct PurchaseInvoice {
     pub total_amount: f64,
     pub tax_amount: f64,
     pub grand_total: f64,
+    /// Rounding added to reach `grand_total` (0 when totals aren't rounded)
+    pub round_off_amount: f64,
     pub discount_rate: Option<f64>,
     pub discount_amount: Option<f64>,
     pub narration: Option<String>,
@@ -93,6 +193,13 @@ pub struct PurchaseInvoice {
     pub deleted_at: Option<String>,
     pub created_by_name: Option<String>,
     pub tax_inclusive: i64,
+    /// ISO code the invoice amounts are in; NULL on invoices from before multi-currency
+    pub currency: Option<String>,
+    /// Base-currency value of one unit of `currency`
+    pub exchange_rate: f64,
+    /// Stored `subtotal - discount_amount` disagrees with stored `total_amount` (list queries only)
+    #[sqlx(default)]
+    pub total_mismatch: bool,
 }
 
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
@@ -157,6 +264,8 @@ pub(crate) struct PreparedVoucherLine {
     hsn_sac_code: Option<String>,
     gst_slab_id: Option<String>,
     effective_rate: f64,
+    /// Per-line override of the invoice's place of supply; None follows the invoice
+    inter_state: Option<bool>,
 }
 
 pub(crate) fn normalize_invoice_discount(
@@ -338,9 +447,24 @@ pub(crate) async fn prepare_voucher_line(
         hsn_sac_code,
         gst_slab_id,
         effective_rate,
+        inter_state: None,
     })
 }
 
+/// Parses a line's `tax_type`: "intra" splits GST into CGST + SGST, "inter"
+/// posts it all as IGST. None keeps the invoice's place-of-supply default.
+fn parse_tax_type(tax_type: Option<&str>) -> Result<Option<bool>, String> {
+    match tax_type.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some("intra") => Ok(Some(false)),
+        Some("inter") => Ok(Some(true)),
+        Some(other) => Err(format!(
+            "Invalid tax type '{}': expected 'intra' or 'inter'",
+            other
+        )),
+    }
+}
+
 pub(crate) fn finalize_processed_items(
     prepared_lines: Vec<PreparedVoucherLine>,
     is_inter_state: bool,
@@ -380,7 +504,7 @@ pub(crate) fn finalize_processed_items(
         let split = crate::commands::tax_utils::compute_split(
             net_amount,
             line.effective_rate,
-            is_inter_state,
+            line.inter_state.unwrap_or(is_inter_state),
         );
 
         total_cgst += split.cgst_amount;
@@ -449,6 +573,8 @@ pub struct CreatePurchaseInvoiceItem {
     pub discount_percent: Option<f64>,
     pub discount_amount: Option<f64>,
     pub remarks: Option<String>,
+    /// "intra" (CGST + SGST) or "inter" (IGST); defaults to the party's place of supply
+    pub tax_type: Option<String>,
     /// Sales rate to assign to the auto-created child product (master product lines only)
     pub sales_rate: Option<f64>,
     /// MRP to assign to the auto-created child product (master product lines only)
@@ -472,14 +598,87 @@ pub struct CreatePurchaseInvoice {
     pub user_id: Option<String>,
     pub tax_inclusive: Option<bool>,
     pub gst_disabled: Option<bool>,
+    pub allow_future: Option<bool>,
+    /// Defaults to the company's base currency
+    pub currency: Option<String>,
+    /// Required when `currency` differs from the base currency
+    pub exchange_rate: Option<f64>,
+    /// Posts even when this supplier's bill reference was already entered
+    pub allow_duplicate_reference: Option<bool>,
+    /// Saves as a draft: no ledger or stock effect until approved
+    pub as_draft: Option<bool>,
+    /// Freight, duty etc. capitalised into the cost of the product lines. On
+    /// update, omitting it keeps the stored costs; an empty list removes them.
+    pub additional_costs: Option<Vec<PurchaseAdditionalCost>>,
 }
 
+#[derive(Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct PurchaseAdditionalCost {
+    pub description: Option<String>,
+    pub amount: f64,
+    /// Expense or payable account credited for this cost
+    pub account_id: String,
+}
+
+/// Voucher number of a live purchase invoice from `supplier_id` carrying the
+/// same bill reference (trimmed, case-insensitive), if any.
+async fn find_duplicate_purchase_reference(
+    pool: &SqlitePool,
+    supplier_id: &str,
+    reference: &str,
+) -> Result<Option<String>, String> {
+    let reference = reference.trim();
+    if reference.is_empty() {
+        return Ok(None);
+    }
+    sqlx::query_scalar(
+        "SELECT voucher_no FROM vouchers
+         WHERE voucher_type = 'purchase_invoice'
+         AND party_id = ?
+         AND LOWER(TRIM(reference)) = LOWER(?)
+         AND deleted_at IS NULL
+         ORDER BY voucher_date ASC
+         LIMIT 1",
+    )
+    .bind(supplier_id)
+    .bind(reference)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Lets the purchase form warn about a repeated supplier bill before submit.
+/// Returns the voucher number of the existing invoice, if any.
+#[tauri::command]
+pub async fn check_duplicate_purchase_reference(
+    registry: State<'_, Arc<DbRegistry>>,
+    supplier_id: String,
+    reference: String,
+) -> Result<Option<String>, String> {
+    let pool = registry.active_pool().await?;
+    find_duplicate_purchase_reference(&pool, &supplier_id, &reference).await
+}
+
+/// Lists posted invoices unless `status` asks for `draft` or `pending` ones.
 #[tauri::command]
 pub async fn get_purchase_invoices(
     registry: State<'_, Arc<DbRegistry>>,
-) -> Result<Vec<PurchaseInvoice>, String> {
+    mismatched_only: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    status: Option<String>,
+) -> Result<InvoicePage<PurchaseInvoice>, String> {
     let pool = registry.active_pool().await?;
-    let invoices = sqlx::query_as::<_, PurchaseInvoice>(
+    let status = status.unwrap_or_else(|| "posted".to_string());
+    let mismatch_filter = if mismatched_only.unwrap_or(false) {
+        "HAVING total_mismatch = 1"
+    } else {
+        ""
+    };
+    let (date_filter, date_binds) = invoice_date_filter(&from_date, &to_date);
+    let base_query = format!(
         "SELECT 
             v.id,
             v.voucher_no,
@@ -490,7 +689,8 @@ pub async fn get_purchase_invoices(
             v.reference,
             v.total_amount,
             ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as tax_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as grand_total,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2) as grand_total,
+            CAST(COALESCE(v.round_off_amount, 0) AS REAL) as round_off_amount,
             v.discount_rate,
             v.discount_amount,
             v.narration,
@@ -498,20 +698,44 @@ pub async fn get_purchase_invoices(
             v.created_at,
             v.deleted_at,
             u.full_name as created_by_name,
-            COALESCE(v.tax_inclusive, 0) as tax_inclusive
+            COALESCE(v.tax_inclusive, 0) as tax_inclusive,
+            v.currency,
+            COALESCE(v.exchange_rate, 1.0) as exchange_rate,
+            ABS(COALESCE(v.subtotal, 0) - COALESCE(v.discount_amount, 0) - COALESCE(v.total_amount, 0)) > 0.01 as total_mismatch
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
         LEFT JOIN users u ON v.created_by = u.id
         WHERE v.voucher_type = 'purchase_invoice' AND v.deleted_at IS NULL
+          AND COALESCE(v.status, 'posted') = ?{}
         GROUP BY v.id
-        ORDER BY v.voucher_date DESC, v.id DESC",
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+        {}",
+        date_filter, mismatch_filter
+    );
+
+    let count_query = format!("SELECT COUNT(*) FROM ({})", base_query);
+    let mut count_q = sqlx::query_scalar::<_, i64>(&count_query).bind(&status);
+    for date in &date_binds {
+        count_q = count_q.bind(*date);
+    }
+    let total_count = count_q.fetch_one(&pool).await.map_err(|e| e.to_string())?;
+
+    let query = format!(
+        "{} ORDER BY v.voucher_date DESC, v.id DESC LIMIT ? OFFSET ?",
+        base_query
+    );
+    let mut q = sqlx::query_as::<_, PurchaseInvoice>(&query).bind(&status);
+    for date in &date_binds {
+        q = q.bind(*date);
+    }
+    let rows = q
+        .bind(limit.unwrap_or(DEFAULT_INVOICE_PAGE_SIZE))
+        .bind(offset.unwrap_or(0))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    Ok(invoices)
+    Ok(InvoicePage { rows, total_count })
 }
 
 #[tauri::command]
@@ -531,7 +755,8 @@ pub async fn get_purchase_invoice(
             v.reference,
             v.total_amount,
             ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as tax_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as grand_total,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2) as grand_total,
+            CAST(COALESCE(v.round_off_amount, 0) AS REAL) as round_off_amount,
             v.discount_rate,
             v.discount_amount,
             v.narration,
@@ -539,7 +764,9 @@ pub async fn get_purchase_invoice(
             v.created_at,
             v.deleted_at,
             u.full_name as created_by_name,
-            COALESCE(v.tax_inclusive, 0) as tax_inclusive
+            COALESCE(v.tax_inclusive, 0) as tax_inclusive,
+            v.currency,
+            COALESCE(v.exchange_rate, 1.0) as exchange_rate
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
@@ -556,25 +783,28 @@ pub async fn get_purchase_invoice(
     Ok(invoice)
 }
 
+/// ORDER BY clause for invoice item listings. `sort_by` accepts "code" or "name";
+/// anything else is rejected, and None keeps insertion order.
+fn invoice_items_order_clause(sort_by: Option<&str>) -> Result<&'static str, String> {
+    match sort_by.map(|s| s.trim().to_lowercase()).as_deref() {
+        None | Some("") => Ok(""),
+        Some("code") => Ok("ORDER BY COALESCE(p.code, s.code) COLLATE NOCASE, vi.id"),
+        Some("name") => Ok("ORDER BY COALESCE(p.name, s.name) COLLATE NOCASE, vi.id"),
+        Some(other) => Err(format!(
+            "Invalid sort_by '{}': expected 'code' or 'name'",
+            other
+        )),
+    }
+}
+
 #[tauri::command]
 pub async fn get_purchase_invoice_items(
     registry: State<'_, Arc<DbRegistry>>,
     voucher_id: String,
+    sort_by: Option<String>,
 ) -> Result<Vec<PurchaseInvoiceItem>, String> {
     let pool = registry.active_pool().await?;
-    sqlx::query_as::<_, PurchaseInvoiceItem>(
-        "SELECT vi.*,
-                COALESCE(p.code, s.code) as product_code,
-                COALESCE(p.name, s.name) as product_name
-         FROM voucher_items vi
-         LEFT JOIN products p ON vi.product_id = p.id
-         LEFT JOIN services s ON vi.service_id = s.id
-         WHERE vi.voucher_id = ?",
-    )
-    .bind(voucher_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| e.to_string())
+    get_purchase_invoice_items_with_pool(&pool, &voucher_id, sort_by.as_deref()).await
 }
 
 /// Internal version for use by other modules (e.g., templates.rs)
@@ -593,7 +823,8 @@ pub(crate) async fn get_purchase_invoice_with_pool(
             v.reference,
             v.total_amount,
             ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as tax_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as grand_total,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2) as grand_total,
+            CAST(COALESCE(v.round_off_amount, 0) AS REAL) as round_off_amount,
             v.discount_rate,
             v.discount_amount,
             v.narration,
@@ -601,7 +832,9 @@ pub(crate) async fn get_purchase_invoice_with_pool(
             v.created_at,
             v.deleted_at,
             u.full_name as created_by_name,
-            COALESCE(v.tax_inclusive, 0) as tax_inclusive
+            COALESCE(v.tax_inclusive, 0) as tax_inclusive,
+            v.currency,
+            COALESCE(v.exchange_rate, 1.0) as exchange_rate
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
@@ -620,31 +853,71 @@ pub(crate) async fn get_purchase_invoice_with_pool(
 pub(crate) async fn get_purchase_invoice_items_with_pool(
     pool: &SqlitePool,
     voucher_id: &str,
+    sort_by: Option<&str>,
 ) -> Result<Vec<PurchaseInvoiceItem>, String> {
-    sqlx::query_as::<_, PurchaseInvoiceItem>(
+    let order_clause = invoice_items_order_clause(sort_by)?;
+    let query = format!(
         "SELECT vi.*,
                 COALESCE(p.code, s.code) as product_code,
                 COALESCE(p.name, s.name) as product_name
          FROM voucher_items vi
          LEFT JOIN products p ON vi.product_id = p.id
          LEFT JOIN services s ON vi.service_id = s.id
-         WHERE vi.voucher_id = ?",
-    )
-    .bind(voucher_id)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| e.to_string())
+         WHERE vi.voucher_id = ?
+         {}",
+        order_clause
+    );
+    sqlx::query_as::<_, PurchaseInvoiceItem>(&query)
+        .bind(voucher_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn create_purchase_invoice(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     invoice: CreatePurchaseInvoice,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    validate_voucher_date(&pool, &invoice.voucher_date, invoice.allow_future).await?;
+    if !invoice.allow_duplicate_reference.unwrap_or(false) {
+        if let Some(reference) = invoice.reference.as_deref() {
+            if let Some(existing_no) =
+                find_duplicate_purchase_reference(&pool, &invoice.supplier_id, reference).await?
+            {
+                return Err(format!(
+                    "Duplicate supplier bill reference '{}' (already entered on {})",
+                    reference.trim(),
+                    existing_no
+                ));
+            }
+        }
+    }
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
-
-    let voucher_no = get_next_voucher_number(&pool, "purchase_invoice").await?;
+    let (currency, exchange_rate) =
+        resolve_invoice_currency(&mut tx, invoice.currency.as_deref(), invoice.exchange_rate)
+            .await?;
+    ensure_party_account(&mut tx, &invoice.supplier_id, "supplier").await?;
+    ensure_products_active(
+        &mut tx,
+        invoice
+            .items
+            .iter()
+            .filter(|item| item.item_type != "service")
+            .filter_map(|item| item.product_id.as_deref()),
+    )
+    .await?;
+
+    let branch_id = session_store.active_branch_id();
+    let voucher_no = get_next_voucher_number(
+        &pool,
+        "purchase_invoice",
+        &invoice.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
 
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -722,28 +995,28 @@ pub async fn create_purchase_invoice(
         } else {
             resolved_product_ids[i].as_deref().unwrap_or("")
         };
-        prepared_lines.push(
-            prepare_voucher_line(
-                &mut tx,
-                &pool,
-                "purchase",
-                &item.item_type,
-                item_id,
-                item.unit_id.as_deref(),
-                item.description.clone(),
-                item.initial_quantity,
-                item.count,
-                item.deduction_per_unit,
-                item.rate,
-                item.tax_rate,
-                item.discount_percent,
-                item.discount_amount,
-                item.remarks.clone(),
-                tax_inclusive,
-                gst_disabled,
-            )
-            .await?,
-        );
+        let mut line = prepare_voucher_line(
+            &mut tx,
+            &pool,
+            "purchase",
+            &item.item_type,
+            item_id,
+            item.unit_id.as_deref(),
+            item.description.clone(),
+            item.initial_quantity,
+            item.count,
+            item.deduction_per_unit,
+            item.rate,
+            item.tax_rate,
+            item.discount_percent,
+            item.discount_amount,
+            item.remarks.clone(),
+            tax_inclusive,
+            gst_disabled,
+        )
+        .await?;
+        line.inter_state = parse_tax_type(item.tax_type.as_deref())?;
+        prepared_lines.push(line);
     }
 
     let (processed, discount_rate, discount_amount) = finalize_processed_items(
@@ -759,17 +1032,33 @@ pub async fn create_purchase_invoice(
     let total_igst = processed.total_igst;
     let total_amount = round2(subtotal - discount_amount);
     let total_tax = round2(total_cgst + total_sgst + total_igst);
-    let grand_total = round2(total_amount + total_tax);
+    let (grand_total, round_off_amount) =
+        apply_invoice_round_off(&mut tx, round2(total_amount + total_tax)).await?;
+    let additional_costs = invoice.additional_costs.clone().unwrap_or_default();
+    let additional_cost_total =
+        validate_additional_costs(&mut tx, &additional_costs, &processed_items).await?;
+
+    let narration = resolve_narration(
+        &mut tx,
+        invoice.narration.as_ref(),
+        "purchase_invoice",
+        Some(&invoice.supplier_id),
+        &voucher_no,
+    )
+    .await?;
 
     let voucher_id = Uuid::now_v7().to_string();
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total, currency, exchange_rate, round_off_amount, base_grand_total, branch_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&voucher_id).bind(&voucher_no).bind("purchase_invoice").bind(&invoice.voucher_date).bind(&invoice.supplier_id)
     .bind(&invoice.party_type).bind(&invoice.reference).bind(subtotal).bind(discount_rate)
-    .bind(discount_amount).bind(total_tax).bind(total_amount).bind(&invoice.narration)
-    .bind(&invoice.user_id).bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total).execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    .bind(discount_amount).bind(total_tax).bind(total_amount).bind(&narration)
+    .bind(&invoice.user_id).bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total)
+    .bind(&currency).bind(exchange_rate).bind(round_off_amount).bind(round2(grand_total * exchange_rate))
+    .bind(&branch_id)
+    .execute(&mut *tx).await.map_err(|e| e.to_string())?;
 
     // Insert items
     for item in &processed_items {
@@ -789,16 +1078,18 @@ pub async fn create_purchase_invoice(
     }
 
     // ============= INSERT STOCK MOVEMENTS (IN) =============
-    for item in &processed_items {
+    let landed_costs = allocate_landed_cost(&processed_items, additional_cost_total);
+    for (item, landed_cost) in processed_items.iter().zip(landed_costs) {
         if item.item_type == "service" {
             continue;
         } // Services have no stock
         let sm_id = Uuid::now_v7().to_string();
         let qty = item.base_quantity;
+        // Stock is valued in the base currency, including its share of landed cost
         let rate_per_base = if qty > 0.0 {
-            item.amount / qty
+            (item.amount + landed_cost) * exchange_rate / qty
         } else {
-            item.rate
+            item.rate * exchange_rate
         };
         let amount = qty * rate_per_base;
         sqlx::query(
@@ -836,7 +1127,7 @@ pub async fn create_purchase_invoice(
         if row.tax_amount > 0.0 {
             let accounts = crate::commands::tax_utils::resolve_gst_account_names(
                 row.resolved_gst_rate,
-                is_inter_state,
+                row.igst_rate > 0.0,
                 true,
             );
             if let Some(cgst_acc) = accounts.cgst_account {
@@ -851,45 +1142,51 @@ pub async fn create_purchase_invoice(
         }
     }
 
+    let mut journal_lines = Vec::new();
+
     // Party entry (Cr supplier)
-    sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-        .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(&party_id).bind(0.0).bind(total_amount + total_tax)
-        .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    journal_lines.push(JournalLine {
+        account_id: party_id.clone(),
+        debit: 0.0,
+        credit: grand_total,
+        is_manual: false,
+        narration: None,
+    });
 
     // Dr 5001 Purchases for product lines
     if product_subtotal > 0.0 {
-        let purchases_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5001'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(purchases_acc).bind(product_subtotal).bind(0.0)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let purchases_acc = resolve_system_account(&mut *tx, "purchases").await?;
+        journal_lines.push(JournalLine {
+            account_id: purchases_acc,
+            debit: product_subtotal,
+            credit: 0.0,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     // Dr 5011 Service Expenses for service lines
     if service_subtotal > 0.0 {
-        let svc_exp_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5011'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(svc_exp_acc).bind(service_subtotal).bind(0.0)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let svc_exp_acc = resolve_system_account(&mut *tx, "service_expenses").await?;
+        journal_lines.push(JournalLine {
+            account_id: svc_exp_acc,
+            debit: service_subtotal,
+            credit: 0.0,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     // Discount entry
     if discount_amount > 0.0 {
-        let dis_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4004'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(dis_acc).bind(0.0).bind(discount_amount)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let dis_acc = resolve_system_account(&mut *tx, "discount_received").await?;
+        journal_lines.push(JournalLine {
+            account_id: dis_acc,
+            debit: 0.0,
+            credit: discount_amount,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     // Tax entries
@@ -899,12 +1196,33 @@ pub async fn create_purchase_invoice(
                 &mut tx, &acc_name, false,
             )
             .await?;
-            sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-                .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(acc_id).bind(amt).bind(0.0)
-                .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+            journal_lines.push(JournalLine {
+                account_id: acc_id,
+                debit: amt,
+                credit: 0.0,
+                is_manual: false,
+                narration: None,
+            });
         }
     }
 
+    // Landed costs: Dr Purchases / Cr the account each cost is owed to
+    journal_lines.extend(landed_cost_journal_lines(&mut tx, &additional_costs).await?);
+    save_additional_costs(&mut tx, &voucher_id, &additional_costs).await?;
+
+    if let Some(line) = round_off_journal_line(&mut tx, round_off_amount, false).await? {
+        journal_lines.push(line);
+    }
+
+    convert_journal_lines_to_base(&mut journal_lines, exchange_rate);
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
+
+    if invoice.as_draft.unwrap_or(false) {
+        hold_voucher_postings(&mut tx, &voucher_id).await?;
+    }
+
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &voucher_id, "create").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_id.to_string())
 }
@@ -912,9 +1230,11 @@ pub async fn create_purchase_invoice(
 #[tauri::command]
 pub async fn delete_purchase_invoice(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Get all payment/receipt vouchers created from this invoice
@@ -984,6 +1304,8 @@ pub async fn delete_purchase_invoice(
         .await
         .map_err(|e| e.to_string())?;
 
+    delete_held_postings(&mut tx, &id).await?;
+
     // Soft delete the voucher
     sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND voucher_type = 'purchase_invoice'")
         .bind(&id)
@@ -991,19 +1313,28 @@ pub async fn delete_purchase_invoice(
         .await
         .map_err(|e| e.to_string())?;
 
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &id, "delete").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
 #[tauri::command]
 pub async fn update_purchase_invoice(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     invoice: CreatePurchaseInvoice,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    ensure_voucher_editable(&pool, &id).await?;
+    validate_voucher_date(&pool, &invoice.voucher_date, invoice.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let (currency, exchange_rate) =
+        resolve_invoice_currency(&mut tx, invoice.currency.as_deref(), invoice.exchange_rate)
+            .await?;
+    ensure_party_account(&mut tx, &invoice.supplier_id, "supplier").await?;
 
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -1078,28 +1409,28 @@ pub async fn update_purchase_invoice(
         } else {
             resolved_product_ids[i].as_deref().unwrap_or("")
         };
-        prepared_lines.push(
-            prepare_voucher_line(
-                &mut tx,
-                &pool,
-                "purchase",
-                &item.item_type,
-                item_id,
-                item.unit_id.as_deref(),
-                item.description.clone(),
-                item.initial_quantity,
-                item.count,
-                item.deduction_per_unit,
-                item.rate,
-                item.tax_rate,
-                item.discount_percent,
-                item.discount_amount,
-                item.remarks.clone(),
-                tax_inclusive,
-                gst_disabled,
-            )
-            .await?,
-        );
+        let mut line = prepare_voucher_line(
+            &mut tx,
+            &pool,
+            "purchase",
+            &item.item_type,
+            item_id,
+            item.unit_id.as_deref(),
+            item.description.clone(),
+            item.initial_quantity,
+            item.count,
+            item.deduction_per_unit,
+            item.rate,
+            item.tax_rate,
+            item.discount_percent,
+            item.discount_amount,
+            item.remarks.clone(),
+            tax_inclusive,
+            gst_disabled,
+        )
+        .await?;
+        line.inter_state = parse_tax_type(item.tax_type.as_deref())?;
+        prepared_lines.push(line);
     }
 
     let (processed, discount_rate, discount_amount) = finalize_processed_items(
@@ -1115,7 +1446,15 @@ pub async fn update_purchase_invoice(
     let total_igst = processed.total_igst;
     let total_amount = round2(subtotal - discount_amount);
     let total_tax = round2(total_cgst + total_sgst + total_igst);
-    let grand_total = round2(total_amount + total_tax);
+    let (grand_total, round_off_amount) =
+        apply_invoice_round_off(&mut tx, round2(total_amount + total_tax)).await?;
+    // Omitted costs keep what is stored; only an explicit list replaces them
+    let additional_costs = match &invoice.additional_costs {
+        Some(costs) => costs.clone(),
+        None => stored_additional_costs(&mut *tx, &id).await?,
+    };
+    let additional_cost_total =
+        validate_additional_costs(&mut tx, &additional_costs, &processed_items).await?;
 
     let voucher_id = id;
     let old_party_id: Option<String> = sqlx::query_scalar(
@@ -1129,14 +1468,16 @@ pub async fn update_purchase_invoice(
         "UPDATE vouchers 
          SET voucher_date = ?, party_id = ?, party_type = ?, reference = ?, subtotal = ?, 
              discount_rate = ?, discount_amount = ?, tax_amount = ?, total_amount = ?, narration = ?,
-             tax_inclusive = ?, cgst_amount = ?, sgst_amount = ?, igst_amount = ?, grand_total = ?
+             tax_inclusive = ?, cgst_amount = ?, sgst_amount = ?, igst_amount = ?, grand_total = ?,
+             currency = ?, exchange_rate = ?, round_off_amount = ?, base_grand_total = ?
          WHERE id = ?"
     )
     .bind(&invoice.voucher_date).bind(&invoice.supplier_id).bind(&invoice.party_type).bind(&invoice.reference)
     .bind(subtotal).bind(discount_rate).bind(discount_amount)
     .bind(total_tax).bind(total_amount).bind(&invoice.narration)
     .bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst)
-    .bind(grand_total).bind(&voucher_id)
+    .bind(grand_total).bind(&currency).bind(exchange_rate).bind(round_off_amount)
+    .bind(round2(grand_total * exchange_rate)).bind(&voucher_id)
     .execute(&mut *tx).await.map_err(|e| e.to_string())?;
 
     if let Some(old_id) = &old_party_id {
@@ -1295,16 +1636,18 @@ pub async fn update_purchase_invoice(
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
-    for item in &processed_items {
+    let landed_costs = allocate_landed_cost(&processed_items, additional_cost_total);
+    for (item, landed_cost) in processed_items.iter().zip(landed_costs) {
         if item.item_type == "service" {
             continue;
         } // Services have no stock
         let sm_id = Uuid::now_v7().to_string();
         let qty = item.base_quantity;
+        // Stock is valued in the base currency, including its share of landed cost
         let rate_per_base = if qty > 0.0 {
-            item.amount / qty
+            (item.amount + landed_cost) * exchange_rate / qty
         } else {
-            item.rate
+            item.rate * exchange_rate
         };
         let amount = qty * rate_per_base;
         sqlx::query(
@@ -1348,7 +1691,7 @@ pub async fn update_purchase_invoice(
         if row.tax_amount > 0.0 {
             let accounts = crate::commands::tax_utils::resolve_gst_account_names(
                 row.resolved_gst_rate,
-                is_inter_state,
+                row.igst_rate > 0.0,
                 true,
             );
             if let Some(cgst_acc) = accounts.cgst_account {
@@ -1363,41 +1706,47 @@ pub async fn update_purchase_invoice(
         }
     }
 
+    let mut journal_lines = Vec::new();
+
     // Party entry (Cr supplier)
-    sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-        .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(&party_id).bind(0.0).bind(total_amount + total_tax)
-        .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    journal_lines.push(JournalLine {
+        account_id: party_id.clone(),
+        debit: 0.0,
+        credit: grand_total,
+        is_manual: false,
+        narration: None,
+    });
 
     if product_subtotal > 0.0 {
-        let purchases_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5001'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(purchases_acc).bind(product_subtotal).bind(0.0)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let purchases_acc = resolve_system_account(&mut *tx, "purchases").await?;
+        journal_lines.push(JournalLine {
+            account_id: purchases_acc,
+            debit: product_subtotal,
+            credit: 0.0,
+            is_manual: false,
+            narration: None,
+        });
     }
     if service_subtotal > 0.0 {
-        let svc_exp_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5011'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(svc_exp_acc).bind(service_subtotal).bind(0.0)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let svc_exp_acc = resolve_system_account(&mut *tx, "service_expenses").await?;
+        journal_lines.push(JournalLine {
+            account_id: svc_exp_acc,
+            debit: service_subtotal,
+            credit: 0.0,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     if discount_amount > 0.0 {
-        let dis_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4004'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(dis_acc).bind(0.0).bind(discount_amount)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let dis_acc = resolve_system_account(&mut *tx, "discount_received").await?;
+        journal_lines.push(JournalLine {
+            account_id: dis_acc,
+            debit: 0.0,
+            credit: discount_amount,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     for (acc_name, amt) in tax_ledgers {
@@ -1406,119 +1755,821 @@ pub async fn update_purchase_invoice(
                 &mut tx, &acc_name, false,
             )
             .await?;
-            sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-                .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(acc_id).bind(amt).bind(0.0)
-                .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+            journal_lines.push(JournalLine {
+                account_id: acc_id,
+                debit: amt,
+                credit: 0.0,
+                is_manual: false,
+                narration: None,
+            });
         }
     }
 
+    // Landed costs: Dr Purchases / Cr the account each cost is owed to
+    journal_lines.extend(landed_cost_journal_lines(&mut tx, &additional_costs).await?);
+    save_additional_costs(&mut tx, &voucher_id, &additional_costs).await?;
+
+    if let Some(line) = round_off_journal_line(&mut tx, round_off_amount, false).await? {
+        journal_lines.push(line);
+    }
+
+    convert_journal_lines_to_base(&mut journal_lines, exchange_rate);
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
+
+    set_voucher_draft_state(&mut tx, &voucher_id, invoice.as_draft.unwrap_or(false)).await?;
+
+    let actor = session_store.current_user_id();
+    record_voucher_audit(&mut *tx, actor.as_deref(), &voucher_id, "update").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_id.to_string())
 }
 
-// ============= SALES INVOICE =============
-#[derive(Serialize, Deserialize, sqlx::FromRow)]
-pub struct SalesInvoice {
-    pub id: String,
-    pub voucher_no: String,
-    pub voucher_date: String,
-    pub customer_id: String,
-    pub customer_name: String,
-    pub salesperson_id: Option<String>,
-    pub party_type: String,
-    pub reference: Option<String>,
-    pub total_amount: f64,
-    pub tax_amount: f64,
-    pub grand_total: f64,
-    pub discount_rate: Option<f64>,
-    pub discount_amount: Option<f64>,
-    pub narration: Option<String>,
-    pub status: String,
-    pub created_at: String,
-    pub deleted_at: Option<String>,
-    pub created_by_name: Option<String>,
-    pub tax_inclusive: i64,
-    pub linked_return_id: Option<String>,
-}
+// ============= LANDED COST =============
 
-#[derive(Serialize, Deserialize, sqlx::FromRow)]
-pub struct SalesInvoiceItem {
-    pub id: String,
-    pub voucher_id: String,
-    pub item_type: Option<String>,
-    pub product_id: Option<String>,
-    pub service_id: Option<String>,
-    pub product_code: Option<String>,
-    pub product_name: Option<String>,
-    pub description: Option<String>,
-    pub initial_quantity: f64,
-    pub count: i64,
-    pub deduction_per_unit: f64,
-    pub final_quantity: f64,
-    pub unit_id: Option<String>,
-    pub base_quantity: f64,
-    pub rate: f64,
-    pub amount: f64,     // gross amount (original, before invoice discount)
-    pub net_amount: f64, // net amount (after invoice discount)
-    pub tax_rate: f64,
+/// Validates a purchase's additional costs against its lines and returns their
+/// total. Costs can only be capitalised into product (stock) lines.
+async fn validate_additional_costs(
+    tx: &mut Transaction<'_, Sqlite>,
+    costs: &[PurchaseAdditionalCost],
+    items: &[ProcessedVoucherItem],
+) -> Result<f64, String> {
+    if costs.is_empty() {
+        return Ok(0.0);
+    }
+    if !items
+        .iter()
+        .any(|item| item.item_type != "service" && item.amount > 0.0)
+    {
+        return Err("Additional costs need at least one product line to absorb them".to_string());
+    }
 
-    pub tax_amount: f64,
-    pub discount_percent: f64,
-    pub discount_amount: f64,
-    pub invoice_discount_amount: f64,
-    pub remarks: Option<String>,
-    pub cgst_rate: f64,
-    pub sgst_rate: f64,
-    pub igst_rate: f64,
-    pub cgst_amount: f64,
-    pub sgst_amount: f64,
-    pub igst_amount: f64,
-    pub hsn_sac_code: Option<String>,
-    pub gst_slab_id: Option<String>,
-    pub resolved_gst_rate: f64,
-}
+    for cost in costs {
+        if cost.amount <= 0.0 {
+            return Err("Every additional cost must have an amount greater than zero".to_string());
+        }
+        let account_exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM chart_of_accounts WHERE id = ? AND deleted_at IS NULL)",
+        )
+        .bind(&cost.account_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if !account_exists {
+            return Err(format!(
+                "Account for additional cost '{}' not found",
+                cost.description.as_deref().unwrap_or("")
+            ));
+        }
+    }
 
-#[derive(Deserialize)]
-pub struct CreateSalesInvoiceItem {
-    #[serde(default = "default_item_type")]
-    pub item_type: String, // "product" | "service"
-    pub product_id: Option<String>, // nullable — None for service lines
-    pub service_id: Option<String>, // Some for service lines
-    pub unit_id: Option<String>,
-    pub description: Option<String>,
-    pub initial_quantity: f64,
-    pub count: i64,
-    pub deduction_per_unit: f64,
-    pub rate: f64,
-    pub tax_rate: f64,
-    pub discount_percent: Option<f64>,
-    pub discount_amount: Option<f64>,
-    pub remarks: Option<String>,
+    Ok(round2(costs.iter().map(|cost| cost.amount).sum()))
 }
 
-#[derive(Deserialize)]
-pub struct CreateSalesInvoice {
-    pub customer_id: String,
-    pub salesperson_id: Option<String>,
-    pub party_type: String,
-    pub voucher_date: String,
-    pub reference: Option<String>,
-    pub narration: Option<String>,
-    pub discount_rate: Option<f64>,
+/// Splits `total` across the product lines in proportion to their line amount
+/// (services get nothing). The last product line takes the rounding remainder.
+fn allocate_landed_cost(items: &[ProcessedVoucherItem], total: f64) -> Vec<f64> {
+    let mut shares = vec![0.0; items.len()];
+    let base: f64 = items
+        .iter()
+        .filter(|item| item.item_type != "service")
+        .map(|item| item.amount)
+        .sum();
+    if total <= 0.0 || base <= 0.0 {
+        return shares;
+    }
+
+    let mut allocated = 0.0;
+    let mut last_product = None;
+    for (index, item) in items.iter().enumerate() {
+        if item.item_type == "service" {
+            continue;
+        }
+        shares[index] = round2(total * item.amount / base);
+        allocated += shares[index];
+        last_product = Some(index);
+    }
+    if let Some(index) = last_product {
+        shares[index] = round2(shares[index] + total - allocated);
+    }
+    shares
+}
+
+/// Dr Purchases / Cr the cost's own account, one pair per additional cost.
+async fn landed_cost_journal_lines(
+    tx: &mut Transaction<'_, Sqlite>,
+    costs: &[PurchaseAdditionalCost],
+) -> Result<Vec<JournalLine>, String> {
+    if costs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let purchases_acc = resolve_system_account(&mut **tx, "purchases").await?;
+
+    let mut lines = Vec::with_capacity(costs.len() * 2);
+    for cost in costs {
+        lines.push(JournalLine {
+            account_id: purchases_acc.clone(),
+            debit: cost.amount,
+            credit: 0.0,
+            is_manual: false,
+            narration: cost.description.clone(),
+        });
+        lines.push(JournalLine {
+            account_id: cost.account_id.clone(),
+            debit: 0.0,
+            credit: cost.amount,
+            is_manual: false,
+            narration: cost.description.clone(),
+        });
+    }
+    Ok(lines)
+}
+
+/// Replaces the additional costs recorded against a purchase invoice.
+async fn save_additional_costs(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_id: &str,
+    costs: &[PurchaseAdditionalCost],
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM purchase_additional_costs WHERE voucher_id = ?")
+        .bind(voucher_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for cost in costs {
+        sqlx::query(
+            "INSERT INTO purchase_additional_costs (id, voucher_id, description, amount, account_id)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(voucher_id)
+        .bind(&cost.description)
+        .bind(cost.amount)
+        .bind(&cost.account_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+async fn stored_additional_costs<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    voucher_id: &str,
+) -> Result<Vec<PurchaseAdditionalCost>, String> {
+    sqlx::query_as::<_, PurchaseAdditionalCost>(
+        "SELECT description, CAST(amount AS REAL) as amount, account_id
+         FROM purchase_additional_costs
+         WHERE voucher_id = ?
+         ORDER BY created_at ASC, id ASC",
+    )
+    .bind(voucher_id)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Additional costs capitalised into a purchase invoice, for editing.
+#[tauri::command]
+pub async fn get_purchase_additional_costs(
+    registry: State<'_, Arc<DbRegistry>>,
+    voucher_id: String,
+) -> Result<Vec<PurchaseAdditionalCost>, String> {
+    let pool = registry.active_pool().await?;
+    stored_additional_costs(&pool, &voucher_id).await
+}
+
+// ============= PARTY ACCOUNT CHECK =============
+
+/// Confirms the party's ledger account exists before an invoice posts to it.
+/// When it doesn't, the error names the party and the account code it should
+/// have, and says how to repair it, instead of surfacing a raw database error.
+pub(crate) async fn ensure_party_account(
+    tx: &mut Transaction<'_, Sqlite>,
+    party_id: &str,
+    party_kind: &str,
+) -> Result<(), String> {
+    let account: Option<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT account_name, account_code, deleted_at FROM chart_of_accounts WHERE id = ?",
+    )
+    .bind(party_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let (party_table, group_code) = if party_kind == "supplier" {
+        ("suppliers", "2001")
+    } else {
+        ("customers", "1003")
+    };
+    let expected_code = format!("{}-{}", group_code, party_id);
+
+    match account {
+        Some((_, _, None)) => Ok(()),
+        Some((name, code, Some(_))) => Err(format!(
+            "The ledger account for {} '{}' (account code {}) has been deleted. \
+             Restore the {} from the deleted {} list, then post the invoice again.",
+            party_kind, name, code, party_kind, party_table
+        )),
+        None => {
+            // The id may belong to the party master whose ledger account was removed
+            let party: Option<(String, String)> = sqlx::query_as(&format!(
+                "SELECT name, code FROM {} WHERE id = ?",
+                party_table
+            ))
+            .bind(party_id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+            Err(match party {
+                Some((name, code)) => format!(
+                    "No ledger account found for {} '{}' ({}, expected account code {}). \
+                     Re-save the {} or recreate its account in Chart of Accounts, then post the invoice again.",
+                    party_kind, name, code, expected_code, party_kind
+                ),
+                None => format!(
+                    "No ledger account found for the selected {} (expected account code {}). \
+                     Select the {} again or recreate its account in Chart of Accounts.",
+                    party_kind, expected_code, party_kind
+                ),
+            })
+        }
+    }
+}
+
+// ============= CREDIT LIMIT CHECK =============
+
+/// Rejects a sale that would take the customer's outstanding balance past
+/// their credit limit. A limit of 0 means unlimited. When an invoice is being
+/// edited, `replaced_voucher_id` keeps its current postings out of the balance
+/// so only the new amount counts.
+pub(crate) async fn ensure_within_credit_limit(
+    tx: &mut Transaction<'_, Sqlite>,
+    party_account_id: &str,
+    additional_amount: f64,
+    replaced_voucher_id: Option<&str>,
+) -> Result<(), String> {
+    let credit_limit: Option<f64> = sqlx::query_scalar(
+        "SELECT COALESCE(c.credit_limit, 0)
+         FROM chart_of_accounts coa
+         JOIN customers c ON c.id = coa.party_id
+         WHERE coa.id = ?",
+    )
+    .bind(party_account_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let credit_limit = credit_limit.unwrap_or(0.0);
+    if credit_limit <= 0.0 {
+        return Ok(());
+    }
+
+    let outstanding: f64 = sqlx::query_scalar(
+        "SELECT
+            COALESCE((SELECT CASE WHEN opening_balance_type = 'Dr' THEN opening_balance ELSE -opening_balance END
+                      FROM chart_of_accounts WHERE id = ?), 0)
+            + COALESCE((SELECT SUM(je.debit - je.credit)
+                        FROM journal_entries je
+                        JOIN vouchers v ON v.id = je.voucher_id
+                        WHERE je.account_id = ? AND v.deleted_at IS NULL
+                        AND (? IS NULL OR v.id != ?)), 0)",
+    )
+    .bind(party_account_id)
+    .bind(party_account_id)
+    .bind(replaced_voucher_id)
+    .bind(replaced_voucher_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let excess = round2(outstanding + additional_amount - credit_limit);
+    if excess > 0.0 {
+        return Err(format!(
+            "Credit limit exceeded by {:.2} (limit {:.2}, outstanding {:.2})",
+            excess, credit_limit, outstanding
+        ));
+    }
+    Ok(())
+}
+
+// ============= STOCK AVAILABILITY CHECK =============
+
+/// When the company has `prevent_negative_stock` enabled, rejects a sale whose
+/// lines would take any product's stock below zero. Quantities are compared in
+/// the base unit, summed across lines for the same product.
+async fn ensure_stock_available(
+    tx: &mut Transaction<'_, Sqlite>,
+    items: &[ProcessedVoucherItem],
+) -> Result<(), String> {
+    let mut required: Vec<(String, f64)> = Vec::new();
+    for item in items.iter().filter(|item| item.item_type != "service") {
+        let Some(product_id) = item.product_id.as_deref() else {
+            continue;
+        };
+        match required.iter_mut().find(|(id, _)| id == product_id) {
+            Some((_, qty)) => *qty += item.base_quantity,
+            None => required.push((product_id.to_string(), item.base_quantity)),
+        }
+    }
+    ensure_stock_covers(tx, required).await
+}
+
+/// The `prevent_negative_stock` check for quantities already totalled per
+/// product in the base unit. A negative quantity is stock coming back in.
+pub(crate) async fn ensure_stock_covers(
+    tx: &mut Transaction<'_, Sqlite>,
+    required: Vec<(String, f64)>,
+) -> Result<(), String> {
+    let enabled: Option<bool> = sqlx::query_scalar(
+        "SELECT COALESCE(prevent_negative_stock, 0) FROM company_profile ORDER BY id LIMIT 1",
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if !enabled.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let stock_query = super::reports::product_stock_qty_sql();
+    for (product_id, qty) in required {
+        let available: Option<f64> = sqlx::query_scalar(&stock_query)
+            .bind(&product_id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        let available = available.unwrap_or(0.0);
+        if available - qty < -0.0001 {
+            let name: Option<String> = sqlx::query_scalar("SELECT name FROM products WHERE id = ?")
+                .bind(&product_id)
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(|e| e.to_string())?;
+            return Err(format!(
+                "Insufficient stock for '{}': available {}, required {}",
+                name.unwrap_or(product_id),
+                available,
+                qty
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ============= ROUND OFF =============
+
+/// When the company has `round_invoice_totals` enabled, rounds an invoice's
+/// grand total to the nearest whole unit. Returns (grand_total, round_off).
+async fn apply_invoice_round_off(
+    tx: &mut Transaction<'_, Sqlite>,
+    grand_total: f64,
+) -> Result<(f64, f64), String> {
+    let enabled: Option<bool> = sqlx::query_scalar(
+        "SELECT COALESCE(round_invoice_totals, 0) FROM company_profile ORDER BY id LIMIT 1",
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if !enabled.unwrap_or(false) {
+        return Ok((grand_total, 0.0));
+    }
+
+    let rounded = grand_total.round();
+    Ok((rounded, round2(rounded - grand_total)))
+}
+
+/// Posts the rounding difference to 5012 Round Off so the voucher still
+/// balances against the rounded party line. `party_debited` is true for sales.
+async fn round_off_journal_line(
+    tx: &mut Transaction<'_, Sqlite>,
+    round_off: f64,
+    party_debited: bool,
+) -> Result<Option<JournalLine>, String> {
+    if round_off.abs() < 0.005 {
+        return Ok(None);
+    }
+
+    let account_id = resolve_system_account(&mut **tx, "round_off").await?;
+
+    // Rounding up on a sale is extra income (Cr); rounding up on a purchase is extra cost (Dr)
+    let amount = round_off.abs();
+    let credit_side = (round_off > 0.0) == party_debited;
+    Ok(Some(JournalLine {
+        account_id,
+        debit: if credit_side { 0.0 } else { amount },
+        credit: if credit_side { amount } else { 0.0 },
+        is_manual: false,
+        narration: Some("Round off".to_string()),
+    }))
+}
+
+/// Debit for a positive amount, credit for a negative one. Sales invoices may
+/// carry return lines (negative quantity), so a ledger's net can land on either side.
+fn signed_journal_line(account_id: String, amount: f64) -> JournalLine {
+    JournalLine {
+        account_id,
+        debit: amount.max(0.0),
+        credit: (-amount).max(0.0),
+        is_manual: false,
+        narration: None,
+    }
+}
+
+/// Stock direction for a sales line: a negative net quantity is a return taken back in.
+fn sales_line_movement_type(base_quantity: f64) -> &'static str {
+    if base_quantity < 0.0 {
+        "RETURN_IN"
+    } else {
+        "OUT"
+    }
+}
+
+// ============= ITEM PRODUCT CHECK =============
+
+/// Confirms every product referenced by invoice lines exists and is active, using a
+/// single lookup. Service lines are skipped. The error lists each offending product.
+pub(crate) async fn ensure_products_active<'a>(
+    tx: &mut Transaction<'_, Sqlite>,
+    product_ids: impl IntoIterator<Item = &'a str>,
+) -> Result<(), String> {
+    let mut ids: Vec<&str> = product_ids
+        .into_iter()
+        .filter(|id| !id.is_empty())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let query = format!(
+        "SELECT id, name, deleted_at IS NULL AND COALESCE(is_active, 1) = 1
+         FROM products WHERE id IN ({})",
+        placeholders
+    );
+    let mut q = sqlx::query_as::<_, (String, String, bool)>(&query);
+    for id in &ids {
+        q = q.bind(*id);
+    }
+    let found = q.fetch_all(&mut **tx).await.map_err(|e| e.to_string())?;
+
+    let mut problems = Vec::new();
+    for id in &ids {
+        match found.iter().find(|(found_id, _, _)| found_id == id) {
+            Some((_, _, true)) => {}
+            Some((_, name, false)) => problems.push(format!("'{}' is deleted or inactive", name)),
+            None => problems.push(format!("id {} does not exist", id)),
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invoice references unavailable products: {}. Remove or replace these lines and try again.",
+            problems.join("; ")
+        ))
+    }
+}
+
+// ============= REORDER DRAFT =============
+
+/// Drafts a purchase invoice for every product that has fallen below its reorder
+/// level and was last purchased from `supplier_id`. The draft carries no stock
+/// movements or journal entries; it is posted when saved via `update_purchase_invoice`.
+#[tauri::command]
+pub async fn draft_reorder_purchase(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    supplier_id: String,
+    user_id: Option<String>,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+
+    // Below-reorder products whose most recent posted purchase came from this supplier.
+    // Suggested quantity tops stock back up to the reorder level; rate is the last
+    // purchase rate per base unit.
+    let candidates_query = format!(
+        "SELECT
+            p.id,
+            p.unit_id,
+            CAST(p.reorder_level - COALESCE((
+                SELECT SUM(CASE
+                    WHEN sm.movement_type IN ({in_types}) THEN sm.quantity
+                    WHEN sm.movement_type IN ({out_types}) THEN -sm.quantity
+                    ELSE 0
+                END)
+                FROM stock_movements sm
+                JOIN vouchers sv ON sm.voucher_id = sv.id
+                WHERE sm.product_id = p.id AND sv.deleted_at IS NULL
+            ), 0) AS REAL) as suggested_qty,
+            CAST(COALESCE(last.rate_per_base, p.purchase_rate, 0) AS REAL) as rate
+        FROM products p
+        JOIN (
+            SELECT
+                vi.product_id,
+                v.party_id,
+                CASE WHEN COALESCE(vi.base_quantity, 0) > 0 THEN vi.amount / vi.base_quantity ELSE vi.rate END as rate_per_base,
+                ROW_NUMBER() OVER (
+                    PARTITION BY vi.product_id
+                    ORDER BY v.voucher_date DESC, v.id DESC
+                ) as rn
+            FROM voucher_items vi
+            JOIN vouchers v ON vi.voucher_id = v.id
+            WHERE v.voucher_type = 'purchase_invoice'
+              AND v.status = 'posted'
+              AND v.deleted_at IS NULL
+              AND vi.product_id IS NOT NULL
+        ) last ON last.product_id = p.id AND last.rn = 1
+        WHERE p.deleted_at IS NULL
+          AND COALESCE(p.is_master, 0) = 0
+          AND COALESCE(p.reorder_level, 0) > 0
+          AND last.party_id = ?
+          AND suggested_qty > 0
+        ORDER BY p.name ASC",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+    );
+    let candidates = sqlx::query_as::<_, (String, String, f64, f64)>(&candidates_query)
+        .bind(&supplier_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if candidates.is_empty() {
+        return Err("No products below reorder level were last purchased from this supplier".to_string());
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let branch_id = session_store.active_branch_id();
+    let voucher_date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let voucher_no = get_next_voucher_number(
+        &pool,
+        "purchase_invoice",
+        &voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
+
+    let company_state: Option<String> =
+        sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
+            .fetch_optional(&mut *tx)
+            .await
+            .ok()
+            .flatten();
+    let party_state: Option<String> =
+        sqlx::query_scalar("SELECT state FROM chart_of_accounts WHERE id = ?")
+            .bind(&supplier_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .ok()
+            .flatten();
+    let is_inter_state = crate::commands::tax_utils::is_inter_state(
+        company_state.as_deref(),
+        party_state.as_deref(),
+    );
+    let gst_enabled_globally: bool = sqlx::query_scalar::<_, String>(
+        "SELECT setting_value FROM app_settings WHERE setting_key = 'gst_enabled'",
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .ok()
+    .flatten()
+    .map(|v| v == "true")
+    .unwrap_or(false);
+
+    let mut prepared_lines = Vec::new();
+    for (product_id, unit_id, suggested_qty, rate) in &candidates {
+        prepared_lines.push(
+            prepare_voucher_line(
+                &mut tx,
+                &pool,
+                "purchase",
+                "product",
+                product_id,
+                Some(unit_id.as_str()),
+                None,
+                round2(*suggested_qty),
+                0,
+                0.0,
+                round2(*rate),
+                0.0,
+                None,
+                None,
+                None,
+                false,
+                !gst_enabled_globally,
+            )
+            .await?,
+        );
+    }
+
+    let (processed, discount_rate, discount_amount) =
+        finalize_processed_items(prepared_lines, is_inter_state, None, None);
+    let processed_items = processed.items;
+    let subtotal = processed.subtotal;
+    let total_cgst = processed.total_cgst;
+    let total_sgst = processed.total_sgst;
+    let total_igst = processed.total_igst;
+    let total_amount = round2(subtotal - discount_amount);
+    let total_tax = round2(total_cgst + total_sgst + total_igst);
+    let grand_total = round2(total_amount + total_tax);
+
+    let voucher_id = Uuid::now_v7().to_string();
+    sqlx::query(
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total, branch_id)
+         VALUES (?, ?, 'purchase_invoice', ?, ?, 'supplier', ?, ?, ?, ?, ?, 'Reorder suggestion', 'draft', ?, 0, ?, ?, ?, ?, ?)"
+    )
+    .bind(&voucher_id).bind(&voucher_no).bind(&voucher_date).bind(&supplier_id)
+    .bind(subtotal).bind(discount_rate).bind(discount_amount).bind(total_tax).bind(total_amount)
+    .bind(&user_id).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total)
+    .bind(&branch_id)
+    .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+
+    for item in &processed_items {
+        sqlx::query(
+            "INSERT INTO voucher_items (id, voucher_id, item_type, product_id, service_id, description, initial_quantity, count, deduction_per_unit, final_quantity, unit_id, base_quantity, rate, amount, net_amount, tax_rate, tax_amount, discount_percent, discount_amount, invoice_discount_amount, remarks, cgst_rate, sgst_rate, igst_rate, cgst_amount, sgst_amount, igst_amount, hsn_sac_code, gst_slab_id, resolved_gst_rate)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&item.id).bind(&voucher_id).bind(&item.item_type).bind(&item.product_id).bind(&item.service_id)
+        .bind(&item.description).bind(item.initial_quantity)
+        .bind(item.count).bind(item.deduction_per_unit).bind(item.final_quantity).bind(&item.unit_id).bind(item.base_quantity)
+        .bind(item.rate).bind(item.amount).bind(item.net_amount).bind(item.tax_rate).bind(item.tax_amount).bind(item.discount_percent).bind(item.discount_amount)
+        .bind(item.invoice_discount_amount).bind(&item.remarks).bind(item.cgst_rate).bind(item.sgst_rate).bind(item.igst_rate).bind(item.cgst_amount).bind(item.sgst_amount)
+        .bind(item.igst_amount).bind(&item.hsn_sac_code).bind(&item.gst_slab_id).bind(item.resolved_gst_rate)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    // NO STOCK MOVEMENTS or JOURNAL ENTRIES until the draft is posted
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(voucher_id)
+}
+
+// ============= SALES INVOICE =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct SalesInvoice {
+    pub id: String,
+    pub voucher_no: String,
+    pub voucher_date: String,
+    pub customer_id: String,
+    pub customer_name: String,
+    pub salesperson_id: Option<String>,
+    pub party_type: String,
+    pub reference: Option<String>,
+    pub total_amount: f64,
+    pub tax_amount: f64,
+    pub grand_total: f64,
+    /// Rounding added to reach `grand_total` (0 when totals aren't rounded)
+    pub round_off_amount: f64,
+    pub discount_rate: Option<f64>,
+    pub discount_amount: Option<f64>,
+    pub narration: Option<String>,
+    pub status: String,
+    pub created_at: String,
+    pub deleted_at: Option<String>,
+    pub created_by_name: Option<String>,
+    pub tax_inclusive: i64,
+    /// ISO code the invoice amounts are in; NULL on invoices from before multi-currency
+    pub currency: Option<String>,
+    /// Base-currency value of one unit of `currency`
+    pub exchange_rate: f64,
+    pub linked_return_id: Option<String>,
+    /// Stored `subtotal - discount_amount` disagrees with stored `total_amount` (list queries only)
+    #[sqlx(default)]
+    pub total_mismatch: bool,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct SalesInvoiceItem {
+    pub id: String,
+    pub voucher_id: String,
+    pub item_type: Option<String>,
+    pub product_id: Option<String>,
+    pub service_id: Option<String>,
+    pub product_code: Option<String>,
+    pub product_name: Option<String>,
+    pub description: Option<String>,
+    pub initial_quantity: f64,
+    pub count: i64,
+    pub deduction_per_unit: f64,
+    pub final_quantity: f64,
+    pub unit_id: Option<String>,
+    pub base_quantity: f64,
+    pub rate: f64,
+    pub amount: f64,     // gross amount (original, before invoice discount)
+    pub net_amount: f64, // net amount (after invoice discount)
+    pub tax_rate: f64,
+
+    pub tax_amount: f64,
+    pub discount_percent: f64,
+    pub discount_amount: f64,
+    pub invoice_discount_amount: f64,
+    pub remarks: Option<String>,
+    pub cgst_rate: f64,
+    pub sgst_rate: f64,
+    pub igst_rate: f64,
+    pub cgst_amount: f64,
+    pub sgst_amount: f64,
+    pub igst_amount: f64,
+    pub hsn_sac_code: Option<String>,
+    pub gst_slab_id: Option<String>,
+    pub resolved_gst_rate: f64,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSalesInvoiceItem {
+    #[serde(default = "default_item_type")]
+    pub item_type: String, // "product" | "service"
+    pub product_id: Option<String>, // nullable — None for service lines
+    pub service_id: Option<String>, // Some for service lines
+    pub unit_id: Option<String>,
+    pub description: Option<String>,
+    /// A net quantity below zero (here or via count/deduction) marks an item
+    /// returned on the same bill: it moves stock in and reduces the invoice total.
+    pub initial_quantity: f64,
+    pub count: i64,
+    pub deduction_per_unit: f64,
+    pub rate: f64,
+    pub tax_rate: f64,
+    pub discount_percent: Option<f64>,
+    pub discount_amount: Option<f64>,
+    pub remarks: Option<String>,
+    /// "intra" (CGST + SGST) or "inter" (IGST); defaults to the party's place of supply
+    pub tax_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSalesInvoice {
+    pub customer_id: String,
+    pub salesperson_id: Option<String>,
+    pub party_type: String,
+    pub voucher_date: String,
+    pub reference: Option<String>,
+    pub narration: Option<String>,
+    pub discount_rate: Option<f64>,
     pub discount_amount: Option<f64>,
     pub items: Vec<CreateSalesInvoiceItem>,
     pub user_id: Option<String>,
     pub tax_inclusive: Option<bool>,
     pub gst_disabled: Option<bool>,
     pub return_items: Option<Vec<CreateSalesReturnItem>>,
+    pub allow_future: Option<bool>,
+    /// Defaults to the company's base currency
+    pub currency: Option<String>,
+    /// Required when `currency` differs from the base currency
+    pub exchange_rate: Option<f64>,
+    /// Posts even when the customer's credit limit would be exceeded
+    pub override_credit_limit: Option<bool>,
+    /// Saves as a draft: no ledger or stock effect until approved
+    pub as_draft: Option<bool>,
 }
 
+/// Lists posted invoices unless `status` asks for `draft` or `pending` ones.
 #[tauri::command]
 pub async fn get_sales_invoices(
     registry: State<'_, Arc<DbRegistry>>,
-) -> Result<Vec<SalesInvoice>, String> {
+    mismatched_only: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    status: Option<String>,
+) -> Result<InvoicePage<SalesInvoice>, String> {
     let pool = registry.active_pool().await?;
-    sqlx::query_as::<_, SalesInvoice>(
+    get_sales_invoices_with_pool(
+        &pool,
+        mismatched_only,
+        limit,
+        offset,
+        from_date,
+        to_date,
+        status,
+    )
+    .await
+}
+
+pub(crate) async fn get_sales_invoices_with_pool(
+    pool: &SqlitePool,
+    mismatched_only: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    status: Option<String>,
+) -> Result<InvoicePage<SalesInvoice>, String> {
+    let status = status.unwrap_or_else(|| "posted".to_string());
+    let mismatch_filter = if mismatched_only.unwrap_or(false) {
+        "HAVING total_mismatch = 1"
+    } else {
+        ""
+    };
+    let (date_filter, date_binds) = invoice_date_filter(&from_date, &to_date);
+    let base_query = format!(
         "SELECT 
             v.id,
             v.voucher_no,
@@ -1530,7 +2581,8 @@ pub async fn get_sales_invoices(
             v.reference,
             v.total_amount,
             ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as tax_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as grand_total,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2) as grand_total,
+            CAST(COALESCE(v.round_off_amount, 0) AS REAL) as round_off_amount,
             v.discount_rate,
             v.discount_amount,
             v.narration,
@@ -1539,18 +2591,44 @@ pub async fn get_sales_invoices(
             v.deleted_at,
             u.full_name as created_by_name,
             COALESCE(v.tax_inclusive, 0) as tax_inclusive,
-            v.linked_return_id
+            v.currency,
+            COALESCE(v.exchange_rate, 1.0) as exchange_rate,
+            v.linked_return_id,
+            ABS(COALESCE(v.subtotal, 0) - COALESCE(v.discount_amount, 0) - COALESCE(v.total_amount, 0)) > 0.01 as total_mismatch
          FROM vouchers v
          LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
          LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
          LEFT JOIN users u ON v.created_by = u.id
          WHERE v.voucher_type = 'sales_invoice' AND v.deleted_at IS NULL
+           AND COALESCE(v.status, 'posted') = ?{}
          GROUP BY v.id
-         ORDER BY v.voucher_date DESC, v.id DESC",
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| e.to_string())
+         {}",
+        date_filter, mismatch_filter
+    );
+
+    let count_query = format!("SELECT COUNT(*) FROM ({})", base_query);
+    let mut count_q = sqlx::query_scalar::<_, i64>(&count_query).bind(&status);
+    for date in &date_binds {
+        count_q = count_q.bind(*date);
+    }
+    let total_count = count_q.fetch_one(pool).await.map_err(|e| e.to_string())?;
+
+    let query = format!(
+        "{} ORDER BY v.voucher_date DESC, v.id DESC LIMIT ? OFFSET ?",
+        base_query
+    );
+    let mut q = sqlx::query_as::<_, SalesInvoice>(&query).bind(&status);
+    for date in &date_binds {
+        q = q.bind(*date);
+    }
+    let rows = q
+        .bind(limit.unwrap_or(DEFAULT_INVOICE_PAGE_SIZE))
+        .bind(offset.unwrap_or(0))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(InvoicePage { rows, total_count })
 }
 
 #[tauri::command]
@@ -1571,7 +2649,8 @@ pub async fn get_sales_invoice(
             v.reference,
             v.total_amount,
             ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as tax_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as grand_total,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2) as grand_total,
+            CAST(COALESCE(v.round_off_amount, 0) AS REAL) as round_off_amount,
             v.discount_rate,
             v.discount_amount,
             v.narration,
@@ -1580,6 +2659,8 @@ pub async fn get_sales_invoice(
             v.deleted_at,
             u.full_name as created_by_name,
             COALESCE(v.tax_inclusive, 0) as tax_inclusive,
+            v.currency,
+            COALESCE(v.exchange_rate, 1.0) as exchange_rate,
             v.linked_return_id
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
@@ -1601,21 +2682,10 @@ pub async fn get_sales_invoice(
 pub async fn get_sales_invoice_items(
     registry: State<'_, Arc<DbRegistry>>,
     voucher_id: String,
+    sort_by: Option<String>,
 ) -> Result<Vec<SalesInvoiceItem>, String> {
     let pool = registry.active_pool().await?;
-    sqlx::query_as::<_, SalesInvoiceItem>(
-        "SELECT vi.*,
-                COALESCE(p.code, s.code) as product_code,
-                COALESCE(p.name, s.name) as product_name
-        FROM voucher_items vi
-        LEFT JOIN products p ON vi.product_id = p.id
-        LEFT JOIN services s ON vi.service_id = s.id
-        WHERE vi.voucher_id = ?",
-    )
-    .bind(voucher_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| e.to_string())
+    get_sales_invoice_items_with_pool(&pool, &voucher_id, sort_by.as_deref()).await
 }
 
 /// Internal version for use by other modules (e.g., templates.rs)
@@ -1635,7 +2705,8 @@ pub(crate) async fn get_sales_invoice_with_pool(
             v.reference,
             v.total_amount,
             ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as tax_amount,
-            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0), 2) as grand_total,
+            ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2) as grand_total,
+            CAST(COALESCE(v.round_off_amount, 0) AS REAL) as round_off_amount,
             v.discount_rate,
             v.discount_amount,
             v.narration,
@@ -1644,6 +2715,8 @@ pub(crate) async fn get_sales_invoice_with_pool(
             v.deleted_at,
             u.full_name as created_by_name,
             COALESCE(v.tax_inclusive, 0) as tax_inclusive,
+            v.currency,
+            COALESCE(v.exchange_rate, 1.0) as exchange_rate,
             v.linked_return_id
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
@@ -1663,20 +2736,25 @@ pub(crate) async fn get_sales_invoice_with_pool(
 pub(crate) async fn get_sales_invoice_items_with_pool(
     pool: &SqlitePool,
     voucher_id: &str,
+    sort_by: Option<&str>,
 ) -> Result<Vec<SalesInvoiceItem>, String> {
-    sqlx::query_as::<_, SalesInvoiceItem>(
+    let order_clause = invoice_items_order_clause(sort_by)?;
+    let query = format!(
         "SELECT vi.*,
                 COALESCE(p.code, s.code) as product_code,
                 COALESCE(p.name, s.name) as product_name
          FROM voucher_items vi
          LEFT JOIN products p ON vi.product_id = p.id
          LEFT JOIN services s ON vi.service_id = s.id
-         WHERE vi.voucher_id = ?",
-    )
-    .bind(voucher_id)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| e.to_string())
+         WHERE vi.voucher_id = ?
+         {}",
+        order_clause
+    );
+    sqlx::query_as::<_, SalesInvoiceItem>(&query)
+        .bind(voucher_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 async fn delete_linked_sales_return_hard_in_tx(
@@ -1718,14 +2796,15 @@ async fn create_draft_return_for_sales_invoice_in_tx(
         return Ok(());
     }
 
-    let existing_return_id: Option<String> = sqlx::query_scalar(
-        "SELECT linked_return_id FROM vouchers WHERE id = ? AND voucher_type = 'sales_invoice'",
+    // The return is recorded under the invoice's branch
+    let (existing_return_id, branch_id): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT linked_return_id, branch_id FROM vouchers WHERE id = ? AND voucher_type = 'sales_invoice'",
     )
     .bind(sales_invoice_id)
     .fetch_optional(&mut **tx)
     .await
     .map_err(|e| e.to_string())?
-    .flatten();
+    .unwrap_or((None, None));
 
     if let Some(return_id) = existing_return_id.as_deref() {
         delete_linked_sales_return_hard_in_tx(tx, return_id).await?;
@@ -1736,14 +2815,17 @@ async fn create_draft_return_for_sales_invoice_in_tx(
         party_type: invoice.party_type.clone(),
         voucher_date: invoice.voucher_date.clone(),
         reference: Some(sales_invoice_no.to_string()),
+        original_invoice_id: Some(sales_invoice_id.to_string()),
         narration: Some(format!("Return against Sales Invoice {}", sales_invoice_no)),
         discount_rate: None,
         discount_amount: None,
         items: return_items,
         tax_inclusive: invoice.tax_inclusive,
         gst_disabled: invoice.gst_disabled,
+        allow_future: invoice.allow_future,
     };
-    let return_id = create_sales_return_in_tx(pool, tx, &sales_return).await?;
+    let return_id =
+        create_sales_return_in_tx(pool, tx, &sales_return, branch_id.as_deref()).await?;
 
     sqlx::query("UPDATE vouchers SET linked_return_id = ? WHERE id = ?")
         .bind(return_id)
@@ -1758,12 +2840,47 @@ async fn create_draft_return_for_sales_invoice_in_tx(
 #[tauri::command]
 pub async fn create_sales_invoice(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     invoice: CreateSalesInvoice,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    let branch_id = session_store.active_branch_id();
+    create_sales_invoice_with_pool(&pool, invoice, actor.as_deref(), branch_id.as_deref()).await
+}
+
+pub(crate) async fn create_sales_invoice_with_pool(
+    pool: &SqlitePool,
+    invoice: CreateSalesInvoice,
+    actor: Option<&str>,
+    branch_id: Option<&str>,
+) -> Result<String, String> {
+    validate_voucher_date(pool, &invoice.voucher_date, invoice.allow_future).await?;
+    if invoice.as_draft.unwrap_or(false)
+        && invoice
+            .return_items
+            .as_ref()
+            .is_some_and(|items| !items.is_empty())
+    {
+        return Err("Returns cannot be attached to a draft invoice".to_string());
+    }
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let (currency, exchange_rate) =
+        resolve_invoice_currency(&mut tx, invoice.currency.as_deref(), invoice.exchange_rate)
+            .await?;
+    ensure_party_account(&mut tx, &invoice.customer_id, "customer").await?;
+    ensure_products_active(
+        &mut tx,
+        invoice
+            .items
+            .iter()
+            .filter(|item| item.item_type != "service")
+            .filter_map(|item| item.product_id.as_deref()),
+    )
+    .await?;
 
-    let voucher_no = get_next_voucher_number(&pool, "sales_invoice").await?;
+    let voucher_no =
+        get_next_voucher_number(pool, "sales_invoice", &invoice.voucher_date, branch_id).await?;
 
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -1802,28 +2919,28 @@ pub async fn create_sales_invoice(
         } else {
             item.product_id.as_deref().unwrap_or("")
         };
-        prepared_lines.push(
-            prepare_voucher_line(
-                &mut tx,
-                &pool,
-                "sale",
-                &item.item_type,
-                item_id,
-                item.unit_id.as_deref(),
-                item.description.clone(),
-                item.initial_quantity,
-                item.count,
-                item.deduction_per_unit,
-                item.rate,
-                item.tax_rate,
-                item.discount_percent,
-                item.discount_amount,
-                item.remarks.clone(),
-                tax_inclusive,
-                gst_disabled,
-            )
-            .await?,
-        );
+        let mut line = prepare_voucher_line(
+            &mut tx,
+            pool,
+            "sale",
+            &item.item_type,
+            item_id,
+            item.unit_id.as_deref(),
+            item.description.clone(),
+            item.initial_quantity,
+            item.count,
+            item.deduction_per_unit,
+            item.rate,
+            item.tax_rate,
+            item.discount_percent,
+            item.discount_amount,
+            item.remarks.clone(),
+            tax_inclusive,
+            gst_disabled,
+        )
+        .await?;
+        line.inter_state = parse_tax_type(item.tax_type.as_deref())?;
+        prepared_lines.push(line);
     }
 
     let (processed, discount_rate, discount_amount) = finalize_processed_items(
@@ -1839,17 +2956,42 @@ pub async fn create_sales_invoice(
     let total_igst = processed.total_igst;
     let total_amount = round2(subtotal - discount_amount);
     let total_tax = round2(total_cgst + total_sgst + total_igst);
-    let grand_total = round2(total_amount + total_tax);
+    let (grand_total, round_off_amount) =
+        apply_invoice_round_off(&mut tx, round2(total_amount + total_tax)).await?;
+
+    ensure_stock_available(&mut tx, &processed_items).await?;
+
+    if !invoice.override_credit_limit.unwrap_or(false) {
+        ensure_within_credit_limit(
+            &mut tx,
+            &invoice.customer_id,
+            grand_total * exchange_rate,
+            None,
+        )
+        .await?;
+    }
+
+    let narration = resolve_narration(
+        &mut tx,
+        invoice.narration.as_ref(),
+        "sales_invoice",
+        Some(&invoice.customer_id),
+        &voucher_no,
+    )
+    .await?;
 
     let voucher_id = Uuid::now_v7().to_string();
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, salesperson_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, salesperson_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total, currency, exchange_rate, round_off_amount, base_grand_total, branch_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&voucher_id).bind(&voucher_no).bind("sales_invoice").bind(&invoice.voucher_date).bind(&invoice.customer_id)
     .bind(&invoice.salesperson_id).bind(&invoice.party_type).bind(&invoice.reference).bind(subtotal).bind(discount_rate)
-    .bind(discount_amount).bind(total_tax).bind(total_amount).bind(&invoice.narration)
-    .bind(&invoice.user_id).bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total).execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    .bind(discount_amount).bind(total_tax).bind(total_amount).bind(&narration)
+    .bind(&invoice.user_id).bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total)
+    .bind(&currency).bind(exchange_rate).bind(round_off_amount).bind(round2(grand_total * exchange_rate))
+    .bind(branch_id)
+    .execute(&mut *tx).await.map_err(|e| e.to_string())?;
 
     // Insert items
     for item in &processed_items {
@@ -1868,26 +3010,29 @@ pub async fn create_sales_invoice(
         .map_err(|e| e.to_string())?;
     }
 
-    // ============= INSERT STOCK MOVEMENTS (OUT) =============
+    // ============= INSERT STOCK MOVEMENTS (OUT, IN for return lines) =============
     for item in &processed_items {
         if item.item_type == "service" {
             continue;
         } // Services have no stock
         let sm_id = Uuid::now_v7().to_string();
-        let qty = item.base_quantity;
+        // A negative-quantity line is an item returned on the same bill: stock comes back in
+        let movement_type = sales_line_movement_type(item.base_quantity);
+        let qty = item.base_quantity.abs();
+        // Stock is valued in the base currency
         let rate_per_base = if qty > 0.0 {
-            item.amount / qty
+            item.amount.abs() * exchange_rate / qty
         } else {
-            item.rate
+            item.rate * exchange_rate
         };
         let amount = qty * rate_per_base;
         let product_id = item.product_id.as_deref().unwrap_or("");
         let cost_rate = get_product_purchase_cost_rate(&mut tx, product_id).await?;
         let cost_amount = qty * cost_rate;
         sqlx::query(
-            "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount) VALUES (?, ?, ?, 'OUT', ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
-        .bind(&sm_id).bind(&voucher_id).bind(&item.product_id)
+        .bind(&sm_id).bind(&voucher_id).bind(&item.product_id).bind(movement_type)
         .bind(qty).bind(item.count).bind(rate_per_base).bind(amount)
         .bind(cost_rate).bind(cost_amount)
         .execute(&mut *tx).await.map_err(|e| e.to_string())?;
@@ -1919,10 +3064,10 @@ pub async fn create_sales_invoice(
     // Group tax
     let mut tax_ledgers: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
     for row in &processed_items {
-        if row.tax_amount > 0.0 {
+        if row.tax_amount != 0.0 {
             let accounts = crate::commands::tax_utils::resolve_gst_account_names(
                 row.resolved_gst_rate,
-                is_inter_state,
+                row.igst_rate > 0.0,
                 false,
             );
             if let Some(cgst_acc) = accounts.cgst_account {
@@ -1937,73 +3082,83 @@ pub async fn create_sales_invoice(
         }
     }
 
+    let mut journal_lines = Vec::new();
+
     // Party entry (Dr customer)
-    sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-        .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(&party_id).bind(grand_total).bind(0.0)
-        .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    journal_lines.push(signed_journal_line(party_id.clone(), grand_total));
 
     // Cr 4001 Sales for product lines
-    if product_subtotal > 0.0 {
-        let sales_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4001'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(sales_acc).bind(0.0).bind(product_subtotal)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    if product_subtotal != 0.0 {
+        let sales_acc = resolve_system_account(&mut *tx, "sales").await?;
+        journal_lines.push(signed_journal_line(sales_acc, -product_subtotal));
     }
 
     // Cr 4002 Services for service lines
-    if service_subtotal > 0.0 {
-        let svc_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4002'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(svc_acc).bind(0.0).bind(service_subtotal)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    if service_subtotal != 0.0 {
+        let svc_acc = resolve_system_account(&mut *tx, "service_income").await?;
+        journal_lines.push(signed_journal_line(svc_acc, -service_subtotal));
     }
 
     // Discount entry
     if discount_amount > 0.0 {
-        let dis_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5007'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(dis_acc).bind(discount_amount).bind(0.0)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let dis_acc = resolve_system_account(&mut *tx, "discount_allowed").await?;
+        journal_lines.push(JournalLine {
+            account_id: dis_acc,
+            debit: discount_amount,
+            credit: 0.0,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     // Tax entries
     for (acc_name, amt) in tax_ledgers {
-        if amt > 0.0 {
+        let amt = round2(amt);
+        if amt != 0.0 {
             let acc_id = crate::commands::tax_utils::ensure_gst_account_exists_in_tx(
                 &mut tx, &acc_name, true,
             )
             .await?;
-            sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-                .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(acc_id).bind(0.0).bind(amt)
-                .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+            journal_lines.push(signed_journal_line(acc_id, -amt));
         }
     }
 
-    create_draft_return_for_sales_invoice_in_tx(&pool, &mut tx, &voucher_id, &voucher_no, &invoice)
+    if let Some(line) = round_off_journal_line(&mut tx, round_off_amount, true).await? {
+        journal_lines.push(line);
+    }
+
+    convert_journal_lines_to_base(&mut journal_lines, exchange_rate);
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
+
+    create_draft_return_for_sales_invoice_in_tx(pool, &mut tx, &voucher_id, &voucher_no, &invoice)
         .await?;
 
+    if invoice.as_draft.unwrap_or(false) {
+        hold_voucher_postings(&mut tx, &voucher_id).await?;
+    }
+
+    record_voucher_audit(&mut *tx, actor, &voucher_id, "create").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-    Ok(voucher_id.to_string())
+    Ok(voucher_id)
 }
 
 #[tauri::command]
 pub async fn delete_sales_invoice(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    delete_sales_invoice_with_pool(&pool, id, actor.as_deref()).await
+}
+
+pub(crate) async fn delete_sales_invoice_with_pool(
+    pool: &SqlitePool,
+    id: String,
+    actor: Option<&str>,
+) -> Result<(), String> {
+    ensure_voucher_unlocked(pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Get all payment/receipt vouchers created from this invoice
@@ -2073,6 +3228,8 @@ pub async fn delete_sales_invoice(
         .await
         .map_err(|e| e.to_string())?;
 
+    delete_held_postings(&mut tx, &id).await?;
+
     // Soft delete the voucher
     sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND voucher_type = 'sales_invoice'")
         .bind(&id)
@@ -2080,19 +3237,45 @@ pub async fn delete_sales_invoice(
         .await
         .map_err(|e| e.to_string())?;
 
+    record_voucher_audit(&mut *tx, actor, &id, "delete").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
-
     Ok(())
 }
 
 #[tauri::command]
 pub async fn update_sales_invoice(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     invoice: CreateSalesInvoice,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    update_sales_invoice_with_pool(&pool, id, invoice, actor.as_deref()).await
+}
+
+pub(crate) async fn update_sales_invoice_with_pool(
+    pool: &SqlitePool,
+    id: String,
+    invoice: CreateSalesInvoice,
+    actor: Option<&str>,
+) -> Result<String, String> {
+    ensure_voucher_unlocked(pool, &id).await?;
+    ensure_voucher_editable(pool, &id).await?;
+    validate_voucher_date(pool, &invoice.voucher_date, invoice.allow_future).await?;
+    if invoice.as_draft.unwrap_or(false)
+        && invoice
+            .return_items
+            .as_ref()
+            .is_some_and(|items| !items.is_empty())
+    {
+        return Err("Returns cannot be attached to a draft invoice".to_string());
+    }
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let (currency, exchange_rate) =
+        resolve_invoice_currency(&mut tx, invoice.currency.as_deref(), invoice.exchange_rate)
+            .await?;
+    ensure_party_account(&mut tx, &invoice.customer_id, "customer").await?;
 
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -2131,28 +3314,28 @@ pub async fn update_sales_invoice(
         } else {
             item.product_id.as_deref().unwrap_or("")
         };
-        prepared_lines.push(
-            prepare_voucher_line(
-                &mut tx,
-                &pool,
-                "sale",
-                &item.item_type,
-                item_id,
-                item.unit_id.as_deref(),
-                item.description.clone(),
-                item.initial_quantity,
-                item.count,
-                item.deduction_per_unit,
-                item.rate,
-                item.tax_rate,
-                item.discount_percent,
-                item.discount_amount,
-                item.remarks.clone(),
-                tax_inclusive,
-                gst_disabled,
-            )
-            .await?,
-        );
+        let mut line = prepare_voucher_line(
+            &mut tx,
+            pool,
+            "sale",
+            &item.item_type,
+            item_id,
+            item.unit_id.as_deref(),
+            item.description.clone(),
+            item.initial_quantity,
+            item.count,
+            item.deduction_per_unit,
+            item.rate,
+            item.tax_rate,
+            item.discount_percent,
+            item.discount_amount,
+            item.remarks.clone(),
+            tax_inclusive,
+            gst_disabled,
+        )
+        .await?;
+        line.inter_state = parse_tax_type(item.tax_type.as_deref())?;
+        prepared_lines.push(line);
     }
 
     let (processed, discount_rate, discount_amount) = finalize_processed_items(
@@ -2168,7 +3351,18 @@ pub async fn update_sales_invoice(
     let total_igst = processed.total_igst;
     let total_amount = round2(subtotal - discount_amount);
     let total_tax = round2(total_cgst + total_sgst + total_igst);
-    let grand_total = round2(total_amount + total_tax);
+    let (grand_total, round_off_amount) =
+        apply_invoice_round_off(&mut tx, round2(total_amount + total_tax)).await?;
+
+    if !invoice.override_credit_limit.unwrap_or(false) {
+        ensure_within_credit_limit(
+            &mut tx,
+            &invoice.customer_id,
+            grand_total * exchange_rate,
+            Some(&id),
+        )
+        .await?;
+    }
 
     let voucher_id = id;
     let (voucher_no, old_party_id): (String, Option<String>) = sqlx::query_as(
@@ -2178,18 +3372,40 @@ pub async fn update_sales_invoice(
     .fetch_one(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
+
+    // Receipts raised from this invoice are re-linked below; anything else allocated
+    // against it would be left pointing at amounts that no longer exist.
+    let external_allocations: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM payment_allocations pa
+         JOIN vouchers p ON pa.payment_voucher_id = p.id
+         WHERE pa.invoice_voucher_id = ?
+           AND (p.created_from_invoice_id IS NULL OR p.created_from_invoice_id != pa.invoice_voucher_id)",
+    )
+    .bind(&voucher_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if external_allocations > 0 {
+        return Err(format!(
+            "Sales invoice {} has {} payment allocation(s). Remove the allocations before editing it.",
+            voucher_no, external_allocations
+        ));
+    }
+
     let _ = sqlx::query(
         "UPDATE vouchers 
          SET voucher_date = ?, party_id = ?, salesperson_id = ?, party_type = ?, reference = ?, subtotal = ?, 
              discount_rate = ?, discount_amount = ?, tax_amount = ?, total_amount = ?, narration = ?,
-             tax_inclusive = ?, cgst_amount = ?, sgst_amount = ?, igst_amount = ?, grand_total = ?
+             tax_inclusive = ?, cgst_amount = ?, sgst_amount = ?, igst_amount = ?, grand_total = ?,
+             currency = ?, exchange_rate = ?, round_off_amount = ?, base_grand_total = ?
          WHERE id = ?"
     )
     .bind(&invoice.voucher_date).bind(&invoice.customer_id).bind(&invoice.salesperson_id).bind(&invoice.party_type).bind(&invoice.reference)
     .bind(subtotal).bind(discount_rate).bind(discount_amount)
     .bind(total_tax).bind(total_amount).bind(&invoice.narration)
     .bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst)
-    .bind(grand_total).bind(&voucher_id)
+    .bind(grand_total).bind(&currency).bind(exchange_rate).bind(round_off_amount)
+    .bind(round2(grand_total * exchange_rate)).bind(&voucher_id)
     .execute(&mut *tx).await.map_err(|e| e.to_string())?;
 
     if let Some(old_id) = &old_party_id {
@@ -2292,32 +3508,16 @@ pub async fn update_sales_invoice(
     .await
     .map_err(|e| e.to_string())?;
 
-    let new_status = if is_cash_or_bank {
-        "paid"
+    if is_cash_or_bank {
+        sqlx::query("UPDATE vouchers SET payment_status = 'paid' WHERE id = ?")
+            .bind(&voucher_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
     } else {
-        let total_allocated: f64 = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(allocated_amount), 0.0) FROM payment_allocations WHERE invoice_voucher_id = ?"
-        )
-        .bind(&voucher_id)
-        .fetch_one(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        if (total_allocated - grand_total).abs() < 0.01 {
-            "paid"
-        } else if total_allocated > 0.0 {
-            "partially_paid"
-        } else {
-            "unpaid"
-        }
-    };
-
-    sqlx::query("UPDATE vouchers SET payment_status = ? WHERE id = ?")
-        .bind(new_status)
-        .bind(&voucher_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
+        // Allocations are in base currency, so compare against the base total
+        recompute_invoice_status(&mut tx, &voucher_id).await?;
+    }
 
     sqlx::query("DELETE FROM voucher_items WHERE voucher_id = ?")
         .bind(&voucher_id)
@@ -2342,31 +3542,36 @@ pub async fn update_sales_invoice(
         .map_err(|e| e.to_string())?;
     }
 
-    // ============= DELETE + REINSERT STOCK MOVEMENTS (OUT) =============
+    // ============= DELETE + REINSERT STOCK MOVEMENTS (OUT, IN for return lines) =============
     sqlx::query("DELETE FROM stock_movements WHERE voucher_id = ?")
         .bind(&voucher_id)
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
+    // Checked after the old movements are gone so the invoice's own quantities count as available
+    ensure_stock_available(&mut tx, &processed_items).await?;
     for item in &processed_items {
         if item.item_type == "service" {
             continue;
         } // Services have no stock
         let sm_id = Uuid::now_v7().to_string();
-        let qty = item.base_quantity;
+        // A negative-quantity line is an item returned on the same bill: stock comes back in
+        let movement_type = sales_line_movement_type(item.base_quantity);
+        let qty = item.base_quantity.abs();
+        // Stock is valued in the base currency
         let rate_per_base = if qty > 0.0 {
-            item.amount / qty
+            item.amount.abs() * exchange_rate / qty
         } else {
-            item.rate
+            item.rate * exchange_rate
         };
         let amount = qty * rate_per_base;
         let product_id = item.product_id.as_deref().unwrap_or("");
         let cost_rate = get_product_purchase_cost_rate(&mut tx, product_id).await?;
         let cost_amount = qty * cost_rate;
         sqlx::query(
-            "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount) VALUES (?, ?, ?, 'OUT', ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
-        .bind(&sm_id).bind(&voucher_id).bind(&item.product_id)
+        .bind(&sm_id).bind(&voucher_id).bind(&item.product_id).bind(movement_type)
         .bind(qty).bind(item.count).bind(rate_per_base).bind(amount)
         .bind(cost_rate).bind(cost_amount)
         .execute(&mut *tx).await.map_err(|e| e.to_string())?;
@@ -2404,10 +3609,10 @@ pub async fn update_sales_invoice(
     // Group tax
     let mut tax_ledgers: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
     for row in &processed_items {
-        if row.tax_amount > 0.0 {
+        if row.tax_amount != 0.0 {
             let accounts = crate::commands::tax_utils::resolve_gst_account_names(
                 row.resolved_gst_rate,
-                is_inter_state,
+                row.igst_rate > 0.0,
                 false,
             );
             if let Some(cgst_acc) = accounts.cgst_account {
@@ -2422,58 +3627,55 @@ pub async fn update_sales_invoice(
         }
     }
 
+    let mut journal_lines = Vec::new();
+
     // Party entry (Dr customer)
-    sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-        .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(&party_id).bind(grand_total).bind(0.0)
-        .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    journal_lines.push(signed_journal_line(party_id.clone(), grand_total));
 
-    if product_subtotal > 0.0 {
-        let sales_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4001'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(sales_acc).bind(0.0).bind(product_subtotal)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    if product_subtotal != 0.0 {
+        let sales_acc = resolve_system_account(&mut *tx, "sales").await?;
+        journal_lines.push(signed_journal_line(sales_acc, -product_subtotal));
     }
-    if service_subtotal > 0.0 {
-        let svc_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4002'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(svc_acc).bind(0.0).bind(service_subtotal)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    if service_subtotal != 0.0 {
+        let svc_acc = resolve_system_account(&mut *tx, "service_income").await?;
+        journal_lines.push(signed_journal_line(svc_acc, -service_subtotal));
     }
 
     if discount_amount > 0.0 {
-        let dis_acc: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5007'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-        sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-            .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(dis_acc).bind(discount_amount).bind(0.0)
-            .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        let dis_acc = resolve_system_account(&mut *tx, "discount_allowed").await?;
+        journal_lines.push(JournalLine {
+            account_id: dis_acc,
+            debit: discount_amount,
+            credit: 0.0,
+            is_manual: false,
+            narration: None,
+        });
     }
 
     for (acc_name, amt) in tax_ledgers {
-        if amt > 0.0 {
+        let amt = round2(amt);
+        if amt != 0.0 {
             let acc_id = crate::commands::tax_utils::ensure_gst_account_exists_in_tx(
                 &mut tx, &acc_name, true,
             )
             .await?;
-            sqlx::query("INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit) VALUES (?, ?, ?, ?, ?)")
-                .bind(Uuid::now_v7().to_string()).bind(&voucher_id).bind(acc_id).bind(0.0).bind(amt)
-                .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+            journal_lines.push(signed_journal_line(acc_id, -amt));
         }
     }
 
-    create_draft_return_for_sales_invoice_in_tx(&pool, &mut tx, &voucher_id, &voucher_no, &invoice)
+    if let Some(line) = round_off_journal_line(&mut tx, round_off_amount, true).await? {
+        journal_lines.push(line);
+    }
+
+    convert_journal_lines_to_base(&mut journal_lines, exchange_rate);
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
+
+    create_draft_return_for_sales_invoice_in_tx(pool, &mut tx, &voucher_id, &voucher_no, &invoice)
         .await?;
 
+    set_voucher_draft_state(&mut tx, &voucher_id, invoice.as_draft.unwrap_or(false)).await?;
+
+    record_voucher_audit(&mut *tx, actor, &voucher_id, "update").await?;
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_id.to_string())
 }
@@ -2493,17 +3695,9 @@ pub struct VoucherSummary {
     pub total_credit: Option<f64>,
 }
 
-#[tauri::command]
-pub async fn list_vouchers(
-    registry: State<'_, Arc<DbRegistry>>,
-    voucher_type: String,
-    limit: i64,
-    offset: i64,
-    search_query: Option<String>,
-) -> Result<Vec<VoucherSummary>, String> {
-    let pool = registry.active_pool().await?;
-    let mut query = String::from(
-        "SELECT 
+/// Columns and joins shared by the voucher list and global search; callers append
+/// their own WHERE clause.
+const VOUCHER_SUMMARY_SELECT: &str = "SELECT 
             v.id,
             v.voucher_no,
             v.voucher_date,
@@ -2518,7 +3712,7 @@ pub async fn list_vouchers(
                     END
                     FROM voucher_items vi WHERE vi.voucher_id = v.id
                 )
-                ELSE COALESCE(coa.account_name, CASE WHEN v.voucher_type = 'journal' THEN 'Journal Entry' WHEN v.voucher_type = 'opening_balance' THEN 'Opening Balance' WHEN v.voucher_type = 'opening_stock' THEN 'Opening Stock' WHEN v.voucher_type = 'stock_journal' THEN 'Stock Journal' ELSE 'N/A' END)
+                ELSE COALESCE(coa.account_name, CASE WHEN v.voucher_type = 'journal' THEN 'Journal Entry' WHEN v.voucher_type = 'opening_balance' THEN 'Opening Balance' WHEN v.voucher_type = 'opening_stock' THEN 'Opening Stock' WHEN v.voucher_type = 'stock_journal' THEN 'Stock Journal' WHEN v.voucher_type = 'stock_adjustment' THEN 'Stock Adjustment' ELSE 'N/A' END)
             END as party_name,
             ROUND(
                 CASE
@@ -2532,40 +3726,63 @@ pub async fn list_vouchers(
             v.voucher_type,
             CASE 
                 WHEN v.voucher_type = 'opening_balance' THEN 
-                    (SELECT COALESCE(SUM(debit), 0.0) FROM journal_entries WHERE voucher_id = v.id AND account_id != (SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1))
+                    (SELECT COALESCE(SUM(debit), 0.0) FROM journal_entries WHERE voucher_id = v.id AND account_id != (SELECT account_id FROM system_accounts WHERE purpose = 'opening_balance_adjustment'))
                 WHEN v.voucher_type = 'journal' THEN
                     (SELECT COALESCE(SUM(debit), 0.0) FROM journal_entries WHERE voucher_id = v.id)
                 ELSE NULL
             END as total_debit,
             CASE 
                 WHEN v.voucher_type = 'opening_balance' THEN 
-                    (SELECT COALESCE(SUM(credit), 0.0) FROM journal_entries WHERE voucher_id = v.id AND account_id != (SELECT id FROM chart_of_accounts WHERE account_code = '3004' LIMIT 1))
+                    (SELECT COALESCE(SUM(credit), 0.0) FROM journal_entries WHERE voucher_id = v.id AND account_id != (SELECT account_id FROM system_accounts WHERE purpose = 'opening_balance_adjustment'))
                 WHEN v.voucher_type = 'journal' THEN
                     (SELECT COALESCE(SUM(credit), 0.0) FROM journal_entries WHERE voucher_id = v.id)
                 ELSE NULL
             END as total_credit
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
-        WHERE v.voucher_type = ? AND v.deleted_at IS NULL ",
+";
+
+#[tauri::command]
+pub async fn list_vouchers(
+    registry: State<'_, Arc<DbRegistry>>,
+    voucher_type: String,
+    limit: i64,
+    offset: i64,
+    search_query: Option<String>,
+) -> Result<Vec<VoucherSummary>, String> {
+    let pool = registry.active_pool().await?;
+    let mut query = format!(
+        "{} WHERE v.voucher_type = ? AND v.deleted_at IS NULL ",
+        VOUCHER_SUMMARY_SELECT
     );
 
-    if let Some(search) = &search_query {
-        if !search.is_empty() {
-            query.push_str("AND (v.voucher_no LIKE ? OR party_name LIKE ?) ");
-        }
+    let search = search_query
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let use_index = match search {
+        Some(_) => search_index_ready(&pool).await,
+        None => false,
+    };
+    if search.is_some() {
+        query.push_str(if use_index {
+            "AND v.id IN (SELECT voucher_id FROM voucher_search WHERE voucher_search MATCH ?) "
+        } else {
+            "AND (v.voucher_no LIKE ? OR party_name LIKE ?) "
+        });
     }
 
     query.push_str("ORDER BY v.voucher_date DESC, v.id DESC LIMIT ? OFFSET ?");
 
-    let search_pattern = search_query
-        .as_ref()
-        .filter(|s| !s.is_empty())
-        .map(|s| format!("%{}%", s));
-
     let mut q = sqlx::query_as::<_, VoucherSummary>(&query).bind(&voucher_type);
 
-    if let Some(ref p) = search_pattern {
-        q = q.bind(p).bind(p);
+    if let Some(search) = search {
+        if use_index {
+            q = q.bind(search_match_query(search));
+        } else {
+            let pattern = format!("%{}%", search);
+            q = q.bind(pattern.clone()).bind(pattern);
+        }
     }
 
     q = q.bind(limit).bind(offset);
@@ -2573,6 +3790,65 @@ pub async fn list_vouchers(
     q.fetch_all(&pool).await.map_err(|e| e.to_string())
 }
 
+/// Amounts typed into the global search match vouchers within this tolerance.
+const AMOUNT_SEARCH_TOLERANCE: f64 = 0.01;
+
+/// Searches every voucher type by number, narration, reference and party name, and by
+/// amount when the query is numeric. Newest first, capped at `limit`. Text matching
+/// goes through the voucher_search index when it is populated.
+#[tauri::command]
+pub async fn search_vouchers(
+    registry: State<'_, Arc<DbRegistry>>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<VoucherSummary>, String> {
+    let pool = registry.active_pool().await?;
+    let term = query.trim();
+    if term.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    let amount = term.replace(',', "").parse::<f64>().ok();
+
+    let amount_filter = if amount.is_some() {
+        "OR ABS(COALESCE(NULLIF(v.grand_total, 0.0), v.total_amount, 0.0) - ?) <= ?"
+    } else {
+        ""
+    };
+    let use_index = search_index_ready(&pool).await;
+    let text_filter = if use_index {
+        "v.id IN (SELECT voucher_id FROM voucher_search WHERE voucher_search MATCH ?)"
+    } else {
+        "v.voucher_no LIKE ? OR v.narration LIKE ? OR v.reference LIKE ? OR party_name LIKE ?"
+    };
+    let sql = format!(
+        "{} WHERE v.deleted_at IS NULL
+           AND ({} {})
+         ORDER BY v.voucher_date DESC, v.id DESC LIMIT ?",
+        VOUCHER_SUMMARY_SELECT, text_filter, amount_filter
+    );
+
+    let mut q = sqlx::query_as::<_, VoucherSummary>(&sql);
+    if use_index {
+        q = q.bind(search_match_query(term));
+    } else {
+        let pattern = format!("%{}%", term);
+        q = q
+            .bind(pattern.clone())
+            .bind(pattern.clone())
+            .bind(pattern.clone())
+            .bind(pattern);
+    }
+    if let Some(amount) = amount {
+        q = q.bind(amount).bind(AMOUNT_SEARCH_TOLERANCE);
+    }
+
+    q.bind(limit)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_previous_voucher_id(
     registry: State<'_, Arc<DbRegistry>>,
@@ -2645,6 +3921,8 @@ pub async fn get_next_voucher_id(
     .map_err(|e| e.to_string())
 }
 
+/// Generic voucher fetch for prev/next navigation. Payments and receipts also
+/// carry their method, and journal-style vouchers their full debit/credit lines.
 #[tauri::command]
 pub async fn get_voucher_by_id(
     registry: State<'_, Arc<DbRegistry>>,
@@ -2653,8 +3931,10 @@ pub async fn get_voucher_by_id(
 ) -> Result<serde_json::Value, String> {
     let pool = registry.active_pool().await?;
     // Fetch generic voucher data
-    let voucher = sqlx::query_as::<_, (String, String, String, Option<String>, Option<String>, f64, String, String, String, String)>(
-        "SELECT id, voucher_no, voucher_date, reference, narration, total_amount, status, created_at, party_id, party_type FROM vouchers WHERE id = ? AND voucher_type = ? AND deleted_at IS NULL"
+    let voucher = sqlx::query_as::<_, (String, String, String, Option<String>, Option<String>, f64, String, String, Option<String>, Option<String>, Option<String>)>(
+        "SELECT id, voucher_no, voucher_date, reference, narration, total_amount, status, created_at, party_id, party_type,
+                CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.method') ELSE metadata END as method
+         FROM vouchers WHERE id = ? AND voucher_type = ? AND deleted_at IS NULL"
     )
     .bind(&id)
     .bind(&voucher_type)
@@ -2664,7 +3944,7 @@ pub async fn get_voucher_by_id(
 
     if let Some(v) = voucher {
         // Fetch items - basic info
-        let items = sqlx::query_as::<_, (String, String, f64, f64, f64)>(
+        let items = sqlx::query_as::<_, (String, Option<String>, f64, f64, f64)>(
              "SELECT id, description, final_quantity, rate, amount FROM voucher_items WHERE voucher_id = ?"
         )
         .bind(&id)
@@ -2672,7 +3952,7 @@ pub async fn get_voucher_by_id(
         .await
         .map_err(|e| e.to_string())?;
 
-        Ok(serde_json::json!({
+        let mut result = serde_json::json!({
             "id": v.0,
             "voucher_no": v.1,
             "voucher_date": v.2,
@@ -2689,7 +3969,36 @@ pub async fn get_voucher_by_id(
                 "rate": i.3,
                 "amount": i.4
             })).collect::<Vec<_>>()
-        }))
+        });
+
+        match voucher_type.as_str() {
+            "payment" => result["payment_method"] = serde_json::json!(v.10),
+            "receipt" => result["receipt_method"] = serde_json::json!(v.10),
+            "journal" | "contra" => {
+                let lines = sqlx::query_as::<_, super::entries::JournalEntryLine>(
+                    "SELECT 
+                        je.id,
+                        je.voucher_id,
+                        je.account_id,
+                        COALESCE(coa.account_name, '') as account_name,
+                        je.debit,
+                        je.credit,
+                        je.narration
+                    FROM journal_entries je
+                    LEFT JOIN chart_of_accounts coa ON je.account_id = coa.id
+                    WHERE je.voucher_id = ?
+                    ORDER BY je.id ASC",
+                )
+                .bind(&id)
+                .fetch_all(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+                result["lines"] = serde_json::to_value(lines).map_err(|e| e.to_string())?;
+            }
+            _ => {}
+        }
+
+        Ok(result)
     } else {
         Err("Voucher not found".to_string())
     }
@@ -2756,12 +4065,9 @@ pub async fn open_whatsapp_url(
         .map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub async fn save_invoice_pdf(html: String, file_name: String) -> Result<String, String> {
-    use std::process::Command;
-
-    // Sanitise file name
-    let safe_name: String = file_name
+/// Replaces anything but letters, digits, '-' and '_' so the name is safe in a path.
+fn sanitize_file_name(file_name: &str) -> String {
+    file_name
         .chars()
         .map(|c| {
             if c.is_alphanumeric() || c == '-' || c == '_' {
@@ -2770,19 +4076,25 @@ pub async fn save_invoice_pdf(html: String, file_name: String) -> Result<String,
                 '_'
             }
         })
-        .collect();
+        .collect()
+}
+
+/// Prints `html` to a PDF at `pdf_path` with headless Edge (the WebView2
+/// engine, so the output matches the on-screen preview). `temp_name` names
+/// the intermediate HTML file.
+async fn print_html_to_pdf(
+    html: &str,
+    temp_name: &str,
+    pdf_path: &std::path::Path,
+) -> Result<String, String> {
+    use std::process::Command;
 
     // 1. Write HTML to a temp file
     let temp_dir = std::env::temp_dir();
-    let temp_html = temp_dir.join(format!("kola_invoice_{}.html", safe_name));
-    std::fs::write(&temp_html, &html).map_err(|e| e.to_string())?;
-
-    // 2. Output PDF path in Downloads
-    let downloads =
-        dirs::download_dir().ok_or_else(|| "Could not find Downloads folder".to_string())?;
-    let pdf_path = downloads.join(format!("Invoice_{}.pdf", safe_name));
+    let temp_html = temp_dir.join(format!("kola_invoice_{}.html", temp_name));
+    std::fs::write(&temp_html, html).map_err(|e| e.to_string())?;
 
-    // 3. Find Edge (always present on Windows 10/11 — Tauri uses WebView2/Edge)
+    // 2. Find Edge (always present on Windows 10/11 — Tauri uses WebView2/Edge)
     let edge_candidates = [
         r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
         r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
@@ -2796,10 +4108,10 @@ pub async fn save_invoice_pdf(html: String, file_name: String) -> Result<String,
                 .to_string()
         })?;
 
-    // 4. Build file:/// URL for the temp HTML (Edge requires forward slashes)
+    // 3. Build file:/// URL for the temp HTML (Edge requires forward slashes)
     let html_url = format!("file:///{}", temp_html.to_string_lossy().replace('\\', "/"));
 
-    // 5. Run Edge headless — prints the page as PDF
+    // 4. Run Edge headless — prints the page as PDF
     let _output = Command::new(edge_exe)
         .args([
             "--headless",
@@ -2816,7 +4128,7 @@ pub async fn save_invoice_pdf(html: String, file_name: String) -> Result<String,
     // Small safety margin for file flush
     tokio::time::sleep(std::time::Duration::from_millis(600)).await;
 
-    // 6. Clean up temp HTML
+    // 5. Clean up temp HTML
     let _ = std::fs::remove_file(&temp_html);
 
     if pdf_path.exists() {
@@ -2825,3 +4137,580 @@ pub async fn save_invoice_pdf(html: String, file_name: String) -> Result<String,
         Err("PDF generation failed. Try updating Microsoft Edge.".to_string())
     }
 }
+
+#[tauri::command]
+pub async fn save_invoice_pdf(html: String, file_name: String) -> Result<String, String> {
+    let safe_name = sanitize_file_name(&file_name);
+
+    // Output PDF path in Downloads
+    let downloads =
+        dirs::download_dir().ok_or_else(|| "Could not find Downloads folder".to_string())?;
+    let pdf_path = downloads.join(format!("Invoice_{}.pdf", safe_name));
+
+    print_html_to_pdf(&html, &safe_name, &pdf_path).await
+}
+
+/// Renders a sales or purchase invoice through the template engine (same data
+/// and template choice as render_invoice) and prints it to `file_path` as PDF.
+#[tauri::command]
+pub async fn generate_invoice_pdf(
+    registry: State<'_, Arc<DbRegistry>>,
+    voucher_id: String,
+    template_id: Option<String>,
+    file_path: String,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    let (voucher_type, voucher_no): (String, String) = sqlx::query_as(
+        "SELECT voucher_type, voucher_no FROM vouchers WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(&voucher_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Invoice not found".to_string())?;
+    if voucher_type != "sales_invoice" && voucher_type != "purchase_invoice" {
+        return Err("Only sales and purchase invoices can be exported as PDF".to_string());
+    }
+
+    let html =
+        super::templates::render_invoice_html(&pool, voucher_id, voucher_type, template_id).await?;
+
+    // Stale output would otherwise pass the existence check after a failed print
+    let pdf_path = std::path::PathBuf::from(&file_path);
+    if pdf_path.exists() {
+        std::fs::remove_file(&pdf_path).map_err(|e| e.to_string())?;
+    }
+    print_html_to_pdf(&html, &sanitize_file_name(&voucher_no), &pdf_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use serde_json::json;
+
+    /// A stocked product with a single sale/purchase unit.
+    async fn insert_product(pool: &SqlitePool, code: &str) -> String {
+        let unit_id = Uuid::now_v7().to_string();
+        sqlx::query("INSERT INTO units (id, name, symbol) VALUES (?, ?, 'Nos')")
+            .bind(&unit_id)
+            .bind(format!("Unit {}", code))
+            .execute(pool)
+            .await
+            .unwrap();
+        let product_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO products (id, code, name, unit_id, purchase_rate, sales_rate, mrp)
+             VALUES (?, ?, ?, ?, 60, 100, 100)",
+        )
+        .bind(&product_id)
+        .bind(code)
+        .bind(format!("Product {}", code))
+        .bind(&unit_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO product_unit_conversions
+                (id, product_id, unit_id, factor_to_base, is_default_sale, is_default_purchase, is_default_report)
+             VALUES (?, ?, ?, 1, 1, 1, 1)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(&product_id)
+        .bind(&unit_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        product_id
+    }
+
+    async fn insert_customer_account(pool: &SqlitePool, code: &str) -> String {
+        let account_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO chart_of_accounts (id, account_code, account_name, account_type, account_group, party_type)
+             VALUES (?, ?, ?, 'Asset', 'Accounts Receivable', 'customer')",
+        )
+        .bind(&account_id)
+        .bind(code)
+        .bind(format!("Customer {}", code))
+        .execute(pool)
+        .await
+        .unwrap();
+        account_id
+    }
+
+    fn sales_line(product_id: &str, quantity: f64) -> serde_json::Value {
+        json!({
+            "product_id": product_id,
+            "service_id": null,
+            "unit_id": null,
+            "description": null,
+            "initial_quantity": quantity,
+            "count": 1,
+            "deduction_per_unit": 0.0,
+            "rate": 100.0,
+            "tax_rate": 0.0,
+        })
+    }
+
+    fn sales_invoice(customer_id: &str, lines: Vec<serde_json::Value>) -> CreateSalesInvoice {
+        serde_json::from_value(json!({
+            "customer_id": customer_id,
+            "party_type": "customer",
+            "voucher_date": "2024-04-01",
+            "items": lines,
+            "gst_disabled": true,
+        }))
+        .unwrap()
+    }
+
+    async fn prevent_negative_stock(pool: &SqlitePool) {
+        sqlx::query(
+            "INSERT INTO company_profile (company_name, prevent_negative_stock) VALUES ('Test Co', 1)",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    /// Brings `quantity` of a product into stock through a bare stock voucher.
+    async fn receive_stock(pool: &SqlitePool, product_id: &str, quantity: f64) {
+        let voucher_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date)
+             VALUES (?, ?, 'stock_journal', '2024-03-31')",
+        )
+        .bind(&voucher_id)
+        .bind(format!("SJ-{}", voucher_id))
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, rate, amount)
+             VALUES (?, ?, ?, 'IN', ?, 60, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(&voucher_id)
+        .bind(product_id)
+        .bind(quantity)
+        .bind(quantity * 60.0)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn mixed_sales_invoice_moves_stock_both_ways_and_posts_net_receivable() {
+        let pool = test_pool().await;
+        let sold = insert_product(&pool, "T-SOLD").await;
+        let returned = insert_product(&pool, "T-RET").await;
+        let customer = insert_customer_account(&pool, "1003-T1").await;
+
+        let invoice = sales_invoice(
+            &customer,
+            vec![sales_line(&sold, 5.0), sales_line(&returned, -2.0)],
+        );
+        let voucher_id = create_sales_invoice_with_pool(&pool, invoice, None, None)
+            .await
+            .unwrap();
+
+        let movements: Vec<(String, String, f64)> = sqlx::query_as(
+            "SELECT product_id, movement_type, quantity FROM stock_movements WHERE voucher_id = ?",
+        )
+        .bind(&voucher_id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(movements.len(), 2);
+        assert!(movements.contains(&(sold, "OUT".to_string(), 5.0)));
+        assert!(movements.contains(&(returned, "RETURN_IN".to_string(), 2.0)));
+
+        let (debit, credit): (f64, f64) = sqlx::query_as(
+            "SELECT CAST(SUM(debit) AS REAL), CAST(SUM(credit) AS REAL)
+             FROM journal_entries WHERE voucher_id = ? AND account_id = ?",
+        )
+        .bind(&voucher_id)
+        .bind(&customer)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((debit, credit), (300.0, 0.0));
+
+        let (total_debit, total_credit): (f64, f64) = sqlx::query_as(
+            "SELECT CAST(SUM(debit) AS REAL), CAST(SUM(credit) AS REAL)
+             FROM journal_entries WHERE voucher_id = ?",
+        )
+        .bind(&voucher_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!((total_debit - total_credit).abs() < 0.005);
+    }
+
+    #[tokio::test]
+    async fn invoice_list_flags_stored_totals_that_disagree() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-SUM").await;
+        let customer = insert_customer_account(&pool, "1003-T7").await;
+        let create = || {
+            create_sales_invoice_with_pool(
+                &pool,
+                sales_invoice(&customer, vec![sales_line(&product, 1.0)]),
+                None,
+                None,
+            )
+        };
+        let consistent = create().await.unwrap();
+        let drifted = create().await.unwrap();
+        sqlx::query("UPDATE vouchers SET total_amount = total_amount + 25 WHERE id = ?")
+            .bind(&drifted)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let all = get_sales_invoices_with_pool(&pool, None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let flagged = |id: &str| {
+            all.rows
+                .iter()
+                .find(|row| row.id == id)
+                .unwrap()
+                .total_mismatch
+        };
+        assert!(!flagged(&consistent));
+        assert!(flagged(&drifted));
+
+        let mismatched =
+            get_sales_invoices_with_pool(&pool, Some(true), None, None, None, None, None)
+                .await
+                .unwrap();
+        assert_eq!(mismatched.total_count, 1);
+        assert_eq!(mismatched.rows[0].id, drifted);
+    }
+
+    #[tokio::test]
+    async fn blank_narration_falls_back_to_the_voucher_type_template() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-NAR").await;
+        let customer = insert_customer_account(&pool, "1003-T8").await;
+        sqlx::query(
+            "INSERT INTO voucher_settings (voucher_type, settings) VALUES ('sales_invoice', ?)",
+        )
+        .bind(
+            json!({ "narration_template": "Being goods sold to {party} vide {voucher_no}" })
+                .to_string(),
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let invoice_id = create_sales_invoice_with_pool(
+            &pool,
+            sales_invoice(&customer, vec![sales_line(&product, 1.0)]),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let (voucher_no, narration): (String, Option<String>) =
+            sqlx::query_as("SELECT voucher_no, narration FROM vouchers WHERE id = ?")
+                .bind(&invoice_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            narration.as_deref(),
+            Some(format!("Being goods sold to Customer 1003-T8 vide {}", voucher_no).as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn invoice_items_can_be_listed_by_product_name() {
+        let pool = test_pool().await;
+        let customer = insert_customer_account(&pool, "1003-T9").await;
+        let mut lines = Vec::new();
+        for code in ["T-ZED", "T-ALPHA", "T-MID"] {
+            lines.push(sales_line(&insert_product(&pool, code).await, 1.0));
+        }
+        let invoice_id =
+            create_sales_invoice_with_pool(&pool, sales_invoice(&customer, lines), None, None)
+                .await
+                .unwrap();
+
+        let names = |items: Vec<SalesInvoiceItem>| -> Vec<String> {
+            items
+                .into_iter()
+                .map(|item| item.product_name.unwrap_or_default())
+                .collect()
+        };
+        let entered = get_sales_invoice_items_with_pool(&pool, &invoice_id, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(entered),
+            ["Product T-ZED", "Product T-ALPHA", "Product T-MID"]
+        );
+        let by_name = get_sales_invoice_items_with_pool(&pool, &invoice_id, Some("name"))
+            .await
+            .unwrap();
+        assert_eq!(
+            names(by_name),
+            ["Product T-ALPHA", "Product T-MID", "Product T-ZED"]
+        );
+
+        let err = get_sales_invoice_items_with_pool(&pool, &invoice_id, Some("price"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Invalid sort_by"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn sales_invoice_names_the_missing_party_account_code() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-MISS").await;
+        let customer = insert_customer_account(&pool, "1003-T2").await;
+        sqlx::query("DELETE FROM chart_of_accounts WHERE id = ?")
+            .bind(&customer)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let invoice = sales_invoice(&customer, vec![sales_line(&product, 1.0)]);
+        let err = create_sales_invoice_with_pool(&pool, invoice, None, None)
+            .await
+            .unwrap_err();
+
+        assert!(err.contains(&format!("1003-{}", customer)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn sales_invoice_with_a_deleted_product_is_rejected_by_name() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-GONE").await;
+        let customer = insert_customer_account(&pool, "1003-T10").await;
+        sqlx::query("UPDATE products SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(&product)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let invoice = sales_invoice(&customer, vec![sales_line(&product, 1.0)]);
+        let err = create_sales_invoice_with_pool(&pool, invoice, None, None)
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.contains("'Product T-GONE' is deleted or inactive"),
+            "{}",
+            err
+        );
+        let vouchers: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM vouchers WHERE voucher_type = 'sales_invoice'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(vouchers, 0);
+    }
+
+    #[tokio::test]
+    async fn sales_beyond_available_stock_are_rejected_on_create_and_edit() {
+        let pool = test_pool().await;
+        prevent_negative_stock(&pool).await;
+        let product = insert_product(&pool, "T-OVER").await;
+        let customer = insert_customer_account(&pool, "1003-T6").await;
+        receive_stock(&pool, &product, 10.0).await;
+
+        let err = create_sales_invoice_with_pool(
+            &pool,
+            sales_invoice(&customer, vec![sales_line(&product, 12.0)]),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Insufficient stock"), "{}", err);
+
+        let invoice_id = create_sales_invoice_with_pool(
+            &pool,
+            sales_invoice(&customer, vec![sales_line(&product, 6.0)]),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        // The invoice's own 6 count as available again, so selling all 10 is fine
+        update_sales_invoice_with_pool(
+            &pool,
+            invoice_id.clone(),
+            sales_invoice(&customer, vec![sales_line(&product, 10.0)]),
+            None,
+        )
+        .await
+        .unwrap();
+        let err = update_sales_invoice_with_pool(
+            &pool,
+            invoice_id.clone(),
+            sales_invoice(&customer, vec![sales_line(&product, 11.0)]),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Insufficient stock"), "{}", err);
+
+        let sold: f64 = sqlx::query_scalar(
+            "SELECT CAST(SUM(quantity) AS REAL) FROM stock_movements WHERE voucher_id = ?",
+        )
+        .bind(&invoice_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(sold, 10.0);
+    }
+
+    #[tokio::test]
+    async fn approval_rechecks_stock_sold_while_invoice_was_pending() {
+        let pool = test_pool().await;
+        prevent_negative_stock(&pool).await;
+        let product = insert_product(&pool, "T-HELD").await;
+        let customer = insert_customer_account(&pool, "1003-T3").await;
+        receive_stock(&pool, &product, 10.0).await;
+
+        let held_id = create_sales_invoice_with_pool(
+            &pool,
+            sales_invoice(&customer, vec![sales_line(&product, 6.0)]),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let mut tx = pool.begin().await.unwrap();
+        crate::commands::approvals::hold_voucher_postings(&mut tx, &held_id)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE vouchers SET status = 'pending', created_by = 'u1' WHERE id = ?")
+            .bind(&held_id)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        create_sales_invoice_with_pool(
+            &pool,
+            sales_invoice(&customer, vec![sales_line(&product, 8.0)]),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let err =
+            crate::commands::approvals::approve_voucher_with_pool(&pool, &held_id, "u2", false)
+                .await
+                .unwrap_err();
+        assert!(err.contains("Insufficient stock"), "{}", err);
+        let status: String = sqlx::query_scalar("SELECT status FROM vouchers WHERE id = ?")
+            .bind(&held_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "pending");
+    }
+
+    #[tokio::test]
+    async fn editing_a_foreign_currency_invoice_keeps_it_paid_in_base_currency() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-USD").await;
+        let customer = insert_customer_account(&pool, "1003-T4").await;
+        let usd_invoice = || {
+            let mut invoice = sales_invoice(&customer, vec![sales_line(&product, 1.0)]);
+            invoice.currency = Some("USD".to_string());
+            invoice.exchange_rate = Some(80.0);
+            invoice
+        };
+
+        let invoice_id = create_sales_invoice_with_pool(&pool, usd_invoice(), None, None)
+            .await
+            .unwrap();
+        let receipt_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, created_from_invoice_id)
+             VALUES (?, 'RV-T-0001', 'receipt', '2024-04-01', ?)",
+        )
+        .bind(&receipt_id)
+        .bind(&invoice_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO payment_allocations (id, payment_voucher_id, invoice_voucher_id, allocated_amount)
+             VALUES (?, ?, ?, 8000)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(&receipt_id)
+        .bind(&invoice_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        update_sales_invoice_with_pool(&pool, invoice_id.clone(), usd_invoice(), None)
+            .await
+            .unwrap();
+
+        let status: String = sqlx::query_scalar("SELECT payment_status FROM vouchers WHERE id = ?")
+            .bind(&invoice_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "paid");
+    }
+
+    #[tokio::test]
+    async fn deleted_invoice_no_longer_counts_towards_balances_or_stock() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-DEL").await;
+        let customer = insert_customer_account(&pool, "1003-T5").await;
+        receive_stock(&pool, &product, 10.0).await;
+
+        let invoice_id = create_sales_invoice_with_pool(
+            &pool,
+            sales_invoice(&customer, vec![sales_line(&product, 4.0)]),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        delete_sales_invoice_with_pool(&pool, invoice_id.clone(), None)
+            .await
+            .unwrap();
+
+        let (journal_rows, stock_rows): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM journal_entries WHERE voucher_id = ?),
+                    (SELECT COUNT(*) FROM stock_movements WHERE voucher_id = ?)",
+        )
+        .bind(&invoice_id)
+        .bind(&invoice_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((journal_rows, stock_rows), (0, 0));
+
+        let balances = crate::commands::entries::account_balances(
+            &pool,
+            std::slice::from_ref(&customer),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(balances.get(&customer).copied(), Some(0.0));
+
+        let stock: f64 = sqlx::query_scalar(&crate::commands::reports::product_stock_qty_sql())
+            .bind(&product)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stock, 10.0);
+    }
+}
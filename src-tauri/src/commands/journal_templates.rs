@@ -0,0 +1,122 @@
+use crate::company_db::DbRegistry;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::State;
+use uuid::Uuid;
+
+use super::entries::{validate_journal_entry, CreateJournalEntry, CreateJournalEntryLine};
+
+// ============= JOURNAL TEMPLATES =============
+// Saved journal entries (depreciation, accruals, ...) that the UI loads to
+// prefill create_journal_entry. The date is left out; it's chosen per posting.
+
+/// A saved template in the CreateJournalEntry shape, minus the date.
+#[derive(Serialize)]
+pub struct JournalTemplate {
+    pub id: String,
+    pub name: String,
+    pub reference: Option<String>,
+    pub narration: Option<String>,
+    pub lines: Vec<CreateJournalEntryLine>,
+    pub created_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct JournalTemplateRow {
+    id: String,
+    name: String,
+    reference: Option<String>,
+    narration: Option<String>,
+    lines_json: String,
+    created_at: String,
+}
+
+/// Saves `entry` as a named template. Its voucher_date is ignored; the lines
+/// must balance just as they would for create_journal_entry.
+#[tauri::command]
+pub async fn save_journal_template(
+    registry: State<'_, Arc<DbRegistry>>,
+    name: String,
+    entry: CreateJournalEntry,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    if entry.lines.is_empty() {
+        return Err("A template needs at least one line".to_string());
+    }
+    validate_journal_entry(&entry)?;
+
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM journal_entry_templates WHERE name = ?)")
+            .bind(name)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    if exists {
+        return Err(format!("A template named '{}' already exists", name));
+    }
+
+    let lines_json = serde_json::to_string(&entry.lines).map_err(|e| e.to_string())?;
+    let id = Uuid::now_v7().to_string();
+    sqlx::query(
+        "INSERT INTO journal_entry_templates (id, name, reference, narration, lines_json)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(name)
+    .bind(&entry.reference)
+    .bind(&entry.narration)
+    .bind(&lines_json)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn list_journal_templates(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<JournalTemplate>, String> {
+    let pool = registry.active_pool().await?;
+    let rows = sqlx::query_as::<_, JournalTemplateRow>(
+        "SELECT id, name, reference, narration, lines_json, created_at
+         FROM journal_entry_templates
+         ORDER BY name ASC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.into_iter()
+        .map(|row| {
+            let lines = serde_json::from_str(&row.lines_json)
+                .map_err(|e| format!("Template '{}' is corrupt: {}", row.name, e))?;
+            Ok(JournalTemplate {
+                id: row.id,
+                name: row.name,
+                reference: row.reference,
+                narration: row.narration,
+                lines,
+                created_at: row.created_at,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn delete_journal_template(
+    registry: State<'_, Arc<DbRegistry>>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query("DELETE FROM journal_entry_templates WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
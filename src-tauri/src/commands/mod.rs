@@ -1,13 +1,18 @@
 pub mod accounts;
 pub mod allocations;
+pub mod approvals;
+pub mod attachments;
+pub mod audit;
 pub mod auth;
 pub mod backups;
+pub mod branches;
 pub mod company;
 pub mod company_cmds;
 pub mod employees;
 pub mod entries;
 pub mod invoice_splits;
 pub mod invoices;
+pub mod journal_templates;
 pub mod license;
 pub mod opening_stock;
 pub mod parties;
@@ -15,27 +20,38 @@ pub mod pdf_export;
 pub mod products;
 pub mod purchase_returns;
 pub mod quotations;
+pub mod reconciliation;
+pub mod recurring;
 pub mod reports;
 pub mod sales_returns;
+pub mod search_index;
 pub mod services;
 pub mod settings;
+pub mod stock_adjustments;
 pub mod stock_journal;
+pub mod system_accounts;
 pub mod tax;
 #[allow(dead_code)]
 pub mod tax_utils;
 pub mod templates;
 pub mod voucher_units;
+pub mod year_end;
 
 pub use accounts::*;
 pub use allocations::*;
+pub use approvals::*;
+pub use attachments::*;
+pub use audit::*;
 pub use auth::*;
 pub use backups::*;
+pub use branches::*;
 pub use company::*;
 pub use company_cmds::*;
 pub use employees::*;
 pub use entries::*;
 pub use invoice_splits::*;
 pub use invoices::*;
+pub use journal_templates::*;
 pub use license::*;
 pub use opening_stock::*;
 pub use parties::*;
@@ -43,11 +59,17 @@ pub use pdf_export::*;
 pub use products::*;
 pub use purchase_returns::*;
 pub use quotations::*;
+pub use reconciliation::*;
+pub use recurring::*;
 pub use reports::*;
 pub use sales_returns::*;
+pub use search_index::*;
 pub use services::*;
 pub use settings::*;
+pub use stock_adjustments::*;
 pub use stock_journal::*;
+pub use system_accounts::*;
 pub use tax::*;
 pub use templates::*;
 pub use voucher_units::*;
+pub use year_end::*;
@@ -5,7 +5,11 @@ use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
+use super::auth::SessionStore;
+use super::entries::{insert_journal_lines, JournalLine};
 use super::resolve_voucher_line_unit;
+use super::system_accounts::find_system_account;
+use super::year_end::{ensure_period_open, ensure_voucher_unlocked};
 
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct OpeningStock {
@@ -161,12 +165,15 @@ pub async fn get_opening_stock_items(
 #[tauri::command]
 pub async fn create_opening_stock(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     data: CreateOpeningStock,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    ensure_period_open(&pool, &data.voucher_date).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Generate voucher number
+    let branch_id = session_store.active_branch_id();
     let voucher_no = get_next_voucher_number(&pool, "opening_stock").await?;
 
     // Calculate total
@@ -179,8 +186,8 @@ pub async fn create_opening_stock(
 
     // Create voucher
     sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, total_amount, narration, status, created_by)
-         VALUES (?, ?, 'opening_stock', ?, ?, ?, 'posted', ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, total_amount, narration, status, created_by, branch_id)
+         VALUES (?, ?, 'opening_stock', ?, ?, ?, 'posted', ?, ?)"
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
@@ -188,6 +195,7 @@ pub async fn create_opening_stock(
     .bind(total_amount)
     .bind(&data.narration)
     .bind(&data.user_id)
+    .bind(&branch_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -244,47 +252,30 @@ pub async fn create_opening_stock(
     // ============= CREATE JOURNAL ENTRIES =============
     if total_amount > 0.0 {
         // Debit: Inventory Account (1004)
-        let inventory_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1004'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+        let inventory_account = find_system_account(&mut *tx, "inventory").await?;
 
         // Credit: Opening Balance Adjustment (3004)
-        let opening_adj_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '3004'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+        let opening_adj_account =
+            find_system_account(&mut *tx, "opening_balance_adjustment").await?;
 
         if let (Some(inv_acc), Some(adj_acc)) = (inventory_account, opening_adj_account) {
-            // Debit Inventory
-            let je_id_1 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, ?, 0, 'Opening Stock Value')",
-            )
-            .bind(&je_id_1)
-            .bind(&voucher_id)
-            .bind(&inv_acc)
-            .bind(total_amount)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-
-            // Credit Opening Balance Adjustment
-            let je_id_2 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, 0, ?, 'Opening Stock Value')",
-            )
-            .bind(&je_id_2)
-            .bind(&voucher_id)
-            .bind(&adj_acc)
-            .bind(total_amount)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
+            let journal_lines = [
+                JournalLine {
+                    account_id: inv_acc,
+                    debit: total_amount,
+                    credit: 0.0,
+                    is_manual: false,
+                    narration: Some("Opening Stock Value".to_string()),
+                },
+                JournalLine {
+                    account_id: adj_acc,
+                    debit: 0.0,
+                    credit: total_amount,
+                    is_manual: false,
+                    narration: Some("Opening Stock Value".to_string()),
+                },
+            ];
+            insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
         }
     }
 
@@ -300,6 +291,8 @@ pub async fn update_opening_stock(
     data: CreateOpeningStock,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    ensure_period_open(&pool, &data.voucher_date).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Calculate total
@@ -393,47 +386,30 @@ pub async fn update_opening_stock(
     // ============= RE-CREATE JOURNAL ENTRIES =============
     if total_amount > 0.0 {
         // Debit: Inventory Account (1004)
-        let inventory_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1004'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+        let inventory_account = find_system_account(&mut *tx, "inventory").await?;
 
         // Credit: Opening Balance Adjustment (3004)
-        let opening_adj_account: Option<String> =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '3004'")
-                .fetch_optional(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
+        let opening_adj_account =
+            find_system_account(&mut *tx, "opening_balance_adjustment").await?;
 
         if let (Some(inv_acc), Some(adj_acc)) = (inventory_account, opening_adj_account) {
-            // Debit Inventory
-            let je_id_1 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, ?, 0, 'Opening Stock Value')",
-            )
-            .bind(&je_id_1)
-            .bind(&id)
-            .bind(&inv_acc)
-            .bind(total_amount)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-
-            // Credit Opening Balance Adjustment
-            let je_id_2 = Uuid::now_v7().to_string();
-            sqlx::query(
-                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-                 VALUES (?, ?, ?, 0, ?, 'Opening Stock Value')",
-            )
-            .bind(&je_id_2)
-            .bind(&id)
-            .bind(&adj_acc)
-            .bind(total_amount)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
+            let journal_lines = [
+                JournalLine {
+                    account_id: inv_acc,
+                    debit: total_amount,
+                    credit: 0.0,
+                    is_manual: false,
+                    narration: Some("Opening Stock Value".to_string()),
+                },
+                JournalLine {
+                    account_id: adj_acc,
+                    debit: 0.0,
+                    credit: total_amount,
+                    is_manual: false,
+                    narration: Some("Opening Stock Value".to_string()),
+                },
+            ];
+            insert_journal_lines(&mut tx, &id, &journal_lines).await?;
         }
     }
 
@@ -448,6 +424,7 @@ pub async fn delete_opening_stock(
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Delete related data
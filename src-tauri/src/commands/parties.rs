@@ -5,6 +5,9 @@ use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
+use super::entries::{insert_journal_lines, JournalLine};
+use super::system_accounts::resolve_system_account;
+
 async fn get_next_party_code(
     pool: &SqlitePool,
     party_table: &str,
@@ -49,6 +52,8 @@ pub struct Customer {
     pub country: Option<String>,
     pub gstin: Option<String>,
     pub currency: Option<String>,
+    /// Maximum receivable balance; 0 means unlimited
+    pub credit_limit: f64,
     pub is_active: i64,
     pub deleted_at: Option<String>,
     pub created_at: String,
@@ -69,13 +74,15 @@ pub struct CreateCustomer {
     pub country: Option<String>,
     pub gstin: Option<String>,
     pub currency: Option<String>,
+    /// 0 or omitted means unlimited
+    pub credit_limit: Option<f64>,
 }
 
 #[tauri::command]
 pub async fn get_customers(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<Customer>, String> {
     let pool = registry.active_pool().await?;
     sqlx::query_as::<_, Customer>(
-        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM customers WHERE deleted_at IS NULL ORDER BY name ASC",
+        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM customers WHERE deleted_at IS NULL ORDER BY name ASC",
     )
     .fetch_all(&pool)
     .await
@@ -88,7 +95,7 @@ pub async fn get_customer(
     id: String,
 ) -> Result<Customer, String> {
     let pool = registry.active_pool().await?;
-    sqlx::query_as::<_, Customer>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM customers WHERE id = ?")
+    sqlx::query_as::<_, Customer>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM customers WHERE id = ?")
         .bind(id)
         .fetch_one(&pool)
         .await
@@ -126,7 +133,7 @@ pub async fn create_customer(
     };
 
     let _ = sqlx::query(
-        "INSERT INTO customers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO customers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, credit_limit) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(&code)
@@ -142,6 +149,7 @@ pub async fn create_customer(
     .bind(&customer.country)
     .bind(&customer.gstin)
     .bind(&customer.currency)
+    .bind(customer.credit_limit.unwrap_or(0.0))
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -168,7 +176,7 @@ pub async fn create_customer(
 
     tx.commit().await.map_err(|e| e.to_string())?;
 
-    sqlx::query_as::<_, Customer>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM customers WHERE id = ?")
+    sqlx::query_as::<_, Customer>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM customers WHERE id = ?")
         .bind(id)
         .fetch_one(&pool)
         .await
@@ -205,7 +213,7 @@ pub async fn batch_create_customers(
         };
 
         let _ = sqlx::query(
-            "INSERT INTO customers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO customers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, credit_limit) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&id)
         .bind(&code)
@@ -221,6 +229,7 @@ pub async fn batch_create_customers(
         .bind(&customer.country)
         .bind(&customer.gstin)
         .bind(&customer.currency)
+        .bind(customer.credit_limit.unwrap_or(0.0))
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
@@ -259,7 +268,7 @@ pub async fn update_customer(
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
     sqlx::query(
-        "UPDATE customers SET name = ?, email = ?, phone = ?, address_line_1 = ?, address_line_2 = ?, address_line_3 = ?, city = ?, state = ?, postal_code = ?, country = ?, gstin = ?, currency = ? WHERE id = ?"
+        "UPDATE customers SET name = ?, email = ?, phone = ?, address_line_1 = ?, address_line_2 = ?, address_line_3 = ?, city = ?, state = ?, postal_code = ?, country = ?, gstin = ?, currency = ?, credit_limit = COALESCE(?, credit_limit) WHERE id = ?"
     )
     .bind(&customer.name)
     .bind(&customer.email)
@@ -273,6 +282,7 @@ pub async fn update_customer(
     .bind(&customer.country)
     .bind(&customer.gstin)
     .bind(&customer.currency)
+    .bind(customer.credit_limit)
     .bind(&id)
     .execute(&pool)
     .await
@@ -353,7 +363,7 @@ pub async fn get_deleted_customers(
 ) -> Result<Vec<Customer>, String> {
     let pool = registry.active_pool().await?;
     sqlx::query_as::<_, Customer>(
-        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM customers WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM customers WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
     )
     .fetch_all(&pool)
     .await
@@ -451,6 +461,8 @@ pub struct Supplier {
     pub country: Option<String>,
     pub gstin: Option<String>,
     pub currency: Option<String>,
+    /// Maximum payable balance; 0 means unlimited
+    pub credit_limit: f64,
     pub is_active: i64,
     pub deleted_at: Option<String>,
     pub created_at: String,
@@ -471,13 +483,15 @@ pub struct CreateSupplier {
     pub country: Option<String>,
     pub gstin: Option<String>,
     pub currency: Option<String>,
+    /// 0 or omitted means unlimited
+    pub credit_limit: Option<f64>,
 }
 
 #[tauri::command]
 pub async fn get_suppliers(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<Supplier>, String> {
     let pool = registry.active_pool().await?;
     sqlx::query_as::<_, Supplier>(
-        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM suppliers WHERE deleted_at IS NULL ORDER BY name ASC",
+        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM suppliers WHERE deleted_at IS NULL ORDER BY name ASC",
     )
     .fetch_all(&pool)
     .await
@@ -498,7 +512,7 @@ pub(crate) async fn get_supplier_with_pool(
     pool: &SqlitePool,
     id: &str,
 ) -> Result<Supplier, String> {
-    sqlx::query_as::<_, Supplier>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM suppliers WHERE id = ?")
+    sqlx::query_as::<_, Supplier>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM suppliers WHERE id = ?")
         .bind(id)
         .fetch_one(pool)
         .await
@@ -510,7 +524,7 @@ pub(crate) async fn get_customer_with_pool(
     pool: &SqlitePool,
     id: &str,
 ) -> Result<Customer, String> {
-    sqlx::query_as::<_, Customer>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM customers WHERE id = ?")
+    sqlx::query_as::<_, Customer>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM customers WHERE id = ?")
         .bind(id)
         .fetch_one(pool)
         .await
@@ -548,7 +562,7 @@ pub async fn create_supplier(
     };
 
     let _ = sqlx::query(
-        "INSERT INTO suppliers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO suppliers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, credit_limit) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(&code)
@@ -564,6 +578,7 @@ pub async fn create_supplier(
     .bind(&supplier.country)
     .bind(&supplier.gstin)
     .bind(&supplier.currency)
+    .bind(supplier.credit_limit.unwrap_or(0.0))
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -590,7 +605,7 @@ pub async fn create_supplier(
 
     tx.commit().await.map_err(|e| e.to_string())?;
 
-    sqlx::query_as::<_, Supplier>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM suppliers WHERE id = ?")
+    sqlx::query_as::<_, Supplier>("SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM suppliers WHERE id = ?")
         .bind(id)
         .fetch_one(&pool)
         .await
@@ -627,7 +642,7 @@ pub async fn batch_create_suppliers(
         };
 
         let _ = sqlx::query(
-            "INSERT INTO suppliers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO suppliers (id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, credit_limit) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&id)
         .bind(&code)
@@ -643,6 +658,7 @@ pub async fn batch_create_suppliers(
         .bind(&supplier.country)
         .bind(&supplier.gstin)
         .bind(&supplier.currency)
+        .bind(supplier.credit_limit.unwrap_or(0.0))
         .execute(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
@@ -681,7 +697,7 @@ pub async fn update_supplier(
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
     sqlx::query(
-        "UPDATE suppliers SET name = ?, email = ?, phone = ?, address_line_1 = ?, address_line_2 = ?, address_line_3 = ?, city = ?, state = ?, postal_code = ?, country = ?, gstin = ?, currency = ? WHERE id = ?"
+        "UPDATE suppliers SET name = ?, email = ?, phone = ?, address_line_1 = ?, address_line_2 = ?, address_line_3 = ?, city = ?, state = ?, postal_code = ?, country = ?, gstin = ?, currency = ?, credit_limit = COALESCE(?, credit_limit) WHERE id = ?"
     )
     .bind(&supplier.name)
     .bind(&supplier.email)
@@ -695,6 +711,7 @@ pub async fn update_supplier(
     .bind(&supplier.country)
     .bind(&supplier.gstin)
     .bind(&supplier.currency)
+    .bind(supplier.credit_limit)
     .bind(&id)
     .execute(&pool)
     .await
@@ -775,7 +792,7 @@ pub async fn get_deleted_suppliers(
 ) -> Result<Vec<Supplier>, String> {
     let pool = registry.active_pool().await?;
     sqlx::query_as::<_, Supplier>(
-        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, is_active, deleted_at, created_at FROM suppliers WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        "SELECT id, code, name, email, phone, address_line_1, address_line_2, address_line_3, city, state, postal_code, country, gstin, currency, COALESCE(credit_limit, 0) as credit_limit, is_active, deleted_at, created_at FROM suppliers WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
     )
     .fetch_all(&pool)
     .await
@@ -856,6 +873,223 @@ pub async fn hard_delete_supplier(
     Ok(())
 }
 
+// ============= PARTY MERGE =============
+
+/// Folds the `remove_id` party into `keep_id`: every voucher, allocation and
+/// ledger line pointing at the removed party (or its COA account) is repointed,
+/// its opening balance is added to the kept account and folded into a single
+/// opening balance voucher, and the removed party and account are soft-deleted.
+/// Runs in one transaction.
+async fn merge_parties(
+    pool: &SqlitePool,
+    party_table: &str,
+    party_type: &str,
+    keep_id: &str,
+    remove_id: &str,
+) -> Result<(), String> {
+    if keep_id == remove_id {
+        return Err(format!("Cannot merge a {} into itself", party_type));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let mut accounts = Vec::with_capacity(2);
+    for party_id in [keep_id, remove_id] {
+        let exists: bool = sqlx::query_scalar(&format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE id = ? AND deleted_at IS NULL)",
+            party_table
+        ))
+        .bind(party_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("{} {} not found", party_type, party_id));
+        }
+
+        let account: (String, f64, String) = sqlx::query_as(
+            "SELECT id, CAST(COALESCE(opening_balance, 0) AS REAL), COALESCE(opening_balance_type, 'Dr')
+             FROM chart_of_accounts WHERE party_id = ? AND deleted_at IS NULL",
+        )
+        .bind(party_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account for {} {} not found", party_type, party_id))?;
+        accounts.push(account);
+    }
+    let (keep_account, keep_opening, keep_opening_type) = &accounts[0];
+    let (remove_account, remove_opening, remove_opening_type) = &accounts[1];
+
+    let signed = |amount: f64, kind: &str| if kind == "Dr" { amount } else { -amount };
+    let opening =
+        signed(*keep_opening, keep_opening_type) + signed(*remove_opening, remove_opening_type);
+
+    // Each account's opening balance voucher mirrors its own opening balance;
+    // keep one (the kept account's if it has one), repost it with the combined
+    // amount and drop the rest so the balance isn't carried twice
+    let ob_vouchers: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM vouchers
+         WHERE voucher_type = 'opening_balance' AND party_id IN (?, ?) AND deleted_at IS NULL
+         ORDER BY party_id = ? DESC, created_at DESC",
+    )
+    .bind(keep_account)
+    .bind(remove_account)
+    .bind(keep_account)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some((ob_voucher, duplicates)) = ob_vouchers.split_first() {
+        for duplicate in duplicates {
+            sqlx::query("UPDATE vouchers SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?")
+                .bind(duplicate)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        let ob_account = resolve_system_account(&mut *tx, "opening_balance_adjustment").await?;
+        sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ? AND account_id IN (?, ?, ?)")
+            .bind(ob_voucher)
+            .bind(keep_account)
+            .bind(remove_account)
+            .bind(&ob_account)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if opening.abs() > 0.001 {
+            let account_name: String =
+                sqlx::query_scalar("SELECT account_name FROM chart_of_accounts WHERE id = ?")
+                    .bind(keep_account)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            let (debit, credit) = if opening > 0.0 {
+                (opening, 0.0)
+            } else {
+                (0.0, -opening)
+            };
+            insert_journal_lines(
+                &mut tx,
+                ob_voucher,
+                &[
+                    JournalLine {
+                        account_id: keep_account.clone(),
+                        debit,
+                        credit,
+                        is_manual: false,
+                        narration: Some(format!("Opening balance: {}", account_name)),
+                    },
+                    JournalLine {
+                        account_id: ob_account,
+                        debit: credit,
+                        credit: debit,
+                        is_manual: false,
+                        narration: Some("Auto-generated balancing entry".to_string()),
+                    },
+                ],
+            )
+            .await?;
+        }
+
+        sqlx::query("UPDATE vouchers SET party_id = ?, total_amount = ? WHERE id = ?")
+            .bind(keep_account)
+            .bind(opening.abs())
+            .bind(ob_voucher)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Vouchers and allocations may carry either the party id or its account id
+    for (table, column) in [
+        ("vouchers", "party_id"),
+        ("payment_allocations", "party_id"),
+        ("deleted_payment_allocations", "party_id"),
+    ] {
+        for (from, to) in [
+            (remove_account.as_str(), keep_account.as_str()),
+            (remove_id, keep_id),
+        ] {
+            sqlx::query(&format!(
+                "UPDATE {} SET {} = ? WHERE {} = ?",
+                table, column, column
+            ))
+            .bind(to)
+            .bind(from)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    for (table, column) in [
+        ("journal_entries", "account_id"),
+        ("held_journal_entries", "account_id"),
+        ("voucher_items", "ledger_id"),
+    ] {
+        sqlx::query(&format!(
+            "UPDATE {} SET {} = ? WHERE {} = ?",
+            table, column, column
+        ))
+        .bind(keep_account)
+        .bind(remove_account)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    sqlx::query(
+        "UPDATE chart_of_accounts SET opening_balance = ?, opening_balance_type = ? WHERE id = ?",
+    )
+    .bind(opening.abs())
+    .bind(if opening < 0.0 { "Cr" } else { "Dr" })
+    .bind(keep_account)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query(&format!(
+        "UPDATE {} SET is_active = 0, deleted_at = CURRENT_TIMESTAMP WHERE id = ?",
+        party_table
+    ))
+    .bind(remove_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    sqlx::query(
+        "UPDATE chart_of_accounts SET is_active = 0, opening_balance = 0, deleted_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(remove_account)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn merge_customers(
+    registry: State<'_, Arc<DbRegistry>>,
+    keep_id: String,
+    remove_id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    merge_parties(&pool, "customers", "customer", &keep_id, &remove_id).await
+}
+
+#[tauri::command]
+pub async fn merge_suppliers(
+    registry: State<'_, Arc<DbRegistry>>,
+    keep_id: String,
+    remove_id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    merge_parties(&pool, "suppliers", "supplier", &keep_id, &remove_id).await
+}
+
 // ============= COMMON PARTY =============
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct Party {
@@ -1,9 +1,15 @@
+use crate::company_db::DbRegistry;
 use dirs::download_dir;
 use printpdf::*;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::State;
+
+use super::company::{get_company_profile_with_pool, CompanyProfile};
+use super::reports::{day_book_entries, party_statement};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LedgerPdfEntry {
@@ -266,3 +272,591 @@ pub async fn generate_ledger_pdf(data: LedgerPdfData, file_path: String) -> Resu
 
     Ok(output_path.to_string_lossy().to_string())
 }
+
+/// Shortens `text` to at most `max_chars` characters, ending in "..." when cut.
+fn truncate_text(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        let kept: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+        format!("{}...", kept)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Company name, address and GSTIN at the top of a report page. Returns the
+/// y position below the block.
+fn draw_company_block(
+    layer: &PdfLayerReference,
+    profile: &CompanyProfile,
+    font: &IndirectFontRef,
+    font_bold: &IndirectFontRef,
+    left_margin: f32,
+    mut y_pos: f32,
+) -> f32 {
+    layer.use_text(
+        &profile.company_name,
+        14.0,
+        Mm(left_margin),
+        Mm(y_pos),
+        font_bold,
+    );
+    y_pos -= 5.0;
+    let address = [
+        profile.address_line1.as_deref(),
+        profile.city.as_deref(),
+        profile.state.as_deref(),
+        profile.pincode.as_deref(),
+    ]
+    .iter()
+    .flatten()
+    .filter(|part| !part.trim().is_empty())
+    .copied()
+    .collect::<Vec<_>>()
+    .join(", ");
+    if !address.is_empty() {
+        layer.use_text(&address, 8.0, Mm(left_margin), Mm(y_pos), font);
+        y_pos -= 4.0;
+    }
+    if let Some(gstin) = profile.gstin.as_deref().filter(|g| !g.trim().is_empty()) {
+        layer.use_text(
+            &format!("GSTIN: {}", gstin),
+            8.0,
+            Mm(left_margin),
+            Mm(y_pos),
+            font,
+        );
+        y_pos -= 4.0;
+    }
+    y_pos - 4.0
+}
+
+/// Renders the day book for the period (one block per voucher with its debit
+/// and credit lines, a subtotal per day and a grand total) to `file_path`.
+#[tauri::command]
+pub async fn generate_day_book_pdf(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    file_path: String,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    let profile = get_company_profile_with_pool(&pool).await?;
+    let entries = day_book_entries(&pool, &from_date, &to_date, true, &[]).await?;
+    let output_path = PathBuf::from(&file_path);
+
+    let (document, page1, layer1) = PdfDocument::new("Day Book", Mm(210.0), Mm(297.0), "Layer 1");
+    let font = document
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+    let font_bold = document
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| e.to_string())?;
+
+    let mut current_layer = document.get_page(page1).get_layer(layer1);
+
+    let left_margin = 12.0;
+    let top_margin = 280.0;
+    let mut y_pos = top_margin;
+
+    y_pos = draw_company_block(
+        &current_layer,
+        &profile,
+        &font,
+        &font_bold,
+        left_margin,
+        y_pos,
+    );
+
+    // Title
+    current_layer.use_text("DAY BOOK", 18.0, Mm(left_margin), Mm(y_pos), &font_bold);
+    y_pos -= 6.0;
+    let period_info = format!("Period: {} to {}", from_date, to_date);
+    current_layer.use_text(&period_info, 9.0, Mm(left_margin), Mm(y_pos), &font);
+    y_pos -= 6.0;
+
+    // 186mm available with 12mm margins
+    let col_widths = [21.0, 26.0, 24.0, 69.0, 23.0, 23.0];
+    let mut col_x = vec![left_margin];
+    for width in &col_widths[0..col_widths.len() - 1] {
+        col_x.push(col_x.last().unwrap() + width);
+    }
+    let headers = ["Date", "Voucher No", "Type", "Account", "Debit", "Credit"];
+    let line_height = 5.0;
+    let cell_padding = 0.8;
+
+    for (i, header) in headers.iter().enumerate() {
+        current_layer.use_text(
+            *header,
+            9.0,
+            Mm(col_x[i] + cell_padding),
+            Mm(y_pos),
+            &font_bold,
+        );
+    }
+    y_pos -= line_height + 1.0;
+
+    let mut day_debit = 0.0;
+    let mut day_credit = 0.0;
+    let mut total_debit = 0.0;
+    let mut total_credit = 0.0;
+
+    for (idx, entry) in entries.iter().enumerate() {
+        let new_voucher = idx == 0 || entries[idx - 1].voucher_no != entry.voucher_no;
+
+        // Create new page if needed
+        if y_pos < 25.0 {
+            let (page, layer) = document.add_page(Mm(210.0), Mm(297.0), "Page");
+            current_layer = document.get_page(page).get_layer(layer);
+            y_pos = top_margin - 15.0;
+
+            for (i, header) in headers.iter().enumerate() {
+                current_layer.use_text(
+                    *header,
+                    9.0,
+                    Mm(col_x[i] + cell_padding),
+                    Mm(y_pos),
+                    &font_bold,
+                );
+            }
+            y_pos -= line_height + 1.0;
+        }
+
+        if new_voucher {
+            y_pos -= 1.0;
+            current_layer.use_text(
+                &entry.voucher_date,
+                7.5,
+                Mm(col_x[0] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+            current_layer.use_text(
+                &entry.voucher_no,
+                7.5,
+                Mm(col_x[1] + cell_padding),
+                Mm(y_pos),
+                &font_bold,
+            );
+            current_layer.use_text(
+                &truncate_text(&entry.voucher_type.replace('_', " "), 16),
+                7.5,
+                Mm(col_x[2] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+        }
+
+        current_layer.use_text(
+            &truncate_text(&entry.account_name, 45),
+            7.5,
+            Mm(col_x[3] + cell_padding),
+            Mm(y_pos),
+            &font,
+        );
+        if entry.debit > 0.01 {
+            current_layer.use_text(
+                &format!("{:>12.2}", entry.debit),
+                7.5,
+                Mm(col_x[4] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+        }
+        if entry.credit > 0.01 {
+            current_layer.use_text(
+                &format!("{:>12.2}", entry.credit),
+                7.5,
+                Mm(col_x[5] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+        }
+        y_pos -= line_height;
+
+        day_debit += entry.debit;
+        day_credit += entry.credit;
+        total_debit += entry.debit;
+        total_credit += entry.credit;
+
+        // Narration under the voucher's last line
+        let next = entries.get(idx + 1);
+        let voucher_ends = next
+            .map(|n| n.voucher_no != entry.voucher_no)
+            .unwrap_or(true);
+        if voucher_ends && !entry.narration.trim().is_empty() {
+            current_layer.use_text(
+                &truncate_text(&entry.narration, 60),
+                7.0,
+                Mm(col_x[3] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+            y_pos -= line_height;
+        }
+
+        // Subtotal when the day changes
+        let day_ends = next
+            .map(|n| n.voucher_date != entry.voucher_date)
+            .unwrap_or(true);
+        if day_ends {
+            current_layer.use_text(
+                &format!("Total for {}", entry.voucher_date),
+                8.0,
+                Mm(col_x[3] + cell_padding),
+                Mm(y_pos),
+                &font_bold,
+            );
+            current_layer.use_text(
+                &format!("{:>12.2}", day_debit),
+                8.0,
+                Mm(col_x[4] + cell_padding),
+                Mm(y_pos),
+                &font_bold,
+            );
+            current_layer.use_text(
+                &format!("{:>12.2}", day_credit),
+                8.0,
+                Mm(col_x[5] + cell_padding),
+                Mm(y_pos),
+                &font_bold,
+            );
+            y_pos -= line_height + 2.0;
+            day_debit = 0.0;
+            day_credit = 0.0;
+        }
+    }
+
+    // Grand total
+    y_pos -= 2.0;
+    current_layer.use_text(
+        "Grand Total",
+        9.0,
+        Mm(col_x[3] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+    current_layer.use_text(
+        &format!("{:>12.2}", total_debit),
+        9.0,
+        Mm(col_x[4] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+    current_layer.use_text(
+        &format!("{:>12.2}", total_credit),
+        9.0,
+        Mm(col_x[5] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+
+    document
+        .save(&mut BufWriter::new(
+            File::create(&output_path).map_err(|e| e.to_string())?,
+        ))
+        .map_err(|e| e.to_string())?;
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Statement of account PDF for a customer or supplier: ledger with running
+/// balance for the period, followed by the invoices still open at the end.
+#[tauri::command]
+pub async fn generate_party_statement_pdf(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String,
+    party_type: String,
+    from_date: String,
+    to_date: String,
+    file_path: String,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    let profile = get_company_profile_with_pool(&pool).await?;
+    let statement = party_statement(&pool, &party_id, &party_type, &from_date, &to_date).await?;
+    let output_path = PathBuf::from(&file_path);
+
+    let (document, page1, layer1) =
+        PdfDocument::new("Statement of Account", Mm(210.0), Mm(297.0), "Layer 1");
+    let font = document
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+    let font_bold = document
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| e.to_string())?;
+
+    let mut current_layer = document.get_page(page1).get_layer(layer1);
+
+    let left_margin = 12.0;
+    let top_margin = 280.0;
+    let mut y_pos = top_margin;
+
+    y_pos = draw_company_block(
+        &current_layer,
+        &profile,
+        &font,
+        &font_bold,
+        left_margin,
+        y_pos,
+    );
+
+    current_layer.use_text(
+        "STATEMENT OF ACCOUNT",
+        18.0,
+        Mm(left_margin),
+        Mm(y_pos),
+        &font_bold,
+    );
+    y_pos -= 7.0;
+    current_layer.use_text(
+        &statement.party_name,
+        12.0,
+        Mm(left_margin),
+        Mm(y_pos),
+        &font_bold,
+    );
+    y_pos -= 5.0;
+    let period_info = format!("Period: {} to {}", from_date, to_date);
+    current_layer.use_text(&period_info, 9.0, Mm(left_margin), Mm(y_pos), &font);
+    y_pos -= 6.0;
+
+    let balance_text = |amount: f64| {
+        format!(
+            "{:.2} {}",
+            amount.abs(),
+            if amount >= 0.0 { "Dr" } else { "Cr" }
+        )
+    };
+
+    // 186mm available with 12mm margins
+    let col_widths = [21.0, 26.0, 22.0, 50.0, 21.0, 21.0, 25.0];
+    let mut col_x = vec![left_margin];
+    for width in &col_widths[0..col_widths.len() - 1] {
+        col_x.push(col_x.last().unwrap() + width);
+    }
+    let headers = [
+        "Date",
+        "Voucher No",
+        "Type",
+        "Narration",
+        "Debit",
+        "Credit",
+        "Balance",
+    ];
+    let line_height = 5.0;
+    let cell_padding = 0.8;
+
+    for (i, header) in headers.iter().enumerate() {
+        current_layer.use_text(
+            *header,
+            9.0,
+            Mm(col_x[i] + cell_padding),
+            Mm(y_pos),
+            &font_bold,
+        );
+    }
+    y_pos -= line_height + 1.0;
+
+    current_layer.use_text(
+        "Opening Balance",
+        8.0,
+        Mm(col_x[0] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+    current_layer.use_text(
+        &balance_text(statement.opening_balance),
+        8.0,
+        Mm(col_x[6] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+    y_pos -= line_height;
+
+    for entry in &statement.entries {
+        // Create new page if needed
+        if y_pos < 25.0 {
+            let (page, layer) = document.add_page(Mm(210.0), Mm(297.0), "Page");
+            current_layer = document.get_page(page).get_layer(layer);
+            y_pos = top_margin - 15.0;
+
+            for (i, header) in headers.iter().enumerate() {
+                current_layer.use_text(
+                    *header,
+                    9.0,
+                    Mm(col_x[i] + cell_padding),
+                    Mm(y_pos),
+                    &font_bold,
+                );
+            }
+            y_pos -= line_height + 1.0;
+        }
+
+        current_layer.use_text(
+            &entry.date,
+            7.5,
+            Mm(col_x[0] + cell_padding),
+            Mm(y_pos),
+            &font,
+        );
+        current_layer.use_text(
+            &entry.voucher_no,
+            7.5,
+            Mm(col_x[1] + cell_padding),
+            Mm(y_pos),
+            &font,
+        );
+        current_layer.use_text(
+            &truncate_text(&entry.voucher_type_label.replace('_', " "), 14),
+            7.5,
+            Mm(col_x[2] + cell_padding),
+            Mm(y_pos),
+            &font,
+        );
+        current_layer.use_text(
+            &truncate_text(&entry.narration, 32),
+            7.5,
+            Mm(col_x[3] + cell_padding),
+            Mm(y_pos),
+            &font,
+        );
+        if entry.debit > 0.01 {
+            current_layer.use_text(
+                &format!("{:>12.2}", entry.debit),
+                7.5,
+                Mm(col_x[4] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+        }
+        if entry.credit > 0.01 {
+            current_layer.use_text(
+                &format!("{:>12.2}", entry.credit),
+                7.5,
+                Mm(col_x[5] + cell_padding),
+                Mm(y_pos),
+                &font,
+            );
+        }
+        current_layer.use_text(
+            &balance_text(entry.balance),
+            7.5,
+            Mm(col_x[6] + cell_padding),
+            Mm(y_pos),
+            &font,
+        );
+        y_pos -= line_height;
+    }
+
+    y_pos -= 2.0;
+    current_layer.use_text(
+        "Closing Balance",
+        9.0,
+        Mm(col_x[0] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+    current_layer.use_text(
+        &balance_text(statement.closing_balance),
+        9.0,
+        Mm(col_x[6] + cell_padding),
+        Mm(y_pos),
+        &font_bold,
+    );
+    y_pos -= line_height + 4.0;
+
+    // Open invoices
+    if !statement.open_invoices.is_empty() {
+        let invoice_x = [
+            left_margin,
+            left_margin + 30.0,
+            left_margin + 55.0,
+            left_margin + 85.0,
+            left_margin + 115.0,
+            left_margin + 150.0,
+        ];
+        let invoice_headers = [
+            "Invoice No",
+            "Date",
+            "Amount",
+            "Paid",
+            "Outstanding",
+            "Days",
+        ];
+
+        if y_pos < 40.0 {
+            let (page, layer) = document.add_page(Mm(210.0), Mm(297.0), "Page");
+            current_layer = document.get_page(page).get_layer(layer);
+            y_pos = top_margin - 15.0;
+        }
+        current_layer.use_text(
+            "Open Invoices",
+            11.0,
+            Mm(left_margin),
+            Mm(y_pos),
+            &font_bold,
+        );
+        y_pos -= 6.0;
+        for (i, header) in invoice_headers.iter().enumerate() {
+            current_layer.use_text(
+                *header,
+                9.0,
+                Mm(invoice_x[i] + cell_padding),
+                Mm(y_pos),
+                &font_bold,
+            );
+        }
+        y_pos -= line_height + 1.0;
+
+        let mut total_outstanding = 0.0;
+        for invoice in &statement.open_invoices {
+            if y_pos < 25.0 {
+                let (page, layer) = document.add_page(Mm(210.0), Mm(297.0), "Page");
+                current_layer = document.get_page(page).get_layer(layer);
+                y_pos = top_margin - 15.0;
+            }
+            let cells = [
+                invoice.voucher_no.clone(),
+                invoice.voucher_date.clone(),
+                format!("{:>12.2}", invoice.total_amount),
+                format!("{:>12.2}", invoice.paid_amount),
+                format!("{:>12.2}", invoice.outstanding_amount),
+                invoice.days_outstanding.to_string(),
+            ];
+            for (i, cell) in cells.iter().enumerate() {
+                current_layer.use_text(
+                    cell,
+                    7.5,
+                    Mm(invoice_x[i] + cell_padding),
+                    Mm(y_pos),
+                    &font,
+                );
+            }
+            total_outstanding += invoice.outstanding_amount;
+            y_pos -= line_height;
+        }
+
+        y_pos -= 2.0;
+        current_layer.use_text(
+            "Total Outstanding",
+            9.0,
+            Mm(invoice_x[0] + cell_padding),
+            Mm(y_pos),
+            &font_bold,
+        );
+        current_layer.use_text(
+            &format!("{:>12.2}", total_outstanding),
+            9.0,
+            Mm(invoice_x[4] + cell_padding),
+            Mm(y_pos),
+            &font_bold,
+        );
+    }
+
+    document
+        .save(&mut BufWriter::new(
+            File::create(&output_path).map_err(|e| e.to_string())?,
+        ))
+        .map_err(|e| e.to_string())?;
+
+    Ok(output_path.to_string_lossy().to_string())
+}
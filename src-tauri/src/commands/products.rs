@@ -10,6 +10,9 @@ use aws_sdk_s3::config::{Credentials, Region};
 use aws_sdk_s3::primitives::ByteStream;
 use image::GenericImageView;
 
+use super::auth::SessionStore;
+use crate::utils::parse_csv;
+
 // ============= PRODUCT GROUPS =============
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct ProductGroup {
@@ -480,6 +483,10 @@ pub struct Product {
     pub sales_rate: f64,
     pub mrp: f64,
     pub cost: Option<f64>,
+    /// Per-product reorder point (0 = use the global stock alert threshold)
+    pub reorder_level: f64,
+    /// Tax rate (%) prefilled on invoice lines for this product
+    pub default_tax_rate: f64,
     pub barcode: Option<String>,
     pub is_active: i64,
     pub created_at: String,
@@ -541,6 +548,8 @@ pub struct CreateProduct {
     pub sales_rate: f64,
     pub mrp: f64,
     pub cost: Option<f64>,
+    pub reorder_level: Option<f64>,
+    pub default_tax_rate: Option<f64>,
     pub barcode: Option<String>,
     #[serde(default)]
     pub conversions: Vec<ProductUnitConversionInput>,
@@ -766,11 +775,114 @@ pub async fn get_product_unit_conversions(
     .map_err(|e| e.to_string())
 }
 
+/// A product's base unit plus its secondary (e.g. carton vs piece) unit, taken
+/// from product_unit_conversions. Without a secondary unit the factor is 1.
+#[derive(Serialize)]
+pub struct ProductConversion {
+    pub product_id: String,
+    pub base_unit_id: String,
+    pub base_unit_symbol: String,
+    pub secondary_unit_id: Option<String>,
+    pub secondary_unit_symbol: Option<String>,
+    pub conversion_factor: f64,
+}
+
+/// The secondary unit is the default purchase unit when it differs from the base
+/// unit, otherwise the largest non-base conversion.
+#[tauri::command]
+pub async fn get_product_conversion(
+    registry: State<'_, Arc<DbRegistry>>,
+    product_id: String,
+) -> Result<ProductConversion, String> {
+    let pool = registry.active_pool().await?;
+    let (base_unit_id, base_unit_symbol): (String, String) = sqlx::query_as(
+        "SELECT p.unit_id, u.symbol
+         FROM products p
+         JOIN units u ON p.unit_id = u.id
+         WHERE p.id = ?",
+    )
+    .bind(&product_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Product '{}' not found", product_id))?;
+
+    let secondary: Option<(String, String, f64)> = sqlx::query_as(
+        "SELECT puc.unit_id, u.symbol, puc.factor_to_base
+         FROM product_unit_conversions puc
+         JOIN units u ON puc.unit_id = u.id
+         WHERE puc.product_id = ? AND puc.unit_id <> ?
+         ORDER BY puc.is_default_purchase DESC, puc.factor_to_base DESC
+         LIMIT 1",
+    )
+    .bind(&product_id)
+    .bind(&base_unit_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let (secondary_unit_id, secondary_unit_symbol, conversion_factor) = match secondary {
+        Some((unit_id, symbol, factor)) => (Some(unit_id), Some(symbol), factor),
+        None => (None, None, 1.0),
+    };
+
+    Ok(ProductConversion {
+        product_id,
+        base_unit_id,
+        base_unit_symbol,
+        secondary_unit_id,
+        secondary_unit_symbol,
+        conversion_factor,
+    })
+}
+
+/// Trims a barcode, treating blank input as "no barcode".
+fn normalize_barcode(barcode: Option<&str>) -> Option<String> {
+    barcode
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(str::to_string)
+}
+
+/// Fails with a readable message when `barcode` already belongs to another product,
+/// instead of letting the unique index surface a raw constraint error.
+async fn ensure_barcode_available(
+    tx: &mut Transaction<'_, Sqlite>,
+    barcode: Option<&str>,
+    exclude_product_id: Option<&str>,
+) -> Result<(), String> {
+    let Some(barcode) = barcode else {
+        return Ok(());
+    };
+    let owner: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT name, deleted_at FROM products WHERE barcode = ? AND id <> COALESCE(?, '') LIMIT 1",
+    )
+    .bind(barcode)
+    .bind(exclude_product_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match owner {
+        Some((name, None)) => Err(format!(
+            "Barcode '{}' is already assigned to product '{}'.",
+            barcode, name
+        )),
+        Some((name, Some(_))) => Err(format!(
+            "Barcode '{}' is already assigned to deleted product '{}'. Restore or permanently delete it first.",
+            barcode, name
+        )),
+        None => Ok(()),
+    }
+}
+
 #[tauri::command]
 pub async fn get_products(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<Product>, String> {
     let pool = registry.active_pool().await?;
     sqlx::query_as::<_, Product>(
-        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost, barcode, is_active, created_at,
+        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost,
+                COALESCE(reorder_level, 0) as reorder_level,
+                COALESCE(default_tax_rate, 0) as default_tax_rate, barcode, is_active, created_at,
                 EXISTS(SELECT 1 FROM voucher_items vi WHERE vi.product_id = products.id) as has_transactions,
                 hsn_sac_code, gst_slab_id,
                 COALESCE(is_master, 0) as is_master,
@@ -785,6 +897,65 @@ pub async fn get_products(registry: State<'_, Arc<DbRegistry>>) -> Result<Vec<Pr
     .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_product_by_barcode(
+    registry: State<'_, Arc<DbRegistry>>,
+    barcode: String,
+) -> Result<Product, String> {
+    let pool = registry.active_pool().await?;
+    let barcode =
+        normalize_barcode(Some(&barcode)).ok_or_else(|| "Barcode is required".to_string())?;
+    sqlx::query_as::<_, Product>(
+        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost,
+                COALESCE(reorder_level, 0) as reorder_level,
+                COALESCE(default_tax_rate, 0) as default_tax_rate, barcode, is_active, created_at,
+                EXISTS(SELECT 1 FROM voucher_items vi WHERE vi.product_id = products.id) as has_transactions,
+                hsn_sac_code, gst_slab_id,
+                COALESCE(is_master, 0) as is_master,
+                parent_product_id,
+                vehicle_manufacturer, vehicle_model, vehicle_year, vehicle_odometer, vehicle_fuel_type, vehicle_transmission, vehicle_owner, vehicle_color
+         FROM products
+         WHERE barcode = ? AND deleted_at IS NULL AND COALESCE(is_active, 1) = 1",
+    )
+    .bind(&barcode)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("No active product found for barcode '{}'", barcode))
+}
+
+/// Everything the invoice line editor prefills when a product is picked.
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ProductDefaults {
+    pub product_id: String,
+    pub sales_rate: f64,
+    pub purchase_rate: f64,
+    pub default_tax_rate: f64,
+    pub unit_id: String,
+    pub unit_symbol: String,
+}
+
+#[tauri::command]
+pub async fn get_product_defaults(
+    registry: State<'_, Arc<DbRegistry>>,
+    product_id: String,
+) -> Result<ProductDefaults, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, ProductDefaults>(
+        "SELECT p.id as product_id, p.sales_rate, p.purchase_rate,
+                COALESCE(p.default_tax_rate, 0) as default_tax_rate,
+                p.unit_id, u.symbol as unit_symbol
+         FROM products p
+         JOIN units u ON u.id = p.unit_id
+         WHERE p.id = ? AND p.deleted_at IS NULL",
+    )
+    .bind(&product_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Product '{}' not found", product_id))
+}
+
 #[tauri::command]
 pub async fn create_product(
     registry: State<'_, Arc<DbRegistry>>,
@@ -820,6 +991,8 @@ pub async fn create_product(
 
     let id = Uuid::now_v7().to_string();
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let barcode = normalize_barcode(product.barcode.as_deref());
+    ensure_barcode_available(&mut tx, barcode.as_deref(), None).await?;
 
     // Master products MUST have a manually typed code — no auto-generation
     let code = if product.is_master {
@@ -834,9 +1007,9 @@ pub async fn create_product(
     };
 
     sqlx::query(
-        "INSERT INTO products (id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost, barcode, hsn_sac_code, gst_slab_id, is_master,
+        "INSERT INTO products (id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost, reorder_level, default_tax_rate, barcode, hsn_sac_code, gst_slab_id, is_master,
                               vehicle_manufacturer, vehicle_model, vehicle_year, vehicle_odometer, vehicle_fuel_type, vehicle_transmission, vehicle_owner, vehicle_color) 
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(&code)
@@ -848,7 +1021,9 @@ pub async fn create_product(
     .bind(product.sales_rate)
     .bind(product.mrp)
     .bind(product.cost)
-    .bind(&product.barcode)
+    .bind(product.reorder_level.unwrap_or(0.0))
+    .bind(product.default_tax_rate.unwrap_or(0.0))
+    .bind(&barcode)
     .bind(&product.hsn_sac_code)
     .bind(&product.gst_slab_id)
     .bind(if product.is_master { 1i64 } else { 0i64 })
@@ -877,7 +1052,9 @@ pub async fn create_product(
     tx.commit().await.map_err(|e| e.to_string())?;
 
     sqlx::query_as::<_, Product>(
-        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost, barcode, is_active, created_at,
+        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost,
+                COALESCE(reorder_level, 0) as reorder_level,
+                COALESCE(default_tax_rate, 0) as default_tax_rate, barcode, is_active, created_at,
                 EXISTS(SELECT 1 FROM voucher_items vi WHERE vi.product_id = products.id) as has_transactions,
                 hsn_sac_code, gst_slab_id,
                 COALESCE(is_master, 0) as is_master,
@@ -903,6 +1080,9 @@ pub async fn batch_create_products(
 
     for product in products {
         let id = Uuid::now_v7().to_string();
+        let barcode = normalize_barcode(product.barcode.as_deref());
+        // Checked in the transaction so duplicates within the batch are caught too
+        ensure_barcode_available(&mut tx, barcode.as_deref(), None).await?;
 
         let code = if product.is_master {
             if product.code.trim().is_empty() {
@@ -916,8 +1096,8 @@ pub async fn batch_create_products(
         };
 
         sqlx::query(
-            "INSERT INTO products (id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, barcode, hsn_sac_code, gst_slab_id, is_master) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO products (id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, reorder_level, default_tax_rate, barcode, hsn_sac_code, gst_slab_id, is_master) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&id)
         .bind(&code)
@@ -928,7 +1108,9 @@ pub async fn batch_create_products(
         .bind(product.purchase_rate)
         .bind(product.sales_rate)
         .bind(product.mrp)
-        .bind(&product.barcode)
+        .bind(product.reorder_level.unwrap_or(0.0))
+        .bind(product.default_tax_rate.unwrap_or(0.0))
+        .bind(&barcode)
         .bind(&product.hsn_sac_code)
         .bind(&product.gst_slab_id)
         .bind(if product.is_master { 1i64 } else { 0i64 })
@@ -952,9 +1134,189 @@ pub async fn batch_create_products(
     Ok(count)
 }
 
+// ============= PRODUCT CSV IMPORT =============
+#[derive(Serialize, Deserialize)]
+pub struct ProductImportRowError {
+    /// 1-based line number in the file, counting the header
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProductImportSummary {
+    pub inserted_count: usize,
+    /// Rows skipped because their code already exists (also listed in `errors`)
+    pub skipped_count: usize,
+    /// Symbols of units created during the import
+    pub created_units: Vec<String>,
+    pub errors: Vec<ProductImportRowError>,
+}
+
+/// Blank means 0; anything else must parse as a number.
+fn parse_import_rate(value: Option<&String>, column: &str) -> Result<f64, String> {
+    match value.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        None => Ok(0.0),
+        Some(v) => v
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid {} '{}'", column, v)),
+    }
+}
+
+/// Imports products from a CSV with the header
+/// `code,name,unit_symbol,purchase_rate,sales_rate,mrp` (any column order).
+/// Rows whose code already exists are skipped; invalid rows are reported and
+/// the rest are inserted in one transaction. A blank code is auto-generated.
+/// Unknown unit symbols are created when `create_missing_units` is set and
+/// reported as row errors otherwise.
+#[tauri::command]
+pub async fn import_products_csv(
+    registry: State<'_, Arc<DbRegistry>>,
+    file_path: String,
+    create_missing_units: Option<bool>,
+) -> Result<ProductImportSummary, String> {
+    let pool = registry.active_pool().await?;
+    let content = std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let mut records = parse_csv(&content).into_iter();
+
+    let (_, header) = records
+        .next()
+        .ok_or_else(|| "The CSV file is empty".to_string())?;
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(code_col), Some(name_col), Some(unit_col)) =
+        (column("code"), column("name"), column("unit_symbol"))
+    else {
+        return Err("CSV header must include code, name and unit_symbol columns".to_string());
+    };
+    let purchase_col = column("purchase_rate");
+    let sales_col = column("sales_rate");
+    let mrp_col = column("mrp");
+
+    let create_missing_units = create_missing_units.unwrap_or(false);
+    let mut summary = ProductImportSummary {
+        inserted_count: 0,
+        skipped_count: 0,
+        created_units: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    for (row, fields) in records {
+        let field = |col: Option<usize>| col.and_then(|c| fields.get(c));
+        let text = |col: usize| fields.get(col).map(|v| v.trim()).unwrap_or("");
+        let mut fail = |message: String| {
+            summary.errors.push(ProductImportRowError { row, message });
+        };
+
+        let name = text(name_col);
+        let unit_symbol = text(unit_col);
+        if name.is_empty() {
+            fail("Name is required".to_string());
+            continue;
+        }
+        if unit_symbol.is_empty() {
+            fail("unit_symbol is required".to_string());
+            continue;
+        }
+        let rates = parse_import_rate(field(purchase_col), "purchase_rate").and_then(|pr| {
+            Ok((
+                pr,
+                parse_import_rate(field(sales_col), "sales_rate")?,
+                parse_import_rate(field(mrp_col), "mrp")?,
+            ))
+        });
+        let (purchase_rate, sales_rate, mrp) = match rates {
+            Ok(rates) => rates,
+            Err(e) => {
+                fail(e);
+                continue;
+            }
+        };
+
+        let code = text(code_col).to_string();
+        if !code.is_empty() {
+            // Deleted products keep their code, so they block it too
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM products WHERE code = ?)")
+                    .bind(&code)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            if exists {
+                fail(format!("Code '{}' already exists; skipped", code));
+                summary.skipped_count += 1;
+                continue;
+            }
+        }
+
+        let unit_id: Option<String> =
+            sqlx::query_scalar("SELECT id FROM units WHERE LOWER(symbol) = LOWER(?) LIMIT 1")
+                .bind(unit_symbol)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        let unit_id = match unit_id {
+            Some(id) => id,
+            None if create_missing_units => {
+                let id = Uuid::now_v7().to_string();
+                let is_default = !has_any_units(&mut tx).await?;
+                sqlx::query("INSERT INTO units (id, name, symbol, is_default) VALUES (?, ?, ?, ?)")
+                    .bind(&id)
+                    .bind(unit_symbol)
+                    .bind(unit_symbol)
+                    .bind(if is_default { 1 } else { 0 })
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                summary.created_units.push(unit_symbol.to_string());
+                id
+            }
+            None => {
+                fail(format!("Unit '{}' not found", unit_symbol));
+                continue;
+            }
+        };
+
+        let code = if code.is_empty() {
+            generate_product_code_in_tx(&mut tx).await?
+        } else {
+            code
+        };
+        let id = Uuid::now_v7().to_string();
+        let inserted = sqlx::query(
+            "INSERT INTO products (id, code, name, unit_id, purchase_rate, sales_rate, mrp, cost)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&code)
+        .bind(name)
+        .bind(&unit_id)
+        .bind(purchase_rate)
+        .bind(sales_rate)
+        .bind(mrp)
+        .bind(purchase_rate)
+        .execute(&mut *tx)
+        .await;
+        if let Err(e) = inserted {
+            fail(e.to_string());
+            continue;
+        }
+
+        replace_product_unit_conversions(&mut tx, &id, &unit_id, purchase_rate, sales_rate, &[])
+            .await?;
+        summary.inserted_count += 1;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
 #[tauri::command]
 pub async fn update_product(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     id: String,
     product: CreateProduct,
 ) -> Result<(), String> {
@@ -1008,10 +1370,21 @@ pub async fn update_product(
     }
 
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let barcode = normalize_barcode(product.barcode.as_deref());
+    ensure_barcode_available(&mut tx, barcode.as_deref(), Some(&id)).await?;
+    record_price_changes(
+        &mut tx,
+        &id,
+        product.purchase_rate,
+        product.sales_rate,
+        product.mrp,
+        session_store.current_user_id().as_deref(),
+    )
+    .await?;
     sqlx::query(
         "UPDATE products 
          SET code = ?, name = ?, group_id = ?, brand_id = ?, unit_id = ?, purchase_rate = ?, sales_rate = ?, mrp = ?, cost = ?,
-             barcode = ?, hsn_sac_code = ?, gst_slab_id = ?, is_master = ?,
+             reorder_level = COALESCE(?, reorder_level), default_tax_rate = COALESCE(?, default_tax_rate), barcode = ?, hsn_sac_code = ?, gst_slab_id = ?, is_master = ?,
              vehicle_manufacturer = ?, vehicle_model = ?, vehicle_year = ?, vehicle_odometer = ?, vehicle_fuel_type = ?, vehicle_transmission = ?, vehicle_owner = ?, vehicle_color = ?,
              updated_at = CURRENT_TIMESTAMP 
          WHERE id = ?",
@@ -1025,7 +1398,9 @@ pub async fn update_product(
     .bind(product.sales_rate)
     .bind(product.mrp)
     .bind(product.cost)
-    .bind(&product.barcode)
+    .bind(product.reorder_level)
+    .bind(product.default_tax_rate)
+    .bind(&barcode)
     .bind(&product.hsn_sac_code)
     .bind(&product.gst_slab_id)
     .bind(if product.is_master { 1i64 } else { 0i64 })
@@ -1069,12 +1444,23 @@ pub struct UpdateProductRates {
 #[tauri::command]
 pub async fn update_multiple_product_rates(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     rates: Vec<UpdateProductRates>,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    let changed_by = session_store.current_user_id();
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     for rate in rates {
+        record_price_changes(
+            &mut tx,
+            &rate.id,
+            rate.purchase_rate,
+            rate.sales_rate,
+            rate.mrp,
+            changed_by.as_deref(),
+        )
+        .await?;
         sqlx::query(
             "UPDATE products 
              SET purchase_rate = ?, sales_rate = ?, mrp = ?, cost = ?, updated_at = CURRENT_TIMESTAMP 
@@ -1108,35 +1494,275 @@ pub async fn update_multiple_product_rates(
     Ok(())
 }
 
+// ============= PRICE HISTORY =============
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ProductPriceChange {
+    pub id: String,
+    pub product_id: String,
+    /// "purchase_rate", "sales_rate" or "mrp"
+    pub field: String,
+    pub old_value: Option<f64>,
+    pub new_value: Option<f64>,
+    pub changed_at: String,
+    pub changed_by: Option<String>,
+    pub changed_by_name: Option<String>,
+}
+
+/// Logs each of purchase_rate, sales_rate and mrp that differs from the
+/// product's stored value. Call before the UPDATE so the old values are read.
+async fn record_price_changes(
+    tx: &mut Transaction<'_, Sqlite>,
+    product_id: &str,
+    purchase_rate: f64,
+    sales_rate: f64,
+    mrp: f64,
+    changed_by: Option<&str>,
+) -> Result<(), String> {
+    let current: Option<(f64, f64, f64)> = sqlx::query_as(
+        "SELECT CAST(COALESCE(purchase_rate, 0) AS REAL), CAST(COALESCE(sales_rate, 0) AS REAL), CAST(COALESCE(mrp, 0) AS REAL)
+         FROM products WHERE id = ?",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some((old_purchase, old_sales, old_mrp)) = current else {
+        return Ok(());
+    };
+
+    for (field, old_value, new_value) in [
+        ("purchase_rate", old_purchase, purchase_rate),
+        ("sales_rate", old_sales, sales_rate),
+        ("mrp", old_mrp, mrp),
+    ] {
+        if (old_value - new_value).abs() < 1e-9 {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO product_price_history (id, product_id, field, old_value, new_value, changed_by)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(product_id)
+        .bind(field)
+        .bind(old_value)
+        .bind(new_value)
+        .bind(changed_by)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Rate and MRP changes for a product, newest first.
 #[tauri::command]
-pub async fn delete_product(
+pub async fn get_product_price_history(
     registry: State<'_, Arc<DbRegistry>>,
-    id: String,
-    deleted_by: String,
+    product_id: String,
+) -> Result<Vec<ProductPriceChange>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, ProductPriceChange>(
+        "SELECT h.id, h.product_id, h.field, h.old_value, h.new_value, h.changed_at, h.changed_by,
+                u.full_name as changed_by_name
+         FROM product_price_history h
+         LEFT JOIN users u ON h.changed_by = u.id
+         WHERE h.product_id = ?
+         ORDER BY h.changed_at DESC, h.id DESC",
+    )
+    .bind(&product_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// ============= PARTY PRICE LISTS =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct PartyPrice {
+    pub id: String,
+    pub party_id: String,
+    pub party_type: String,
+    pub product_id: String,
+    pub product_code: String,
+    pub product_name: String,
+    pub rate: f64,
+    /// The product's standard rate for this party type, for comparison
+    pub standard_rate: f64,
+    pub updated_at: String,
+}
+
+fn validate_party_type(party_type: &str) -> Result<(), String> {
+    match party_type {
+        "customer" | "supplier" => Ok(()),
+        _ => Err("Party type must be 'customer' or 'supplier'".to_string()),
+    }
+}
+
+/// Sets (or replaces) the negotiated rate for a product for one party.
+#[tauri::command]
+pub async fn set_party_price(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String,
+    party_type: String,
+    product_id: String,
+    rate: f64,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    validate_party_type(&party_type)?;
+    if !rate.is_finite() || rate < 0.0 {
+        return Err("Rate cannot be negative".to_string());
+    }
+
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM products WHERE id = ? AND deleted_at IS NULL)",
+    )
+    .bind(&product_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err("Product not found".to_string());
+    }
+
+    sqlx::query(
+        "INSERT INTO party_price_list (id, party_id, party_type, product_id, rate)
+         VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(party_id, party_type, product_id)
+         DO UPDATE SET rate = excluded.rate, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(Uuid::now_v7().to_string())
+    .bind(&party_id)
+    .bind(&party_type)
+    .bind(&product_id)
+    .bind(rate)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_party_prices(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String,
+    party_type: String,
+) -> Result<Vec<PartyPrice>, String> {
+    let pool = registry.active_pool().await?;
+    validate_party_type(&party_type)?;
+
+    sqlx::query_as::<_, PartyPrice>(
+        "SELECT pl.id, pl.party_id, pl.party_type, pl.product_id,
+                p.code as product_code, p.name as product_name, pl.rate,
+                CAST(COALESCE(CASE WHEN pl.party_type = 'customer' THEN p.sales_rate ELSE p.purchase_rate END, 0) AS REAL) as standard_rate,
+                pl.updated_at
+         FROM party_price_list pl
+         JOIN products p ON pl.product_id = p.id
+         WHERE pl.party_id = ? AND pl.party_type = ? AND p.deleted_at IS NULL
+         ORDER BY p.name",
+    )
+    .bind(&party_id)
+    .bind(&party_type)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_party_price(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String,
+    party_type: String,
+    product_id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+
+    sqlx::query(
+        "DELETE FROM party_price_list WHERE party_id = ? AND party_type = ? AND product_id = ?",
+    )
+    .bind(&party_id)
+    .bind(&party_type)
+    .bind(&product_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Rate to prefill on a sales line: the customer's negotiated rate when one is
+/// set, otherwise the product's sales_rate.
+#[tauri::command]
+pub async fn get_effective_sales_rate(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String,
+    product_id: String,
+) -> Result<f64, String> {
+    let pool = registry.active_pool().await?;
+
+    sqlx::query_scalar::<_, Option<f64>>(
+        "SELECT CAST(COALESCE(
+            (SELECT rate FROM party_price_list
+             WHERE party_id = ? AND party_type = 'customer' AND product_id = p.id),
+            p.sales_rate,
+            0
+         ) AS REAL)
+         FROM products p WHERE p.id = ?",
+    )
+    .bind(&party_id)
+    .bind(&product_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .flatten()
+    .ok_or_else(|| "Product not found".to_string())
+}
+
+/// Why a product cannot be soft-deleted, if anything references it.
+async fn product_delete_blocker(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: &str,
+) -> Result<Option<&'static str>, String> {
     // Check for references in voucher_items
     let ref_count: i64 =
         sqlx::query_scalar("SELECT COUNT(*) FROM voucher_items WHERE product_id = ?")
-            .bind(&id)
-            .fetch_one(&pool)
+            .bind(id)
+            .fetch_one(&mut **tx)
             .await
             .map_err(|e| e.to_string())?;
 
     if ref_count > 0 {
-        return Err("Cannot delete product as it is referenced in vouchers.".to_string());
+        return Ok(Some("Cannot delete product as it is referenced in vouchers."));
     }
 
     // Check for references in stock_movements
     let stock_ref_count: i64 =
         sqlx::query_scalar("SELECT COUNT(*) FROM stock_movements WHERE product_id = ?")
-            .bind(&id)
-            .fetch_one(&pool)
+            .bind(id)
+            .fetch_one(&mut **tx)
             .await
             .map_err(|e| e.to_string())?;
 
     if stock_ref_count > 0 {
-        return Err("Cannot delete product as it has stock movement records.".to_string());
+        return Ok(Some("Cannot delete product as it has stock movement records."));
+    }
+
+    Ok(None)
+}
+
+#[tauri::command]
+pub async fn delete_product(
+    registry: State<'_, Arc<DbRegistry>>,
+    id: String,
+    deleted_by: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    if let Some(reason) = product_delete_blocker(&mut tx, &id).await? {
+        return Err(reason.to_string());
     }
 
     sqlx::query(
@@ -1146,20 +1772,93 @@ pub async fn delete_product(
     )
     .bind(deleted_by)
     .bind(id)
-    .execute(&pool)
+    .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
 
+    tx.commit().await.map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
+/// Outcome for one id of a bulk delete/restore: `status` is "deleted",
+/// "restored" or "skipped", with `reason` set when skipped.
+#[derive(Serialize)]
+pub struct BulkProductResult {
+    pub id: String,
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+impl BulkProductResult {
+    fn done(id: String, status: &str) -> Self {
+        Self {
+            id,
+            status: status.to_string(),
+            reason: None,
+        }
+    }
+
+    fn skipped(id: String, reason: &str) -> Self {
+        Self {
+            id,
+            status: "skipped".to_string(),
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Soft-deletes each product that passes the delete_product reference checks;
+/// blocked or missing products are reported as skipped instead of failing the batch.
+#[tauri::command]
+pub async fn bulk_delete_products(
+    registry: State<'_, Arc<DbRegistry>>,
+    ids: Vec<String>,
+    deleted_by: String,
+) -> Result<Vec<BulkProductResult>, String> {
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut results = Vec::with_capacity(ids.len());
+
+    for id in ids {
+        if let Some(reason) = product_delete_blocker(&mut tx, &id).await? {
+            results.push(BulkProductResult::skipped(id, reason));
+            continue;
+        }
+
+        let affected = sqlx::query(
+            "UPDATE products 
+             SET deleted_at = CURRENT_TIMESTAMP, deleted_by = ?, is_active = 0, updated_at = CURRENT_TIMESTAMP 
+             WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(&deleted_by)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+
+        results.push(if affected > 0 {
+            BulkProductResult::done(id, "deleted")
+        } else {
+            BulkProductResult::skipped(id, "Product not found or already deleted.")
+        });
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
 #[tauri::command]
 pub async fn get_deleted_products(
     registry: State<'_, Arc<DbRegistry>>,
 ) -> Result<Vec<Product>, String> {
     let pool = registry.active_pool().await?;
     sqlx::query_as::<_, Product>(
-        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost, barcode, is_active, created_at,
+        "SELECT id, code, name, group_id, brand_id, unit_id, purchase_rate, sales_rate, mrp, cost,
+                COALESCE(reorder_level, 0) as reorder_level,
+                COALESCE(default_tax_rate, 0) as default_tax_rate, barcode, is_active, created_at,
                 EXISTS(SELECT 1 FROM voucher_items vi WHERE vi.product_id = products.id) as has_transactions,
                 hsn_sac_code, gst_slab_id,
                 COALESCE(is_master, 0) as is_master,
@@ -1193,6 +1892,40 @@ pub async fn restore_product(
     Ok(())
 }
 
+/// Restores each soft-deleted product; ids that are not deleted are reported as skipped.
+#[tauri::command]
+pub async fn bulk_restore_products(
+    registry: State<'_, Arc<DbRegistry>>,
+    ids: Vec<String>,
+) -> Result<Vec<BulkProductResult>, String> {
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut results = Vec::with_capacity(ids.len());
+
+    for id in ids {
+        let affected = sqlx::query(
+            "UPDATE products 
+             SET deleted_at = NULL, deleted_by = NULL, is_active = 1, updated_at = CURRENT_TIMESTAMP 
+             WHERE id = ? AND deleted_at IS NOT NULL",
+        )
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+
+        results.push(if affected > 0 {
+            BulkProductResult::done(id, "restored")
+        } else {
+            BulkProductResult::skipped(id, "Product not found or not deleted.")
+        });
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
 #[tauri::command]
 pub async fn hard_delete_product(
     registry: State<'_, Arc<DbRegistry>>,
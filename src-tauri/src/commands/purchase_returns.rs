@@ -1,11 +1,17 @@
 use crate::company_db::DbRegistry;
 use serde::{Deserialize, Serialize};
+use sqlx::{Sqlite, Transaction};
 use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
+use super::auth::SessionStore;
+use super::entries::{insert_journal_lines, JournalLine};
 use super::invoices::{finalize_processed_items, prepare_voucher_line};
 use super::resolve_voucher_line_unit;
+use super::settings::{resolve_narration, validate_voucher_date};
+use super::system_accounts::resolve_system_account;
+use super::year_end::ensure_voucher_unlocked;
 use crate::voucher_seq::get_next_voucher_number;
 
 // ============= PURCHASE RETURN =============
@@ -99,6 +105,7 @@ pub struct CreatePurchaseReturn {
     pub items: Vec<CreatePurchaseReturnItem>,
     pub tax_inclusive: Option<bool>,
     pub gst_disabled: Option<bool>,
+    pub allow_future: Option<bool>,
 }
 
 #[tauri::command]
@@ -200,12 +207,21 @@ pub async fn get_purchase_return_items(
 #[tauri::command]
 pub async fn create_purchase_return(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     invoice: CreatePurchaseReturn,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    validate_voucher_date(&pool, &invoice.voucher_date, invoice.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
-    let voucher_no = get_next_voucher_number(&pool, "purchase_return").await?;
+    let branch_id = session_store.active_branch_id();
+    let voucher_no = get_next_voucher_number(
+        &pool,
+        "purchase_return",
+        &invoice.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
             .fetch_optional(&mut *tx)
@@ -279,10 +295,19 @@ pub async fn create_purchase_return(
     let total_amount = subtotal - discount_amount;
     let grand_total = total_amount + total_tax;
 
+    let narration = resolve_narration(
+        &mut tx,
+        invoice.narration.as_ref(),
+        "purchase_return",
+        Some(&invoice.supplier_id),
+        &voucher_no,
+    )
+    .await?;
+
     let voucher_id = Uuid::now_v7().to_string();
     sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, tax_inclusive, grand_total)
-         VALUES (?, ?, 'purchase_return', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?)",
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, tax_inclusive, grand_total, branch_id)
+         VALUES (?, ?, 'purchase_return', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?)",
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
@@ -295,9 +320,10 @@ pub async fn create_purchase_return(
     .bind(discount_amount)
     .bind(total_tax)
     .bind(total_amount)
-    .bind(&invoice.narration)
+    .bind(&narration)
     .bind(tax_inclusive as i64)
     .bind(grand_total)
+    .bind(&branch_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -343,79 +369,21 @@ pub async fn create_purchase_return(
     }
 
     let party_id = invoice.supplier_id;
-    let purchase_return_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5003'")
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    let tax_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1005'")
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
     let party_account: String = sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE id = ?")
         .bind(&party_id)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| format!("Party account not found: {}", e))?;
 
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, 0, ?, 'Purchase Return (Goods returned)')",
+    let journal_lines = purchase_return_journal_lines(
+        &mut tx,
+        &party_account,
+        subtotal,
+        total_tax,
+        discount_amount,
     )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&voucher_id)
-    .bind(&purchase_return_account)
-    .bind(subtotal)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if total_tax > 0.0 {
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Tax Reversal on Purchase Return')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&voucher_id)
-        .bind(&tax_account)
-        .bind(total_tax)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
-
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, ?, 0, 'Debit Note issued to Supplier')",
-    )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&voucher_id)
-    .bind(&party_account)
-    .bind(subtotal - discount_amount + total_tax)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if discount_amount > 0.0 {
-        let discount_received_account: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4004'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Reversal of Discount Received')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&voucher_id)
-        .bind(&discount_received_account)
-        .bind(discount_amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+    .await?;
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
 
     let items_for_stock: Vec<(Option<String>, Option<String>, f64, i64, f64, f64)> = sqlx::query_as(
         "SELECT item_type, product_id, base_quantity, count, rate, amount FROM voucher_items WHERE voucher_id = ?",
@@ -438,7 +406,7 @@ pub async fn create_purchase_return(
         let amount = base_qty * rate_per_base;
         sqlx::query(
             "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount)
-             VALUES (?, ?, ?, 'OUT', ?, ?, ?, ?, ?, ?)",
+             VALUES (?, ?, ?, 'RETURN_OUT', ?, ?, ?, ?, ?, ?)",
         )
         .bind(Uuid::now_v7().to_string())
         .bind(&voucher_id)
@@ -458,6 +426,58 @@ pub async fn create_purchase_return(
     Ok(voucher_id)
 }
 
+/// Cr Purchase Returns (and input tax), Dr the supplier for the debit note and
+/// Discount Received for the bill discount being reversed.
+async fn purchase_return_journal_lines(
+    tx: &mut Transaction<'_, Sqlite>,
+    party_account: &str,
+    subtotal: f64,
+    total_tax: f64,
+    discount_amount: f64,
+) -> Result<Vec<JournalLine>, String> {
+    let purchase_return_account = resolve_system_account(&mut **tx, "purchase_returns").await?;
+    let tax_account = resolve_system_account(&mut **tx, "input_tax").await?;
+    let line = |account_id: String, debit: f64, credit: f64, narration: &str| JournalLine {
+        account_id,
+        debit,
+        credit,
+        is_manual: false,
+        narration: Some(narration.to_string()),
+    };
+
+    let mut lines = vec![line(
+        purchase_return_account,
+        0.0,
+        subtotal,
+        "Purchase Return (Goods returned)",
+    )];
+    if total_tax > 0.0 {
+        lines.push(line(
+            tax_account,
+            0.0,
+            total_tax,
+            "Tax Reversal on Purchase Return",
+        ));
+    }
+    lines.push(line(
+        party_account.to_string(),
+        subtotal - discount_amount + total_tax,
+        0.0,
+        "Debit Note issued to Supplier",
+    ));
+    if discount_amount > 0.0 {
+        let discount_received_account =
+            resolve_system_account(&mut **tx, "discount_received").await?;
+        lines.push(line(
+            discount_received_account,
+            discount_amount,
+            0.0,
+            "Reversal of Discount Received",
+        ));
+    }
+    Ok(lines)
+}
+
 #[tauri::command]
 pub async fn update_purchase_return(
     registry: State<'_, Arc<DbRegistry>>,
@@ -465,6 +485,8 @@ pub async fn update_purchase_return(
     invoice: CreatePurchaseReturn,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    validate_voucher_date(&pool, &invoice.voucher_date, invoice.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -617,79 +639,21 @@ pub async fn update_purchase_return(
         .map_err(|e| e.to_string())?;
     }
 
-    let purchase_return_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5003'")
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    let tax_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '1005'")
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
     let party_account: String = sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE id = ?")
         .bind(&invoice.supplier_id)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
 
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, 0, ?, 'Purchase Return (Goods returned)')",
+    let journal_lines = purchase_return_journal_lines(
+        &mut tx,
+        &party_account,
+        subtotal,
+        total_tax,
+        discount_amount,
     )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&id)
-    .bind(&purchase_return_account)
-    .bind(subtotal)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if total_tax > 0.0 {
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Tax Reversal on Purchase Return')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&id)
-        .bind(&tax_account)
-        .bind(total_tax)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
-
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, ?, 0, 'Debit Note issued to Supplier')",
-    )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&id)
-    .bind(&party_account)
-    .bind(subtotal - discount_amount + total_tax)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if discount_amount > 0.0 {
-        let discount_received_account: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4004'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Reversal of Discount Received')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&id)
-        .bind(&discount_received_account)
-        .bind(discount_amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+    .await?;
+    insert_journal_lines(&mut tx, &id, &journal_lines).await?;
 
     for item in &invoice.items {
         if item.item_type == "service" {
@@ -716,7 +680,7 @@ pub async fn update_purchase_return(
         let amount = base_qty * rate_per_base;
         sqlx::query(
             "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount)
-             VALUES (?, ?, ?, 'OUT', ?, ?, ?, ?, ?, ?)",
+             VALUES (?, ?, ?, 'RETURN_OUT', ?, ?, ?, ?, ?, ?)",
         )
         .bind(Uuid::now_v7().to_string())
         .bind(&id)
@@ -742,6 +706,7 @@ pub async fn delete_purchase_return(
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ?")
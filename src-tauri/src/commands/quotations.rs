@@ -4,6 +4,7 @@ use sqlx::SqlitePool;
 use std::sync::Arc;
 use tauri::State;
 
+use super::auth::SessionStore;
 use super::invoices::{finalize_processed_items, prepare_voucher_line};
 use crate::voucher_seq::get_next_voucher_number;
 use uuid::Uuid;
@@ -172,12 +173,20 @@ pub async fn get_sales_quotation_items(
 #[tauri::command]
 pub async fn create_sales_quotation(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     quotation: CreateSalesQuotation,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
-    let voucher_no = get_next_voucher_number(&pool, "sales_quotation").await?;
+    let branch_id = session_store.active_branch_id();
+    let voucher_no = get_next_voucher_number(
+        &pool,
+        "sales_quotation",
+        &quotation.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
 
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -262,13 +271,13 @@ pub async fn create_sales_quotation(
 
     let voucher_id = Uuid::now_v7().to_string();
     let _ = sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, salesperson_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total, metadata)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, salesperson_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, created_by, tax_inclusive, cgst_amount, sgst_amount, igst_amount, grand_total, metadata, branch_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&voucher_id).bind(&voucher_no).bind("sales_quotation").bind(&quotation.voucher_date).bind(&quotation.customer_id)
     .bind(&quotation.salesperson_id).bind(&quotation.party_type).bind(&quotation.reference).bind(subtotal).bind(discount_rate)
     .bind(discount_amount).bind(total_tax).bind(total_amount).bind(&quotation.narration)
-    .bind(&quotation.user_id).bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total).bind(&metadata).execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    .bind(&quotation.user_id).bind(tax_inclusive as i64).bind(total_cgst).bind(total_sgst).bind(total_igst).bind(grand_total).bind(&metadata).bind(&branch_id).execute(&mut *tx).await.map_err(|e| e.to_string())?;
 
     // Insert items
     for item in &processed_items {
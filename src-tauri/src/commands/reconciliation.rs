@@ -0,0 +1,218 @@
+use crate::company_db::DbRegistry;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tauri::State;
+
+// ============= BANK RECONCILIATION =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReconciliationEntry {
+    pub id: String,
+    pub voucher_id: String,
+    pub voucher_no: String,
+    pub voucher_type: String,
+    pub date: String,
+    pub debit: f64,
+    pub credit: f64,
+    pub narration: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReconciliationSummary {
+    pub account_id: String,
+    pub as_on_date: String,
+    /// Balance per books (opening balance + every posting up to the date)
+    pub book_balance: f64,
+    /// Balance per statement (opening balance + postings reconciled on or before the date)
+    pub reconciled_balance: f64,
+    pub unreconciled_debit: f64,
+    pub unreconciled_credit: f64,
+    /// book_balance - reconciled_balance; zero when the account ties out
+    pub difference: f64,
+}
+
+/// Fails unless the account belongs to the Cash or Bank Account group.
+async fn ensure_cash_bank_account(pool: &SqlitePool, account_id: &str) -> Result<(), String> {
+    let group: Option<String> =
+        sqlx::query_scalar("SELECT account_group FROM chart_of_accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    match group.as_deref() {
+        Some("Cash") | Some("Bank Account") => Ok(()),
+        Some(_) => Err("Only Cash and Bank accounts can be reconciled".to_string()),
+        None => Err("Account not found".to_string()),
+    }
+}
+
+/// Resolves the account of a journal line, rejecting lines that are not on a cash/bank account.
+async fn reconcilable_entry_account(pool: &SqlitePool, entry_id: &str) -> Result<String, String> {
+    let account_id: String = sqlx::query_scalar(
+        "SELECT je.account_id
+         FROM journal_entries je
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE je.id = ? AND v.deleted_at IS NULL",
+    )
+    .bind(entry_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Journal entry not found".to_string())?;
+
+    ensure_cash_bank_account(pool, &account_id).await?;
+    Ok(account_id)
+}
+
+#[tauri::command]
+pub async fn get_unreconciled_entries(
+    registry: State<'_, Arc<DbRegistry>>,
+    account_id: String,
+    from_date: Option<String>,
+    to_date: String,
+) -> Result<Vec<ReconciliationEntry>, String> {
+    let pool = registry.active_pool().await?;
+    ensure_cash_bank_account(&pool, &account_id).await?;
+
+    let from_filter = if from_date.is_some() {
+        "AND v.voucher_date >= ?"
+    } else {
+        ""
+    };
+    let query = format!(
+        "SELECT
+            je.id,
+            v.id as voucher_id,
+            v.voucher_no,
+            v.voucher_type,
+            v.voucher_date as date,
+            CAST(je.debit AS REAL) as debit,
+            CAST(je.credit AS REAL) as credit,
+            COALESCE(je.narration, v.narration, '') as narration
+         FROM journal_entries je
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE je.account_id = ?
+           AND COALESCE(je.reconciled, 0) = 0
+           AND v.deleted_at IS NULL
+           {} AND v.voucher_date <= ?
+         ORDER BY v.voucher_date ASC, v.id ASC",
+        from_filter
+    );
+
+    let mut q = sqlx::query_as::<_, ReconciliationEntry>(&query).bind(&account_id);
+    if let Some(from) = &from_date {
+        q = q.bind(from);
+    }
+    q.bind(&to_date)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn mark_entry_reconciled(
+    registry: State<'_, Arc<DbRegistry>>,
+    entry_id: String,
+    statement_date: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    chrono::NaiveDate::parse_from_str(&statement_date, "%Y-%m-%d")
+        .map_err(|_| "Statement date must be in YYYY-MM-DD format".to_string())?;
+    reconcilable_entry_account(&pool, &entry_id).await?;
+
+    sqlx::query("UPDATE journal_entries SET reconciled = 1, reconciled_date = ? WHERE id = ?")
+        .bind(&statement_date)
+        .bind(&entry_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unmark_entry_reconciled(
+    registry: State<'_, Arc<DbRegistry>>,
+    entry_id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    reconcilable_entry_account(&pool, &entry_id).await?;
+
+    sqlx::query("UPDATE journal_entries SET reconciled = 0, reconciled_date = NULL WHERE id = ?")
+        .bind(&entry_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_reconciliation_summary(
+    registry: State<'_, Arc<DbRegistry>>,
+    account_id: String,
+    as_on_date: String,
+) -> Result<ReconciliationSummary, String> {
+    let pool = registry.active_pool().await?;
+    ensure_cash_bank_account(&pool, &account_id).await?;
+
+    let opening_balance: f64 = sqlx::query_scalar(
+        "SELECT CAST(CASE WHEN opening_balance_type = 'Cr'
+                    THEN -COALESCE(opening_balance, 0)
+                    ELSE COALESCE(opening_balance, 0)
+                END AS REAL)
+         FROM chart_of_accounts WHERE id = ?",
+    )
+    .bind(&account_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // (book movement, reconciled movement, unreconciled debit, unreconciled credit)
+    let (book_movement, reconciled_movement, unreconciled_debit, unreconciled_credit): (
+        f64,
+        f64,
+        f64,
+        f64,
+    ) = sqlx::query_as(
+        "SELECT
+            CAST(COALESCE(SUM(je.debit - je.credit), 0) AS REAL),
+            CAST(COALESCE(SUM(CASE
+                WHEN COALESCE(je.reconciled, 0) = 1 AND je.reconciled_date <= ? THEN je.debit - je.credit
+                ELSE 0
+            END), 0) AS REAL),
+            CAST(COALESCE(SUM(CASE
+                WHEN COALESCE(je.reconciled, 0) = 1 AND je.reconciled_date <= ? THEN 0
+                ELSE je.debit
+            END), 0) AS REAL),
+            CAST(COALESCE(SUM(CASE
+                WHEN COALESCE(je.reconciled, 0) = 1 AND je.reconciled_date <= ? THEN 0
+                ELSE je.credit
+            END), 0) AS REAL)
+         FROM journal_entries je
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE je.account_id = ? AND v.deleted_at IS NULL AND v.voucher_date <= ?",
+    )
+    .bind(&as_on_date)
+    .bind(&as_on_date)
+    .bind(&as_on_date)
+    .bind(&account_id)
+    .bind(&as_on_date)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let book_balance = opening_balance + book_movement;
+    let reconciled_balance = opening_balance + reconciled_movement;
+
+    Ok(ReconciliationSummary {
+        account_id,
+        as_on_date,
+        book_balance,
+        reconciled_balance,
+        unreconciled_debit,
+        unreconciled_credit,
+        difference: book_balance - reconciled_balance,
+    })
+}
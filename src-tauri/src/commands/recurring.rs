@@ -0,0 +1,287 @@
+use crate::company_db::DbRegistry;
+use chrono::{Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+use uuid::Uuid;
+
+use super::auth::SessionStore;
+use super::entries::{
+    create_payment_with_pool, create_receipt_with_pool, CreatePayment, CreateReceipt,
+};
+
+// ============= RECURRING VOUCHERS =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecurringVoucher {
+    pub id: String,
+    pub name: String,
+    pub voucher_type: String,
+    pub template: String,
+    pub frequency: String,
+    pub next_run_date: String,
+    pub end_date: Option<String>,
+    pub is_active: i64,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateRecurringVoucher {
+    pub name: String,
+    pub voucher_type: String,
+    /// CreatePayment / CreateReceipt body; its voucher_date is replaced on every run
+    pub template: serde_json::Value,
+    pub frequency: String,
+    pub next_run_date: String,
+    pub end_date: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GeneratedRecurringVoucher {
+    pub recurring_voucher_id: String,
+    pub run_date: String,
+    pub voucher_id: String,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+fn advance_run_date(date: NaiveDate, frequency: &str) -> Result<NaiveDate, String> {
+    let next = match frequency {
+        "weekly" => date.checked_add_signed(chrono::Duration::days(7)),
+        "monthly" => date.checked_add_months(Months::new(1)),
+        "yearly" => date.checked_add_months(Months::new(12)),
+        other => return Err(format!("Unsupported frequency '{}'", other)),
+    };
+    next.ok_or_else(|| format!("Cannot advance {} past {}", frequency, date))
+}
+
+/// Checks the template can be posted as the given voucher type.
+fn validate_template(voucher_type: &str, template: &serde_json::Value) -> Result<(), String> {
+    match voucher_type {
+        "payment" => serde_json::from_value::<CreatePayment>(template.clone())
+            .map(|_| ())
+            .map_err(|e| format!("Invalid payment template: {}", e)),
+        "receipt" => serde_json::from_value::<CreateReceipt>(template.clone())
+            .map(|_| ())
+            .map_err(|e| format!("Invalid receipt template: {}", e)),
+        other => Err(format!(
+            "Recurring vouchers support payment and receipt, not '{}'",
+            other
+        )),
+    }
+}
+
+#[tauri::command]
+pub async fn create_recurring_voucher(
+    registry: State<'_, Arc<DbRegistry>>,
+    recurring: CreateRecurringVoucher,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    if recurring.name.trim().is_empty() {
+        return Err("Name is required".to_string());
+    }
+    validate_template(&recurring.voucher_type, &recurring.template)?;
+    let next_run = parse_date(&recurring.next_run_date)?;
+    advance_run_date(next_run, &recurring.frequency)?;
+    if let Some(end) = &recurring.end_date {
+        if parse_date(end)? < next_run {
+            return Err("End date cannot be before the first run date".to_string());
+        }
+    }
+
+    let id = Uuid::now_v7().to_string();
+    sqlx::query(
+        "INSERT INTO recurring_vouchers (id, name, voucher_type, template, frequency, next_run_date, end_date)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(recurring.name.trim())
+    .bind(&recurring.voucher_type)
+    .bind(recurring.template.to_string())
+    .bind(&recurring.frequency)
+    .bind(&recurring.next_run_date)
+    .bind(&recurring.end_date)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn list_recurring_vouchers(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<RecurringVoucher>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, RecurringVoucher>(
+        "SELECT id, name, voucher_type, template, frequency, next_run_date, end_date,
+                COALESCE(is_active, 1) as is_active, created_at
+         FROM recurring_vouchers
+         ORDER BY next_run_date ASC, name ASC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_recurring_voucher(
+    registry: State<'_, Arc<DbRegistry>>,
+    id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    // Posted vouchers stay; only the schedule and its run log are removed
+    sqlx::query("DELETE FROM recurring_voucher_runs WHERE recurring_voucher_id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM recurring_vouchers WHERE id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Posts every occurrence due on or before `as_on_date` and advances each schedule.
+/// Each occurrence is claimed in recurring_voucher_runs before posting, so repeated
+/// calls never post the same (schedule, date) twice.
+#[tauri::command]
+pub async fn generate_due_recurring(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    as_on_date: String,
+) -> Result<Vec<GeneratedRecurringVoucher>, String> {
+    let pool = registry.active_pool().await?;
+    let actor = session_store.current_user_id();
+    let branch_id = session_store.active_branch_id();
+    let as_on = parse_date(&as_on_date)?;
+
+    let due = sqlx::query_as::<_, RecurringVoucher>(
+        "SELECT id, name, voucher_type, template, frequency, next_run_date, end_date,
+                COALESCE(is_active, 1) as is_active, created_at
+         FROM recurring_vouchers
+         WHERE COALESCE(is_active, 1) = 1 AND next_run_date <= ?
+         ORDER BY next_run_date ASC",
+    )
+    .bind(&as_on_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut generated = Vec::new();
+
+    for schedule in due {
+        let template: serde_json::Value =
+            serde_json::from_str(&schedule.template).map_err(|e| e.to_string())?;
+        let end_date = schedule.end_date.as_deref().map(parse_date).transpose()?;
+        let mut run_date = parse_date(&schedule.next_run_date)?;
+
+        while run_date <= as_on && !end_date.is_some_and(|end| run_date > end) {
+            let run_date_str = run_date.format("%Y-%m-%d").to_string();
+
+            let claimed = sqlx::query(
+                "INSERT OR IGNORE INTO recurring_voucher_runs (id, recurring_voucher_id, run_date, voucher_id)
+                 VALUES (?, ?, ?, '')",
+            )
+            .bind(Uuid::now_v7().to_string())
+            .bind(&schedule.id)
+            .bind(&run_date_str)
+            .execute(&pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .rows_affected()
+                > 0;
+
+            if claimed {
+                let mut body = template.clone();
+                body["voucher_date"] = serde_json::Value::String(run_date_str.clone());
+
+                let posted = match schedule.voucher_type.as_str() {
+                    "payment" => match serde_json::from_value::<CreatePayment>(body) {
+                        Ok(payment) => {
+                            create_payment_with_pool(
+                                &pool,
+                                payment,
+                                actor.as_deref(),
+                                branch_id.as_deref(),
+                            )
+                            .await
+                        }
+                        Err(e) => Err(e.to_string()),
+                    },
+                    "receipt" => match serde_json::from_value::<CreateReceipt>(body) {
+                        Ok(receipt) => {
+                            create_receipt_with_pool(
+                                &pool,
+                                receipt,
+                                actor.as_deref(),
+                                branch_id.as_deref(),
+                            )
+                            .await
+                        }
+                        Err(e) => Err(e.to_string()),
+                    },
+                    other => Err(format!("Unsupported recurring voucher type '{}'", other)),
+                };
+
+                let voucher_id = match posted {
+                    Ok(voucher_id) => voucher_id,
+                    Err(e) => {
+                        // Release the claim so the occurrence is retried next time
+                        let _ = sqlx::query(
+                            "DELETE FROM recurring_voucher_runs WHERE recurring_voucher_id = ? AND run_date = ?",
+                        )
+                        .bind(&schedule.id)
+                        .bind(&run_date_str)
+                        .execute(&pool)
+                        .await;
+                        return Err(format!(
+                            "Failed to post '{}' for {}: {}",
+                            schedule.name, run_date_str, e
+                        ));
+                    }
+                };
+
+                sqlx::query(
+                    "UPDATE recurring_voucher_runs SET voucher_id = ? WHERE recurring_voucher_id = ? AND run_date = ?",
+                )
+                .bind(&voucher_id)
+                .bind(&schedule.id)
+                .bind(&run_date_str)
+                .execute(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+
+                generated.push(GeneratedRecurringVoucher {
+                    recurring_voucher_id: schedule.id.clone(),
+                    run_date: run_date_str,
+                    voucher_id,
+                });
+            }
+
+            run_date = advance_run_date(run_date, &schedule.frequency)?;
+            let finished = end_date.is_some_and(|end| run_date > end);
+            sqlx::query(
+                "UPDATE recurring_vouchers
+                 SET next_run_date = ?, is_active = ?, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?",
+            )
+            .bind(run_date.format("%Y-%m-%d").to_string())
+            .bind(if finished { 0i64 } else { 1i64 })
+            .bind(&schedule.id)
+            .execute(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(generated)
+}
@@ -1,16 +1,26 @@
 use crate::company_db::DbRegistry;
-use chrono;
+use chrono::{self, Datelike};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 use tauri::State;
 
+use super::system_accounts::resolve_system_account;
+use crate::utils::write_csv;
+
 // ============= TRIAL BALANCE =============
-#[derive(Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Serialize, Deserialize)]
 pub struct TrialBalanceRow {
     pub account_code: String,
     pub account_name: String,
+    /// Balance brought forward to from_date (movements before it, opening
+    /// balance vouchers included); Dr positive, Cr negative
+    pub opening_balance: f64,
+    /// Movements within the period
     pub debit: f64,
     pub credit: f64,
+    /// opening_balance + debit - credit
+    pub closing_balance: f64,
 }
 
 #[tauri::command]
@@ -20,35 +30,105 @@ pub async fn get_trial_balance(
     to_date: String,
 ) -> Result<Vec<TrialBalanceRow>, String> {
     let pool = registry.active_pool().await?;
-    let date_filter = if let Some(from) = from_date {
-        format!(
-            "AND v.voucher_date >= '{}' AND v.voucher_date <= '{}'",
-            from, to_date
-        )
-    } else {
-        format!("AND v.voucher_date <= '{}'", to_date)
-    };
+    trial_balance_rows(&pool, from_date.as_deref(), &to_date).await
+}
 
-    let query = format!(
+/// Trial balance rows up to `to_date`, built from journal lines only: account
+/// opening balances are already posted as `opening_balance` vouchers. Without
+/// `from_date` the period covers every voucher; with it, movements before
+/// `from_date` are folded into the opening instead of being counted in the period.
+pub(crate) async fn trial_balance_rows(
+    pool: &sqlx::SqlitePool,
+    from_date: Option<&str>,
+    to_date: &str,
+) -> Result<Vec<TrialBalanceRow>, String> {
+    // An empty lower bound puts every voucher inside the period
+    let from_date = from_date.unwrap_or("");
+
+    let rows = sqlx::query_as::<_, (String, String, f64, f64, f64)>(
         "SELECT 
             coa.account_code,
             coa.account_name,
-            COALESCE(SUM(je.debit), 0) as debit,
-            COALESCE(SUM(je.credit), 0) as credit
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_date < ? THEN je.debit - je.credit END), 0) AS REAL) as opening_balance,
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_date >= ? THEN je.debit END), 0) AS REAL) as debit,
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_date >= ? THEN je.credit END), 0) AS REAL) as credit
         FROM chart_of_accounts coa
         LEFT JOIN journal_entries je ON coa.id = je.account_id
         LEFT JOIN vouchers v ON je.voucher_id = v.id
-        WHERE coa.is_active = 1 AND v.deleted_at IS NULL {}
+            AND v.deleted_at IS NULL
+            AND v.voucher_date <= ?
+        WHERE coa.is_active = 1
         GROUP BY coa.id, coa.account_code, coa.account_name
-        HAVING debit > 0 OR credit > 0
+        HAVING ABS(opening_balance) >= 0.005 OR debit > 0 OR credit > 0
         ORDER BY coa.account_code ASC",
-        date_filter
-    );
+    )
+    .bind(from_date)
+    .bind(from_date)
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
 
-    sqlx::query_as::<_, TrialBalanceRow>(&query)
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| e.to_string())
+    Ok(rows
+        .into_iter()
+        .map(
+            |(account_code, account_name, opening_balance, debit, credit)| TrialBalanceRow {
+                account_code,
+                account_name,
+                opening_balance,
+                debit,
+                credit,
+                closing_balance: opening_balance + debit - credit,
+            },
+        )
+        .collect())
+}
+
+// ============= VOUCHER INTEGRITY =============
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct UnbalancedVoucher {
+    pub voucher_id: String,
+    pub voucher_no: String,
+    pub voucher_type: String,
+    pub voucher_date: String,
+    pub total_debit: f64,
+    pub total_credit: f64,
+    /// total_debit - total_credit
+    pub difference: f64,
+}
+
+/// Diagnostic: live vouchers of any type whose journal lines don't balance
+/// (debits and credits differ by more than 0.01).
+#[tauri::command]
+pub async fn get_unbalanced_vouchers(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<UnbalancedVoucher>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, UnbalancedVoucher>(
+        "SELECT
+            v.id as voucher_id,
+            v.voucher_no,
+            v.voucher_type,
+            v.voucher_date,
+            CAST(COALESCE(SUM(je.debit), 0) AS REAL) as total_debit,
+            CAST(COALESCE(SUM(je.credit), 0) AS REAL) as total_credit,
+            CAST(COALESCE(SUM(je.debit), 0) - COALESCE(SUM(je.credit), 0) AS REAL) as difference
+         FROM vouchers v
+         JOIN journal_entries je ON je.voucher_id = v.id
+         WHERE v.deleted_at IS NULL
+           AND v.voucher_date >= ? AND v.voucher_date <= ?
+         GROUP BY v.id
+         HAVING ABS(difference) > 0.01
+         ORDER BY v.voucher_date ASC, v.voucher_no ASC",
+    )
+    .bind(&from_date)
+    .bind(&to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
 }
 
 // ============= LEDGER REPORT =============
@@ -58,10 +138,16 @@ pub struct LedgerEntry {
     pub date: String,
     pub voucher_no: String,
     pub voucher_type: String,
+    /// Label for display; opening-balance vouchers show as "Opening Balance"
+    pub voucher_type_label: String,
     pub narration: String,
     pub debit: f64,
     pub credit: f64,
     pub balance: f64,
+    /// Other accounts on the same voucher, comma-separated; only filled when
+    /// the ledger is requested with `include_contra`
+    #[sqlx(default)]
+    pub contra_accounts: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -71,12 +157,55 @@ pub struct LedgerReport {
     pub closing_balance: f64,
 }
 
+/// Caches the pre-period (debit, credit) totals used as a ledger's opening running
+/// balance, keyed by company + account + from_date. Entries are tagged with the
+/// company's `ledger_cache_version`, which DB triggers bump on every journal or
+/// voucher mutation, so a stale entry is discarded on the next lookup.
+pub struct LedgerOpeningCache {
+    entries: Mutex<HashMap<(String, String, String), (i64, f64, f64)>>,
+}
+
+impl LedgerOpeningCache {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key: &(String, String, String), version: i64) -> Option<(f64, f64)> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(cached_version, _, _)| *cached_version == version)
+            .map(|(_, dr, cr)| (*dr, *cr))
+    }
+
+    fn insert(&self, key: (String, String, String), version: i64, dr: f64, cr: f64) {
+        let mut entries = self.entries.lock().unwrap();
+        // Drop everything recorded under an older version before adding the new entry
+        entries.retain(|(company, _, _), (cached_version, _, _)| {
+            company != &key.0 || *cached_version == version
+        });
+        entries.insert(key, (version, dr, cr));
+    }
+}
+
+impl Default for LedgerOpeningCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[tauri::command]
 pub async fn get_ledger_report(
     registry: State<'_, Arc<DbRegistry>>,
+    cache: State<'_, LedgerOpeningCache>,
     account_id: String,
     from_date: Option<String>,
     to_date: String,
+    include_contra: Option<bool>,
+    branch_id: Option<String>,
 ) -> Result<LedgerReport, String> {
     let pool = registry.active_pool().await?;
     let account = sqlx::query_as::<_, (f64, String)>(
@@ -87,7 +216,11 @@ pub async fn get_ledger_report(
     .await
     .map_err(|e| format!("Failed to fetch account {}: {}", account_id, e))?;
 
-    let opening_balance = if account.1 == "Dr" {
+    // The account's master opening balance is company-wide, so a branch view
+    // starts from that branch's own postings only
+    let opening_balance = if branch_id.is_some() {
+        0.0
+    } else if account.1 == "Dr" {
         account.0
     } else {
         -account.0
@@ -95,31 +228,202 @@ pub async fn get_ledger_report(
 
     let mut running_balance = opening_balance;
 
-    if let Some(ref from) = from_date {
-        let balance_before: Option<(f64, f64)> = sqlx::query_as(
+    if let (Some(from), Some(branch)) = (&from_date, &branch_id) {
+        let (dr, cr): (f64, f64) = sqlx::query_as(
             "SELECT CAST(COALESCE(SUM(je.debit), 0) AS REAL), CAST(COALESCE(SUM(je.credit), 0) AS REAL)
              FROM journal_entries je
              JOIN vouchers v ON je.voucher_id = v.id
-             WHERE je.account_id = ? AND v.voucher_date < ? AND v.deleted_at IS NULL",
+             WHERE je.account_id = ? AND v.voucher_date < ? AND v.deleted_at IS NULL
+             AND v.branch_id = ?",
         )
         .bind(&account_id)
         .bind(from)
-        .fetch_optional(&pool)
+        .bind(branch)
+        .fetch_one(&pool)
         .await
         .map_err(|e| e.to_string())?;
+        running_balance += dr - cr;
+    } else if let Some(ref from) = from_date {
+        let version: i64 =
+            sqlx::query_scalar("SELECT version FROM ledger_cache_version WHERE id = 1")
+                .fetch_optional(&pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .unwrap_or(0);
+        let company_id = registry.active_company_id().await.unwrap_or_default();
+        let cache_key = (company_id, account_id.clone(), from.clone());
+
+        let balance_before = match cache.get(&cache_key, version) {
+            Some(cached) => Some(cached),
+            None => {
+                let fresh: Option<(f64, f64)> = sqlx::query_as(
+                    "SELECT CAST(COALESCE(SUM(je.debit), 0) AS REAL), CAST(COALESCE(SUM(je.credit), 0) AS REAL)
+                     FROM journal_entries je
+                     JOIN vouchers v ON je.voucher_id = v.id
+                     WHERE je.account_id = ? AND v.voucher_date < ? AND v.deleted_at IS NULL",
+                )
+                .bind(&account_id)
+                .bind(from)
+                .fetch_optional(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+
+                if let Some((dr, cr)) = fresh {
+                    cache.insert(cache_key, version, dr, cr);
+                }
+                fresh
+            }
+        };
 
         if let Some((dr, cr)) = balance_before {
             running_balance += dr - cr;
         }
     }
 
-    let date_filter = if let Some(ref from) = from_date {
-        format!(
-            "AND v.voucher_date >= '{}' AND v.voucher_date <= '{}'",
-            from, to_date
-        )
+    let mut entries = ledger_entries_between(
+        &pool,
+        &account_id,
+        from_date.as_deref(),
+        &to_date,
+        include_contra.unwrap_or(false),
+        branch_id.as_deref(),
+    )
+    .await?;
+
+    let report_opening_balance = if from_date.is_some() {
+        running_balance
     } else {
-        format!("AND v.voucher_date <= '{}'", to_date)
+        opening_balance
+    };
+    let closing_balance = apply_running_balance(&mut entries, running_balance);
+
+    Ok(LedgerReport {
+        entries,
+        opening_balance: report_opening_balance,
+        closing_balance,
+    })
+}
+
+/// A ledger line or a synthesized month subtotal in a monthly account statement.
+/// Subtotal rows carry the month's total debit/credit and the balance at month end.
+#[derive(Serialize)]
+pub struct MonthlyStatementRow {
+    #[serde(flatten)]
+    pub entry: LedgerEntry,
+    pub is_subtotal: bool,
+    /// Year-month (YYYY-MM) the row belongs to
+    pub month: String,
+}
+
+#[derive(Serialize)]
+pub struct AccountMonthlyStatement {
+    pub rows: Vec<MonthlyStatementRow>,
+    pub opening_balance: f64,
+    pub closing_balance: f64,
+}
+
+fn month_subtotal_row(month: &str, debit: f64, credit: f64, balance: f64) -> MonthlyStatementRow {
+    let label = chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map(|d| d.format("%b %Y").to_string())
+        .unwrap_or_else(|_| month.to_string());
+    MonthlyStatementRow {
+        entry: LedgerEntry {
+            id: String::new(),
+            date: String::new(),
+            voucher_no: String::new(),
+            voucher_type: "month_total".to_string(),
+            voucher_type_label: format!("Total for {}", label),
+            narration: String::new(),
+            debit,
+            credit,
+            balance,
+            contra_accounts: None,
+        },
+        is_subtotal: true,
+        month: month.to_string(),
+    }
+}
+
+/// Ledger for one account with a subtotal row after each calendar month.
+#[tauri::command]
+pub async fn get_account_monthly_statement(
+    registry: State<'_, Arc<DbRegistry>>,
+    cache: State<'_, LedgerOpeningCache>,
+    account_id: String,
+    from_date: Option<String>,
+    to_date: String,
+) -> Result<AccountMonthlyStatement, String> {
+    let report =
+        get_ledger_report(registry, cache, account_id, from_date, to_date, None, None).await?;
+
+    let mut rows = Vec::with_capacity(report.entries.len());
+    let mut current_month: Option<String> = None;
+    let (mut month_debit, mut month_credit) = (0.0, 0.0);
+    let mut month_balance = report.opening_balance;
+
+    for entry in report.entries {
+        let month: String = entry.date.chars().take(7).collect();
+        if let Some(prev) = current_month.as_deref() {
+            if prev != month {
+                rows.push(month_subtotal_row(
+                    prev,
+                    month_debit,
+                    month_credit,
+                    month_balance,
+                ));
+                month_debit = 0.0;
+                month_credit = 0.0;
+            }
+        }
+        month_debit += entry.debit;
+        month_credit += entry.credit;
+        month_balance = entry.balance;
+        current_month = Some(month.clone());
+        rows.push(MonthlyStatementRow {
+            entry,
+            is_subtotal: false,
+            month,
+        });
+    }
+
+    if let Some(last) = current_month.as_deref() {
+        rows.push(month_subtotal_row(
+            last,
+            month_debit,
+            month_credit,
+            month_balance,
+        ));
+    }
+
+    Ok(AccountMonthlyStatement {
+        rows,
+        opening_balance: report.opening_balance,
+        closing_balance: report.closing_balance,
+    })
+}
+
+/// Journal lines posted to `account_id` within the period, oldest first, with
+/// `balance` left at zero for `apply_running_balance` to fill in. With
+/// `include_contra`, each line also lists the voucher's other accounts.
+async fn ledger_entries_between(
+    pool: &sqlx::SqlitePool,
+    account_id: &str,
+    from_date: Option<&str>,
+    to_date: &str,
+    include_contra: bool,
+    branch_id: Option<&str>,
+) -> Result<Vec<LedgerEntry>, String> {
+    // Lines of the same account elsewhere in the voucher are not contra entries
+    let contra_column = if include_contra {
+        "(SELECT GROUP_CONCAT(account_name, ', ') FROM (
+            SELECT DISTINCT coa.account_name
+            FROM journal_entries other
+            JOIN chart_of_accounts coa ON coa.id = other.account_id
+            WHERE other.voucher_id = je.voucher_id AND other.account_id != je.account_id
+            ORDER BY coa.account_name
+        ))"
+    } else {
+        "NULL"
     };
 
     let query = format!(
@@ -128,39 +432,145 @@ pub async fn get_ledger_report(
             v.voucher_date as date,
             v.voucher_no,
             v.voucher_type,
+            CASE WHEN v.voucher_type = 'opening_balance' THEN 'Opening Balance' ELSE v.voucher_type END as voucher_type_label,
             je.narration,
             CAST(je.debit AS REAL) as debit,
             CAST(je.credit AS REAL) as credit,
-            0.0 as balance
+            0.0 as balance,
+            {} as contra_accounts
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
-        WHERE je.account_id = ? AND v.deleted_at IS NULL {}
+        WHERE je.account_id = ? AND v.deleted_at IS NULL
+        AND (? IS NULL OR v.voucher_date >= ?) AND v.voucher_date <= ?
+        AND (? IS NULL OR v.branch_id = ?)
         ORDER BY v.voucher_date ASC, v.id ASC",
-        date_filter
+        contra_column
     );
 
-    let mut entries: Vec<LedgerEntry> = sqlx::query_as(&query)
+    sqlx::query_as(&query)
         .bind(account_id)
-        .fetch_all(&pool)
+        .bind(from_date)
+        .bind(from_date)
+        .bind(to_date)
+        .bind(branch_id)
+        .bind(branch_id)
+        .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    for entry in &mut entries {
+/// Fills in each entry's running balance starting from `opening_balance`,
+/// returning the closing balance.
+fn apply_running_balance(entries: &mut [LedgerEntry], opening_balance: f64) -> f64 {
+    let mut running_balance = opening_balance;
+    for entry in entries.iter_mut() {
         running_balance += entry.debit - entry.credit;
         entry.balance = running_balance;
     }
+    running_balance
+}
+
+// ============= GENERAL LEDGER =============
+#[derive(Serialize, Deserialize)]
+pub struct GeneralLedgerAccount {
+    pub account_id: String,
+    pub account_code: String,
+    pub account_name: String,
+    pub opening_balance: f64,
+    pub entries: Vec<LedgerEntry>,
+    pub closing_balance: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct AccountLedgerRow {
+    account_id: String,
+    #[sqlx(flatten)]
+    entry: LedgerEntry,
+}
+
+#[tauri::command]
+pub async fn get_general_ledger(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<GeneralLedgerAccount>, String> {
+    let pool = registry.active_pool().await?;
+
+    // Opening = account opening balance + all postings before the period
+    let accounts = sqlx::query_as::<_, (String, String, String, f64)>(
+        "SELECT
+            coa.id,
+            coa.account_code,
+            coa.account_name,
+            CASE WHEN coa.opening_balance_type = 'Dr'
+                THEN CAST(COALESCE(coa.opening_balance, 0) AS REAL)
+                ELSE -CAST(COALESCE(coa.opening_balance, 0) AS REAL)
+            END + CAST(COALESCE((
+                SELECT SUM(je.debit - je.credit)
+                FROM journal_entries je
+                JOIN vouchers v ON je.voucher_id = v.id
+                WHERE je.account_id = coa.id AND v.deleted_at IS NULL AND v.voucher_date < ?
+            ), 0) AS REAL)
+        FROM chart_of_accounts coa
+        WHERE coa.is_active = 1 AND coa.deleted_at IS NULL
+        ORDER BY coa.account_code",
+    )
+    .bind(&from_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let rows = sqlx::query_as::<_, AccountLedgerRow>(
+        "SELECT
+            je.account_id,
+            v.id,
+            v.voucher_date as date,
+            v.voucher_no,
+            v.voucher_type,
+            CASE WHEN v.voucher_type = 'opening_balance' THEN 'Opening Balance' ELSE v.voucher_type END as voucher_type_label,
+            COALESCE(je.narration, v.narration, '') as narration,
+            CAST(je.debit AS REAL) as debit,
+            CAST(je.credit AS REAL) as credit,
+            0.0 as balance
+        FROM journal_entries je
+        JOIN vouchers v ON je.voucher_id = v.id
+        WHERE v.deleted_at IS NULL AND v.voucher_date >= ? AND v.voucher_date <= ?
+        ORDER BY je.account_id, v.voucher_date ASC, v.id ASC",
+    )
+    .bind(&from_date)
+    .bind(&to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut entries_by_account: HashMap<String, Vec<LedgerEntry>> = HashMap::new();
+    for row in rows {
+        entries_by_account
+            .entry(row.account_id)
+            .or_default()
+            .push(row.entry);
+    }
 
-    let report_opening_balance = if from_date.is_some() {
-        running_balance - entries.iter().map(|e| e.debit - e.credit).sum::<f64>()
-    } else {
-        opening_balance
-    };
+    let mut sections = Vec::new();
+    for (account_id, account_code, account_name, opening_balance) in accounts {
+        let mut entries = entries_by_account.remove(&account_id).unwrap_or_default();
+        // Skip accounts with nothing to show; keep those carrying only an opening balance
+        if entries.is_empty() && opening_balance.abs() < 0.01 {
+            continue;
+        }
 
-    Ok(LedgerReport {
-        entries,
-        opening_balance: report_opening_balance,
-        closing_balance: running_balance,
-    })
+        let closing_balance = apply_running_balance(&mut entries, opening_balance);
+        sections.push(GeneralLedgerAccount {
+            account_id,
+            account_code,
+            account_name,
+            opening_balance,
+            entries,
+            closing_balance,
+        });
+    }
+
+    Ok(sections)
 }
 
 // ============= BALANCE SHEET =============
@@ -169,6 +579,10 @@ pub struct BSAccount {
     pub account_name: String,
     pub account_code: String,
     pub amount: f64,
+    /// Amount as on compare_date; only set when a comparison was requested
+    pub compare_amount: Option<f64>,
+    /// amount - compare_amount
+    pub delta: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -179,14 +593,75 @@ pub struct BalanceSheetData {
     pub total_assets: f64,
     pub total_liabilities: f64,
     pub total_equity: f64,
+    pub compare_date: Option<String>,
+    pub compare_total_assets: Option<f64>,
+    pub compare_total_liabilities: Option<f64>,
+    pub compare_total_equity: Option<f64>,
 }
 
+/// Balance sheet as on `as_on_date`. With `compare_date`, every account also
+/// carries its amount on that date and the change, and comparative totals are
+/// filled in; accounts present on only one of the dates show 0 on the other.
 #[tauri::command]
 pub async fn get_balance_sheet(
     registry: State<'_, Arc<DbRegistry>>,
     as_on_date: String,
+    compare_date: Option<String>,
 ) -> Result<BalanceSheetData, String> {
     let pool = registry.active_pool().await?;
+    let mut current = balance_sheet_as_on(&pool, &as_on_date).await?;
+
+    let Some(compare_date) = compare_date else {
+        return Ok(current);
+    };
+    let prior = balance_sheet_as_on(&pool, &compare_date).await?;
+
+    current.assets = merge_bs_comparison(current.assets, prior.assets);
+    current.liabilities = merge_bs_comparison(current.liabilities, prior.liabilities);
+    current.equity = merge_bs_comparison(current.equity, prior.equity);
+    current.compare_date = Some(compare_date);
+    current.compare_total_assets = Some(prior.total_assets);
+    current.compare_total_liabilities = Some(prior.total_liabilities);
+    current.compare_total_equity = Some(prior.total_equity);
+    Ok(current)
+}
+
+/// Pairs each current account with its prior-date amount by account code.
+fn merge_bs_comparison(current: Vec<BSAccount>, prior: Vec<BSAccount>) -> Vec<BSAccount> {
+    let mut prior_amounts: HashMap<String, f64> = prior
+        .iter()
+        .map(|acc| (acc.account_code.clone(), acc.amount))
+        .collect();
+
+    let mut merged: Vec<BSAccount> = current
+        .into_iter()
+        .map(|mut acc| {
+            let compare_amount = prior_amounts.remove(&acc.account_code).unwrap_or(0.0);
+            acc.delta = Some(acc.amount - compare_amount);
+            acc.compare_amount = Some(compare_amount);
+            acc
+        })
+        .collect();
+
+    // Accounts that had a balance only on the compare date
+    for acc in prior {
+        if prior_amounts.remove(&acc.account_code).is_some() {
+            merged.push(BSAccount {
+                account_name: acc.account_name,
+                account_code: acc.account_code,
+                amount: 0.0,
+                compare_amount: Some(acc.amount),
+                delta: Some(-acc.amount),
+            });
+        }
+    }
+    merged
+}
+
+async fn balance_sheet_as_on(
+    pool: &sqlx::SqlitePool,
+    as_on_date: &str,
+) -> Result<BalanceSheetData, String> {
     let query = "
         SELECT 
             coa.account_name,
@@ -194,8 +669,8 @@ pub async fn get_balance_sheet(
             coa.account_type,
             CAST(coa.opening_balance AS REAL) as opening_balance,
             coa.opening_balance_type,
-            CAST(COALESCE(SUM(je.debit), 0) AS REAL) as total_debit,
-            CAST(COALESCE(SUM(je.credit), 0) AS REAL) as total_credit
+            CAST(COALESCE(SUM(CASE WHEN v.id IS NOT NULL THEN je.debit END), 0) AS REAL) as total_debit,
+            CAST(COALESCE(SUM(CASE WHEN v.id IS NOT NULL THEN je.credit END), 0) AS REAL) as total_credit
         FROM chart_of_accounts coa
         LEFT JOIN journal_entries je ON coa.id = je.account_id
         LEFT JOIN vouchers v ON je.voucher_id = v.id AND v.voucher_date <= ? AND v.deleted_at IS NULL
@@ -204,8 +679,8 @@ pub async fn get_balance_sheet(
     ";
 
     let rows = sqlx::query_as::<_, (String, String, String, f64, String, f64, f64)>(query)
-        .bind(&as_on_date)
-        .fetch_all(&pool)
+        .bind(as_on_date)
+        .fetch_all(pool)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -242,6 +717,8 @@ pub async fn get_balance_sheet(
             account_name: name,
             account_code: code,
             amount: balance.abs(),
+            compare_amount: None,
+            delta: None,
         };
 
         match acc_type.as_str() {
@@ -276,8 +753,8 @@ pub async fn get_balance_sheet(
     ";
 
     let pl_rows = sqlx::query_as::<_, (String, f64, f64)>(pl_query)
-        .bind(&as_on_date)
-        .fetch_all(&pool)
+        .bind(as_on_date)
+        .fetch_all(pool)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -296,6 +773,8 @@ pub async fn get_balance_sheet(
             account_name: "Net Profit for the Period".to_string(),
             account_code: "NET_PROFIT".to_string(),
             amount: net_profit,
+            compare_amount: None,
+            delta: None,
         });
     }
 
@@ -306,6 +785,10 @@ pub async fn get_balance_sheet(
         total_assets,
         total_liabilities,
         total_equity,
+        compare_date: None,
+        compare_total_assets: None,
+        compare_total_liabilities: None,
+        compare_total_equity: None,
     })
 }
 
@@ -317,6 +800,12 @@ pub struct ProfitLossData {
     pub total_income: f64,
     pub total_expenses: f64,
     pub net_profit: f64,
+    /// COGS-mode figures (`use_cogs`); None in the simple income-vs-expense view
+    pub opening_stock_value: Option<f64>,
+    pub closing_stock_value: Option<f64>,
+    pub cost_of_goods_sold: Option<f64>,
+    /// Revenue less cost of goods sold
+    pub gross_profit: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -326,13 +815,153 @@ pub struct PLAccount {
     pub amount: f64,
 }
 
+/// With `use_cogs`, purchases and purchase returns are replaced by cost of goods
+/// sold (opening stock + net purchases - closing stock, at weighted-average cost)
+/// and a gross profit line is filled in. The default nets income against expense.
 #[tauri::command]
 pub async fn get_profit_loss(
     registry: State<'_, Arc<DbRegistry>>,
     from_date: String,
     to_date: String,
+    use_cogs: Option<bool>,
 ) -> Result<ProfitLossData, String> {
     let pool = registry.active_pool().await?;
+    let mut data = profit_loss_data(&pool, &from_date, &to_date).await?;
+    if use_cogs.unwrap_or(false) {
+        apply_cost_of_goods_sold(&pool, &mut data, &from_date, &to_date).await?;
+    }
+    Ok(data)
+}
+
+/// Ledgers whose postings are inventory purchases (Purchases, Purchase Returns).
+const PURCHASE_PURPOSES: [&str; 2] = ["purchases", "purchase_returns"];
+
+async fn apply_cost_of_goods_sold(
+    pool: &sqlx::SqlitePool,
+    data: &mut ProfitLossData,
+    from_date: &str,
+    to_date: &str,
+) -> Result<(), String> {
+    let day_before = chrono::NaiveDate::parse_from_str(from_date, "%Y-%m-%d")
+        .map_err(|e| e.to_string())?
+        .pred_opt()
+        .ok_or_else(|| format!("Invalid from date {}", from_date))?
+        .to_string();
+    let stock_total = |values: HashMap<String, (f64, f64)>| -> f64 {
+        values.values().map(|(_, value)| value).sum()
+    };
+    // Opening stock entered during the period is stock brought in, not bought
+    let opening_stock_entered: f64 = sqlx::query_scalar(
+        "SELECT CAST(COALESCE(SUM(COALESCE(sm.cost_amount, sm.amount)), 0) AS REAL)
+         FROM stock_movements sm
+         JOIN vouchers v ON sm.voucher_id = v.id
+         WHERE v.voucher_type = 'opening_stock'
+           AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL",
+    )
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let opening_stock = stock_total(
+        product_stock_values(pool, Some(&day_before), ValuationMethod::WeightedAverage).await?,
+    ) + opening_stock_entered;
+    let closing_stock = stock_total(
+        product_stock_values(pool, Some(to_date), ValuationMethod::WeightedAverage).await?,
+    );
+
+    let mut purchase_accounts = Vec::with_capacity(PURCHASE_PURPOSES.len());
+    for purpose in PURCHASE_PURPOSES {
+        purchase_accounts.push(resolve_system_account(pool, purpose).await?);
+    }
+    let net_purchases: f64 = sqlx::query_scalar(
+        "SELECT CAST(COALESCE(SUM(je.debit - je.credit), 0) AS REAL)
+         FROM journal_entries je
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE je.account_id IN (?, ?)
+           AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
+           AND v.voucher_type NOT IN ('year_end_close', 'opening_stock')",
+    )
+    .bind(&purchase_accounts[0])
+    .bind(&purchase_accounts[1])
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Stock adjustments already move the closing stock value, so their ledger
+    // would be counted twice if it stayed alongside cost of goods sold
+    let stock_adjustment_account = resolve_system_account(pool, "stock_adjustment").await?;
+    let mut replaced_ids = purchase_accounts;
+    replaced_ids.push(stock_adjustment_account);
+    let mut replaced_codes = Vec::with_capacity(replaced_ids.len());
+    for id in &replaced_ids {
+        let code: String =
+            sqlx::query_scalar("SELECT account_code FROM chart_of_accounts WHERE id = ?")
+                .bind(id)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        replaced_codes.push(code);
+    }
+    let replaced_expenses: f64 = data
+        .expenses
+        .iter()
+        .filter(|account| replaced_codes.contains(&account.account_code))
+        .map(|account| account.amount)
+        .sum();
+    data.expenses
+        .retain(|account| !replaced_codes.contains(&account.account_code));
+
+    // The cost of goods sold ledger carries no postings of its own, so it only
+    // ever shows the computed figure
+    let cogs = opening_stock + net_purchases - closing_stock;
+    let cogs_account = resolve_system_account(pool, "cost_of_goods_sold").await?;
+    let (cogs_code, cogs_name): (String, String) =
+        sqlx::query_as("SELECT account_code, account_name FROM chart_of_accounts WHERE id = ?")
+            .bind(&cogs_account)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    data.expenses.insert(
+        0,
+        PLAccount {
+            account_name: cogs_name,
+            account_code: cogs_code,
+            amount: cogs,
+        },
+    );
+
+    let revenue: f64 = sqlx::query_scalar(
+        "SELECT CAST(COALESCE(SUM(je.credit - je.debit), 0) AS REAL)
+         FROM journal_entries je
+         JOIN chart_of_accounts coa ON je.account_id = coa.id
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE coa.account_type = 'Income' AND coa.account_group = 'Revenue'
+           AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
+           AND v.voucher_type != 'year_end_close'",
+    )
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    data.total_expenses = data.total_expenses - replaced_expenses + cogs;
+    data.net_profit = data.total_income - data.total_expenses;
+    data.opening_stock_value = Some(opening_stock);
+    data.closing_stock_value = Some(closing_stock);
+    data.cost_of_goods_sold = Some(cogs);
+    data.gross_profit = Some(revenue - cogs);
+    Ok(())
+}
+
+pub(crate) async fn profit_loss_data(
+    pool: &sqlx::SqlitePool,
+    from_date: &str,
+    to_date: &str,
+) -> Result<ProfitLossData, String> {
     let query = "
         SELECT 
             coa.account_name,
@@ -345,13 +974,14 @@ pub async fn get_profit_loss(
         JOIN vouchers v ON je.voucher_id = v.id
         WHERE v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
         AND coa.account_type IN ('Income', 'Expense')
+        AND v.voucher_type != 'year_end_close'
         GROUP BY coa.id
     ";
 
     let rows = sqlx::query_as::<_, (String, String, String, f64, f64)>(query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_all(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_all(pool)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -390,22 +1020,108 @@ pub async fn get_profit_loss(
         total_income,
         total_expenses,
         net_profit: total_income - total_expenses,
+        opening_stock_value: None,
+        closing_stock_value: None,
+        cost_of_goods_sold: None,
+        gross_profit: None,
     })
 }
 
-// ============= CASH FLOW =============
 #[derive(Serialize, Deserialize)]
-pub struct CashFlowItem {
-    pub description: String,
-    pub amount: f64,
+pub struct MonthlyProfitLoss {
+    pub month: String,
+    pub income: f64,
+    pub expenses: f64,
+    pub net_profit: f64,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct CashFlowData {
-    pub operating_activities: Vec<CashFlowItem>,
-    pub investing_activities: Vec<CashFlowItem>,
-    pub financing_activities: Vec<CashFlowItem>,
-    pub net_operating: f64,
+/// Income/expense totals per calendar month (`YYYY-MM`) across the range.
+/// Months with no postings are still returned with zero totals so the grid
+/// always has one column per month.
+#[tauri::command]
+pub async fn get_monthly_profit_loss(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<MonthlyProfitLoss>, String> {
+    let pool = registry.active_pool().await?;
+    monthly_profit_loss(&pool, &from_date, &to_date).await
+}
+
+async fn monthly_profit_loss(
+    pool: &sqlx::SqlitePool,
+    from_date: &str,
+    to_date: &str,
+) -> Result<Vec<MonthlyProfitLoss>, String> {
+    let from = chrono::NaiveDate::parse_from_str(from_date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid from_date: {}", from_date))?;
+    let to = chrono::NaiveDate::parse_from_str(to_date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid to_date: {}", to_date))?;
+    if from > to {
+        return Err("from_date must be on or before to_date".to_string());
+    }
+
+    let rows = sqlx::query_as::<_, (String, f64, f64)>(
+        "SELECT 
+            strftime('%Y-%m', v.voucher_date) as month,
+            CAST(COALESCE(SUM(CASE WHEN coa.account_type = 'Income' THEN je.credit - je.debit ELSE 0 END), 0) AS REAL) as income,
+            CAST(COALESCE(SUM(CASE WHEN coa.account_type = 'Expense' THEN je.debit - je.credit ELSE 0 END), 0) AS REAL) as expenses
+        FROM journal_entries je
+        JOIN chart_of_accounts coa ON coa.id = je.account_id
+        JOIN vouchers v ON je.voucher_id = v.id
+        WHERE v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
+        AND coa.account_type IN ('Income', 'Expense')
+        AND v.voucher_type != 'year_end_close'
+        GROUP BY month
+        ORDER BY month",
+    )
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let totals: HashMap<String, (f64, f64)> = rows
+        .into_iter()
+        .map(|(month, income, expenses)| (month, (income, expenses)))
+        .collect();
+
+    let mut result = Vec::new();
+    let (mut year, mut month) = (from.year(), from.month());
+    while (year, month) <= (to.year(), to.month()) {
+        let key = format!("{:04}-{:02}", year, month);
+        let (income, expenses) = totals.get(&key).copied().unwrap_or((0.0, 0.0));
+        result.push(MonthlyProfitLoss {
+            month: key,
+            income,
+            expenses,
+            net_profit: income - expenses,
+        });
+
+        if month == 12 {
+            year += 1;
+            month = 1;
+        } else {
+            month += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+// ============= CASH FLOW =============
+#[derive(Serialize, Deserialize)]
+pub struct CashFlowItem {
+    pub description: String,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CashFlowData {
+    pub operating_activities: Vec<CashFlowItem>,
+    pub investing_activities: Vec<CashFlowItem>,
+    pub financing_activities: Vec<CashFlowItem>,
+    pub net_operating: f64,
     pub net_investing: f64,
     pub net_financing: f64,
     pub net_change: f64,
@@ -420,24 +1136,32 @@ pub async fn get_cash_flow(
     to_date: String,
 ) -> Result<CashFlowData, String> {
     let pool = registry.active_pool().await?;
+    cash_flow(&pool, &from_date, &to_date).await
+}
+
+async fn cash_flow(
+    pool: &sqlx::SqlitePool,
+    from_date: &str,
+    to_date: &str,
+) -> Result<CashFlowData, String> {
     // Get opening date (day before from_date)
     let opening_date_obj =
-        chrono::NaiveDate::parse_from_str(&from_date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        chrono::NaiveDate::parse_from_str(from_date, "%Y-%m-%d").map_err(|e| e.to_string())?;
     let opening_date = (opening_date_obj - chrono::Duration::days(1)).to_string();
 
-    // 1. Calculate Opening Cash
+    // 1. Calculate Opening Cash (across every Cash and Bank Account ledger)
     let opening_cash_query = "
         SELECT CAST(COALESCE(SUM(je.debit - je.credit), 0) AS REAL)
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let opening_cash: f64 = sqlx::query_scalar(opening_cash_query)
         .bind(&opening_date)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -447,13 +1171,13 @@ pub async fn get_cash_flow(
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let closing_cash: f64 = sqlx::query_scalar(closing_cash_query)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -467,15 +1191,15 @@ pub async fn get_cash_flow(
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_type = 'sales_invoice'
         AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let cash_sales: f64 = sqlx::query_scalar(cash_sales_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -485,15 +1209,15 @@ pub async fn get_cash_flow(
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_type = 'purchase_invoice'
         AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let cash_purchases: f64 = sqlx::query_scalar(cash_purchases_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -504,15 +1228,15 @@ pub async fn get_cash_flow(
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_type = 'receipt'
         AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let debtor_payment: f64 = sqlx::query_scalar(debtor_payment_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -523,15 +1247,15 @@ pub async fn get_cash_flow(
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_type = 'payment'
         AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let creditor_payment: f64 = sqlx::query_scalar(creditor_payment_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -541,16 +1265,16 @@ pub async fn get_cash_flow(
         FROM journal_entries je
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
-        WHERE coa.account_name = 'Cash' 
+        WHERE coa.account_group IN ('Cash', 'Bank Account')
         AND v.voucher_type = 'journal'
         AND coa.account_type = 'Expense'
         AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let other_expenses: f64 = sqlx::query_scalar(other_expenses_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -604,14 +1328,15 @@ pub async fn get_cash_flow(
         JOIN vouchers v ON je.voucher_id = v.id
         JOIN chart_of_accounts coa ON je.account_id = coa.id
         WHERE coa.account_type = 'Asset' 
-        AND coa.account_name NOT IN ('Cash', 'Accounts Receivable', 'Bank Account')
+        AND coa.account_group NOT IN ('Cash', 'Bank Account')
+        AND coa.account_name != 'Accounts Receivable'
         AND v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
     ";
 
     let net_investing: f64 = sqlx::query_scalar(investing_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -637,9 +1362,9 @@ pub async fn get_cash_flow(
     ";
 
     let net_financing: f64 = sqlx::query_scalar(financing_query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_one(&pool)
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0);
 
@@ -683,10 +1408,40 @@ pub async fn get_day_book(
     from_date: String,
     to_date: String,
     detailed: Option<bool>,
+    exclude_account_ids: Option<Vec<String>>,
 ) -> Result<Vec<DayBookEntry>, String> {
     let pool = registry.active_pool().await?;
-    let query = if detailed.unwrap_or(false) {
-        "
+    // Optional exclusion of high-volume control accounts (e.g. Opening Balance Adjustment)
+    let excluded = exclude_account_ids.unwrap_or_default();
+    day_book_entries(
+        &pool,
+        &from_date,
+        &to_date,
+        detailed.unwrap_or(false),
+        &excluded,
+    )
+    .await
+}
+
+/// Day book rows for the period: one row per journal line when `detailed`,
+/// otherwise one summarised row per voucher. Shared with the day book PDF.
+pub(crate) async fn day_book_entries(
+    pool: &sqlx::SqlitePool,
+    from_date: &str,
+    to_date: &str,
+    detailed: bool,
+    excluded: &[String],
+) -> Result<Vec<DayBookEntry>, String> {
+    let exclude_filter = if excluded.is_empty() {
+        String::new()
+    } else {
+        let placeholders = vec!["?"; excluded.len()].join(", ");
+        format!("AND je.account_id NOT IN ({})", placeholders)
+    };
+
+    let query = if detailed {
+        format!(
+            "
             SELECT 
                 v.voucher_no,
                 v.voucher_type,
@@ -703,11 +1458,14 @@ pub async fn get_day_book(
             FROM journal_entries je
             JOIN vouchers v ON je.voucher_id = v.id
             JOIN chart_of_accounts coa ON je.account_id = coa.id
-            WHERE v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
+            WHERE v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL {}
             ORDER BY v.voucher_date ASC, v.id ASC, je.id ASC
-        "
+        ",
+            exclude_filter
+        )
     } else {
-        "
+        format!(
+            "
             SELECT 
                 v.voucher_no,
                 v.voucher_type,
@@ -743,18 +1501,23 @@ pub async fn get_day_book(
             JOIN vouchers v ON je.voucher_id = v.id
             JOIN chart_of_accounts coa ON je.account_id = coa.id
             LEFT JOIN chart_of_accounts party_coa ON v.party_id = party_coa.id
-            WHERE v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
+            WHERE v.voucher_date >= ? AND v.voucher_date <= ? AND v.deleted_at IS NULL {}
             GROUP BY v.id, v.voucher_no, v.voucher_type, v.voucher_date, v.party_type, v.party_id, v.narration, party_coa.account_name
             ORDER BY v.voucher_date ASC, v.id ASC
-        "
+        ",
+            exclude_filter
+        )
     };
 
-    sqlx::query_as::<_, DayBookEntry>(query)
-        .bind(&from_date)
-        .bind(&to_date)
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| e.to_string())
+    let mut q = sqlx::query_as::<_, DayBookEntry>(&query)
+        .bind(from_date)
+        .bind(to_date);
+
+    for account_id in excluded {
+        q = q.bind(account_id);
+    }
+
+    q.fetch_all(pool).await.map_err(|e| e.to_string())
 }
 
 // ============= TRANSACTION REPORT =============
@@ -802,12 +1565,13 @@ pub async fn get_transaction_report(
                 )
                 ELSE coa.account_name
             END as party_name,
-            COALESCE(v.grand_total, v.total_amount, 0.0) as amount,
+            COALESCE(v.base_grand_total, v.grand_total, v.total_amount, 0.0) as amount,
             v.narration,
             v.created_at
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
         WHERE v.deleted_at IS NULL 
+        AND COALESCE(v.status, 'posted') = 'posted'
         AND v.voucher_date >= ? AND v.voucher_date <= ?",
     );
 
@@ -862,13 +1626,14 @@ pub async fn get_sales_return_report(
             v.voucher_date,
             coa.account_name as party_name,
             v.reference,
-            CAST(COALESCE(v.grand_total, v.total_amount, 0.0) AS REAL) as amount
+            CAST(COALESCE(v.base_grand_total, v.grand_total, v.total_amount, 0.0) AS REAL) as amount
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
         WHERE v.voucher_type IN ('sales_invoice', 'sales_return')
           AND v.voucher_date >= ?
           AND v.voucher_date <= ?
           AND v.deleted_at IS NULL
+          AND COALESCE(v.status, 'posted') = 'posted'
         ORDER BY v.voucher_date ASC, v.created_at ASC, v.id ASC
     ";
 
@@ -900,6 +1665,14 @@ pub async fn get_party_outstanding(
     as_on_date: String,
 ) -> Result<Vec<PartyOutstanding>, String> {
     let pool = registry.active_pool().await?;
+    party_outstanding(&pool, &party_type, &as_on_date).await
+}
+
+async fn party_outstanding(
+    pool: &sqlx::SqlitePool,
+    party_type: &str,
+    as_on_date: &str,
+) -> Result<Vec<PartyOutstanding>, String> {
     let (account_group, voucher_type, _code_prefix) = if party_type == "customer" {
         ("Accounts Receivable", "sales_invoice", "1003-")
     } else {
@@ -964,10 +1737,11 @@ pub async fn get_party_outstanding(
                 v.party_id,
                 v.party_type,
                 COUNT(v.id) as total_invoices,
-                SUM(COALESCE(v.grand_total, v.total_amount, 0.0)) as total_amount,
+                SUM(COALESCE(v.base_grand_total, v.grand_total, v.total_amount, 0.0)) as total_amount,
                 MIN(v.voucher_date) as oldest_invoice_date
             FROM vouchers v
             WHERE v.voucher_type = ? AND v.party_type = ? AND v.voucher_date <= ? AND v.deleted_at IS NULL
+              AND COALESCE(v.status, 'posted') = 'posted'
             GROUP BY v.party_id, v.party_type
         ) v_stats ON (
             coa.id = v_stats.party_id AND v_stats.party_type = ?
@@ -981,13 +1755,13 @@ pub async fn get_party_outstanding(
 
     let rows =
         sqlx::query_as::<_, (String, String, i64, f64, f64, f64, Option<String>)>(query.as_str())
-            .bind(&as_on_date)
+            .bind(as_on_date)
             .bind(voucher_type)
-            .bind(&party_type)
-            .bind(&as_on_date)
-            .bind(&party_type)
+            .bind(party_type)
+            .bind(as_on_date)
+            .bind(party_type)
             .bind(account_group)
-            .fetch_all(&pool)
+            .fetch_all(pool)
             .await
             .map_err(|e| e.to_string())?;
 
@@ -1028,99 +1802,839 @@ pub struct InvoiceDetail {
     pub days_outstanding: i64,
 }
 
-#[tauri::command]
-pub async fn get_party_invoice_details(
-    registry: State<'_, Arc<DbRegistry>>,
-    party_id: String, // This is coa.id
-    party_type: String,
-    as_on_date: String,
-) -> Result<Vec<InvoiceDetail>, String> {
-    let pool = registry.active_pool().await?;
+#[derive(sqlx::FromRow)]
+struct OpenInvoice {
+    voucher_id: String,
+    party_id: String,
+    party_name: String,
+    voucher_no: String,
+    voucher_date: String,
+    total_amount: f64,
+    paid_amount: f64,
+    /// Date up to which overdue interest has already been booked on this invoice
+    interest_posted_through: Option<String>,
+}
+
+/// Unpaid invoices as on a date. Paid is the sum of allocations dated on or
+/// before `as_on_date`; fully settled invoices are omitted.
+async fn open_invoice_balances(
+    pool: &sqlx::SqlitePool,
+    party_type: &str,
+    party_id: Option<&str>,
+    as_on_date: &str,
+) -> Result<Vec<OpenInvoice>, String> {
     let (voucher_type, code_prefix) = if party_type == "customer" {
         ("sales_invoice", "1003-")
     } else {
         ("purchase_invoice", "2001-")
     };
+    let party_filter = if party_id.is_some() {
+        "coa.id = ? AND"
+    } else {
+        ""
+    };
 
     let query = format!(
         "
         SELECT 
+            v.id as voucher_id,
+            coa.id as party_id,
+            coa.account_name as party_name,
             v.voucher_no,
             v.voucher_date,
-            CAST(COALESCE(v.grand_total, v.total_amount, 0.0) AS REAL) as total_amount,
+            CAST(COALESCE(v.base_grand_total, v.grand_total, v.total_amount, 0.0) AS REAL) as total_amount,
             CAST(COALESCE((
                 SELECT SUM(allocated_amount) FROM payment_allocations 
                 WHERE invoice_voucher_id = v.id AND allocation_date <= ?
-            ), 0) AS REAL) as paid_amount
+            ), 0) AS REAL) as paid_amount,
+            v.interest_posted_through
         FROM vouchers v
         JOIN chart_of_accounts coa ON coa.account_code = '{}' || v.party_id
-        WHERE coa.id = ? AND v.party_type = ? AND v.voucher_type = ?
+        WHERE {} v.party_type = ? AND v.voucher_type = ?
         AND v.voucher_date <= ? AND v.deleted_at IS NULL
+        AND COALESCE(v.status, 'posted') = 'posted'
         GROUP BY v.id
         HAVING (total_amount - paid_amount) > 0.01
+        ORDER BY v.voucher_date ASC, v.id ASC
     ",
-        code_prefix
+        code_prefix, party_filter
     );
 
-    let rows = sqlx::query_as::<_, (String, String, f64, f64)>(query.as_str())
-        .bind(&as_on_date)
-        .bind(party_id)
-        .bind(&party_type)
+    let mut q = sqlx::query_as::<_, OpenInvoice>(query.as_str()).bind(as_on_date);
+    if let Some(id) = party_id {
+        q = q.bind(id);
+    }
+    q.bind(party_type)
         .bind(voucher_type)
-        .bind(&as_on_date)
-        .fetch_all(&pool)
+        .bind(as_on_date)
+        .fetch_all(pool)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_party_invoice_details(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String, // This is coa.id
+    party_type: String,
+    as_on_date: String,
+) -> Result<Vec<InvoiceDetail>, String> {
+    let pool = registry.active_pool().await?;
+    let rows = open_invoice_balances(&pool, &party_type, Some(&party_id), &as_on_date).await?;
 
     let today = chrono::Local::now().naive_local().date();
 
     Ok(rows
         .into_iter()
-        .map(|(no, date, total, paid)| {
-            let days = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map(|row| {
+            let days = chrono::NaiveDate::parse_from_str(&row.voucher_date, "%Y-%m-%d")
                 .ok()
                 .map(|d| (today - d).num_days())
                 .unwrap_or(0);
 
             InvoiceDetail {
-                voucher_no: no,
-                voucher_date: date,
-                total_amount: total,
-                paid_amount: paid,
-                outstanding_amount: total - paid,
+                voucher_no: row.voucher_no,
+                voucher_date: row.voucher_date,
+                total_amount: row.total_amount,
+                paid_amount: row.paid_amount,
+                outstanding_amount: row.total_amount - row.paid_amount,
                 days_outstanding: days,
             }
         })
         .collect())
 }
 
-// ============= SINGLE PRODUCT STOCK QTY =============
+// ============= OVERDUE INTEREST =============
+#[derive(Serialize, Deserialize)]
+pub struct InvoiceInterest {
+    pub voucher_id: String,
+    pub voucher_no: String,
+    pub voucher_date: String,
+    pub outstanding_amount: f64,
+    /// Days charged: past voucher_date + grace_days, or past the date interest
+    /// was last posted through, whichever is later
+    pub overdue_days: i64,
+    pub interest: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OverdueInterest {
+    pub party_id: String,
+    pub party_type: String,
+    pub as_on_date: String,
+    pub annual_rate: f64,
+    pub grace_days: i64,
+    pub invoices: Vec<InvoiceInterest>,
+    pub total_interest: f64,
+}
+
+/// Simple interest on each open invoice for the days it is overdue beyond
+/// `grace_days`, at `annual_rate` percent per year (365-day basis). Days already
+/// covered by a posted interest journal are not charged again.
+pub(crate) async fn overdue_interest(
+    pool: &sqlx::SqlitePool,
+    party_id: &str,
+    party_type: &str,
+    as_on_date: &str,
+    annual_rate: f64,
+    grace_days: i64,
+) -> Result<OverdueInterest, String> {
+    if party_type != "customer" && party_type != "supplier" {
+        return Err(format!("Unknown party type '{}'", party_type));
+    }
+    if annual_rate < 0.0 {
+        return Err("Interest rate cannot be negative".to_string());
+    }
+    let as_on = chrono::NaiveDate::parse_from_str(as_on_date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}'", as_on_date))?;
+    let grace_days = grace_days.max(0);
+
+    let rows = open_invoice_balances(pool, party_type, Some(party_id), as_on_date).await?;
+
+    let mut invoices = Vec::new();
+    for row in rows {
+        let Ok(date) = chrono::NaiveDate::parse_from_str(&row.voucher_date, "%Y-%m-%d") else {
+            continue;
+        };
+        let mut overdue_days = (as_on - date).num_days() - grace_days;
+        if let Some(posted_through) = row
+            .interest_posted_through
+            .as_deref()
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        {
+            overdue_days = overdue_days.min((as_on - posted_through).num_days());
+        }
+        if overdue_days <= 0 {
+            continue;
+        }
+        let outstanding_amount = row.total_amount - row.paid_amount;
+        let interest = outstanding_amount * annual_rate / 100.0 * overdue_days as f64 / 365.0;
+        invoices.push(InvoiceInterest {
+            voucher_id: row.voucher_id,
+            voucher_no: row.voucher_no,
+            voucher_date: row.voucher_date,
+            outstanding_amount,
+            overdue_days,
+            interest: (interest * 100.0).round() / 100.0,
+        });
+    }
+
+    let total_interest = invoices.iter().map(|i| i.interest).sum::<f64>();
+
+    Ok(OverdueInterest {
+        party_id: party_id.to_string(),
+        party_type: party_type.to_string(),
+        as_on_date: as_on_date.to_string(),
+        annual_rate,
+        grace_days,
+        invoices,
+        total_interest: (total_interest * 100.0).round() / 100.0,
+    })
+}
 
 #[tauri::command]
-pub async fn get_product_stock_qty(
+pub async fn calculate_overdue_interest(
     registry: State<'_, Arc<DbRegistry>>,
-    product_id: String,
-) -> Result<f64, String> {
+    party_id: String, // This is coa.id
+    party_type: String,
+    as_on_date: String,
+    annual_rate: f64,
+    grace_days: Option<i64>,
+) -> Result<OverdueInterest, String> {
+    let pool = registry.active_pool().await?;
+    overdue_interest(
+        &pool,
+        &party_id,
+        &party_type,
+        &as_on_date,
+        annual_rate,
+        grace_days.unwrap_or(0),
+    )
+    .await
+}
+
+// ============= PARTY STATEMENT =============
+#[derive(Serialize, Deserialize)]
+pub struct PartyStatement {
+    pub party_id: String,
+    pub party_name: String,
+    pub party_type: String,
+    pub from_date: String,
+    pub to_date: String,
+    pub opening_balance: f64,
+    pub entries: Vec<LedgerEntry>,
+    pub closing_balance: f64,
+    /// Invoices still unpaid as on to_date
+    pub open_invoices: Vec<InvoiceDetail>,
+}
+
+/// Statement of account for a customer or supplier: the party ledger for the
+/// period with running balance (Dr positive), plus its open invoices.
+#[tauri::command]
+pub async fn get_party_statement(
+    registry: State<'_, Arc<DbRegistry>>,
+    party_id: String, // This is coa.id
+    party_type: String,
+    from_date: String,
+    to_date: String,
+) -> Result<PartyStatement, String> {
     let pool = registry.active_pool().await?;
+    party_statement(&pool, &party_id, &party_type, &from_date, &to_date).await
+}
+
+pub(crate) async fn party_statement(
+    pool: &sqlx::SqlitePool,
+    party_id: &str,
+    party_type: &str,
+    from_date: &str,
+    to_date: &str,
+) -> Result<PartyStatement, String> {
+    if party_type != "customer" && party_type != "supplier" {
+        return Err(format!("Unknown party type '{}'", party_type));
+    }
+
+    let (party_name, opening, opening_type): (String, f64, String) = sqlx::query_as(
+        "SELECT account_name, CAST(COALESCE(opening_balance, 0) AS REAL), COALESCE(opening_balance_type, 'Dr')
+         FROM chart_of_accounts WHERE id = ?",
+    )
+    .bind(party_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Party account {} not found", party_id))?;
+
+    let (before_dr, before_cr): (f64, f64) = sqlx::query_as(
+        "SELECT CAST(COALESCE(SUM(je.debit), 0) AS REAL), CAST(COALESCE(SUM(je.credit), 0) AS REAL)
+         FROM journal_entries je
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE je.account_id = ? AND v.voucher_date < ? AND v.deleted_at IS NULL",
+    )
+    .bind(party_id)
+    .bind(from_date)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let account_opening = if opening_type == "Dr" {
+        opening
+    } else {
+        -opening
+    };
+    let opening_balance = account_opening + before_dr - before_cr;
+
+    let mut entries =
+        ledger_entries_between(pool, party_id, Some(from_date), to_date, false, None).await?;
+    let closing_balance = apply_running_balance(&mut entries, opening_balance);
+
+    let statement_date = chrono::NaiveDate::parse_from_str(to_date, "%Y-%m-%d").ok();
+    let open_invoices = open_invoice_balances(pool, party_type, Some(party_id), to_date)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let days = chrono::NaiveDate::parse_from_str(&row.voucher_date, "%Y-%m-%d")
+                .ok()
+                .zip(statement_date)
+                .map(|(d, as_on)| (as_on - d).num_days())
+                .unwrap_or(0);
+
+            InvoiceDetail {
+                voucher_no: row.voucher_no,
+                voucher_date: row.voucher_date,
+                total_amount: row.total_amount,
+                paid_amount: row.paid_amount,
+                outstanding_amount: row.total_amount - row.paid_amount,
+                days_outstanding: days,
+            }
+        })
+        .collect();
+
+    Ok(PartyStatement {
+        party_id: party_id.to_string(),
+        party_name,
+        party_type: party_type.to_string(),
+        from_date: from_date.to_string(),
+        to_date: to_date.to_string(),
+        opening_balance,
+        entries,
+        closing_balance,
+        open_invoices,
+    })
+}
+
+// ============= AGED RECEIVABLES / PAYABLES =============
+#[derive(Serialize, Deserialize)]
+pub struct AgedPartyBalance {
+    pub party_id: String,
+    pub party_name: String,
+    pub days_0_30: f64,
+    pub days_31_60: f64,
+    pub days_61_90: f64,
+    pub days_over_90: f64,
+    pub total: f64,
+}
+
+/// Open invoices as on `as_on_date`, each with its age in days (as_on_date -
+/// voucher_date) and outstanding amount. Shared by the aging reports.
+async fn aged_open_invoices(
+    pool: &sqlx::SqlitePool,
+    party_type: &str,
+    as_on_date: &str,
+) -> Result<Vec<(OpenInvoice, i64, f64)>, String> {
+    let as_on = chrono::NaiveDate::parse_from_str(as_on_date, "%Y-%m-%d")
+        .map_err(|_| "As-on date must be in YYYY-MM-DD format".to_string())?;
+    let rows = open_invoice_balances(pool, party_type, None, as_on_date).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let age = chrono::NaiveDate::parse_from_str(&row.voucher_date, "%Y-%m-%d")
+                .map(|d| (as_on - d).num_days())
+                .unwrap_or(0);
+            let outstanding = row.total_amount - row.paid_amount;
+            (row, age, outstanding)
+        })
+        .collect())
+}
+
+/// Buckets each open invoice by its age and sums per party.
+async fn aged_party_balances(
+    pool: &sqlx::SqlitePool,
+    party_type: &str,
+    as_on_date: &str,
+) -> Result<Vec<AgedPartyBalance>, String> {
+    let mut parties: Vec<AgedPartyBalance> = Vec::new();
+    for (row, age, outstanding) in aged_open_invoices(pool, party_type, as_on_date).await? {
+        let idx = match parties.iter().position(|p| p.party_id == row.party_id) {
+            Some(idx) => idx,
+            None => {
+                parties.push(AgedPartyBalance {
+                    party_id: row.party_id,
+                    party_name: row.party_name,
+                    days_0_30: 0.0,
+                    days_31_60: 0.0,
+                    days_61_90: 0.0,
+                    days_over_90: 0.0,
+                    total: 0.0,
+                });
+                parties.len() - 1
+            }
+        };
+        let party = &mut parties[idx];
+        match age {
+            ..=30 => party.days_0_30 += outstanding,
+            31..=60 => party.days_31_60 += outstanding,
+            61..=90 => party.days_61_90 += outstanding,
+            _ => party.days_over_90 += outstanding,
+        }
+        party.total += outstanding;
+    }
+
+    parties.sort_by(|a, b| a.party_name.cmp(&b.party_name));
+    Ok(parties)
+}
+
+#[tauri::command]
+pub async fn get_aged_receivables(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+) -> Result<Vec<AgedPartyBalance>, String> {
+    let pool = registry.active_pool().await?;
+    aged_party_balances(&pool, "customer", &as_on_date).await
+}
+
+#[tauri::command]
+pub async fn get_aged_payables(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+) -> Result<Vec<AgedPartyBalance>, String> {
+    let pool = registry.active_pool().await?;
+    aged_party_balances(&pool, "supplier", &as_on_date).await
+}
+
+// ============= AGING SUMMARY =============
+/// Outstanding totals across all parties of one type, bucketed by invoice age.
+/// "Current" covers invoices dated on the as-on date itself.
+#[derive(Serialize, Deserialize)]
+pub struct AgingSummary {
+    pub as_on_date: String,
+    pub current: f64,
+    pub days_1_30: f64,
+    pub days_31_60: f64,
+    pub days_over_60: f64,
+    pub total: f64,
+    pub invoice_count: i64,
+}
+
+async fn aging_summary(
+    pool: &sqlx::SqlitePool,
+    party_type: &str,
+    as_on_date: &str,
+) -> Result<AgingSummary, String> {
+    let mut summary = AgingSummary {
+        as_on_date: as_on_date.to_string(),
+        current: 0.0,
+        days_1_30: 0.0,
+        days_31_60: 0.0,
+        days_over_60: 0.0,
+        total: 0.0,
+        invoice_count: 0,
+    };
+    for (_, age, outstanding) in aged_open_invoices(pool, party_type, as_on_date).await? {
+        match age {
+            ..=0 => summary.current += outstanding,
+            1..=30 => summary.days_1_30 += outstanding,
+            31..=60 => summary.days_31_60 += outstanding,
+            _ => summary.days_over_60 += outstanding,
+        }
+        summary.total += outstanding;
+        summary.invoice_count += 1;
+    }
+
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn get_receivables_aging_summary(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+) -> Result<AgingSummary, String> {
+    let pool = registry.active_pool().await?;
+    aging_summary(&pool, "customer", &as_on_date).await
+}
+
+#[tauri::command]
+pub async fn get_payables_aging_summary(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+) -> Result<AgingSummary, String> {
+    let pool = registry.active_pool().await?;
+    aging_summary(&pool, "supplier", &as_on_date).await
+}
+
+// ============= STOCK MOVEMENT TYPES =============
+
+/// Movement types that add to stock. Stock adjustments and returns (including
+/// return lines on a sales invoice) post the subtypes; rows saved before they
+/// did are plain IN/OUT and are told apart by voucher type.
+pub(crate) const STOCK_IN_TYPES: &str = "'IN', 'ADJUSTMENT_IN', 'RETURN_IN'";
+/// Movement types that reduce stock.
+pub(crate) const STOCK_OUT_TYPES: &str = "'OUT', 'ADJUSTMENT_OUT', 'RETURN_OUT'";
+
+/// Weighted-average cost per unit over the movements aliased `alias`: net cost
+/// value divided by net quantity, 0 when nothing is in stock.
+fn weighted_average_rate_sql(alias: &str) -> String {
+    format!(
+        "CAST(COALESCE(
+                SUM(CASE
+                    WHEN {a}.movement_type IN ({in_types}) THEN COALESCE({a}.cost_amount, {a}.amount)
+                    WHEN {a}.movement_type IN ({out_types}) THEN -COALESCE({a}.cost_amount, {a}.amount)
+                    ELSE 0
+                END) / NULLIF(SUM(CASE
+                    WHEN {a}.movement_type IN ({in_types}) THEN {a}.quantity
+                    WHEN {a}.movement_type IN ({out_types}) THEN -{a}.quantity
+                    ELSE 0
+                END), 0),
+                0
+            ) AS REAL)",
+        a = alias,
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES
+    )
+}
+
+/// +1.0 for inward movement types, -1.0 for outward ones, 0.0 for anything unknown.
+fn stock_direction(movement_type: &str) -> f64 {
+    match movement_type {
+        "IN" | "ADJUSTMENT_IN" | "RETURN_IN" => 1.0,
+        "OUT" | "ADJUSTMENT_OUT" | "RETURN_OUT" => -1.0,
+        _ => 0.0,
+    }
+}
+
+/// Display label for a movement, distinguishing adjustments and returns from
+/// ordinary purchases/sales (explicit subtypes first, then the voucher type).
+fn stock_movement_label(movement_type: &str, voucher_type: &str) -> String {
+    let label = match (movement_type, voucher_type) {
+        ("ADJUSTMENT_IN", _) | ("IN", "stock_journal" | "stock_adjustment") => "Adjustment In",
+        ("ADJUSTMENT_OUT", _) | ("OUT", "stock_journal" | "stock_adjustment") => "Adjustment Out",
+        ("RETURN_IN", _) | ("IN", "sales_return") => "Return In",
+        ("RETURN_OUT", _) | ("OUT", "purchase_return") => "Return Out",
+        ("IN", "opening_stock") => "Opening Stock",
+        ("IN", _) => "In",
+        ("OUT", _) => "Out",
+        (other, _) => other,
+    };
+    label.to_string()
+}
+
+// ============= SINGLE PRODUCT STOCK QTY =============
 
-    let qty: Option<f64> = sqlx::query_scalar(
+/// Quantity on hand for one product (bound as the only parameter), summed over
+/// all live movements. Shared with the sales-invoice stock guard.
+pub(crate) fn product_stock_qty_sql() -> String {
+    format!(
         "SELECT CAST(COALESCE(SUM(
             CASE
-                WHEN sm.movement_type = 'IN' THEN sm.quantity
-                WHEN sm.movement_type = 'OUT' THEN -sm.quantity
+                WHEN sm.movement_type IN ({in_types}) THEN sm.quantity
+                WHEN sm.movement_type IN ({out_types}) THEN -sm.quantity
                 ELSE 0
             END
         ), 0) AS REAL)
          FROM stock_movements sm
          JOIN vouchers v ON sm.voucher_id = v.id
          WHERE sm.product_id = ? AND v.deleted_at IS NULL",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES
     )
-    .bind(&product_id)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+}
+
+#[tauri::command]
+pub async fn get_product_stock_qty(
+    registry: State<'_, Arc<DbRegistry>>,
+    product_id: String,
+) -> Result<f64, String> {
+    let pool = registry.active_pool().await?;
+
+    let query = product_stock_qty_sql();
+    let qty: Option<f64> = sqlx::query_scalar(&query)
+        .bind(&product_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(qty.unwrap_or(0.0))
+}
+
+// ============= STOCK VALUATION =============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValuationMethod {
+    /// Stored inventory cost: net cost of all movements spread over the quantity on hand
+    WeightedAverage,
+    /// Quantity on hand valued at the cost of the most recent receipts still in stock
+    Fifo,
+    /// Quantity on hand valued at the unit cost of the latest receipt
+    LastPurchase,
+}
+
+impl ValuationMethod {
+    pub(crate) fn parse(method: Option<&str>) -> Result<Self, String> {
+        match method.map(|m| m.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("weighted_average") | Some("average") => {
+                Ok(ValuationMethod::WeightedAverage)
+            }
+            Some("fifo") => Ok(ValuationMethod::Fifo),
+            Some("last_purchase") => Ok(ValuationMethod::LastPurchase),
+            Some(other) => Err(format!(
+                "Unknown valuation method '{}': expected 'weighted_average', 'fifo' or 'last_purchase'",
+                other
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ValuationMethod::WeightedAverage => "weighted_average",
+            ValuationMethod::Fifo => "fifo",
+            ValuationMethod::LastPurchase => "last_purchase",
+        }
+    }
+}
+
+/// Quantity on hand and value at cost per product (non-master, not deleted),
+/// optionally as on a date. This is the single valuation shared by the stock
+/// report, the valuation summary and the dashboard.
+pub(crate) async fn product_stock_values(
+    pool: &sqlx::SqlitePool,
+    as_on_date: Option<&str>,
+    method: ValuationMethod,
+) -> Result<HashMap<String, (f64, f64)>, String> {
+    let date_filter = if as_on_date.is_some() {
+        "AND date(v.voucher_date) <= date(?)"
+    } else {
+        ""
+    };
+    let query = format!(
+        "SELECT sm.product_id, sm.movement_type, CAST(sm.quantity AS REAL),
+                CAST(COALESCE(sm.cost_amount, sm.amount, 0) AS REAL)
+         FROM stock_movements sm
+         JOIN vouchers v ON sm.voucher_id = v.id
+         JOIN products p ON sm.product_id = p.id
+         WHERE v.deleted_at IS NULL
+         AND p.deleted_at IS NULL
+         AND COALESCE(p.is_master, 0) = 0 {}
+         ORDER BY v.voucher_date ASC, v.id ASC, sm.id ASC",
+        date_filter
+    );
+    let mut q = sqlx::query_as::<_, (String, String, f64, f64)>(&query);
+    if let Some(date) = as_on_date {
+        q = q.bind(date);
+    }
+    let movements = q.fetch_all(pool).await.map_err(|e| e.to_string())?;
+
+    // Per product: (qty, net cost, FIFO layers of (qty, unit cost), latest receipt unit cost).
+    // Movements arrive in date order, so FIFO lots are consumed oldest-first.
+    let mut state: HashMap<String, (f64, f64, VecDeque<(f64, f64)>, f64)> = HashMap::new();
+    for (product_id, movement_type, quantity, cost) in movements {
+        let direction = stock_direction(&movement_type);
+        let (qty, net_cost, layers, last_rate) = state.entry(product_id).or_default();
+        *qty += direction * quantity;
+        *net_cost += direction * cost;
+
+        if direction > 0.0 && quantity > 0.0 {
+            layers.push_back((quantity, cost / quantity));
+            *last_rate = cost / quantity;
+        } else if direction < 0.0 {
+            let mut to_consume = quantity;
+            while to_consume > 0.0 {
+                let Some(front) = layers.front_mut() else {
+                    break;
+                };
+                let used = front.0.min(to_consume);
+                front.0 -= used;
+                to_consume -= used;
+                if front.0 <= f64::EPSILON {
+                    layers.pop_front();
+                }
+            }
+        }
+    }
+
+    Ok(state
+        .into_iter()
+        .map(|(product_id, (qty, net_cost, layers, last_rate))| {
+            let value = match method {
+                ValuationMethod::WeightedAverage if qty.abs() > f64::EPSILON => net_cost,
+                ValuationMethod::WeightedAverage => 0.0,
+                ValuationMethod::Fifo if qty > 0.0 => layers.iter().map(|(q, rate)| q * rate).sum(),
+                ValuationMethod::Fifo => 0.0,
+                ValuationMethod::LastPurchase if qty > 0.0 => qty * last_rate,
+                ValuationMethod::LastPurchase => 0.0,
+            };
+            (product_id, (qty, value))
+        })
+        .collect())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StockValuationSummary {
+    pub as_on_date: String,
+    pub method: String,
+    pub product_count: i64,
+    pub total_value: f64,
+}
+
+/// Total inventory value at cost as on a date, for tying out to the Inventory
+/// asset on the balance sheet. Equals the sum of `stock_value` from
+/// `get_stock_report` under the same method.
+#[tauri::command]
+pub async fn get_stock_valuation_summary(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+    method: Option<String>,
+) -> Result<StockValuationSummary, String> {
+    let pool = registry.active_pool().await?;
+    let method = ValuationMethod::parse(method.as_deref())?;
+    stock_valuation_summary(&pool, as_on_date, method).await
+}
+
+async fn stock_valuation_summary(
+    pool: &sqlx::SqlitePool,
+    as_on_date: String,
+    method: ValuationMethod,
+) -> Result<StockValuationSummary, String> {
+    let values = product_stock_values(pool, Some(&as_on_date), method).await?;
+
+    Ok(StockValuationSummary {
+        as_on_date,
+        method: method.as_str().to_string(),
+        product_count: values
+            .values()
+            .filter(|(qty, _)| qty.abs() > f64::EPSILON)
+            .count() as i64,
+        total_value: values.values().map(|(_, value)| value).sum(),
+    })
+}
+
+// ============= INVENTORY RECONCILIATION =============
+#[derive(Serialize, Deserialize)]
+pub struct InventoryReconciliationRow {
+    pub product_id: String,
+    pub product_code: String,
+    pub product_name: String,
+    pub quantity: f64,
+    /// Weighted-average stock value, as in get_stock_report
+    pub stock_value: f64,
+    /// Net cost of this product's movements on vouchers that post to the
+    /// inventory account
+    pub posted_value: f64,
+    pub difference: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InventoryReconciliation {
+    pub as_on_date: String,
+    pub inventory_account_id: Option<String>,
+    /// Sum of weighted-average stock values
+    pub stock_value: f64,
+    /// Balance of the inventory control account
+    pub ledger_balance: f64,
+    /// stock_value - ledger_balance
+    pub difference: f64,
+    /// Per-product breakdown; only filled when requested with `detail`
+    pub products: Vec<InventoryReconciliationRow>,
+}
+
+/// Diagnostic comparing the stock valuation from stock_movements with the
+/// inventory control account in the ledger as on a date. A non-zero difference
+/// points at vouchers whose stock and journal postings disagree.
+#[tauri::command]
+pub async fn get_inventory_reconciliation(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+    detail: Option<bool>,
+) -> Result<InventoryReconciliation, String> {
+    let pool = registry.active_pool().await?;
+    let values =
+        product_stock_values(&pool, Some(&as_on_date), ValuationMethod::WeightedAverage).await?;
+    let stock_value: f64 = values.values().map(|(_, value)| value).sum();
+
+    let inventory_account_id =
+        super::system_accounts::find_system_account(&pool, "inventory").await?;
+    let ledger_balance: f64 = match &inventory_account_id {
+        Some(account_id) => sqlx::query_scalar(&format!(
+            "SELECT CAST({} AS REAL) FROM chart_of_accounts coa WHERE coa.id = ?",
+            signed_account_balance_sql(true)
+        ))
+        .bind(&as_on_date)
+        .bind(account_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?,
+        None => 0.0,
+    };
+
+    let mut products = Vec::new();
+    if detail.unwrap_or(false) {
+        let posted: Vec<(String, String, String, f64)> = sqlx::query_as(&format!(
+            "SELECT p.id, p.code, p.name,
+                CAST(COALESCE(SUM(
+                    CASE
+                        WHEN sm.movement_type IN ({in_types}) THEN COALESCE(sm.cost_amount, sm.amount, 0)
+                        WHEN sm.movement_type IN ({out_types}) THEN -COALESCE(sm.cost_amount, sm.amount, 0)
+                        ELSE 0
+                    END
+                ), 0) AS REAL) as posted_value
+             FROM products p
+             LEFT JOIN stock_movements sm ON sm.product_id = p.id
+                AND sm.voucher_id IN (
+                    SELECT v.id FROM vouchers v
+                    JOIN journal_entries je ON je.voucher_id = v.id
+                    WHERE je.account_id = ? AND v.deleted_at IS NULL
+                    AND date(v.voucher_date) <= date(?)
+                )
+             WHERE p.deleted_at IS NULL AND COALESCE(p.is_master, 0) = 0
+             GROUP BY p.id
+             ORDER BY p.name ASC",
+            in_types = STOCK_IN_TYPES,
+            out_types = STOCK_OUT_TYPES,
+        ))
+        .bind(inventory_account_id.as_deref().unwrap_or_default())
+        .bind(&as_on_date)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    Ok(qty.unwrap_or(0.0))
+        products = posted
+            .into_iter()
+            .filter_map(|(product_id, product_code, product_name, posted_value)| {
+                let (quantity, stock_value) = values.get(&product_id).copied().unwrap_or_default();
+                let difference = stock_value - posted_value;
+                // Products with no stock and nothing posted have nothing to reconcile
+                if quantity.abs() <= f64::EPSILON && difference.abs() < 0.01 {
+                    return None;
+                }
+                Some(InventoryReconciliationRow {
+                    product_id,
+                    product_code,
+                    product_name,
+                    quantity,
+                    stock_value,
+                    posted_value,
+                    difference,
+                })
+            })
+            .collect();
+    }
+
+    Ok(InventoryReconciliation {
+        as_on_date,
+        inventory_account_id,
+        stock_value,
+        ledger_balance,
+        difference: stock_value - ledger_balance,
+        products,
+    })
 }
 
 // ============= STOCK REPORT =============
@@ -1136,23 +2650,69 @@ pub struct StockSummary {
     pub stock_value: f64,
     pub last_purchase_date: Option<String>,
     pub last_sale_date: Option<String>,
+    /// 0 when the product has no reorder level set
+    pub reorder_level: f64,
 }
 
+/// Stock register as on `as_on_date`. `product_id` narrows it to one product;
+/// `only_below_reorder` keeps only products with a reorder level that stock has
+/// fallen to or below (a purchase-planning list).
 #[tauri::command]
 pub async fn get_stock_report(
     registry: State<'_, Arc<DbRegistry>>,
     group_id: Option<String>,
     as_on_date: String,
+    valuation_method: Option<String>,
+    product_id: Option<String>,
+    only_below_reorder: Option<bool>,
 ) -> Result<Vec<StockSummary>, String> {
     let pool = registry.active_pool().await?;
-    let group_filter = if let Some(gid) = group_id {
-        format!("AND p.group_id = '{}'", gid)
+    let method = ValuationMethod::parse(valuation_method.as_deref())?;
+    stock_report(
+        &pool,
+        group_id.as_deref(),
+        &as_on_date,
+        method,
+        product_id.as_deref(),
+        only_below_reorder.unwrap_or(false),
+    )
+    .await
+}
+
+async fn stock_report(
+    pool: &sqlx::SqlitePool,
+    group_id: Option<&str>,
+    as_on_date: &str,
+    method: ValuationMethod,
+    product_id: Option<&str>,
+    only_below_reorder: bool,
+) -> Result<Vec<StockSummary>, String> {
+    // The SQL below already yields weighted-average figures; other methods are
+    // re-valued from the shared per-product valuation.
+    let revalued = if method == ValuationMethod::WeightedAverage {
+        None
     } else {
-        String::new()
+        Some(product_stock_values(pool, Some(as_on_date), method).await?)
+    };
+    let reorder_filter = if only_below_reorder {
+        "HAVING COALESCE(p.reorder_level, 0) > 0 AND current_stock <= p.reorder_level"
+    } else {
+        ""
     };
 
+    // Every figure below is computed from `dated_movements`, the single set of
+    // live movements on or before as_on_date, so later-dated vouchers can never
+    // leak into quantity, average rate or last purchase/sale dates.
     let query = format!(
         "
+        WITH dated_movements AS (
+            SELECT sm.product_id, sm.movement_type, sm.quantity, sm.amount, sm.cost_amount,
+                   v.voucher_date
+            FROM stock_movements sm
+            JOIN vouchers v ON sm.voucher_id = v.id
+            WHERE date(v.voucher_date) <= date(?)
+            AND v.deleted_at IS NULL
+        )
         SELECT 
             p.id as product_id,
             p.code as product_code,
@@ -1161,58 +2721,31 @@ pub async fn get_stock_report(
             u.symbol as unit_symbol,
             CAST(COALESCE(SUM(
                 CASE 
-                    WHEN v.id IS NOT NULL AND sm.movement_type = 'IN' THEN sm.quantity
-                    WHEN v.id IS NOT NULL AND sm.movement_type = 'OUT' THEN -sm.quantity
+                    WHEN dm.movement_type IN ({in_types}) THEN dm.quantity
+                    WHEN dm.movement_type IN ({out_types}) THEN -dm.quantity
                     ELSE 0
                 END
             ), 0) AS REAL) as current_stock,
-            CAST(COALESCE(
-                (SELECT
-                    SUM(CASE
-                        WHEN sm2.movement_type = 'IN' THEN COALESCE(sm2.cost_amount, sm2.amount)
-                        WHEN sm2.movement_type = 'OUT' THEN -COALESCE(sm2.cost_amount, sm2.amount)
-                        ELSE 0
-                    END) / NULLIF(SUM(CASE
-                        WHEN sm2.movement_type = 'IN' THEN sm2.quantity
-                        WHEN sm2.movement_type = 'OUT' THEN -sm2.quantity
-                        ELSE 0
-                    END), 0)
-                 FROM stock_movements sm2
-                 JOIN vouchers v2 ON sm2.voucher_id = v2.id
-                 WHERE sm2.product_id = p.id 
-                 AND v2.voucher_date <= ?
-                 AND v2.deleted_at IS NULL),
-                0
-            ) AS REAL) as average_rate,
-            (
-                SELECT MAX(v.voucher_date)
-                FROM stock_movements sm3
-                JOIN vouchers v ON sm3.voucher_id = v.id
-                WHERE sm3.product_id = p.id
-                AND sm3.movement_type = 'IN'
-                AND v.voucher_date <= ?
-                AND v.deleted_at IS NULL
-            ) as last_purchase_date,
-            (
-                SELECT MAX(v.voucher_date)
-                FROM stock_movements sm4
-                JOIN vouchers v ON sm4.voucher_id = v.id
-                WHERE sm4.product_id = p.id
-                AND sm4.movement_type = 'OUT'
-                AND v.voucher_date <= ?
-                AND v.deleted_at IS NULL
-            ) as last_sale_date
+            {average_rate} as average_rate,
+            MAX(CASE WHEN dm.movement_type IN ({in_types}) THEN dm.voucher_date END) as last_purchase_date,
+            MAX(CASE WHEN dm.movement_type IN ({out_types}) THEN dm.voucher_date END) as last_sale_date,
+            CAST(COALESCE(p.reorder_level, 0) AS REAL) as reorder_level
         FROM products p
         LEFT JOIN product_groups pg ON p.group_id = pg.id
         JOIN units u ON p.unit_id = u.id
-        LEFT JOIN stock_movements sm ON p.id = sm.product_id
-        LEFT JOIN vouchers v ON sm.voucher_id = v.id AND v.voucher_date <= ? AND v.deleted_at IS NULL
+        LEFT JOIN dated_movements dm ON dm.product_id = p.id
         WHERE p.deleted_at IS NULL
-        AND COALESCE(p.is_master, 0) = 0 {}
+        AND COALESCE(p.is_master, 0) = 0
+        AND (? IS NULL OR p.group_id = ?)
+        AND (? IS NULL OR p.id = ?)
         GROUP BY p.id
+        {reorder_filter}
         ORDER BY p.name ASC
         ",
-        group_filter
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+        average_rate = weighted_average_rate_sql("dm"),
+        reorder_filter = reorder_filter
     );
 
     let rows = sqlx::query_as::<
@@ -1227,20 +2760,34 @@ pub async fn get_stock_report(
             f64,
             Option<String>,
             Option<String>,
+            f64,
         ),
     >(query.as_str())
-    .bind(&as_on_date)
-    .bind(&as_on_date)
-    .bind(&as_on_date)
-    .bind(&as_on_date)
-    .fetch_all(&pool)
+    .bind(as_on_date)
+    .bind(group_id)
+    .bind(group_id)
+    .bind(product_id)
+    .bind(product_id)
+    .fetch_all(pool)
     .await
     .map_err(|e| e.to_string())?;
 
     Ok(rows
         .into_iter()
         .map(
-            |(id, code, name, group, unit, stock, avg_rate, last_purchase, last_sale)| {
+            |(id, code, name, group, unit, stock, avg_rate, last_purchase, last_sale, reorder)| {
+                let (average_rate, stock_value) = match revalued.as_ref() {
+                    Some(values) => {
+                        let value = values.get(&id).map(|(_, v)| *v).unwrap_or(0.0);
+                        let rate = if stock.abs() > f64::EPSILON {
+                            value / stock
+                        } else {
+                            0.0
+                        };
+                        (rate, value)
+                    }
+                    None => (avg_rate, stock * avg_rate),
+                };
                 StockSummary {
                     product_id: id,
                     product_code: code,
@@ -1248,10 +2795,11 @@ pub async fn get_stock_report(
                     group_name: group,
                     unit_symbol: unit,
                     current_stock: stock,
-                    average_rate: avg_rate,
-                    stock_value: stock * avg_rate,
+                    average_rate,
+                    stock_value,
                     last_purchase_date: last_purchase,
                     last_sale_date: last_sale,
+                    reorder_level: reorder,
                 }
             },
         )
@@ -1264,6 +2812,7 @@ pub struct StockMovement {
     pub voucher_no: String,
     pub voucher_type: String,
     pub movement_type: String,
+    pub movement_label: String,
     pub quantity: f64,
     pub rate: f64,
     pub amount: f64,
@@ -1291,23 +2840,25 @@ pub async fn get_stock_movements(
     // Get opening balance if from_date is specified
     let mut opening_balance = 0.0;
     if let Some(ref from) = from_date {
-        let balance: Option<f64> = sqlx::query_scalar(
+        let balance_query = format!(
             "SELECT CAST(COALESCE(SUM(
                 CASE 
-                    WHEN sm.movement_type = 'IN' THEN sm.quantity
-                    WHEN sm.movement_type = 'OUT' THEN -sm.quantity
+                    WHEN sm.movement_type IN ({}) THEN sm.quantity
+                    WHEN sm.movement_type IN ({}) THEN -sm.quantity
                     ELSE 0
                 END
             ), 0) AS REAL)
              FROM stock_movements sm
              JOIN vouchers v ON sm.voucher_id = v.id
              WHERE sm.product_id = ? AND v.voucher_date < ? AND v.deleted_at IS NULL",
-        )
-        .bind(&product_id)
-        .bind(from)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
+            STOCK_IN_TYPES, STOCK_OUT_TYPES
+        );
+        let balance: Option<f64> = sqlx::query_scalar(&balance_query)
+            .bind(&product_id)
+            .bind(from)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
 
         opening_balance = balance.unwrap_or(0.0);
     }
@@ -1350,17 +2901,15 @@ pub async fn get_stock_movements(
         .into_iter()
         .map(
             |(date, voucher_no, voucher_type, movement_type, qty, rate, amt, party)| {
-                if movement_type == "IN" {
-                    running_balance += qty;
-                } else {
-                    running_balance -= qty;
-                }
+                running_balance += stock_direction(&movement_type) * qty;
+                let movement_label = stock_movement_label(&movement_type, &voucher_type);
 
                 StockMovement {
                     date,
                     voucher_no,
                     voucher_type,
                     movement_type,
+                    movement_label,
                     quantity: qty,
                     rate,
                     amount: amt,
@@ -1374,7 +2923,167 @@ pub async fn get_stock_movements(
     Ok(result)
 }
 
+// ============= INVENTORY MOVEMENT SUMMARY =============
+#[derive(Serialize, Deserialize)]
+pub struct InventoryMovementSummary {
+    pub product_id: String,
+    pub product_code: String,
+    pub product_name: String,
+    pub group_name: Option<String>,
+    pub unit_symbol: String,
+    /// Net movement before from_date
+    pub opening_stock: f64,
+    pub period_in: f64,
+    pub period_out: f64,
+    pub closing_stock: f64,
+}
+
+/// One row per product with opening, inward, outward and closing quantities
+/// for the period, for the printed stock register.
+#[tauri::command]
+pub async fn get_inventory_movement_summary(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    group_id: Option<String>,
+) -> Result<Vec<InventoryMovementSummary>, String> {
+    let pool = registry.active_pool().await?;
+    let group_filter = if group_id.is_some() {
+        "AND p.group_id = ?"
+    } else {
+        ""
+    };
+
+    let query = format!(
+        "
+        WITH dated_movements AS (
+            SELECT sm.product_id, sm.movement_type, sm.quantity, v.voucher_date
+            FROM stock_movements sm
+            JOIN vouchers v ON sm.voucher_id = v.id
+            WHERE date(v.voucher_date) <= date(?)
+            AND v.deleted_at IS NULL
+        )
+        SELECT
+            p.id as product_id,
+            p.code as product_code,
+            p.name as product_name,
+            pg.name as group_name,
+            u.symbol as unit_symbol,
+            CAST(COALESCE(SUM(CASE
+                WHEN date(dm.voucher_date) < date(?) AND dm.movement_type IN ({in_types}) THEN dm.quantity
+                WHEN date(dm.voucher_date) < date(?) AND dm.movement_type IN ({out_types}) THEN -dm.quantity
+                ELSE 0
+            END), 0) AS REAL) as opening_stock,
+            CAST(COALESCE(SUM(CASE
+                WHEN date(dm.voucher_date) >= date(?) AND dm.movement_type IN ({in_types}) THEN dm.quantity
+                ELSE 0
+            END), 0) AS REAL) as period_in,
+            CAST(COALESCE(SUM(CASE
+                WHEN date(dm.voucher_date) >= date(?) AND dm.movement_type IN ({out_types}) THEN dm.quantity
+                ELSE 0
+            END), 0) AS REAL) as period_out
+        FROM products p
+        LEFT JOIN product_groups pg ON p.group_id = pg.id
+        JOIN units u ON p.unit_id = u.id
+        LEFT JOIN dated_movements dm ON dm.product_id = p.id
+        WHERE p.deleted_at IS NULL
+        AND COALESCE(p.is_master, 0) = 0 {group_filter}
+        GROUP BY p.id
+        ORDER BY p.name ASC
+        ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+        group_filter = group_filter
+    );
+
+    let mut rows_query = sqlx::query_as::<
+        _,
+        (
+            String,
+            String,
+            String,
+            Option<String>,
+            String,
+            f64,
+            f64,
+            f64,
+        ),
+    >(query.as_str())
+    .bind(&to_date)
+    .bind(&from_date)
+    .bind(&from_date)
+    .bind(&from_date)
+    .bind(&from_date);
+    if let Some(gid) = &group_id {
+        rows_query = rows_query.bind(gid);
+    }
+    let rows = rows_query
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, code, name, group, unit, opening, period_in, period_out)| {
+                InventoryMovementSummary {
+                    product_id: id,
+                    product_code: code,
+                    product_name: name,
+                    group_name: group,
+                    unit_symbol: unit,
+                    opening_stock: opening,
+                    period_in,
+                    period_out,
+                    closing_stock: opening + period_in - period_out,
+                }
+            },
+        )
+        .collect())
+}
+
 // ============= DASHBOARD =============
+/// Sum of party outstanding balances in `account_group` as of `as_on_date`, on
+/// the same basis as `get_party_outstanding`: opening balance plus journal
+/// postings, signed by account type, skipping parties within 0.01 of zero.
+async fn total_party_outstanding(
+    pool: &sqlx::SqlitePool,
+    account_group: &str,
+    as_on_date: &str,
+) -> Result<f64, String> {
+    let total: Option<f64> = sqlx::query_scalar(
+        "SELECT CAST(COALESCE(SUM(outstanding_amount), 0) AS REAL)
+         FROM (
+            SELECT
+                CASE
+                    WHEN coa.account_type = 'Asset' THEN
+                        (CASE WHEN coa.opening_balance_type = 'Dr' THEN coa.opening_balance ELSE -coa.opening_balance END) +
+                        COALESCE(je_stats.net_dr_cr, 0)
+                    ELSE
+                        (CASE WHEN coa.opening_balance_type = 'Cr' THEN coa.opening_balance ELSE -coa.opening_balance END) -
+                        COALESCE(je_stats.net_dr_cr, 0)
+                END as outstanding_amount
+            FROM chart_of_accounts coa
+            LEFT JOIN (
+                SELECT je.account_id, SUM(je.debit - je.credit) as net_dr_cr
+                FROM journal_entries je
+                JOIN vouchers v ON je.voucher_id = v.id
+                WHERE v.voucher_date <= ? AND v.deleted_at IS NULL
+                GROUP BY je.account_id
+            ) je_stats ON coa.id = je_stats.account_id
+            WHERE coa.account_group = ? AND coa.deleted_at IS NULL
+         )
+         WHERE ABS(outstanding_amount) > 0.01",
+    )
+    .bind(as_on_date)
+    .bind(account_group)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(total.unwrap_or(0.0))
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DashboardMetrics {
     pub total_revenue: f64,
@@ -1387,128 +3096,76 @@ pub struct DashboardMetrics {
     pub payables: f64,
     pub revenue_growth: f64,
     pub profit_growth: f64,
+    /// Fiscal-year-to-date figures, from the FY start containing to_date
+    pub fy_start_date: String,
+    pub ytd_revenue: f64,
+    pub ytd_expenses: f64,
+    pub ytd_profit: f64,
 }
 
-#[tauri::command]
-pub async fn get_dashboard_metrics(
-    registry: State<'_, Arc<DbRegistry>>,
-    from_date: String,
-    to_date: String,
-) -> Result<DashboardMetrics, String> {
-    let pool = registry.active_pool().await?;
-    // Get revenue (credits - debits for Income accounts)
-    let revenue: Option<f64> = sqlx::query_scalar(
-        "SELECT CAST(COALESCE(SUM(je.credit - je.debit), 0.0) AS REAL)
+/// (revenue, expenses) from Income/Expense postings dated within the range,
+/// ignoring year-end closing vouchers.
+async fn revenue_and_expenses(
+    pool: &sqlx::SqlitePool,
+    from_date: &str,
+    to_date: &str,
+) -> Result<(f64, f64), String> {
+    // Revenue is credits - debits on Income, expenses debits - credits on Expense
+    let (revenue, expenses): (f64, f64) = sqlx::query_as(
+        "SELECT
+            CAST(COALESCE(SUM(CASE WHEN coa.account_type = 'Income' THEN je.credit - je.debit ELSE 0 END), 0.0) AS REAL),
+            CAST(COALESCE(SUM(CASE WHEN coa.account_type = 'Expense' THEN je.debit - je.credit ELSE 0 END), 0.0) AS REAL)
          FROM journal_entries je
          JOIN chart_of_accounts coa ON je.account_id = coa.id
          JOIN vouchers v ON je.voucher_id = v.id
-         WHERE coa.account_type = 'Income'
+         WHERE coa.account_type IN ('Income', 'Expense')
          AND v.voucher_date >= ? AND v.voucher_date <= ?
-         AND v.deleted_at IS NULL",
+         AND v.deleted_at IS NULL
+         AND v.voucher_type != 'year_end_close'",
     )
-    .bind(&from_date)
-    .bind(&to_date)
-    .fetch_optional(&pool)
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_one(pool)
     .await
     .map_err(|e| e.to_string())?;
 
-    // Get expenses (debits - credits for Expense accounts)
-    let expenses: Option<f64> = sqlx::query_scalar(
-        "SELECT CAST(COALESCE(SUM(je.debit - je.credit), 0.0) AS REAL)
-         FROM journal_entries je
-         JOIN chart_of_accounts coa ON je.account_id = coa.id
-         JOIN vouchers v ON je.voucher_id = v.id
-         WHERE coa.account_type = 'Expense'
-         AND v.voucher_date >= ? AND v.voucher_date <= ?
-         AND v.deleted_at IS NULL",
-    )
-    .bind(&from_date)
-    .bind(&to_date)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    Ok((revenue, expenses))
+}
 
-    let total_revenue = revenue.unwrap_or(0.0);
-    let total_expenses = expenses.unwrap_or(0.0);
+#[tauri::command]
+pub async fn get_dashboard_metrics(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+) -> Result<DashboardMetrics, String> {
+    let pool = registry.active_pool().await?;
+    let (total_revenue, total_expenses) = revenue_and_expenses(&pool, &from_date, &to_date).await?;
 
     // Get stock value using stored inventory cost (same as Stock Report)
-    let stock_value: Option<f64> = sqlx::query_scalar(
-        "SELECT CAST(COALESCE(SUM(
-            COALESCE((
-                SELECT SUM(CASE
-                    WHEN sm.movement_type = 'IN' THEN COALESCE(sm.cost_amount, sm.amount)
-                    WHEN sm.movement_type = 'OUT' THEN -COALESCE(sm.cost_amount, sm.amount)
-                    ELSE 0
-                END)
-                FROM stock_movements sm
-                JOIN vouchers v ON sm.voucher_id = v.id
-                WHERE sm.product_id = p.id AND v.deleted_at IS NULL
-            ), 0)
-        ), 0) AS REAL)
-         FROM products p
-         WHERE p.deleted_at IS NULL
-         AND COALESCE(p.is_master, 0) = 0",
-    )
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    let stock_value: f64 = product_stock_values(&pool, None, ValuationMethod::WeightedAverage)
+        .await?
+        .values()
+        .map(|(_, value)| value)
+        .sum();
 
     // Get cash balance (sum of cash/bank accounts)
-    let cash_balance: Option<f64> = sqlx::query_scalar(
-        "SELECT CAST(COALESCE(SUM(
-            CASE
-                WHEN coa.opening_balance_type = 'Dr' THEN coa.opening_balance ELSE -coa.opening_balance
-            END +
-            COALESCE((SELECT SUM(je.debit - je.credit)
-                      FROM journal_entries je
-                      JOIN vouchers v ON je.voucher_id = v.id
-                      WHERE je.account_id = coa.id AND v.deleted_at IS NULL), 0)
-        ), 0) AS REAL)
-         FROM chart_of_accounts coa
-         WHERE coa.account_group IN ('Cash', 'Bank Accounts')
-         AND coa.deleted_at IS NULL",
-    )
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    // Get receivables
-    let receivables: Option<f64> = sqlx::query_scalar(
-        "SELECT CAST(COALESCE(SUM(
-            CASE
-                WHEN coa.opening_balance_type = 'Dr' THEN coa.opening_balance ELSE -coa.opening_balance
-            END +
-            COALESCE((SELECT SUM(je.debit - je.credit)
-                      FROM journal_entries je
-                      JOIN vouchers v ON je.voucher_id = v.id
-                      WHERE je.account_id = coa.id AND v.deleted_at IS NULL), 0)
-        ), 0) AS REAL)
+    let cash_balance: Option<f64> = sqlx::query_scalar(&format!(
+        "SELECT CAST(COALESCE(SUM({}), 0) AS REAL)
          FROM chart_of_accounts coa
-         WHERE coa.account_group = 'Accounts Receivable'
+         WHERE coa.account_group IN ({})
          AND coa.deleted_at IS NULL",
-    )
+        signed_account_balance_sql(false),
+        CASH_BANK_GROUPS
+    ))
     .fetch_optional(&pool)
     .await
     .map_err(|e| e.to_string())?;
 
-    // Get payables
-    let payables: Option<f64> = sqlx::query_scalar(
-        "SELECT CAST(COALESCE(SUM(
-            CASE
-                WHEN coa.opening_balance_type = 'Cr' THEN coa.opening_balance ELSE -coa.opening_balance
-            END +
-            COALESCE((SELECT SUM(je.credit - je.debit)
-                      FROM journal_entries je
-                      JOIN vouchers v ON je.voucher_id = v.id
-                      WHERE je.account_id = coa.id AND v.deleted_at IS NULL), 0)
-        ), 0) AS REAL)
-         FROM chart_of_accounts coa
-         WHERE coa.account_group = 'Accounts Payable'
-         AND coa.deleted_at IS NULL",
-    )
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| e.to_string())?;
+    // Receivables/payables use the journal-balance basis shared with
+    // get_party_outstanding (opening + postings up to to_date, per-party sign by
+    // account type), so the dashboard cards equal the outstanding report totals.
+    let receivables = total_party_outstanding(&pool, "Accounts Receivable", &to_date).await?;
+    let payables = total_party_outstanding(&pool, "Accounts Payable", &to_date).await?;
 
     // Calculate previous period for growth
     let prev_from =
@@ -1526,7 +3183,8 @@ pub async fn get_dashboard_metrics(
          JOIN vouchers v ON je.voucher_id = v.id
          WHERE coa.account_type = 'Income'
          AND v.voucher_date >= ? AND v.voucher_date <= ?
-         AND v.deleted_at IS NULL",
+         AND v.deleted_at IS NULL
+         AND v.voucher_type != 'year_end_close'",
     )
     .bind(prev_period_from.to_string())
     .bind(prev_period_to.to_string())
@@ -1552,20 +3210,90 @@ pub async fn get_dashboard_metrics(
     };
     let profit_growth = revenue_growth; // Simplified for now
 
+    let fy_start = super::company::fiscal_year_start_for(
+        prev_to,
+        super::company::financial_year_start_month(&pool).await?,
+    )
+    .to_string();
+    let (ytd_revenue, ytd_expenses) = revenue_and_expenses(&pool, &fy_start, &to_date).await?;
+
     Ok(DashboardMetrics {
         total_revenue,
         total_expenses,
         net_profit,
         profit_margin,
-        stock_value: stock_value.unwrap_or(0.0),
+        stock_value,
         cash_balance: cash_balance.unwrap_or(0.0),
-        receivables: receivables.unwrap_or(0.0),
-        payables: payables.unwrap_or(0.0),
+        receivables,
+        payables,
         revenue_growth,
         profit_growth,
+        fy_start_date: fy_start,
+        ytd_revenue,
+        ytd_expenses,
+        ytd_profit: ytd_revenue - ytd_expenses,
     })
 }
 
+/// Account groups whose balances count as cash in hand or at bank.
+const CASH_BANK_GROUPS: &str = "'Cash', 'Bank Account'";
+
+/// Signed (Dr positive) balance of the account aliased `coa`: its opening
+/// balance plus postings on live vouchers. With `up_to_date`, only postings
+/// dated on or before a bound `?` count.
+fn signed_account_balance_sql(up_to_date: bool) -> String {
+    format!(
+        "CASE
+            WHEN coa.opening_balance_type = 'Dr' THEN COALESCE(coa.opening_balance, 0)
+            ELSE -COALESCE(coa.opening_balance, 0)
+        END +
+        COALESCE((SELECT SUM(je.debit - je.credit)
+                  FROM journal_entries je
+                  JOIN vouchers v ON je.voucher_id = v.id
+                  WHERE je.account_id = coa.id AND v.deleted_at IS NULL{}), 0)",
+        if up_to_date {
+            " AND v.voucher_date <= ?"
+        } else {
+            ""
+        }
+    )
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct CashBankBalance {
+    pub account_id: String,
+    pub account_name: String,
+    pub account_group: String,
+    pub balance: f64,
+}
+
+/// Live balance of each active cash/bank account as on `as_on_date`, the
+/// per-account breakdown of the dashboard's cash balance.
+#[tauri::command]
+pub async fn get_cash_bank_balances(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+) -> Result<Vec<CashBankBalance>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, CashBankBalance>(&format!(
+        "SELECT
+            coa.id as account_id,
+            coa.account_name,
+            coa.account_group,
+            CAST({} AS REAL) as balance
+         FROM chart_of_accounts coa
+         WHERE coa.account_group IN ({})
+           AND coa.is_active = 1 AND coa.deleted_at IS NULL
+         ORDER BY coa.account_code ASC",
+        signed_account_balance_sql(true),
+        CASH_BANK_GROUPS
+    ))
+    .bind(&as_on_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RevenueTrend {
     pub date: String,
@@ -1595,7 +3323,8 @@ pub async fn get_revenue_trend(
              JOIN vouchers v ON je.voucher_id = v.id
              WHERE coa.account_type = 'Income'
              AND v.voucher_date = ?
-             AND v.deleted_at IS NULL",
+             AND v.deleted_at IS NULL
+             AND v.voucher_type != 'year_end_close'",
         )
         .bind(&date_str)
         .fetch_optional(&pool)
@@ -1609,7 +3338,8 @@ pub async fn get_revenue_trend(
              JOIN vouchers v ON je.voucher_id = v.id
              WHERE coa.account_type = 'Expense'
              AND v.voucher_date = ?
-             AND v.deleted_at IS NULL",
+             AND v.deleted_at IS NULL
+             AND v.voucher_type != 'year_end_close'",
         )
         .bind(&date_str)
         .fetch_optional(&pool)
@@ -1635,6 +3365,8 @@ pub struct TopProduct {
     pub total_revenue: f64,
 }
 
+/// Top sellers by revenue net of sales returns within the period. IN rows on a
+/// sales invoice are items returned on the same bill and count as returns.
 #[tauri::command]
 pub async fn get_top_products(
     registry: State<'_, Arc<DbRegistry>>,
@@ -1643,17 +3375,29 @@ pub async fn get_top_products(
     to_date: String,
 ) -> Result<Vec<TopProduct>, String> {
     let pool = registry.active_pool().await?;
-    let query = "
+    top_products(&pool, limit, &from_date, &to_date).await
+}
+
+async fn top_products(
+    pool: &sqlx::SqlitePool,
+    limit: i32,
+    from_date: &str,
+    to_date: &str,
+) -> Result<Vec<TopProduct>, String> {
+    let query = format!(
+        "
         SELECT
             COALESCE(parent.name, p.name) as product_name,
-            CAST(SUM(sm.quantity) AS REAL) as total_quantity,
-            CAST(SUM(sm.amount) AS REAL) as total_revenue
+            CAST(SUM(CASE WHEN sm.movement_type IN ({out_types}) THEN sm.quantity ELSE -sm.quantity END) AS REAL) as total_quantity,
+            CAST(SUM(CASE WHEN sm.movement_type IN ({out_types}) THEN sm.amount ELSE -sm.amount END) AS REAL) as total_revenue
         FROM stock_movements sm
         JOIN products p ON sm.product_id = p.id
         LEFT JOIN products parent ON p.parent_product_id = parent.id
         JOIN vouchers v ON sm.voucher_id = v.id
-        WHERE sm.movement_type = 'OUT'
-        AND v.voucher_type = 'sales_invoice'
+        WHERE (
+            (v.voucher_type = 'sales_invoice' AND sm.movement_type IN ({out_types}, {in_types}))
+            OR (v.voucher_type = 'sales_return' AND sm.movement_type IN ({in_types}))
+        )
         AND v.voucher_date >= ? AND v.voucher_date <= ?
         AND v.deleted_at IS NULL
         AND p.deleted_at IS NULL
@@ -1661,17 +3405,104 @@ pub async fn get_top_products(
         GROUP BY COALESCE(parent.id, p.id), COALESCE(parent.name, p.name)
         ORDER BY total_revenue DESC
         LIMIT ?
+    ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+    );
+
+    sqlx::query_as::<_, TopProduct>(&query)
+        .bind(from_date)
+        .bind(to_date)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct TopParty {
+    pub party_id: String,
+    pub party_name: String,
+    pub invoice_count: i64,
+    pub total_amount: f64,
+}
+
+/// Parties ranked by posted invoice value of `voucher_type` within the period.
+async fn top_parties(
+    pool: &sqlx::SqlitePool,
+    voucher_type: &str,
+    party_type: &str,
+    limit: i32,
+    from_date: &str,
+    to_date: &str,
+) -> Result<Vec<TopParty>, String> {
+    let query = "
+        SELECT
+            v.party_id,
+            coa.account_name as party_name,
+            COUNT(v.id) as invoice_count,
+            CAST(SUM(COALESCE(v.base_grand_total, v.grand_total, v.total_amount, 0.0)) AS REAL) as total_amount
+        FROM vouchers v
+        JOIN chart_of_accounts coa ON v.party_id = coa.id
+        WHERE v.voucher_type = ?
+        AND v.party_type = ?
+        AND v.voucher_date >= ? AND v.voucher_date <= ?
+        AND v.deleted_at IS NULL
+        AND COALESCE(v.status, 'posted') = 'posted'
+        GROUP BY v.party_id, coa.account_name
+        ORDER BY total_amount DESC
+        LIMIT ?
     ";
 
-    sqlx::query_as::<_, TopProduct>(query)
-        .bind(&from_date)
-        .bind(&to_date)
+    sqlx::query_as::<_, TopParty>(query)
+        .bind(voucher_type)
+        .bind(party_type)
+        .bind(from_date)
+        .bind(to_date)
         .bind(limit)
-        .fetch_all(&pool)
+        .fetch_all(pool)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_top_customers(
+    registry: State<'_, Arc<DbRegistry>>,
+    limit: i32,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<TopParty>, String> {
+    let pool = registry.active_pool().await?;
+    top_parties(
+        &pool,
+        "sales_invoice",
+        "customer",
+        limit,
+        &from_date,
+        &to_date,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_top_suppliers(
+    registry: State<'_, Arc<DbRegistry>>,
+    limit: i32,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<TopParty>, String> {
+    let pool = registry.active_pool().await?;
+    top_parties(
+        &pool,
+        "purchase_invoice",
+        "supplier",
+        limit,
+        &from_date,
+        &to_date,
+    )
+    .await
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CashFlowSummary {
     pub date: String,
@@ -1753,26 +3584,35 @@ pub struct StockAlert {
     pub product_id: String,
     pub product_name: String,
     pub current_stock: f64,
+    /// Level the stock was compared against: the product's own reorder_level, or the threshold
+    pub reorder_level: f64,
     pub unit_symbol: String,
 }
 
+/// Lists products running low. Products with their own reorder_level are compared against
+/// it; `threshold` only applies to products without one.
 #[tauri::command]
 pub async fn get_stock_alerts(
     registry: State<'_, Arc<DbRegistry>>,
     threshold: f64,
 ) -> Result<Vec<StockAlert>, String> {
     let pool = registry.active_pool().await?;
-    let query = "
+    let query = format!(
+        "
         SELECT
             p.id as product_id,
             p.name as product_name,
             CAST(COALESCE(SUM(
                 CASE
-                    WHEN v.id IS NOT NULL AND sm.movement_type = 'IN' THEN sm.quantity
-                    WHEN v.id IS NOT NULL AND sm.movement_type = 'OUT' THEN -sm.quantity
+                    WHEN v.id IS NOT NULL AND sm.movement_type IN ({in_types}) THEN sm.quantity
+                    WHEN v.id IS NOT NULL AND sm.movement_type IN ({out_types}) THEN -sm.quantity
                     ELSE 0
                 END
             ), 0) AS REAL) as current_stock,
+            CAST(CASE
+                WHEN COALESCE(p.reorder_level, 0) > 0 THEN p.reorder_level
+                ELSE ?
+            END AS REAL) as reorder_level,
             u.symbol as unit_symbol
         FROM products p
         JOIN units u ON p.unit_id = u.id
@@ -1785,12 +3625,15 @@ pub async fn get_stock_alerts(
         AND p.parent_product_id IS NULL
         AND COALESCE(p.is_master, 0) = 0
         GROUP BY p.id
-        HAVING current_stock < ? AND current_stock >= 0
+        HAVING current_stock < reorder_level AND current_stock >= 0
         ORDER BY current_stock ASC
         LIMIT 10
-    ";
+    ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+    );
 
-    sqlx::query_as::<_, StockAlert>(query)
+    sqlx::query_as::<_, StockAlert>(&query)
         .bind(threshold)
         .fetch_all(&pool)
         .await
@@ -1837,7 +3680,7 @@ pub async fn get_recent_activity(
                 )
                 ELSE coa.account_name
             END as party_name,
-            CAST(COALESCE(v.grand_total, v.total_amount, 0.0) AS REAL) as amount
+            CAST(COALESCE(v.base_grand_total, v.grand_total, v.total_amount, 0.0) AS REAL) as amount
         FROM vouchers v
         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
         WHERE v.deleted_at IS NULL
@@ -1864,14 +3707,15 @@ pub async fn get_product_groups_distribution(
     registry: State<'_, Arc<DbRegistry>>,
 ) -> Result<Vec<ProductGroupData>, String> {
     let pool = registry.active_pool().await?;
-    let query = "
+    let query = format!(
+        "
         SELECT 
             COALESCE(pg.name, 'Ungrouped') as group_name,
             COUNT(DISTINCT p.id) as product_count,
             CAST(COALESCE(SUM(
                 (SELECT COALESCE(SUM(CASE
-                    WHEN sm.movement_type = 'IN' THEN COALESCE(sm.cost_amount, sm.amount)
-                    WHEN sm.movement_type = 'OUT' THEN -COALESCE(sm.cost_amount, sm.amount)
+                    WHEN sm.movement_type IN ({in_types}) THEN COALESCE(sm.cost_amount, sm.amount)
+                    WHEN sm.movement_type IN ({out_types}) THEN -COALESCE(sm.cost_amount, sm.amount)
                     ELSE 0
                  END), 0) FROM stock_movements sm
                  JOIN vouchers v ON sm.voucher_id = v.id
@@ -1884,9 +3728,12 @@ pub async fn get_product_groups_distribution(
         AND COALESCE(p.is_master, 0) = 0
         GROUP BY pg.id, pg.name
         ORDER BY total_stock_value DESC
-    ";
+    ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+    );
 
-    sqlx::query_as::<_, ProductGroupData>(query)
+    sqlx::query_as::<_, ProductGroupData>(&query)
         .fetch_all(&pool)
         .await
         .map_err(|e| e.to_string())
@@ -1940,7 +3787,8 @@ pub async fn get_product_profit_report(
     // 1. Build and execute main profit query using WAC (Weighted Average Cost from IN movements)
     // WAC per unit = SUM(cost_amount from purchase IN movements) / SUM(quantity from purchase IN movements)
     // Actual cost for the period = WAC * net_qty_sold
-    let mut query_str = String::from("
+    let mut query_str = format!(
+        "
         SELECT
             p.id as product_id,
             p.code as product_code,
@@ -1950,24 +3798,24 @@ pub async fn get_product_profit_report(
             -- Net qty sold (sales OUT minus returns IN)
             CAST(COALESCE(SUM(
                 CASE
-                    WHEN sm.movement_type = 'OUT' THEN sm.quantity
-                    WHEN sm.movement_type = 'IN' THEN -sm.quantity
+                    WHEN sm.movement_type IN ({out_types}) THEN sm.quantity
+                    WHEN sm.movement_type IN ({in_types}) THEN -sm.quantity
                     ELSE 0
                 END
             ), 0) AS REAL) as qty_sold,
             -- Total revenue (sales amount minus return amount)
             CAST(COALESCE(SUM(
                 CASE
-                    WHEN sm.movement_type = 'OUT' THEN sm.amount
-                    WHEN sm.movement_type = 'IN' THEN -sm.amount
+                    WHEN sm.movement_type IN ({out_types}) THEN sm.amount
+                    WHEN sm.movement_type IN ({in_types}) THEN -sm.amount
                     ELSE 0
                 END
             ), 0) AS REAL) as total_revenue,
             -- Total cost (sum of cost_amount from sales OUT minus returns IN)
             CAST(COALESCE(SUM(
                 CASE
-                    WHEN sm.movement_type = 'OUT' THEN COALESCE(sm.cost_amount, 0)
-                    WHEN sm.movement_type = 'IN' THEN -COALESCE(sm.cost_amount, 0)
+                    WHEN sm.movement_type IN ({out_types}) THEN COALESCE(sm.cost_amount, 0)
+                    WHEN sm.movement_type IN ({in_types}) THEN -COALESCE(sm.cost_amount, 0)
                     ELSE 0
                 END
             ), 0) AS REAL) as total_cost
@@ -1977,13 +3825,16 @@ pub async fn get_product_profit_report(
         LEFT JOIN product_groups pg ON p.group_id = pg.id
         JOIN units u ON p.unit_id = u.id
         WHERE (
-            (v.voucher_type = 'sales_invoice' AND sm.movement_type = 'OUT')
-            OR (v.voucher_type = 'sales_return' AND sm.movement_type = 'IN')
+            (v.voucher_type = 'sales_invoice' AND sm.movement_type IN ({out_types}, {in_types}))
+            OR (v.voucher_type = 'sales_return' AND sm.movement_type IN ({in_types}))
         )
           AND v.voucher_date >= ? AND v.voucher_date <= ?
           AND v.deleted_at IS NULL
           AND p.deleted_at IS NULL
-    ");
+    ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+    );
 
     if group_id.is_some() {
         query_str.push_str(" AND p.group_id = ?");
@@ -2039,7 +3890,8 @@ pub async fn get_product_profit_invoices(
     to_date: String,
 ) -> Result<Vec<ProductProfitInvoiceRow>, String> {
     let pool = registry.active_pool().await?;
-    let query = "
+    let query = format!(
+        "
         SELECT
             v.id as voucher_id,
             v.voucher_no,
@@ -2052,27 +3904,30 @@ pub async fn get_product_profit_invoices(
                 (SELECT account_name FROM chart_of_accounts WHERE id = v.account_id),
                 'Cash/Bank Account'
             ) as party_name,
-            CAST(CASE WHEN sm.movement_type = 'OUT' THEN sm.quantity ELSE -sm.quantity END AS REAL) as qty_sold,
+            CAST(CASE WHEN sm.movement_type IN ({out_types}) THEN sm.quantity ELSE -sm.quantity END AS REAL) as qty_sold,
             u.symbol as unit_symbol,
             CAST(sm.rate AS REAL) as rate,
-            CAST(CASE WHEN sm.movement_type = 'OUT' THEN sm.amount ELSE -sm.amount END AS REAL) as total_revenue,
+            CAST(CASE WHEN sm.movement_type IN ({out_types}) THEN sm.amount ELSE -sm.amount END AS REAL) as total_revenue,
             CAST(COALESCE(sm.cost_rate, 0) AS REAL) as cost_rate,
-            CAST(CASE WHEN sm.movement_type = 'OUT' THEN COALESCE(sm.cost_amount, 0) ELSE -COALESCE(sm.cost_amount, 0) END AS REAL) as total_cost
+            CAST(CASE WHEN sm.movement_type IN ({out_types}) THEN COALESCE(sm.cost_amount, 0) ELSE -COALESCE(sm.cost_amount, 0) END AS REAL) as total_cost
         FROM stock_movements sm
         JOIN vouchers v ON sm.voucher_id = v.id
         JOIN products p ON sm.product_id = p.id
         JOIN units u ON p.unit_id = u.id
         WHERE sm.product_id = ?
           AND (
-            (v.voucher_type = 'sales_invoice' AND sm.movement_type = 'OUT')
-            OR (v.voucher_type = 'sales_return' AND sm.movement_type = 'IN')
+            (v.voucher_type = 'sales_invoice' AND sm.movement_type IN ({out_types}, {in_types}))
+            OR (v.voucher_type = 'sales_return' AND sm.movement_type IN ({in_types}))
           )
           AND v.voucher_date >= ? AND v.voucher_date <= ?
           AND v.deleted_at IS NULL
         ORDER BY v.voucher_date DESC, v.voucher_no DESC
-    ";
+    ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+    );
 
-    let rows = sqlx::query_as::<_, (String, String, String, String, String, f64, String, f64, f64, f64, f64)>(query)
+    let rows = sqlx::query_as::<_, (String, String, String, String, String, f64, String, f64, f64, f64, f64)>(&query)
         .bind(&product_id)
         .bind(&from_date)
         .bind(&to_date)
@@ -2104,3 +3959,851 @@ pub async fn get_product_profit_invoices(
         .collect())
 }
 
+// ============= PRODUCT PROFITABILITY =============
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProductProfitability {
+    pub product_id: String,
+    pub product_code: String,
+    pub product_name: String,
+    pub qty_sold: f64,
+    pub revenue: f64,
+    pub cogs: f64,
+    pub gross_profit: f64,
+    pub margin_percent: f64,
+}
+
+/// Gross margin per product from sales invoices in the period, net of items
+/// returned on the same bill. Unlike get_product_profit_report, which uses the
+/// cost stamped on each movement, COGS here re-values every sale at the
+/// weighted-average cost of the product's other movements dated on or before
+/// the sale.
+#[tauri::command]
+pub async fn get_product_profitability(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<ProductProfitability>, String> {
+    let pool = registry.active_pool().await?;
+    let query = format!(
+        "
+        WITH sales AS (
+            SELECT sm.id, sm.product_id,
+                   CASE WHEN sm.movement_type IN ({in_types}) THEN -sm.quantity ELSE sm.quantity END as quantity,
+                   CASE WHEN sm.movement_type IN ({in_types}) THEN -sm.amount ELSE sm.amount END as amount,
+                   v.voucher_date
+            FROM stock_movements sm
+            JOIN vouchers v ON sm.voucher_id = v.id
+            WHERE v.voucher_type = 'sales_invoice'
+              AND sm.movement_type IN ({out_types}, {in_types})
+              AND v.voucher_date >= ? AND v.voucher_date <= ?
+              AND v.deleted_at IS NULL
+        ),
+        costed AS (
+            SELECT s.product_id, s.quantity, s.amount,
+                   s.quantity * COALESCE((
+                       SELECT {average_rate}
+                       FROM stock_movements dm
+                       JOIN vouchers dv ON dm.voucher_id = dv.id
+                       WHERE dm.product_id = s.product_id
+                         AND dm.id != s.id
+                         AND dv.deleted_at IS NULL
+                         AND date(dv.voucher_date) <= date(s.voucher_date)
+                   ), 0) as cogs
+            FROM sales s
+        )
+        SELECT
+            p.id as product_id,
+            p.code as product_code,
+            p.name as product_name,
+            CAST(SUM(c.quantity) AS REAL) as qty_sold,
+            CAST(SUM(c.amount) AS REAL) as revenue,
+            CAST(SUM(c.cogs) AS REAL) as cogs,
+            CAST(SUM(c.amount) - SUM(c.cogs) AS REAL) as gross_profit,
+            CAST(CASE
+                WHEN SUM(c.amount) != 0 THEN (SUM(c.amount) - SUM(c.cogs)) * 100.0 / SUM(c.amount)
+                ELSE 0
+            END AS REAL) as margin_percent
+        FROM costed c
+        JOIN products p ON c.product_id = p.id
+        WHERE p.deleted_at IS NULL
+        GROUP BY p.id
+        ORDER BY gross_profit DESC, p.name ASC
+        ",
+        in_types = STOCK_IN_TYPES,
+        out_types = STOCK_OUT_TYPES,
+        average_rate = weighted_average_rate_sql("dm")
+    );
+
+    sqlx::query_as::<_, ProductProfitability>(&query)
+        .bind(&from_date)
+        .bind(&to_date)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============= SALES / PURCHASE REGISTER =============
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct RegisterRow {
+    pub voucher_id: String,
+    pub voucher_date: String,
+    pub voucher_no: String,
+    pub party_name: Option<String>,
+    pub party_gstin: Option<String>,
+    /// Invoice value after line and bill discounts, before tax
+    pub taxable_value: f64,
+    pub cgst_amount: f64,
+    pub sgst_amount: f64,
+    pub igst_amount: f64,
+    pub tax_amount: f64,
+    pub round_off_amount: f64,
+    pub grand_total: f64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct RegisterTotals {
+    pub taxable_value: f64,
+    pub cgst_amount: f64,
+    pub sgst_amount: f64,
+    pub igst_amount: f64,
+    pub tax_amount: f64,
+    pub round_off_amount: f64,
+    pub grand_total: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InvoiceRegister {
+    pub rows: Vec<RegisterRow>,
+    pub totals: RegisterTotals,
+}
+
+/// One row per live invoice of `voucher_type` dated within the period, oldest first,
+/// with column totals. Amounts are converted to the base currency.
+async fn invoice_register(
+    pool: &sqlx::SqlitePool,
+    voucher_type: &str,
+    from_date: &str,
+    to_date: &str,
+    branch_id: Option<&str>,
+) -> Result<InvoiceRegister, String> {
+    let rows: Vec<RegisterRow> = sqlx::query_as(
+        "SELECT
+            v.id as voucher_id,
+            v.voucher_date,
+            v.voucher_no,
+            coa.account_name as party_name,
+            coa.gstin as party_gstin,
+            CAST(ROUND((COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0)) * COALESCE(v.exchange_rate, 1.0), 2) AS REAL) as taxable_value,
+            CAST(ROUND(COALESCE(SUM(vi.cgst_amount), 0) * COALESCE(v.exchange_rate, 1.0), 2) AS REAL) as cgst_amount,
+            CAST(ROUND(COALESCE(SUM(vi.sgst_amount), 0) * COALESCE(v.exchange_rate, 1.0), 2) AS REAL) as sgst_amount,
+            CAST(ROUND(COALESCE(SUM(vi.igst_amount), 0) * COALESCE(v.exchange_rate, 1.0), 2) AS REAL) as igst_amount,
+            CAST(ROUND(COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) * COALESCE(v.exchange_rate, 1.0), 2) AS REAL) as tax_amount,
+            CAST(ROUND(COALESCE(v.round_off_amount, 0) * COALESCE(v.exchange_rate, 1.0), 2) AS REAL) as round_off_amount,
+            CAST(COALESCE(v.base_grand_total, ROUND(COALESCE(v.subtotal, v.total_amount, 0) - COALESCE(v.discount_amount, 0) + COALESCE(v.tax_amount, COALESCE(SUM(vi.tax_amount), 0), 0) + COALESCE(v.round_off_amount, 0), 2)) AS REAL) as grand_total
+         FROM vouchers v
+         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
+         LEFT JOIN voucher_items vi ON vi.voucher_id = v.id
+         WHERE v.voucher_type = ?
+           AND v.voucher_date >= ? AND v.voucher_date <= ?
+           AND v.deleted_at IS NULL
+           AND COALESCE(v.status, 'posted') = 'posted'
+           AND (? IS NULL OR v.branch_id = ?)
+         GROUP BY v.id
+         ORDER BY v.voucher_date ASC, v.voucher_no ASC",
+    )
+    .bind(voucher_type)
+    .bind(from_date)
+    .bind(to_date)
+    .bind(branch_id)
+    .bind(branch_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut totals = RegisterTotals::default();
+    for row in &rows {
+        totals.taxable_value += row.taxable_value;
+        totals.cgst_amount += row.cgst_amount;
+        totals.sgst_amount += row.sgst_amount;
+        totals.igst_amount += row.igst_amount;
+        totals.tax_amount += row.tax_amount;
+        totals.round_off_amount += row.round_off_amount;
+        totals.grand_total += row.grand_total;
+    }
+
+    Ok(InvoiceRegister { rows, totals })
+}
+
+#[tauri::command]
+pub async fn get_sales_register(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    branch_id: Option<String>,
+) -> Result<InvoiceRegister, String> {
+    let pool = registry.active_pool().await?;
+    invoice_register(
+        &pool,
+        "sales_invoice",
+        &from_date,
+        &to_date,
+        branch_id.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_purchase_register(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    branch_id: Option<String>,
+) -> Result<InvoiceRegister, String> {
+    let pool = registry.active_pool().await?;
+    invoice_register(
+        &pool,
+        "purchase_invoice",
+        &from_date,
+        &to_date,
+        branch_id.as_deref(),
+    )
+    .await
+}
+
+// ============= CSV EXPORT =============
+
+#[tauri::command]
+pub async fn export_trial_balance_csv(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: Option<String>,
+    to_date: String,
+    file_path: String,
+) -> Result<usize, String> {
+    let pool = registry.active_pool().await?;
+    let rows = trial_balance_rows(&pool, from_date.as_deref(), &to_date).await?;
+
+    let mut records = vec![vec![
+        "Account Code".to_string(),
+        "Account Name".to_string(),
+        "Opening".to_string(),
+        "Debit".to_string(),
+        "Credit".to_string(),
+        "Closing".to_string(),
+    ]];
+    let (mut total_debit, mut total_credit) = (0.0, 0.0);
+    for row in &rows {
+        total_debit += row.debit;
+        total_credit += row.credit;
+        records.push(vec![
+            row.account_code.clone(),
+            row.account_name.clone(),
+            format!("{:.2}", row.opening_balance),
+            format!("{:.2}", row.debit),
+            format!("{:.2}", row.credit),
+            format!("{:.2}", row.closing_balance),
+        ]);
+    }
+    records.push(vec![
+        String::new(),
+        "Total".to_string(),
+        String::new(),
+        format!("{:.2}", total_debit),
+        format!("{:.2}", total_credit),
+        String::new(),
+    ]);
+
+    write_csv(&file_path, &records)?;
+    Ok(rows.len())
+}
+
+#[tauri::command]
+pub async fn export_profit_loss_csv(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    file_path: String,
+) -> Result<usize, String> {
+    let pool = registry.active_pool().await?;
+    let data = profit_loss_data(&pool, &from_date, &to_date).await?;
+
+    let mut records = vec![vec![
+        "Section".to_string(),
+        "Account Code".to_string(),
+        "Account Name".to_string(),
+        "Amount".to_string(),
+    ]];
+    for (section, accounts) in [("Income", &data.income), ("Expense", &data.expenses)] {
+        for account in accounts {
+            records.push(vec![
+                section.to_string(),
+                account.account_code.clone(),
+                account.account_name.clone(),
+                format!("{:.2}", account.amount),
+            ]);
+        }
+    }
+    let data_rows = records.len() - 1;
+    records.push(vec![
+        "Total Income".to_string(),
+        String::new(),
+        String::new(),
+        format!("{:.2}", data.total_income),
+    ]);
+    records.push(vec![
+        "Total Expenses".to_string(),
+        String::new(),
+        String::new(),
+        format!("{:.2}", data.total_expenses),
+    ]);
+    records.push(vec![
+        "Net Profit".to_string(),
+        String::new(),
+        String::new(),
+        format!("{:.2}", data.net_profit),
+    ]);
+
+    write_csv(&file_path, &records)?;
+    Ok(data_rows)
+}
+
+fn register_csv_records(register: &InvoiceRegister, party_label: &str) -> Vec<Vec<String>> {
+    let mut records = vec![vec![
+        "Date".to_string(),
+        "Voucher No".to_string(),
+        party_label.to_string(),
+        "GSTIN".to_string(),
+        "Taxable Value".to_string(),
+        "CGST".to_string(),
+        "SGST".to_string(),
+        "IGST".to_string(),
+        "Total Tax".to_string(),
+        "Round Off".to_string(),
+        "Total".to_string(),
+    ]];
+    for row in &register.rows {
+        records.push(vec![
+            row.voucher_date.clone(),
+            row.voucher_no.clone(),
+            row.party_name.clone().unwrap_or_default(),
+            row.party_gstin.clone().unwrap_or_default(),
+            format!("{:.2}", row.taxable_value),
+            format!("{:.2}", row.cgst_amount),
+            format!("{:.2}", row.sgst_amount),
+            format!("{:.2}", row.igst_amount),
+            format!("{:.2}", row.tax_amount),
+            format!("{:.2}", row.round_off_amount),
+            format!("{:.2}", row.grand_total),
+        ]);
+    }
+    let totals = &register.totals;
+    records.push(vec![
+        String::new(),
+        "Total".to_string(),
+        String::new(),
+        String::new(),
+        format!("{:.2}", totals.taxable_value),
+        format!("{:.2}", totals.cgst_amount),
+        format!("{:.2}", totals.sgst_amount),
+        format!("{:.2}", totals.igst_amount),
+        format!("{:.2}", totals.tax_amount),
+        format!("{:.2}", totals.round_off_amount),
+        format!("{:.2}", totals.grand_total),
+    ]);
+    records
+}
+
+#[tauri::command]
+pub async fn export_sales_register_csv(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    file_path: String,
+    branch_id: Option<String>,
+) -> Result<usize, String> {
+    let pool = registry.active_pool().await?;
+    let register = invoice_register(
+        &pool,
+        "sales_invoice",
+        &from_date,
+        &to_date,
+        branch_id.as_deref(),
+    )
+    .await?;
+    write_csv(&file_path, &register_csv_records(&register, "Customer"))?;
+    Ok(register.rows.len())
+}
+
+#[tauri::command]
+pub async fn export_purchase_register_csv(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+    file_path: String,
+    branch_id: Option<String>,
+) -> Result<usize, String> {
+    let pool = registry.active_pool().await?;
+    let register = invoice_register(
+        &pool,
+        "purchase_invoice",
+        &from_date,
+        &to_date,
+        branch_id.as_deref(),
+    )
+    .await?;
+    write_csv(&file_path, &register_csv_records(&register, "Supplier"))?;
+    Ok(register.rows.len())
+}
+
+// ============= JSON EXPORT =============
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct AccountExportRow {
+    pub account_code: String,
+    pub account_name: String,
+    pub account_type: String,
+    pub account_group: String,
+    pub opening_balance: f64,
+    pub opening_balance_type: Option<String>,
+    pub closing_balance: f64,
+    pub closing_balance_type: String,
+}
+
+/// Writes every live account with its closing balance as of `as_on_date` to a
+/// JSON array at `file_path`. Returns the number of accounts written.
+#[tauri::command]
+pub async fn export_chart_of_accounts_json(
+    registry: State<'_, Arc<DbRegistry>>,
+    as_on_date: String,
+    file_path: String,
+) -> Result<usize, String> {
+    let pool = registry.active_pool().await?;
+    let query = format!(
+        "SELECT
+            coa.account_code,
+            coa.account_name,
+            coa.account_type,
+            coa.account_group,
+            CAST(COALESCE(coa.opening_balance, 0) AS REAL) as opening_balance,
+            coa.opening_balance_type,
+            CAST(ABS({balance}) AS REAL) as closing_balance,
+            CASE WHEN {balance} < 0 THEN 'Cr' ELSE 'Dr' END as closing_balance_type
+         FROM chart_of_accounts coa
+         WHERE coa.deleted_at IS NULL
+         ORDER BY coa.account_code ASC",
+        balance = signed_account_balance_sql(true)
+    );
+
+    let rows = sqlx::query_as::<_, AccountExportRow>(&query)
+        .bind(&as_on_date)
+        .bind(&as_on_date)
+        .bind(&as_on_date)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let json = serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?;
+    std::fs::write(&file_path, json)
+        .map_err(|e| format!("Failed to write {}: {}", file_path, e))?;
+    Ok(rows.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use sqlx::SqlitePool;
+
+    /// A product named `Product <code>` with its own unit.
+    async fn insert_product(pool: &SqlitePool, code: &str) -> String {
+        let unit_id = uuid::Uuid::now_v7().to_string();
+        sqlx::query("INSERT INTO units (id, name, symbol) VALUES (?, ?, 'Nos')")
+            .bind(&unit_id)
+            .bind(format!("Unit {}", code))
+            .execute(pool)
+            .await
+            .unwrap();
+        let product_id = uuid::Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO products (id, code, name, unit_id, purchase_rate, sales_rate, mrp)
+             VALUES (?, ?, ?, ?, 10, 15, 15)",
+        )
+        .bind(&product_id)
+        .bind(code)
+        .bind(format!("Product {}", code))
+        .bind(&unit_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        product_id
+    }
+
+    async fn insert_movement(
+        pool: &SqlitePool,
+        product_id: &str,
+        voucher_type: &str,
+        movement_type: &str,
+        quantity: f64,
+    ) {
+        let movement = (movement_type, quantity, 10.0);
+        insert_dated_movement(pool, product_id, voucher_type, "2024-04-01", movement).await;
+    }
+
+    /// `movement` is (movement type, quantity, rate); the rate is also the cost rate.
+    async fn insert_dated_movement(
+        pool: &SqlitePool,
+        product_id: &str,
+        voucher_type: &str,
+        date: &str,
+        (movement_type, quantity, rate): (&str, f64, f64),
+    ) {
+        let voucher_id = uuid::Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&voucher_id)
+        .bind(format!("T-{}", voucher_id))
+        .bind(voucher_type)
+        .bind(date)
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO stock_movements
+                (id, voucher_id, product_id, movement_type, quantity, rate, amount, cost_rate, cost_amount)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::now_v7().to_string())
+        .bind(&voucher_id)
+        .bind(product_id)
+        .bind(movement_type)
+        .bind(quantity)
+        .bind(rate)
+        .bind(quantity * rate)
+        .bind(rate)
+        .bind(quantity * rate)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn adjustment_and_return_subtypes_count_towards_stock() {
+        let pool = test_pool().await;
+        let product_id = insert_product(&pool, "T-SUB").await;
+
+        insert_movement(&pool, &product_id, "purchase_invoice", "IN", 10.0).await;
+        insert_movement(
+            &pool,
+            &product_id,
+            "stock_adjustment",
+            "ADJUSTMENT_OUT",
+            3.0,
+        )
+        .await;
+        insert_movement(&pool, &product_id, "sales_return", "RETURN_IN", 2.0).await;
+
+        let stock: f64 = sqlx::query_scalar(&product_stock_qty_sql())
+            .bind(&product_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stock, 9.0);
+
+        assert_eq!(
+            stock_movement_label("ADJUSTMENT_OUT", "stock_adjustment"),
+            "Adjustment Out"
+        );
+        assert_eq!(
+            stock_movement_label("RETURN_IN", "sales_return"),
+            "Return In"
+        );
+        assert_eq!(stock_movement_label("IN", "purchase_invoice"), "In");
+    }
+
+    async fn account_id(pool: &SqlitePool, code: &str) -> String {
+        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = ?")
+            .bind(code)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    /// A posted journal voucher; each line is (account code, debit, credit).
+    async fn insert_journal(pool: &SqlitePool, date: &str, lines: &[(&str, f64, f64)]) -> String {
+        let voucher_id = uuid::Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date)
+             VALUES (?, ?, 'journal', ?)",
+        )
+        .bind(&voucher_id)
+        .bind(format!("JV-{}", voucher_id))
+        .bind(date)
+        .execute(pool)
+        .await
+        .unwrap();
+        for (code, debit, credit) in lines {
+            sqlx::query(
+                "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(uuid::Uuid::now_v7().to_string())
+            .bind(&voucher_id)
+            .bind(account_id(pool, code).await)
+            .bind(debit)
+            .bind(credit)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        voucher_id
+    }
+
+    #[tokio::test]
+    async fn day_book_leaves_out_excluded_accounts() {
+        let pool = test_pool().await;
+        insert_journal(
+            &pool,
+            "2024-04-05",
+            &[("1001", 500.0, 0.0), ("3004", 0.0, 500.0)],
+        )
+        .await;
+        let adjustment = account_id(&pool, "3004").await;
+
+        let all = day_book_entries(&pool, "2024-04-01", "2024-04-30", true, &[])
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+
+        let filtered = day_book_entries(
+            &pool,
+            "2024-04-01",
+            "2024-04-30",
+            true,
+            std::slice::from_ref(&adjustment),
+        )
+        .await
+        .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].account_name, "Cash");
+        assert_eq!(filtered[0].debit, 500.0);
+    }
+
+    #[tokio::test]
+    async fn monthly_profit_loss_nets_each_month_separately() {
+        let pool = test_pool().await;
+        // (date, debit account, credit account, amount); nothing is posted in May
+        for (date, debit, credit, amount) in [
+            ("2024-04-10", "1001", "4001", 1000.0),
+            ("2024-04-20", "5004", "1001", 300.0),
+            ("2024-06-05", "5004", "1001", 200.0),
+            ("2024-07-01", "1001", "4001", 900.0),
+        ] {
+            insert_journal(&pool, date, &[(debit, amount, 0.0), (credit, 0.0, amount)]).await;
+        }
+
+        let months = monthly_profit_loss(&pool, "2024-04-01", "2024-06-30")
+            .await
+            .unwrap();
+        let grid: Vec<(&str, f64)> = months
+            .iter()
+            .map(|m| (m.month.as_str(), m.net_profit))
+            .collect();
+        assert_eq!(
+            grid,
+            vec![("2024-04", 700.0), ("2024-05", 0.0), ("2024-06", -200.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn cash_flow_spans_renamed_cash_and_bank_accounts() {
+        let pool = test_pool().await;
+        sqlx::query("UPDATE chart_of_accounts SET account_name = 'Cash in Hand' WHERE account_code = '1001'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        // (date, debit account, credit account, amount); 1002 is the seeded bank account
+        for (date, debit, credit, amount) in [
+            ("2024-03-31", "1001", "3004", 300.0),
+            ("2024-04-10", "1002", "4001", 500.0),
+            ("2024-04-20", "5004", "1001", 120.0),
+        ] {
+            insert_journal(&pool, date, &[(debit, amount, 0.0), (credit, 0.0, amount)]).await;
+        }
+
+        let flow = cash_flow(&pool, "2024-04-01", "2024-04-30").await.unwrap();
+        assert_eq!(flow.opening_cash, 300.0);
+        assert_eq!(flow.closing_cash, 680.0);
+        assert_eq!(flow.net_change, flow.closing_cash - flow.opening_cash);
+    }
+
+    #[tokio::test]
+    async fn top_products_rank_by_revenue_net_of_returns() {
+        let pool = test_pool().await;
+        let returned = insert_product(&pool, "T-BIG").await;
+        let steady = insert_product(&pool, "T-STEADY").await;
+        insert_movement(&pool, &returned, "sales_invoice", "OUT", 10.0).await;
+        insert_movement(&pool, &returned, "sales_return", "RETURN_IN", 6.0).await;
+        insert_movement(&pool, &steady, "sales_invoice", "OUT", 5.0).await;
+
+        let top = top_products(&pool, 10, "2024-04-01", "2024-04-30")
+            .await
+            .unwrap();
+        let ranking: Vec<(&str, f64, f64)> = top
+            .iter()
+            .map(|p| (p.product_name.as_str(), p.total_quantity, p.total_revenue))
+            .collect();
+        assert_eq!(
+            ranking,
+            vec![
+                ("Product T-STEADY", 5.0, 50.0),
+                ("Product T-BIG", 4.0, 40.0)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stock_report_ignores_movements_after_the_as_on_date() {
+        let pool = test_pool().await;
+        let product = insert_product(&pool, "T-ASON").await;
+        // The May purchase comes after the report date and at a much higher rate
+        for (date, rate) in [("2024-04-01", 10.0), ("2024-05-01", 40.0)] {
+            let purchase = ("IN", 10.0, rate);
+            insert_dated_movement(&pool, &product, "purchase_invoice", date, purchase).await;
+        }
+
+        let report = stock_report(
+            &pool,
+            None,
+            "2024-04-30",
+            ValuationMethod::WeightedAverage,
+            Some(&product),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].current_stock, 10.0);
+        assert_eq!(report[0].average_rate, 10.0);
+        assert_eq!(report[0].stock_value, 100.0);
+        assert_eq!(report[0].last_purchase_date.as_deref(), Some("2024-04-01"));
+    }
+
+    #[tokio::test]
+    async fn dashboard_receivables_match_the_party_outstanding_total() {
+        let pool = test_pool().await;
+        for code in ["1003-TA", "1003-TB"] {
+            sqlx::query(
+                "INSERT INTO chart_of_accounts (id, account_code, account_name, account_type, account_group, party_type)
+                 VALUES (?, ?, ?, 'Asset', 'Accounts Receivable', 'customer')",
+            )
+            .bind(uuid::Uuid::now_v7().to_string())
+            .bind(code)
+            .bind(format!("Customer {}", code))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        // TA owes 600 after a part receipt, TB has a 200 advance; the May sale is too late
+        for (date, debit, credit, amount) in [
+            ("2024-04-02", "1003-TA", "4001", 1000.0),
+            ("2024-04-10", "1001", "1003-TA", 400.0),
+            ("2024-04-12", "1001", "1003-TB", 200.0),
+            ("2024-05-02", "1003-TB", "4001", 700.0),
+        ] {
+            insert_journal(&pool, date, &[(debit, amount, 0.0), (credit, 0.0, amount)]).await;
+        }
+
+        let dashboard = total_party_outstanding(&pool, "Accounts Receivable", "2024-04-30")
+            .await
+            .unwrap();
+        let report: f64 = party_outstanding(&pool, "customer", "2024-04-30")
+            .await
+            .unwrap()
+            .iter()
+            .map(|party| party.outstanding_amount)
+            .sum();
+        assert_eq!(dashboard, report);
+        assert_eq!(dashboard, 400.0);
+    }
+
+    #[tokio::test]
+    async fn customer_ledger_labels_its_opening_line_without_the_balancing_entry() {
+        let pool = test_pool().await;
+        let customer = crate::commands::accounts::create_chart_of_account_with_pool(
+            &pool,
+            serde_json::from_value(serde_json::json!({
+                "account_code": "1003-TL",
+                "account_name": "Ledger Customer",
+                "account_type": "Asset",
+                "account_group": "Accounts Receivable",
+                "opening_balance": 500.0,
+                "opening_balance_type": "Dr",
+                "opening_balance_date": "2024-04-01",
+            }))
+            .unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let entries = ledger_entries_between(&pool, &customer.id, None, "2024-12-31", false, None)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].voucher_type_label, "Opening Balance");
+        assert_eq!((entries[0].debit, entries[0].credit), (500.0, 0.0));
+
+        let adjustment = account_id(&pool, "3004").await;
+        let balancing = ledger_entries_between(&pool, &adjustment, None, "2024-12-31", false, None)
+            .await
+            .unwrap();
+        assert_eq!(balancing.len(), 1);
+        assert_eq!(balancing[0].id, entries[0].id);
+        assert_eq!(balancing[0].credit, 500.0);
+    }
+
+    #[tokio::test]
+    async fn valuation_summary_ties_to_the_stock_report_for_every_method() {
+        let pool = test_pool().await;
+        let layered = insert_product(&pool, "T-LAYER").await;
+        let single = insert_product(&pool, "T-SINGLE").await;
+        for (product, date, movement) in [
+            (&layered, "2024-04-01", ("IN", 10.0, 10.0)),
+            (&layered, "2024-04-05", ("IN", 10.0, 20.0)),
+            (&layered, "2024-04-10", ("OUT", 5.0, 15.0)),
+            (&single, "2024-04-02", ("IN", 4.0, 30.0)),
+        ] {
+            insert_dated_movement(&pool, product, "stock_journal", date, movement).await;
+        }
+
+        for method in [
+            ValuationMethod::WeightedAverage,
+            ValuationMethod::Fifo,
+            ValuationMethod::LastPurchase,
+        ] {
+            let summary = stock_valuation_summary(&pool, "2024-04-30".to_string(), method)
+                .await
+                .unwrap();
+            let report_total: f64 = stock_report(&pool, None, "2024-04-30", method, None, false)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.stock_value)
+                .sum();
+            assert!(
+                (summary.total_value - report_total).abs() < 0.005,
+                "{}: summary {} vs report {}",
+                method.as_str(),
+                summary.total_value,
+                report_total
+            );
+            assert_eq!(summary.product_count, 2);
+            if method == ValuationMethod::WeightedAverage {
+                assert_eq!(summary.total_value, 345.0);
+            }
+        }
+    }
+}
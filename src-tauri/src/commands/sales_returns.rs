@@ -6,11 +6,17 @@ use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
+use super::auth::SessionStore;
+use super::entries::{insert_journal_lines, JournalLine};
 use super::invoices::{
     finalize_processed_items, get_product_purchase_cost_rate, prepare_voucher_line,
     ProcessedVoucherItem,
 };
+use super::reports::STOCK_OUT_TYPES;
 use super::resolve_voucher_line_unit;
+use super::settings::{resolve_narration, validate_voucher_date};
+use super::system_accounts::resolve_system_account;
+use super::year_end::ensure_voucher_unlocked;
 use crate::voucher_seq::get_next_voucher_number_in_tx;
 
 // ============= SALES RETURN =============
@@ -115,6 +121,45 @@ async fn linked_sales_invoice_id_by_reference(
     .map_err(|e| e.to_string())
 }
 
+/// The reference that links a return to its sales invoice. An explicit
+/// `original_invoice_id` must be a live sales invoice of the same customer and
+/// supplies that invoice's number; otherwise the typed reference is used.
+async fn resolve_sales_return_reference(
+    tx: &mut Transaction<'_, Sqlite>,
+    invoice: &CreateSalesReturn,
+) -> Result<Option<String>, String> {
+    let Some(original_invoice_id) = invoice.original_invoice_id.as_deref() else {
+        return Ok(invoice.reference.clone());
+    };
+
+    let original: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT voucher_no, party_id, deleted_at
+         FROM vouchers
+         WHERE id = ? AND voucher_type = 'sales_invoice'",
+    )
+    .bind(original_invoice_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match original {
+        None => Err("Original sales invoice not found".to_string()),
+        Some((voucher_no, _, Some(_))) => Err(format!(
+            "Sales invoice {} is deleted and cannot be returned against",
+            voucher_no
+        )),
+        Some((voucher_no, party_id, None))
+            if party_id.as_deref() != Some(invoice.customer_id.as_str()) =>
+        {
+            Err(format!(
+                "Sales invoice {} belongs to a different customer",
+                voucher_no
+            ))
+        }
+        Some((voucher_no, _, None)) => Ok(Some(voucher_no)),
+    }
+}
+
 async fn validate_linked_return_quantities(
     tx: &mut Transaction<'_, Sqlite>,
     reference: Option<&str>,
@@ -193,15 +238,16 @@ async fn sales_return_cost_rate(
     product_id: &str,
 ) -> Result<f64, String> {
     if let Some(invoice_id) = linked_sales_invoice_id {
-        let original_cost: Option<f64> = sqlx::query_scalar(
+        let original_cost: Option<f64> = sqlx::query_scalar(&format!(
             "SELECT COALESCE(cost_rate, 0)
              FROM stock_movements
              WHERE voucher_id = ?
                AND product_id = ?
-               AND movement_type = 'OUT'
+               AND movement_type IN ({})
              ORDER BY created_at ASC, id ASC
              LIMIT 1",
-        )
+            STOCK_OUT_TYPES
+        ))
         .bind(invoice_id)
         .bind(product_id)
         .fetch_optional(&mut **tx)
@@ -224,12 +270,16 @@ pub struct CreateSalesReturn {
     pub party_type: String,
     pub voucher_date: String,
     pub reference: Option<String>,
+    /// Sales invoice being returned against; its number becomes the reference
+    #[serde(default)]
+    pub original_invoice_id: Option<String>,
     pub narration: Option<String>,
     pub discount_rate: Option<f64>,
     pub discount_amount: Option<f64>,
     pub items: Vec<CreateSalesReturnItem>,
     pub tax_inclusive: Option<bool>,
     pub gst_disabled: Option<bool>,
+    pub allow_future: Option<bool>,
 }
 
 #[tauri::command]
@@ -328,14 +378,66 @@ pub async fn get_sales_return_items(
     .map_err(|e| e.to_string())
 }
 
+/// Live sales returns raised against a sales invoice, matched by
+/// original_invoice_id or, for older returns, by the invoice number reference.
+#[tauri::command]
+pub async fn get_returns_for_invoice(
+    registry: State<'_, Arc<DbRegistry>>,
+    invoice_id: String,
+) -> Result<Vec<SalesReturn>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, SalesReturn>(
+        "SELECT 
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            v.party_id as customer_id,
+            coa.account_name as customer_name,
+            v.party_type,
+            v.reference,
+            v.total_amount,
+            COALESCE(SUM(vi.tax_amount), 0) as tax_amount,
+            v.grand_total,
+            v.discount_rate,
+            v.discount_amount,
+            v.narration,
+            v.status,
+            v.created_at,
+            v.deleted_at,
+            COALESCE(v.tax_inclusive, 0) as tax_inclusive
+         FROM vouchers v
+         LEFT JOIN chart_of_accounts coa ON v.party_id = coa.id
+         LEFT JOIN voucher_items vi ON v.id = vi.voucher_id
+         WHERE v.voucher_type = 'sales_return' AND v.deleted_at IS NULL
+           AND (
+                v.original_invoice_id = ?
+                OR (v.original_invoice_id IS NULL AND v.reference = (
+                    SELECT si.voucher_no FROM vouchers si
+                    WHERE si.id = ? AND si.voucher_type = 'sales_invoice'
+                ))
+           )
+         GROUP BY v.id
+         ORDER BY v.voucher_date ASC, v.id ASC",
+    )
+    .bind(&invoice_id)
+    .bind(&invoice_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn create_sales_return(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     invoice: CreateSalesReturn,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    validate_voucher_date(&pool, &invoice.voucher_date, invoice.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
-    let voucher_id = create_sales_return_in_tx(&pool, &mut tx, &invoice).await?;
+    let branch_id = session_store.active_branch_id();
+    let voucher_id =
+        create_sales_return_in_tx(&pool, &mut tx, &invoice, branch_id.as_deref()).await?;
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_id)
 }
@@ -344,8 +446,10 @@ pub(crate) async fn create_sales_return_in_tx(
     pool: &SqlitePool,
     tx: &mut Transaction<'_, Sqlite>,
     invoice: &CreateSalesReturn,
+    branch_id: Option<&str>,
 ) -> Result<String, String> {
-    let voucher_no = get_next_voucher_number_in_tx(tx, "sales_return").await?;
+    let voucher_no =
+        get_next_voucher_number_in_tx(tx, "sales_return", &invoice.voucher_date, branch_id).await?;
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
             .fetch_optional(&mut **tx)
@@ -419,28 +523,41 @@ pub(crate) async fn create_sales_return_in_tx(
     let total_amount = subtotal - discount_amount;
     let grand_total = total_amount + total_tax;
 
-    validate_linked_return_quantities(tx, invoice.reference.as_deref(), None, &processed_items)
-        .await?;
+    let reference = resolve_sales_return_reference(tx, invoice).await?;
+    validate_linked_return_quantities(tx, reference.as_deref(), None, &processed_items).await?;
+    let linked_sales_invoice_id =
+        linked_sales_invoice_id_by_reference(tx, reference.as_deref()).await?;
+
+    let narration = resolve_narration(
+        tx,
+        invoice.narration.as_ref(),
+        "sales_return",
+        Some(&invoice.customer_id),
+        &voucher_no,
+    )
+    .await?;
 
     let voucher_id = Uuid::now_v7().to_string();
     sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, tax_inclusive, grand_total)
-         VALUES (?, ?, 'sales_return', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?)",
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, party_id, party_type, reference, original_invoice_id, subtotal, discount_rate, discount_amount, tax_amount, total_amount, narration, status, tax_inclusive, grand_total, branch_id)
+         VALUES (?, ?, 'sales_return', ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'posted', ?, ?, ?)",
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
     .bind(&invoice.voucher_date)
     .bind(&invoice.customer_id)
     .bind(&invoice.party_type)
-    .bind(&invoice.reference)
+    .bind(&reference)
+    .bind(&linked_sales_invoice_id)
     .bind(subtotal)
     .bind(discount_rate)
     .bind(discount_amount)
     .bind(total_tax)
     .bind(total_amount)
-    .bind(&invoice.narration)
+    .bind(&narration)
     .bind(tax_inclusive as i64)
     .bind(grand_total)
+    .bind(branch_id)
     .execute(&mut **tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -486,79 +603,16 @@ pub(crate) async fn create_sales_return_in_tx(
     }
 
     let party_id = invoice.customer_id.clone();
-    let sales_return_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4003'")
-            .fetch_one(&mut **tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    let tax_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '2002'")
-            .fetch_one(&mut **tx)
-            .await
-            .map_err(|e| e.to_string())?;
     let party_account: String = sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE id = ?")
         .bind(&party_id)
         .fetch_one(&mut **tx)
         .await
         .map_err(|e| format!("Party account not found: {}", e))?;
 
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, ?, 0, 'Sales Return (Goods returned)')",
-    )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&voucher_id)
-    .bind(&sales_return_account)
-    .bind(subtotal)
-    .execute(&mut **tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if total_tax > 0.0 {
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Tax Reversal on Sales Return')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&voucher_id)
-        .bind(&tax_account)
-        .bind(total_tax)
-        .execute(&mut **tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
-
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, 0, ?, 'Credit Note issued to Customer')",
-    )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&voucher_id)
-    .bind(&party_account)
-    .bind(subtotal - discount_amount + total_tax)
-    .execute(&mut **tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if discount_amount > 0.0 {
-        let discount_allowed_account: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5007'")
-                .fetch_one(&mut **tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Reversal of Discount Allowed')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&voucher_id)
-        .bind(&discount_allowed_account)
-        .bind(discount_amount)
-        .execute(&mut **tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
+    let journal_lines =
+        sales_return_journal_lines(tx, &party_account, subtotal, total_tax, discount_amount)
+            .await?;
+    insert_journal_lines(tx, &voucher_id, &journal_lines).await?;
 
     let items_for_stock: Vec<(Option<String>, Option<String>, f64, i64, f64, f64)> = sqlx::query_as(
         "SELECT item_type, product_id, base_quantity, count, rate, amount FROM voucher_items WHERE voucher_id = ?",
@@ -568,9 +622,6 @@ pub(crate) async fn create_sales_return_in_tx(
     .await
     .map_err(|e| e.to_string())?;
 
-    let linked_sales_invoice_id =
-        linked_sales_invoice_id_by_reference(tx, invoice.reference.as_deref()).await?;
-
     for item in items_for_stock {
         if item.0.as_deref() == Some("service") {
             continue;
@@ -588,7 +639,7 @@ pub(crate) async fn create_sales_return_in_tx(
         let cost_amount = base_qty * cost_rate;
         sqlx::query(
             "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount)
-             VALUES (?, ?, ?, 'IN', ?, ?, ?, ?, ?, ?)",
+             VALUES (?, ?, ?, 'RETURN_IN', ?, ?, ?, ?, ?, ?)",
         )
         .bind(Uuid::now_v7().to_string())
         .bind(&voucher_id)
@@ -604,11 +655,63 @@ pub(crate) async fn create_sales_return_in_tx(
         .map_err(|e| e.to_string())?;
     }
 
-    sync_sales_invoice_link_for_return(tx, &voucher_id, invoice.reference.as_deref()).await?;
+    sync_sales_invoice_link_for_return(tx, &voucher_id, reference.as_deref()).await?;
 
     Ok(voucher_id)
 }
 
+/// Dr Sales Returns (and output tax), Cr the customer for the credit note and
+/// Discount Allowed for the bill discount being reversed.
+async fn sales_return_journal_lines(
+    tx: &mut Transaction<'_, Sqlite>,
+    party_account: &str,
+    subtotal: f64,
+    total_tax: f64,
+    discount_amount: f64,
+) -> Result<Vec<JournalLine>, String> {
+    let sales_return_account = resolve_system_account(&mut **tx, "sales_returns").await?;
+    let tax_account = resolve_system_account(&mut **tx, "output_tax").await?;
+    let line = |account_id: String, debit: f64, credit: f64, narration: &str| JournalLine {
+        account_id,
+        debit,
+        credit,
+        is_manual: false,
+        narration: Some(narration.to_string()),
+    };
+
+    let mut lines = vec![line(
+        sales_return_account,
+        subtotal,
+        0.0,
+        "Sales Return (Goods returned)",
+    )];
+    if total_tax > 0.0 {
+        lines.push(line(
+            tax_account,
+            total_tax,
+            0.0,
+            "Tax Reversal on Sales Return",
+        ));
+    }
+    lines.push(line(
+        party_account.to_string(),
+        0.0,
+        subtotal - discount_amount + total_tax,
+        "Credit Note issued to Customer",
+    ));
+    if discount_amount > 0.0 {
+        let discount_allowed_account =
+            resolve_system_account(&mut **tx, "discount_allowed").await?;
+        lines.push(line(
+            discount_allowed_account,
+            0.0,
+            discount_amount,
+            "Reversal of Discount Allowed",
+        ));
+    }
+    Ok(lines)
+}
+
 async fn sync_sales_invoice_link_for_return(
     tx: &mut Transaction<'_, Sqlite>,
     return_id: &str,
@@ -648,6 +751,8 @@ pub async fn update_sales_return(
     invoice: CreateSalesReturn,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    validate_voucher_date(&pool, &invoice.voucher_date, invoice.allow_future).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
     let company_state: Option<String> =
         sqlx::query_scalar("SELECT state FROM company_profile ORDER BY id DESC LIMIT 1")
@@ -722,23 +827,22 @@ pub async fn update_sales_return(
     let total_amount = subtotal - discount_amount;
     let grand_total = total_amount + total_tax;
 
-    validate_linked_return_quantities(
-        &mut tx,
-        invoice.reference.as_deref(),
-        Some(&id),
-        &processed_items,
-    )
-    .await?;
+    let reference = resolve_sales_return_reference(&mut tx, &invoice).await?;
+    validate_linked_return_quantities(&mut tx, reference.as_deref(), Some(&id), &processed_items)
+        .await?;
+    let linked_sales_invoice_id =
+        linked_sales_invoice_id_by_reference(&mut tx, reference.as_deref()).await?;
 
     sqlx::query(
         "UPDATE vouchers
-         SET voucher_date = ?, party_id = ?, party_type = ?, reference = ?, subtotal = ?, discount_rate = ?, discount_amount = ?, tax_amount = ?, total_amount = ?, narration = ?, status = 'posted', tax_inclusive = ?, grand_total = ?
+         SET voucher_date = ?, party_id = ?, party_type = ?, reference = ?, original_invoice_id = ?, subtotal = ?, discount_rate = ?, discount_amount = ?, tax_amount = ?, total_amount = ?, narration = ?, status = 'posted', tax_inclusive = ?, grand_total = ?
          WHERE id = ? AND voucher_type = 'sales_return'",
     )
     .bind(&invoice.voucher_date)
     .bind(&invoice.customer_id)
     .bind(&invoice.party_type)
-    .bind(&invoice.reference)
+    .bind(&reference)
+    .bind(&linked_sales_invoice_id)
     .bind(subtotal)
     .bind(discount_rate)
     .bind(discount_amount)
@@ -808,82 +912,21 @@ pub async fn update_sales_return(
         .map_err(|e| e.to_string())?;
     }
 
-    let sales_return_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '4003'")
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
-    let tax_account: String =
-        sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '2002'")
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|e| e.to_string())?;
     let party_account: String = sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE id = ?")
         .bind(&invoice.customer_id)
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| e.to_string())?;
 
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, ?, 0, 'Sales Return (Goods returned)')",
-    )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&id)
-    .bind(&sales_return_account)
-    .bind(subtotal)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if total_tax > 0.0 {
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, ?, 0, 'Tax Reversal on Sales Return')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&id)
-        .bind(&tax_account)
-        .bind(total_tax)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
-
-    sqlx::query(
-        "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-         VALUES (?, ?, ?, 0, ?, 'Credit Note issued to Customer')",
+    let journal_lines = sales_return_journal_lines(
+        &mut tx,
+        &party_account,
+        subtotal,
+        total_tax,
+        discount_amount,
     )
-    .bind(Uuid::now_v7().to_string())
-    .bind(&id)
-    .bind(&party_account)
-    .bind(subtotal - discount_amount + total_tax)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    if discount_amount > 0.0 {
-        let discount_allowed_account: String =
-            sqlx::query_scalar("SELECT id FROM chart_of_accounts WHERE account_code = '5007'")
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|e| e.to_string())?;
-
-        sqlx::query(
-            "INSERT INTO journal_entries (id, voucher_id, account_id, debit, credit, narration)
-             VALUES (?, ?, ?, 0, ?, 'Reversal of Discount Allowed')",
-        )
-        .bind(Uuid::now_v7().to_string())
-        .bind(&id)
-        .bind(&discount_allowed_account)
-        .bind(discount_amount)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| e.to_string())?;
-    }
-
-    let linked_sales_invoice_id =
-        linked_sales_invoice_id_by_reference(&mut tx, invoice.reference.as_deref()).await?;
+    .await?;
+    insert_journal_lines(&mut tx, &id, &journal_lines).await?;
 
     for item in &invoice.items {
         if item.item_type == "service" {
@@ -907,7 +950,7 @@ pub async fn update_sales_return(
         let cost_amount = base_qty * cost_rate;
         sqlx::query(
             "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount)
-             VALUES (?, ?, ?, 'IN', ?, ?, ?, ?, ?, ?)",
+             VALUES (?, ?, ?, 'RETURN_IN', ?, ?, ?, ?, ?, ?)",
         )
         .bind(Uuid::now_v7().to_string())
         .bind(&id)
@@ -923,7 +966,7 @@ pub async fn update_sales_return(
         .map_err(|e| e.to_string())?;
     }
 
-    sync_sales_invoice_link_for_return(&mut tx, &id, invoice.reference.as_deref()).await?;
+    sync_sales_invoice_link_for_return(&mut tx, &id, reference.as_deref()).await?;
 
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(())
@@ -935,6 +978,7 @@ pub async fn delete_sales_return(
     id: String,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ?")
@@ -0,0 +1,56 @@
+use crate::company_db::DbRegistry;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tauri::State;
+
+// ============= VOUCHER SEARCH INDEX =============
+// voucher_search is an FTS5 table over voucher_no, narration, reference and
+// party name. Triggers on vouchers keep it current; rebuild_search_index
+// resyncs it (e.g. after parties are renamed). Where SQLite was built without
+// FTS5 the table doesn't exist and searches fall back to LIKE.
+
+/// Indexes every live voucher; run against an empty voucher_search.
+pub(crate) const INDEX_VOUCHERS_SQL: &str =
+    "INSERT INTO voucher_search (voucher_id, voucher_no, narration, reference, party_name)
+     SELECT v.id, v.voucher_no, v.narration, v.reference, coa.account_name
+     FROM vouchers v
+     LEFT JOIN chart_of_accounts coa ON coa.id = v.party_id
+     WHERE v.deleted_at IS NULL";
+
+/// True when the FTS table exists and holds rows, so MATCH can replace LIKE.
+pub(crate) async fn search_index_ready(pool: &SqlitePool) -> bool {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM voucher_search)")
+        .fetch_one(pool)
+        .await
+        .unwrap_or(false)
+}
+
+/// Turns free text into an FTS5 query: every word must match as a prefix.
+/// Words are quoted so punctuation in voucher numbers isn't read as syntax.
+pub(crate) fn search_match_query(term: &str) -> String {
+    term.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Rebuilds the voucher search index from scratch. Returns the number of
+/// vouchers indexed.
+#[tauri::command]
+pub async fn rebuild_search_index(registry: State<'_, Arc<DbRegistry>>) -> Result<u64, String> {
+    let pool = registry.active_pool().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM voucher_search")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Search index unavailable: {}", e))?;
+    let indexed = sqlx::query(INDEX_VOUCHERS_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(indexed)
+}
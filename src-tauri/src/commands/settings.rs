@@ -66,6 +66,94 @@ pub async fn set_app_setting(
     Ok(())
 }
 
+/// Rejects a voucher date more than `max_future_voucher_days` (app setting,
+/// default 0 = no future dating) ahead of today, unless `allow_future` is set.
+pub async fn validate_voucher_date(
+    pool: &sqlx::SqlitePool,
+    voucher_date: &str,
+    allow_future: Option<bool>,
+) -> Result<(), String> {
+    let date = chrono::NaiveDate::parse_from_str(
+        voucher_date.get(..10).unwrap_or(voucher_date),
+        "%Y-%m-%d",
+    )
+    .map_err(|_| format!("Invalid voucher date: {}", voucher_date))?;
+    super::year_end::ensure_period_open(pool, voucher_date).await?;
+    if allow_future.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let max_days: i64 = sqlx::query_scalar::<_, String>(
+        "SELECT setting_value FROM app_settings WHERE setting_key = 'max_future_voucher_days'",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .and_then(|v| v.trim().parse().ok())
+    .unwrap_or(0);
+
+    let latest_allowed =
+        chrono::Local::now().naive_local().date() + chrono::Duration::days(max_days.max(0));
+    if date > latest_allowed {
+        return Err(format!(
+            "Voucher date {} is beyond the allowed limit ({}). Enable future dating to post it anyway.",
+            date, latest_allowed
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns `narration` if the user supplied one; otherwise applies the voucher
+/// type's `narration_template` from voucher_settings, substituting `{party}`
+/// (the party account's name) and `{voucher_no}`.
+pub async fn resolve_narration(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    narration: Option<&String>,
+    voucher_type: &str,
+    party_id: Option<&str>,
+    voucher_no: &str,
+) -> Result<Option<String>, String> {
+    if let Some(text) = narration.filter(|n| !n.trim().is_empty()) {
+        return Ok(Some(text.clone()));
+    }
+
+    let settings_json: Option<String> =
+        sqlx::query_scalar("SELECT settings FROM voucher_settings WHERE voucher_type = ?")
+            .bind(voucher_type)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    let template = settings_json
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|settings| {
+            settings
+                .get("narration_template")
+                .and_then(|t| t.as_str())
+                .map(|t| t.trim().to_string())
+        })
+        .filter(|t| !t.is_empty());
+    let Some(template) = template else {
+        return Ok(None);
+    };
+
+    let party_name: String = match party_id {
+        Some(id) => sqlx::query_scalar("SELECT account_name FROM chart_of_accounts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+
+    Ok(Some(
+        template
+            .replace("{party}", &party_name)
+            .replace("{voucher_no}", voucher_no),
+    ))
+}
+
 /// Get print settings
 #[tauri::command]
 pub async fn get_print_settings(
@@ -347,6 +435,11 @@ pub async fn reset_database_data(
                 .execute(&mut *tx)
                 .await
                 .map_err(|e| format!("Failed to reset sequence for {}: {}", v_type, e))?;
+            sqlx::query("DELETE FROM voucher_sequence_years WHERE voucher_type = ?")
+                .bind(v_type)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to reset sequence for {}: {}", v_type, e))?;
         }
     }
 
@@ -577,6 +670,72 @@ pub async fn update_voucher_sequence(
     data: UpdateVoucherSequence,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    if data.next_number < 1 {
+        return Err("Next number must be at least 1".to_string());
+    }
+
+    let (voucher_type, current_next): (String, i64) =
+        sqlx::query_as("SELECT voucher_type, next_number FROM voucher_sequences WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    // Lowering the counter is only safe if none of the numbers it would hand
+    // out again are already taken (voucher_no is unique across all rows,
+    // deleted ones included).
+    if data.next_number < current_next {
+        let used: std::collections::HashSet<String> =
+            sqlx::query_scalar("SELECT voucher_no FROM vouchers WHERE voucher_type = ?")
+                .bind(&voucher_type)
+                .fetch_all(&pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .collect();
+        // Numbers are issued under every branch's code (or none) and in the
+        // financial year of each voucher's date
+        let mut branch_codes: Vec<Option<String>> = vec![None];
+        branch_codes.extend(
+            sqlx::query_scalar::<_, String>("SELECT DISTINCT code FROM branches")
+                .fetch_all(&pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(Some),
+        );
+        let start_month = super::company::financial_year_start_month(&pool).await?;
+        let mut years: Vec<String> = vec![crate::voucher_seq::current_financial_year(start_month)];
+        for date in sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT substr(voucher_date, 1, 10) FROM vouchers WHERE voucher_type = ?",
+        )
+        .bind(&voucher_type)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?
+        {
+            let fy = financial_year_from_date(&date, start_month);
+            if !years.contains(&fy) {
+                years.push(fy);
+            }
+        }
+
+        let seq = crate::voucher_seq::VoucherSeqRow::from_settings(&data);
+        for number in data.next_number..current_next {
+            for fy in &years {
+                for code in &branch_codes {
+                    let candidate = seq.format(number, fy, code.as_deref());
+                    if used.contains(&candidate) {
+                        return Err(format!(
+                            "Voucher number {} is already in use; next number cannot be lowered to {}",
+                            candidate, data.next_number
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
     sqlx::query(
         "UPDATE voucher_sequences 
          SET prefix = ?, next_number = ?, padding = ?, suffix = ?, separator = ?, include_financial_year = ?, reset_yearly = ?
@@ -599,66 +758,52 @@ pub async fn update_voucher_sequence(
 
 #[tauri::command]
 pub async fn preview_voucher_number(
-    _pool: State<'_, Arc<DbRegistry>>,
+    registry: State<'_, Arc<DbRegistry>>,
     data: UpdateVoucherSequence,
 ) -> Result<String, String> {
-    let sep = &data.separator;
-    let padded_num = format!(
-        "{:0width$}",
+    let pool = registry.active_pool().await?;
+    let start_month = super::company::financial_year_start_month(&pool).await?;
+    // Matches get_next_voucher_number: a yearly reset always shows the FY
+    let fy = crate::voucher_seq::current_financial_year(start_month);
+    let include_fy = data.include_financial_year || data.reset_yearly;
+    Ok(crate::voucher_seq::format_voucher_number(
+        &data.prefix,
+        data.suffix.as_deref().unwrap_or(""),
+        &data.separator,
+        data.padding,
+        include_fy.then_some(fy.as_str()),
         data.next_number,
-        width = data.padding as usize
-    );
-
-    // Build parts list — only include non-empty segments so no spurious separators appear
-    let mut parts: Vec<String> = Vec::new();
-    if !data.prefix.is_empty() {
-        parts.push(data.prefix.clone());
-    }
-    if data.include_financial_year {
-        parts.push(crate::voucher_seq::current_financial_year());
-    }
-    parts.push(padded_num);
-
-    let base = parts.join(sep);
-
-    // Append suffix (if any) with separator
-    let result = match &data.suffix {
-        Some(s) if !s.is_empty() => format!("{}{}{}", base, sep, s),
-        _ => base,
-    };
-
-    Ok(result)
+    ))
 }
 
 // ============= VOUCHER NUMBER REASSIGNMENT =============
 
-/// Derive the Indian financial year string from a "YYYY-MM-DD" date string.
+/// Derive the financial year string from a "YYYY-MM-DD" date string for a year
+/// starting in `start_month`.
 /// April–March cycle: e.g. "2026-01-15" → "25-26", "2026-06-01" → "26-27"
-fn financial_year_from_date(date_str: &str) -> String {
-    let year: i32 = date_str.get(..4).and_then(|s| s.parse().ok()).unwrap_or(2024);
-    let month: u32 = date_str.get(5..7).and_then(|s| s.parse().ok()).unwrap_or(1);
-    if month >= 4 {
-        format!("{}-{}", year % 100, (year + 1) % 100)
-    } else {
-        format!("{}-{}", (year - 1) % 100, year % 100)
-    }
+pub(crate) fn financial_year_from_date(date_str: &str, start_month: u32) -> String {
+    let date = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+        .unwrap_or_else(|_| chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    crate::voucher_seq::financial_year_label(date, start_month)
 }
 
-/// Returns the (start, end) date strings "YYYY-MM-DD" for the current Indian financial year.
-fn current_fy_range() -> (String, String) {
+/// Returns the (start, end) date strings "YYYY-MM-DD" for the current financial year.
+fn current_fy_range(start_month: u32) -> (String, String) {
     use chrono::{Datelike, FixedOffset, Utc};
     let now = Utc::now().with_timezone(&FixedOffset::east_opt(5 * 3600 + 1800).unwrap());
-    let year = now.year();
-    let month = now.month();
-    let fy_start_year = if month >= 4 { year } else { year - 1 };
+    let start = super::company::fiscal_year_start_for(now.date_naive(), start_month);
+    let end = start
+        .with_year(start.year() + 1)
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(start);
     (
-        format!("{}-04-01", fy_start_year),
-        format!("{}-03-31", fy_start_year + 1),
+        start.format("%Y-%m-%d").to_string(),
+        end.format("%Y-%m-%d").to_string(),
     )
 }
 
-/// Reassign voucher numbers for a given voucher type within the **current Indian
-/// financial year** (April 1 – March 31), ordering by invoice date ASC then id ASC.
+/// Reassign voucher numbers for a given voucher type within the **current
+/// financial year** (e.g. April 1 – March 31), ordering by invoice date ASC then id ASC.
 ///
 /// Three-phase, collision-free strategy:
 ///   Phase 1 — Fetch ALL non-deleted vouchers (saving their current voucher_no),
@@ -691,9 +836,10 @@ pub async fn reassign_voucher_numbers(
         ));
     }
 
-    let (fy_start, fy_end) = current_fy_range();
-
     let pool = registry.active_pool().await?;
+    let start_month = super::company::financial_year_start_month(&pool).await?;
+    let (fy_start, fy_end) = current_fy_range(start_month);
+
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     // Fetch sequence config
@@ -710,7 +856,7 @@ pub async fn reassign_voucher_numbers(
                 COALESCE(suffix, '') AS suffix,
                 COALESCE(separator, '-') AS separator,
                 padding,
-                COALESCE(include_financial_year, 0) AS include_financial_year
+                (COALESCE(include_financial_year, 0) OR COALESCE(reset_yearly, 0)) AS include_financial_year
          FROM voucher_sequences WHERE voucher_type = ?",
     )
     .bind(&voucher_type)
@@ -781,7 +927,7 @@ pub async fn reassign_voucher_numbers(
             parts.push(seq.prefix.clone());
         }
         if seq.include_financial_year {
-            parts.push(financial_year_from_date(&v.voucher_date));
+            parts.push(financial_year_from_date(&v.voucher_date, start_month));
         }
         parts.push(padded);
 
@@ -837,3 +983,66 @@ pub async fn reassign_voucher_numbers(
     tx.commit().await.map_err(|e| e.to_string())?;
     Ok(fy_count)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    fn days_from_today(days: i64) -> String {
+        (chrono::Local::now().naive_local().date() + chrono::Duration::days(days))
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn future_dates_need_the_override_or_a_wider_limit() {
+        let pool = test_pool().await;
+        let next_year = days_from_today(365);
+
+        let err = validate_voucher_date(&pool, &next_year, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("beyond the allowed limit"), "{}", err);
+        validate_voucher_date(&pool, &days_from_today(0), None)
+            .await
+            .unwrap();
+        validate_voucher_date(&pool, &next_year, Some(true))
+            .await
+            .unwrap();
+
+        sqlx::query(
+            "INSERT INTO app_settings (id, setting_key, setting_value)
+             VALUES (?, 'max_future_voucher_days', '400')
+             ON CONFLICT(setting_key) DO UPDATE SET setting_value = excluded.setting_value",
+        )
+        .bind(uuid::Uuid::now_v7().to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+        validate_voucher_date(&pool, &next_year, None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn closed_periods_reject_dates_even_with_the_override() {
+        let pool = test_pool().await;
+        sqlx::query(
+            "INSERT INTO financial_year_closings (id, fy_end_date, carry_forward_year)
+             VALUES (?, '2024-03-31', '2024-25')",
+        )
+        .bind(uuid::Uuid::now_v7().to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = validate_voucher_date(&pool, "2024-03-31", Some(true))
+            .await
+            .unwrap_err();
+        assert!(err.contains("is closed"), "{}", err);
+        validate_voucher_date(&pool, "2024-04-01", None)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use crate::company_db::DbRegistry;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+use uuid::Uuid;
+
+use super::auth::SessionStore;
+use super::entries::{insert_journal_lines, JournalLine};
+use super::invoices::get_product_purchase_cost_rate;
+use super::system_accounts::resolve_system_account;
+use super::year_end::ensure_period_open;
+use crate::voucher_seq::get_next_voucher_number_in_tx;
+
+// ============= STOCK ADJUSTMENT =============
+// Books shrinkage or surplus found in a physical count. Each line is a plain
+// IN/OUT stock movement valued at the product's purchase rate, balanced in the
+// journal between Inventory (1004) and Stock Adjustment (5013).
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockAdjustment {
+    pub id: String,
+    pub voucher_no: String,
+    pub voucher_date: String,
+    pub total_amount: f64,
+    /// Reason for the adjustment, stored as the voucher narration
+    pub reason: Option<String>,
+    pub created_at: String,
+    pub created_by_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockAdjustmentItem {
+    pub id: String,
+    pub product_id: String,
+    pub product_code: String,
+    pub product_name: String,
+    /// "in" for surplus, "out" for shrinkage
+    pub direction: String,
+    pub quantity: f64,
+    pub rate: f64,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StockAdjustmentDetail {
+    pub adjustment: StockAdjustment,
+    pub items: Vec<StockAdjustmentItem>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStockAdjustmentItem {
+    pub product_id: String,
+    pub quantity: f64,
+    /// "in" or "out"
+    pub direction: String,
+    /// Defaults to the product's purchase rate
+    pub rate: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStockAdjustment {
+    pub voucher_date: String,
+    pub reason: Option<String>,
+    pub items: Vec<CreateStockAdjustmentItem>,
+    pub user_id: Option<String>,
+}
+
+fn validate_items(items: &[CreateStockAdjustmentItem]) -> Result<(), String> {
+    if items.is_empty() {
+        return Err("Please add at least one item".to_string());
+    }
+
+    for item in items {
+        if item.product_id.trim().is_empty() {
+            return Err("Every item must have a product selected".to_string());
+        }
+        if item.quantity <= 0.0 {
+            return Err("Every item must have quantity greater than zero".to_string());
+        }
+        if !matches!(item.direction.as_str(), "in" | "out") {
+            return Err(format!(
+                "Invalid direction '{}': expected 'in' or 'out'",
+                item.direction
+            ));
+        }
+        if item.rate.is_some_and(|rate| rate < 0.0) {
+            return Err("Every item must have a valid rate".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_stock_adjustments(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<StockAdjustment>, String> {
+    let pool = registry.active_pool().await?;
+    sqlx::query_as::<_, StockAdjustment>(
+        "SELECT
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            v.total_amount,
+            v.narration as reason,
+            v.created_at,
+            u.full_name as created_by_name
+         FROM vouchers v
+         LEFT JOIN users u ON v.created_by = u.id
+         WHERE v.voucher_type = 'stock_adjustment' AND v.deleted_at IS NULL
+         ORDER BY v.voucher_date DESC, v.id DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_stock_adjustment(
+    registry: State<'_, Arc<DbRegistry>>,
+    id: String,
+) -> Result<StockAdjustmentDetail, String> {
+    let pool = registry.active_pool().await?;
+    let adjustment = sqlx::query_as::<_, StockAdjustment>(
+        "SELECT
+            v.id,
+            v.voucher_no,
+            v.voucher_date,
+            v.total_amount,
+            v.narration as reason,
+            v.created_at,
+            u.full_name as created_by_name
+         FROM vouchers v
+         LEFT JOIN users u ON v.created_by = u.id
+         WHERE v.id = ? AND v.voucher_type = 'stock_adjustment' AND v.deleted_at IS NULL",
+    )
+    .bind(&id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Stock adjustment not found".to_string())?;
+
+    let items = sqlx::query_as::<_, StockAdjustmentItem>(
+        "SELECT
+            vi.id,
+            vi.product_id,
+            p.code as product_code,
+            p.name as product_name,
+            COALESCE(vi.remarks, '') as direction,
+            vi.initial_quantity as quantity,
+            vi.rate,
+            vi.amount
+         FROM voucher_items vi
+         JOIN products p ON vi.product_id = p.id
+         WHERE vi.voucher_id = ?
+         ORDER BY vi.created_at ASC, vi.id ASC",
+    )
+    .bind(&id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(StockAdjustmentDetail { adjustment, items })
+}
+
+#[tauri::command]
+pub async fn create_stock_adjustment(
+    registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
+    data: CreateStockAdjustment,
+) -> Result<String, String> {
+    let pool = registry.active_pool().await?;
+    ensure_period_open(&pool, &data.voucher_date).await?;
+    validate_items(&data.items)?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let branch_id = session_store.active_branch_id();
+    let voucher_no = get_next_voucher_number_in_tx(
+        &mut tx,
+        "stock_adjustment",
+        &data.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
+    let voucher_id = Uuid::now_v7().to_string();
+
+    sqlx::query(
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, total_amount, narration, status, created_by, branch_id)
+         VALUES (?, ?, 'stock_adjustment', ?, 0, ?, 'posted', ?, ?)",
+    )
+    .bind(&voucher_id)
+    .bind(&voucher_no)
+    .bind(&data.voucher_date)
+    .bind(&data.reason)
+    .bind(&data.user_id)
+    .bind(&branch_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut surplus_value = 0.0;
+    let mut shrinkage_value = 0.0;
+    for item in &data.items {
+        let rate = match item.rate {
+            Some(rate) => rate,
+            None => get_product_purchase_cost_rate(&mut tx, &item.product_id).await?,
+        };
+        let amount = item.quantity * rate;
+        let movement_type = if item.direction == "in" {
+            surplus_value += amount;
+            "ADJUSTMENT_IN"
+        } else {
+            shrinkage_value += amount;
+            "ADJUSTMENT_OUT"
+        };
+
+        sqlx::query(
+            "INSERT INTO voucher_items (id, voucher_id, product_id, initial_quantity, count, base_quantity, rate, amount, remarks)
+             VALUES (?, ?, ?, ?, 0, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(&voucher_id)
+        .bind(&item.product_id)
+        .bind(item.quantity)
+        .bind(item.quantity)
+        .bind(rate)
+        .bind(amount)
+        .bind(&item.direction)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        sqlx::query(
+            "INSERT INTO stock_movements (id, voucher_id, product_id, movement_type, quantity, count, rate, amount, cost_rate, cost_amount)
+             VALUES (?, ?, ?, ?, ?, 0, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::now_v7().to_string())
+        .bind(&voucher_id)
+        .bind(&item.product_id)
+        .bind(movement_type)
+        .bind(item.quantity)
+        .bind(rate)
+        .bind(amount)
+        .bind(rate)
+        .bind(amount)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    sqlx::query("UPDATE vouchers SET total_amount = ? WHERE id = ?")
+        .bind(surplus_value + shrinkage_value)
+        .bind(&voucher_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Surplus: Dr Inventory / Cr Stock Adjustment; shrinkage the reverse
+    let inventory_account = resolve_system_account(&mut *tx, "inventory").await?;
+    let adjustment_account = resolve_system_account(&mut *tx, "stock_adjustment").await?;
+    let mut journal_lines = Vec::new();
+    for (value, debit_account, credit_account) in [
+        (surplus_value, &inventory_account, &adjustment_account),
+        (shrinkage_value, &adjustment_account, &inventory_account),
+    ] {
+        if value <= 0.0 {
+            continue;
+        }
+        journal_lines.push(JournalLine {
+            account_id: debit_account.clone(),
+            debit: value,
+            credit: 0.0,
+            is_manual: false,
+            narration: data.reason.clone(),
+        });
+        journal_lines.push(JournalLine {
+            account_id: credit_account.clone(),
+            debit: 0.0,
+            credit: value,
+            is_manual: false,
+            narration: data.reason.clone(),
+        });
+    }
+    insert_journal_lines(&mut tx, &voucher_id, &journal_lines).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(voucher_id)
+}
@@ -4,7 +4,9 @@ use std::sync::Arc;
 use tauri::State;
 use uuid::Uuid;
 
+use super::auth::SessionStore;
 use super::resolve_voucher_line_unit;
+use super::year_end::{ensure_period_open, ensure_voucher_unlocked};
 use crate::voucher_seq::get_next_voucher_number;
 
 #[derive(Serialize, Deserialize, sqlx::FromRow)]
@@ -201,9 +203,11 @@ pub async fn get_stock_journal_items(
 #[tauri::command]
 pub async fn create_stock_journal(
     registry: State<'_, Arc<DbRegistry>>,
+    session_store: State<'_, SessionStore>,
     data: CreateStockJournal,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    ensure_period_open(&pool, &data.voucher_date).await?;
     validate_items(&data.source_items, "source")?;
     validate_items(&data.destination_items, "destination")?;
 
@@ -216,12 +220,19 @@ pub async fn create_stock_journal(
     }
 
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
-    let voucher_no = get_next_voucher_number(&pool, "stock_journal").await?;
+    let branch_id = session_store.active_branch_id();
+    let voucher_no = get_next_voucher_number(
+        &pool,
+        "stock_journal",
+        &data.voucher_date,
+        branch_id.as_deref(),
+    )
+    .await?;
     let voucher_id = Uuid::now_v7().to_string();
 
     sqlx::query(
-        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, total_amount, narration, status, created_by)
-         VALUES (?, ?, 'stock_journal', ?, ?, ?, 'posted', ?)",
+        "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, total_amount, narration, status, created_by, branch_id)
+         VALUES (?, ?, 'stock_journal', ?, ?, ?, 'posted', ?, ?)",
     )
     .bind(&voucher_id)
     .bind(&voucher_no)
@@ -229,6 +240,7 @@ pub async fn create_stock_journal(
     .bind(destination_total)
     .bind(&data.narration)
     .bind(&data.user_id)
+    .bind(&branch_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -254,6 +266,8 @@ pub async fn update_stock_journal(
     data: CreateStockJournal,
 ) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
+    ensure_period_open(&pool, &data.voucher_date).await?;
     validate_items(&data.source_items, "source")?;
     validate_items(&data.destination_items, "destination")?;
 
@@ -309,6 +323,7 @@ pub async fn update_stock_journal(
 #[tauri::command]
 pub async fn delete_stock_journal(registry: State<'_, Arc<DbRegistry>>, id: String) -> Result<(), String> {
     let pool = registry.active_pool().await?;
+    ensure_voucher_unlocked(&pool, &id).await?;
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
 
     sqlx::query("DELETE FROM stock_movements WHERE voucher_id = ?")
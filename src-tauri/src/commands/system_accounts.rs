@@ -0,0 +1,163 @@
+use crate::company_db::DbRegistry;
+use serde::Serialize;
+use sqlx::SqliteExecutor;
+use std::sync::Arc;
+use tauri::State;
+
+// ============= SYSTEM ACCOUNTS =============
+// Posting logic looks up its fixed ledgers (sales, purchases, tax, discounts, ...)
+// by purpose rather than by account code, so a company can rename, renumber or
+// remap them. Unmapped purposes fall back to the seeded default code.
+
+/// (purpose, default account code, label)
+pub const SYSTEM_ACCOUNT_PURPOSES: &[(&str, &str, &str)] = &[
+    ("inventory", "1004", "Inventory"),
+    ("input_tax", "1005", "Input Tax"),
+    ("output_tax", "2002", "Output Tax"),
+    ("retained_earnings", "3002", "Retained Earnings"),
+    (
+        "opening_balance_adjustment",
+        "3004",
+        "Opening Balance Adjustment",
+    ),
+    ("sales", "4001", "Sales"),
+    ("service_income", "4002", "Service Income"),
+    ("sales_returns", "4003", "Sales Returns"),
+    ("discount_received", "4004", "Discount Received"),
+    ("purchases", "5001", "Purchases"),
+    ("cost_of_goods_sold", "5002", "Cost of Goods Sold"),
+    ("purchase_returns", "5003", "Purchase Returns"),
+    ("discount_allowed", "5007", "Discount Allowed"),
+    ("service_expenses", "5011", "Service Expenses"),
+    ("round_off", "5012", "Round Off"),
+    ("stock_adjustment", "5013", "Stock Adjustment"),
+];
+
+fn purpose_entry(
+    purpose: &str,
+) -> Result<&'static (&'static str, &'static str, &'static str), String> {
+    SYSTEM_ACCOUNT_PURPOSES
+        .iter()
+        .find(|(p, _, _)| *p == purpose)
+        .ok_or_else(|| format!("Unknown system account purpose '{}'", purpose))
+}
+
+/// Account id mapped to `purpose`, falling back to the default account code.
+/// None when neither resolves to a live account.
+pub(crate) async fn find_system_account<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    purpose: &str,
+) -> Result<Option<String>, String> {
+    let (_, default_code, _) = purpose_entry(purpose)?;
+    sqlx::query_scalar::<_, Option<String>>(
+        "SELECT COALESCE(
+            (SELECT sa.account_id FROM system_accounts sa
+             JOIN chart_of_accounts coa ON coa.id = sa.account_id
+             WHERE sa.purpose = ? AND coa.deleted_at IS NULL),
+            (SELECT id FROM chart_of_accounts
+             WHERE account_code = ? AND deleted_at IS NULL LIMIT 1)
+        )",
+    )
+    .bind(purpose)
+    .bind(default_code)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Like `find_system_account`, but a missing account is an error naming the purpose.
+pub(crate) async fn resolve_system_account<'e, E: SqliteExecutor<'e>>(
+    executor: E,
+    purpose: &str,
+) -> Result<String, String> {
+    let (_, _, label) = purpose_entry(purpose)?;
+    find_system_account(executor, purpose)
+        .await?
+        .ok_or_else(|| {
+            format!(
+                "No account is set for '{}'. Map one under system accounts.",
+                label
+            )
+        })
+}
+
+#[derive(Serialize)]
+pub struct SystemAccount {
+    pub purpose: String,
+    pub label: String,
+    pub default_code: String,
+    pub account_id: Option<String>,
+    pub account_code: Option<String>,
+    pub account_name: Option<String>,
+}
+
+/// Every posting purpose with the account it currently resolves to.
+#[tauri::command]
+pub async fn get_system_accounts(
+    registry: State<'_, Arc<DbRegistry>>,
+) -> Result<Vec<SystemAccount>, String> {
+    let pool = registry.active_pool().await?;
+
+    let mut accounts = Vec::with_capacity(SYSTEM_ACCOUNT_PURPOSES.len());
+    for (purpose, default_code, label) in SYSTEM_ACCOUNT_PURPOSES {
+        let account_id = find_system_account(&pool, purpose).await?;
+        let details: Option<(String, String)> = match &account_id {
+            Some(id) => sqlx::query_as(
+                "SELECT account_code, account_name FROM chart_of_accounts WHERE id = ?",
+            )
+            .bind(id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| e.to_string())?,
+            None => None,
+        };
+        let (account_code, account_name) = details.unzip();
+
+        accounts.push(SystemAccount {
+            purpose: purpose.to_string(),
+            label: label.to_string(),
+            default_code: default_code.to_string(),
+            account_id,
+            account_code,
+            account_name,
+        });
+    }
+
+    Ok(accounts)
+}
+
+/// Maps `purpose` to `account_id`. Existing vouchers keep their postings; only
+/// new postings use the new account.
+#[tauri::command]
+pub async fn set_system_account(
+    registry: State<'_, Arc<DbRegistry>>,
+    purpose: String,
+    account_id: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    purpose_entry(&purpose)?;
+
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM chart_of_accounts WHERE id = ? AND deleted_at IS NULL)",
+    )
+    .bind(&account_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err("Account not found".to_string());
+    }
+
+    sqlx::query(
+        "INSERT INTO system_accounts (purpose, account_id, updated_at)
+         VALUES (?, ?, CURRENT_TIMESTAMP)
+         ON CONFLICT(purpose) DO UPDATE SET account_id = excluded.account_id, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&purpose)
+    .bind(&account_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
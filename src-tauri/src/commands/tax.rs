@@ -255,6 +255,7 @@ pub async fn get_gstr1_summary(
          WHERE v.voucher_type = 'sales_invoice'
            AND v.voucher_date BETWEEN ? AND ?
            AND v.deleted_at IS NULL
+           AND COALESCE(v.status, 'posted') = 'posted'
          GROUP BY v.id, vi.hsn_sac_code, vi.resolved_gst_rate, p.name, vi.description, u.symbol
          ORDER BY v.voucher_date ASC, v.voucher_no ASC, vi.hsn_sac_code",
     )
@@ -310,7 +311,8 @@ pub async fn get_gstr3b_summary(
          JOIN vouchers v ON vi.voucher_id = v.id
          WHERE v.voucher_type = 'sales_invoice'
            AND v.voucher_date BETWEEN ? AND ?
-           AND v.deleted_at IS NULL",
+           AND v.deleted_at IS NULL
+           AND COALESCE(v.status, 'posted') = 'posted'",
     )
     .bind(&from_date)
     .bind(&to_date)
@@ -329,7 +331,8 @@ pub async fn get_gstr3b_summary(
          JOIN vouchers v ON vi.voucher_id = v.id
          WHERE v.voucher_type = 'purchase_invoice'
            AND v.voucher_date BETWEEN ? AND ?
-           AND v.deleted_at IS NULL",
+           AND v.deleted_at IS NULL
+           AND COALESCE(v.status, 'posted') = 'posted'",
     )
     .bind(&from_date)
     .bind(&to_date)
@@ -351,3 +354,94 @@ pub async fn get_gstr3b_summary(
         "net_igst":         out.3 - inp.3,
     }))
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct TaxRateSummary {
+    pub tax_rate: f64,
+    pub sales_invoice_count: i64,
+    pub purchase_invoice_count: i64,
+    pub taxable_sales: f64,
+    pub output_tax: f64,
+    pub taxable_purchases: f64,
+    pub input_tax: f64,
+    pub net_tax: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TaxSummary {
+    pub rates: Vec<TaxRateSummary>,
+    pub total_output_tax: f64,
+    pub total_input_tax: f64,
+    /// Positive when tax is payable, negative when input credit exceeds output tax.
+    pub net_tax_payable: f64,
+}
+
+/// Output tax collected on sales vs input tax paid on purchases, bucketed by line tax rate.
+#[tauri::command]
+pub async fn get_tax_summary(
+    registry: State<'_, Arc<DbRegistry>>,
+    from_date: String,
+    to_date: String,
+) -> Result<TaxSummary, String> {
+    let pool = registry.active_pool().await?;
+    // Each row: (tax_rate, sales_count, purchase_count, taxable_sales, output_tax, taxable_purchases, input_tax)
+    let rows: Vec<(f64, i64, i64, f64, f64, f64, f64)> = sqlx::query_as(
+        "SELECT
+            CAST(COALESCE(vi.tax_rate, 0) AS REAL)                                              AS tax_rate,
+            COUNT(DISTINCT CASE WHEN v.voucher_type = 'sales_invoice' THEN v.id END)            AS sales_count,
+            COUNT(DISTINCT CASE WHEN v.voucher_type = 'purchase_invoice' THEN v.id END)         AS purchase_count,
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_type = 'sales_invoice' THEN vi.amount END), 0) AS REAL),
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_type = 'sales_invoice' THEN vi.tax_amount END), 0) AS REAL),
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_type = 'purchase_invoice' THEN vi.amount END), 0) AS REAL),
+            CAST(COALESCE(SUM(CASE WHEN v.voucher_type = 'purchase_invoice' THEN vi.tax_amount END), 0) AS REAL)
+         FROM voucher_items vi
+         JOIN vouchers v ON vi.voucher_id = v.id
+         WHERE v.voucher_type IN ('sales_invoice', 'purchase_invoice')
+           AND v.voucher_date BETWEEN ? AND ?
+           AND v.deleted_at IS NULL
+           AND COALESCE(v.status, 'posted') = 'posted'
+         GROUP BY COALESCE(vi.tax_rate, 0)
+         ORDER BY tax_rate ASC",
+    )
+    .bind(&from_date)
+    .bind(&to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let rates: Vec<TaxRateSummary> = rows
+        .into_iter()
+        .map(
+            |(
+                tax_rate,
+                sales_count,
+                purchase_count,
+                taxable_sales,
+                output_tax,
+                taxable_purchases,
+                input_tax,
+            )| {
+                TaxRateSummary {
+                    tax_rate,
+                    sales_invoice_count: sales_count,
+                    purchase_invoice_count: purchase_count,
+                    taxable_sales,
+                    output_tax,
+                    taxable_purchases,
+                    input_tax,
+                    net_tax: output_tax - input_tax,
+                }
+            },
+        )
+        .collect();
+
+    let total_output_tax: f64 = rates.iter().map(|r| r.output_tax).sum();
+    let total_input_tax: f64 = rates.iter().map(|r| r.input_tax).sum();
+
+    Ok(TaxSummary {
+        rates,
+        total_output_tax,
+        total_input_tax,
+        net_tax_payable: total_output_tax - total_input_tax,
+    })
+}
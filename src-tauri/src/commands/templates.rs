@@ -224,17 +224,28 @@ pub async fn render_invoice(
     template_id: Option<String>,
 ) -> Result<String, String> {
     let pool = registry.active_pool().await?;
+    render_invoice_html(&pool, voucher_id, voucher_type, template_id).await
+}
+
+/// Full HTML for a voucher rendered through `template_id`, or the voucher
+/// type's default template. Shared by render_invoice and generate_invoice_pdf.
+pub(crate) async fn render_invoice_html(
+    pool: &SqlitePool,
+    voucher_id: String,
+    voucher_type: String,
+    template_id: Option<String>,
+) -> Result<String, String> {
     // 1. Get template
     let template = if let Some(tid) = template_id {
         sqlx::query_as::<_, InvoiceTemplate>(
             "SELECT * FROM invoice_templates WHERE id = ? AND is_active = 1",
         )
         .bind(tid)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .map_err(|e| e.to_string())?
     } else {
-        get_template_by_voucher_type(&pool, voucher_type.clone())
+        get_template_by_voucher_type(pool, voucher_type.clone())
             .await?
             .ok_or_else(|| "No template found for voucher type".to_string())?
     };
@@ -264,18 +275,18 @@ pub async fn render_invoice(
     }
 
     // 2. Get company profile
-    let company = crate::commands::company::get_company_profile_with_pool(&pool)
+    let company = crate::commands::company::get_company_profile_with_pool(pool)
         .await
         .map_err(|e| e.to_string())?;
 
     // 3. Get voucher data
     let voucher_data = match voucher_type.as_str() {
-        "purchase_invoice" => get_purchase_invoice_data(&pool, voucher_id).await?,
-        "sales_invoice" => get_sales_invoice_data(&pool, voucher_id).await?,
-        "sales_quotation" => get_sales_quotation_data(&pool, voucher_id).await?,
-        "sales_return" => get_sales_return_data(&pool, voucher_id).await?,
-        "payment" => get_payment_data(&pool, voucher_id).await?,
-        "receipt" => get_receipt_data(&pool, voucher_id).await?,
+        "purchase_invoice" => get_purchase_invoice_data(pool, voucher_id).await?,
+        "sales_invoice" => get_sales_invoice_data(pool, voucher_id).await?,
+        "sales_quotation" => get_sales_quotation_data(pool, voucher_id).await?,
+        "sales_return" => get_sales_return_data(pool, voucher_id).await?,
+        "payment" => get_payment_data(pool, voucher_id).await?,
+        "receipt" => get_receipt_data(pool, voucher_id).await?,
         _ => return Err("Unsupported voucher type".to_string()),
     };
 
@@ -639,7 +650,7 @@ async fn get_purchase_invoice_data(
 ) -> Result<serde_json::Value, String> {
     let invoice = crate::commands::invoices::get_purchase_invoice_with_pool(pool, &id).await?;
     let items =
-        crate::commands::invoices::get_purchase_invoice_items_with_pool(pool, &id).await?;
+        crate::commands::invoices::get_purchase_invoice_items_with_pool(pool, &id, None).await?;
 
     let coa_details: Option<(Option<String>, String, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>)> = sqlx::query_as(
         "SELECT party_id, account_name, gstin, address_line_1, state, city, postal_code FROM chart_of_accounts WHERE id = ?"
@@ -929,7 +940,7 @@ async fn get_sales_invoice_data(
 ) -> Result<serde_json::Value, String> {
     let invoice = crate::commands::invoices::get_sales_invoice_with_pool(pool, &id).await?;
     let items =
-        crate::commands::invoices::get_sales_invoice_items_with_pool(pool, &id).await?;
+        crate::commands::invoices::get_sales_invoice_items_with_pool(pool, &id, None).await?;
 
     let coa_details: Option<(Option<String>, String, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>)> = sqlx::query_as(
         "SELECT party_id, account_name, gstin, address_line_1, state, city, postal_code FROM chart_of_accounts WHERE id = ?"
@@ -0,0 +1,402 @@
+use crate::company_db::DbRegistry;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tauri::State;
+use uuid::Uuid;
+
+use super::company::financial_year_start_month;
+use super::entries::{insert_journal_lines, JournalLine};
+use super::settings::financial_year_from_date;
+use super::system_accounts::resolve_system_account;
+
+/// Voucher type of the system-posted entry that moves a year's P&L into Retained Earnings.
+/// Profit & loss reports skip it so the closed year still shows its own result.
+pub(crate) const YEAR_END_CLOSE_VOUCHER: &str = "year_end_close";
+
+// ============= FINANCIAL YEAR CLOSING =============
+#[derive(Serialize, Deserialize)]
+pub struct FinancialYearClosing {
+    pub fy_end_date: String,
+    /// Year label the closing balances were carried forward into (e.g. "26-27")
+    pub carry_forward_year: String,
+    pub closing_voucher_id: Option<String>,
+    pub net_profit: f64,
+    pub locked_vouchers: u64,
+    /// Balance-sheet accounts with a non-zero closing balance. No carry-forward entries
+    /// are posted: ledgers run across years from the posted journals, so these accounts
+    /// open the next year at their closing balance.
+    pub carried_forward_accounts: usize,
+}
+
+/// Latest closed year end, if any year has been closed.
+async fn last_closed_year_end(pool: &SqlitePool) -> Result<Option<String>, String> {
+    sqlx::query_scalar("SELECT MAX(fy_end_date) FROM financial_year_closings")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Rejects posting a voucher dated inside a closed financial year.
+pub(crate) async fn ensure_period_open(
+    pool: &SqlitePool,
+    voucher_date: &str,
+) -> Result<(), String> {
+    let date = voucher_date.get(..10).unwrap_or(voucher_date);
+    if let Some(closed) = last_closed_year_end(pool).await? {
+        if date <= closed.as_str() {
+            return Err(format!(
+                "The financial year ending {} is closed. Vouchers dated {} cannot be posted until it is reopened.",
+                closed, date
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects changes to a voucher that belongs to a closed financial year.
+pub(crate) async fn ensure_voucher_unlocked(
+    pool: &SqlitePool,
+    voucher_id: &str,
+) -> Result<(), String> {
+    let locked: Option<(String, i64)> =
+        sqlx::query_as("SELECT voucher_no, COALESCE(locked, 0) FROM vouchers WHERE id = ?")
+            .bind(voucher_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    match locked {
+        Some((voucher_no, 1)) => Err(format!(
+            "Voucher {} is in a closed financial year and cannot be changed. Reopen the year to make corrections.",
+            voucher_no
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Closes the financial year ending `fy_end_date`, which must be the day before the
+/// configured financial year start:
+/// 1. posts a closing voucher that zeroes every Income/Expense account into Retained Earnings,
+/// 2. locks every voucher dated on or before the year end.
+///
+/// The closing voucher is numbered after the year end rather than from the journal series.
+#[tauri::command]
+pub async fn close_financial_year(
+    registry: State<'_, Arc<DbRegistry>>,
+    fy_end_date: String,
+) -> Result<FinancialYearClosing, String> {
+    let pool = registry.active_pool().await?;
+    close_financial_year_with_pool(&pool, fy_end_date).await
+}
+
+pub(crate) async fn close_financial_year_with_pool(
+    pool: &SqlitePool,
+    fy_end_date: String,
+) -> Result<FinancialYearClosing, String> {
+    let fy_end = NaiveDate::parse_from_str(&fy_end_date, "%Y-%m-%d")
+        .map_err(|_| "Year end date must be in YYYY-MM-DD format".to_string())?;
+    let next_day = fy_end
+        .succ_opt()
+        .ok_or_else(|| format!("Cannot carry balances forward past {}", fy_end))?;
+    let start_month = financial_year_start_month(pool).await?;
+    if next_day.day() != 1 || next_day.month() != start_month {
+        return Err(format!(
+            "{} is not a financial year end; the financial year starts in month {}",
+            fy_end, start_month
+        ));
+    }
+    let carry_forward_year =
+        financial_year_from_date(&next_day.format("%Y-%m-%d").to_string(), start_month);
+
+    if let Some(closed) = last_closed_year_end(pool).await? {
+        if fy_end_date <= closed {
+            return Err(format!(
+                "The financial year ending {} is already closed",
+                closed
+            ));
+        }
+    }
+
+    let retained_earnings_id = resolve_system_account(pool, "retained_earnings").await?;
+
+    // Net (Dr - Cr) of every P&L account up to the year end. Earlier closings have already
+    // zeroed prior years, so this is the result of the year being closed.
+    let pl_balances = sqlx::query_as::<_, (String, f64)>(
+        "SELECT coa.id, CAST(SUM(je.debit - je.credit) AS REAL) as balance
+         FROM journal_entries je
+         JOIN chart_of_accounts coa ON je.account_id = coa.id
+         JOIN vouchers v ON je.voucher_id = v.id
+         WHERE coa.account_type IN ('Income', 'Expense')
+           AND v.voucher_date <= ? AND v.deleted_at IS NULL
+         GROUP BY coa.id
+         HAVING ABS(balance) >= 0.005",
+    )
+    .bind(&fy_end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let narration = format!("Year-end closing for the year ending {}", fy_end_date);
+    let mut lines: Vec<JournalLine> = pl_balances
+        .iter()
+        .map(|(account_id, balance)| JournalLine {
+            account_id: account_id.clone(),
+            debit: if *balance < 0.0 { -balance } else { 0.0 },
+            credit: if *balance > 0.0 { *balance } else { 0.0 },
+            is_manual: false,
+            narration: Some(narration.clone()),
+        })
+        .collect();
+    let net_profit = -pl_balances.iter().map(|(_, b)| b).sum::<f64>();
+    if net_profit.abs() >= 0.005 {
+        lines.push(JournalLine {
+            account_id: retained_earnings_id,
+            debit: if net_profit < 0.0 { -net_profit } else { 0.0 },
+            credit: if net_profit > 0.0 { net_profit } else { 0.0 },
+            is_manual: false,
+            narration: Some(narration.clone()),
+        });
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let closing_voucher_id = if lines.is_empty() {
+        None
+    } else {
+        let voucher_id = Uuid::now_v7().to_string();
+        let voucher_no = format!("YE-{}", fy_end_date);
+        let total: f64 = lines.iter().map(|l| l.debit).sum();
+        sqlx::query(
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date, narration, status, total_amount)
+             VALUES (?, ?, ?, ?, ?, 'posted', ?)",
+        )
+        .bind(&voucher_id)
+        .bind(&voucher_no)
+        .bind(YEAR_END_CLOSE_VOUCHER)
+        .bind(&fy_end_date)
+        .bind(&narration)
+        .bind(total)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        insert_journal_lines(&mut tx, &voucher_id, &lines).await?;
+        Some(voucher_id)
+    };
+
+    // Closing balances of balance-sheet accounts, including the transfer posted above.
+    // Opening balances are posted as journals, so the journals alone give the balance.
+    let closing_balances = sqlx::query_as::<_, (String, f64)>(
+        "SELECT coa.id, CAST(SUM(je.debit - je.credit) AS REAL) as balance
+         FROM chart_of_accounts coa
+         JOIN journal_entries je ON je.account_id = coa.id
+         JOIN vouchers v ON je.voucher_id = v.id AND v.deleted_at IS NULL AND v.voucher_date <= ?
+         WHERE coa.account_type IN ('Asset', 'Liability', 'Equity') AND coa.deleted_at IS NULL
+         GROUP BY coa.id
+         HAVING ABS(balance) >= 0.005",
+    )
+    .bind(&fy_end_date)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let locked_vouchers = sqlx::query(
+        "UPDATE vouchers SET locked = 1
+         WHERE voucher_date <= ? AND deleted_at IS NULL AND voucher_type != 'sales_quotation'",
+    )
+    .bind(&fy_end_date)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .rows_affected();
+
+    sqlx::query(
+        "INSERT INTO financial_year_closings (id, fy_end_date, closing_voucher_id, net_profit, carry_forward_year)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::now_v7().to_string())
+    .bind(&fy_end_date)
+    .bind(&closing_voucher_id)
+    .bind(net_profit)
+    .bind(&carry_forward_year)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(FinancialYearClosing {
+        fy_end_date,
+        carry_forward_year,
+        closing_voucher_id,
+        net_profit,
+        locked_vouchers,
+        carried_forward_accounts: closing_balances.len(),
+    })
+}
+
+/// Undoes `close_financial_year` for the latest closed year: unlocks its vouchers and removes
+/// the closing voucher, so closing the year again can reuse its number.
+#[tauri::command]
+pub async fn reopen_financial_year(
+    registry: State<'_, Arc<DbRegistry>>,
+    fy_end_date: String,
+) -> Result<(), String> {
+    let pool = registry.active_pool().await?;
+    reopen_financial_year_with_pool(&pool, fy_end_date).await
+}
+
+pub(crate) async fn reopen_financial_year_with_pool(
+    pool: &SqlitePool,
+    fy_end_date: String,
+) -> Result<(), String> {
+    let (closing_id, closing_voucher_id): (String, Option<String>) = sqlx::query_as(
+        "SELECT id, closing_voucher_id FROM financial_year_closings WHERE fy_end_date = ?",
+    )
+    .bind(&fy_end_date)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("The financial year ending {} is not closed", fy_end_date))?;
+
+    if let Some(closed) = last_closed_year_end(pool).await? {
+        if closed > fy_end_date {
+            return Err(format!("Reopen the financial year ending {} first", closed));
+        }
+    }
+
+    // Vouchers of earlier closed years stay locked
+    let previous_close: Option<String> = sqlx::query_scalar(
+        "SELECT MAX(fy_end_date) FROM financial_year_closings WHERE fy_end_date < ?",
+    )
+    .bind(&fy_end_date)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let unlock_query = format!(
+        "UPDATE vouchers SET locked = 0 WHERE voucher_date <= ? {}",
+        if previous_close.is_some() {
+            "AND voucher_date > ?"
+        } else {
+            ""
+        }
+    );
+    let mut unlock = sqlx::query(&unlock_query).bind(&fy_end_date);
+    if let Some(previous) = &previous_close {
+        unlock = unlock.bind(previous);
+    }
+    unlock.execute(&mut *tx).await.map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM financial_year_closings WHERE id = ?")
+        .bind(&closing_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(voucher_id) = &closing_voucher_id {
+        sqlx::query("DELETE FROM journal_entries WHERE voucher_id = ?")
+            .bind(voucher_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query("DELETE FROM vouchers WHERE id = ? AND voucher_type = ?")
+            .bind(voucher_id)
+            .bind(YEAR_END_CLOSE_VOUCHER)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::accounts::{create_chart_of_account_with_pool, CreateChartOfAccount};
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn closing_counts_journal_balances_and_reopening_undoes_it() {
+        let pool = test_pool().await;
+        let account = create_chart_of_account_with_pool(
+            &pool,
+            CreateChartOfAccount {
+                account_code: "T-YE".to_string(),
+                account_name: "Year End Test".to_string(),
+                account_type: "Asset".to_string(),
+                account_group: "Current Assets".to_string(),
+                description: None,
+                opening_balance: Some(500.0),
+                opening_balance_type: Some("Dr".to_string()),
+                opening_balance_date: Some("2024-04-01".to_string()),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Move the whole opening balance into inventory, leaving the account at zero
+        let voucher_id = Uuid::now_v7().to_string();
+        sqlx::query(
+            "INSERT INTO vouchers (id, voucher_no, voucher_type, voucher_date)
+             VALUES (?, 'JV-T-YE', 'journal', '2024-06-01')",
+        )
+        .bind(&voucher_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let inventory = resolve_system_account(&pool, "inventory").await.unwrap();
+        let lines = [
+            JournalLine {
+                account_id: inventory,
+                debit: 500.0,
+                credit: 0.0,
+                is_manual: true,
+                narration: None,
+            },
+            JournalLine {
+                account_id: account.id.clone(),
+                debit: 0.0,
+                credit: 500.0,
+                is_manual: true,
+                narration: None,
+            },
+        ];
+        let mut tx = pool.begin().await.unwrap();
+        insert_journal_lines(&mut tx, &voucher_id, &lines)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let closing = close_financial_year_with_pool(&pool, "2025-03-31".to_string())
+            .await
+            .unwrap();
+        // Inventory and the opening balance adjustment; the emptied account is not carried
+        assert_eq!(closing.carried_forward_accounts, 2);
+        assert_eq!(closing.locked_vouchers, 2);
+        assert!(closing.closing_voucher_id.is_none());
+
+        reopen_financial_year_with_pool(&pool, "2025-03-31".to_string())
+            .await
+            .unwrap();
+        let (closings, locked): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM financial_year_closings),
+                    (SELECT COUNT(*) FROM vouchers WHERE locked = 1)",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((closings, locked), (0, 0));
+
+        close_financial_year_with_pool(&pool, "2025-03-31".to_string())
+            .await
+            .unwrap();
+    }
+}
@@ -372,6 +372,20 @@ impl DbRegistry {
         count.map(|(n,)| n > 0).unwrap_or(false)
     }
 
+    /// Close and forget the cached pool for a company.
+    /// Clears the active selection if it pointed at that company.
+    pub async fn close_company_pool(&self, company_id: &str) {
+        let pool = self.pools.write().await.remove(company_id);
+        if let Some(pool) = pool {
+            pool.close().await;
+        }
+
+        let mut active = self.active_id.write().await;
+        if active.as_deref() == Some(company_id) {
+            *active = None;
+        }
+    }
+
     /// Get info for the currently active company.
     pub async fn get_active_company_info(&self) -> Result<Option<CompanyInfo>, String> {
         let active_id = self.active_id.read().await;
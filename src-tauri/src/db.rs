@@ -1,3 +1,4 @@
+use crate::commands::reports::STOCK_OUT_TYPES;
 use sqlx::sqlite::SqlitePool;
 
 async fn backfill_stock_movement_costs(
@@ -31,7 +32,7 @@ async fn backfill_stock_movement_costs(
     .execute(pool)
     .await?;
 
-    sqlx::query(
+    sqlx::query(&format!(
         "UPDATE stock_movements
          SET cost_rate = COALESCE((
                  SELECT sm_sale.cost_rate
@@ -41,7 +42,7 @@ async fn backfill_stock_movement_costs(
                      AND si.deleted_at IS NULL
                  JOIN stock_movements sm_sale ON sm_sale.voucher_id = si.id
                      AND sm_sale.product_id = stock_movements.product_id
-                     AND sm_sale.movement_type = 'OUT'
+                     AND sm_sale.movement_type IN ({out_types})
                  WHERE sr.id = stock_movements.voucher_id
                    AND sr.voucher_type = 'sales_return'
                    AND sr.deleted_at IS NULL
@@ -56,7 +57,7 @@ async fn backfill_stock_movement_costs(
                      AND si.deleted_at IS NULL
                  JOIN stock_movements sm_sale ON sm_sale.voucher_id = si.id
                      AND sm_sale.product_id = stock_movements.product_id
-                     AND sm_sale.movement_type = 'OUT'
+                     AND sm_sale.movement_type IN ({out_types})
                  WHERE sr.id = stock_movements.voucher_id
                    AND sr.voucher_type = 'sales_return'
                    AND sr.deleted_at IS NULL
@@ -66,7 +67,8 @@ async fn backfill_stock_movement_costs(
          WHERE voucher_id IN (
              SELECT id FROM vouchers WHERE voucher_type = 'sales_return'
          )",
-    )
+        out_types = STOCK_OUT_TYPES,
+    ))
     .execute(pool)
     .await?;
 
@@ -114,6 +116,19 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
     .execute(pool)
     .await?;
 
+    // States / provinces (keyed by ISO country code)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS states (
+            id TEXT PRIMARY KEY,
+            country_code TEXT NOT NULL,
+            name TEXT NOT NULL,
+            code TEXT,
+            UNIQUE(country_code, name)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
     // Currencies
     println!("DB: Creating currencies table...");
     sqlx::query(
@@ -216,6 +231,16 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
     let _ = sqlx::query("ALTER TABLE products ADD COLUMN barcode TEXT")
         .execute(pool)
         .await;
+    // Barcodes are unique when set; blank strings are stored as NULL so they never collide.
+    // Left best-effort so a database that already holds duplicates still opens.
+    let _ = sqlx::query("UPDATE products SET barcode = NULL WHERE TRIM(barcode) = ''")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_products_barcode ON products(barcode) WHERE barcode IS NOT NULL",
+    )
+    .execute(pool)
+    .await;
 
     // Migration: Add brand_id to products if not exists
     let _ = sqlx::query("ALTER TABLE products ADD COLUMN brand_id TEXT REFERENCES product_brands(id)")
@@ -416,6 +441,14 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
         .execute(pool)
         .await;
 
+    // Migration: invoice currency (NULL = company base currency) and rate to base currency
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN currency TEXT")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN exchange_rate REAL DEFAULT 1.0")
+        .execute(pool)
+        .await;
+
     // Migration: Add GST split columns to vouchers if not exists
     let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN cgst_amount REAL DEFAULT 0")
         .execute(pool)
@@ -437,6 +470,30 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
         .execute(pool)
         .await;
 
+    // Sales returns: the sales invoice a return was raised against
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN original_invoice_id TEXT")
+        .execute(pool)
+        .await;
+
+    // Migration: invoice grand total in the company's base currency, for reports that
+    // add up invoices across currencies. Other vouchers are always in base currency.
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN base_grand_total REAL")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query(
+        "UPDATE vouchers
+         SET base_grand_total = ROUND(COALESCE(grand_total, total_amount, 0) * COALESCE(exchange_rate, 1.0), 2)
+         WHERE base_grand_total IS NULL
+           AND voucher_type IN ('sales_invoice', 'purchase_invoice')",
+    )
+    .execute(pool)
+    .await;
+
+    // Overdue interest: last date interest was booked on an invoice
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN interest_posted_through TEXT")
+        .execute(pool)
+        .await;
+
     // Data fix: Backfill grand_total for payment/receipt vouchers where it was never stored (still 0).
     // We derive grand_total from the journal credit (payment) or debit (receipt) side which was
     // always correctly recorded. Falls back to total_amount if no journal entries exist.
@@ -655,6 +712,43 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
         .execute(pool)
         .await?;
 
+    // Ledger cache version: bumped by triggers on every posting change so the
+    // in-memory ledger opening-balance cache knows when to discard entries.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS ledger_cache_version (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            version INTEGER NOT NULL DEFAULT 0
+        )",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("INSERT OR IGNORE INTO ledger_cache_version (id, version) VALUES (1, 0)")
+        .execute(pool)
+        .await?;
+    let ledger_cache_triggers = [
+        "CREATE TRIGGER IF NOT EXISTS trg_ledger_cache_je_insert AFTER INSERT ON journal_entries
+         BEGIN UPDATE ledger_cache_version SET version = version + 1 WHERE id = 1; END",
+        "CREATE TRIGGER IF NOT EXISTS trg_ledger_cache_je_update AFTER UPDATE ON journal_entries
+         BEGIN UPDATE ledger_cache_version SET version = version + 1 WHERE id = 1; END",
+        "CREATE TRIGGER IF NOT EXISTS trg_ledger_cache_je_delete AFTER DELETE ON journal_entries
+         BEGIN UPDATE ledger_cache_version SET version = version + 1 WHERE id = 1; END",
+        "CREATE TRIGGER IF NOT EXISTS trg_ledger_cache_voucher_update AFTER UPDATE OF voucher_date, deleted_at ON vouchers
+         BEGIN UPDATE ledger_cache_version SET version = version + 1 WHERE id = 1; END",
+        "CREATE TRIGGER IF NOT EXISTS trg_ledger_cache_voucher_delete AFTER DELETE ON vouchers
+         BEGIN UPDATE ledger_cache_version SET version = version + 1 WHERE id = 1; END",
+    ];
+    for trigger in ledger_cache_triggers {
+        sqlx::query(trigger).execute(pool).await?;
+    }
+
+    // Migration: bank reconciliation status per journal line
+    let _ = sqlx::query("ALTER TABLE journal_entries ADD COLUMN reconciled INTEGER DEFAULT 0")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE journal_entries ADD COLUMN reconciled_date DATE")
+        .execute(pool)
+        .await;
+
     // Stock Movements
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS stock_movements (
@@ -717,6 +811,27 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_allocations_invoice ON payment_allocations(invoice_voucher_id)").execute(pool).await?;
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_allocations_party ON payment_allocations(party_id, party_type)").execute(pool).await?;
 
+    // Allocations of soft-deleted or unapproved payments/receipts, moved back on
+    // restore/approval
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS deleted_payment_allocations (
+            id TEXT PRIMARY KEY,
+            payment_voucher_id TEXT NOT NULL,
+            invoice_voucher_id TEXT NOT NULL,
+            allocated_amount REAL NOT NULL,
+            allocation_date DATE NOT NULL,
+            remarks TEXT,
+            party_id TEXT,
+            party_type TEXT,
+            created_at DATETIME,
+            deleted_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_deleted_allocations_payment ON deleted_payment_allocations(payment_voucher_id)").execute(pool).await?;
+
     // ==================== SETTINGS & CONFIG ====================
 
     // Invoice Templates
@@ -959,6 +1074,22 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
     let _ = sqlx::query("ALTER TABLE voucher_sequences ADD COLUMN reset_yearly INTEGER DEFAULT 0")
         .execute(pool)
         .await;
+    // FY of the last number issued; a yearly reset triggers when it changes
+    let _ = sqlx::query("ALTER TABLE voucher_sequences ADD COLUMN last_issued_fy TEXT")
+        .execute(pool)
+        .await;
+    // With a yearly reset, counters of years other than last_issued_fy, so a
+    // voucher dated into an earlier year continues that year's numbering
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS voucher_sequence_years (
+            voucher_type TEXT NOT NULL,
+            financial_year TEXT NOT NULL,
+            next_number INTEGER NOT NULL,
+            PRIMARY KEY (voucher_type, financial_year)
+        )",
+    )
+    .execute(pool)
+    .await?;
 
     sqlx::query(
         "INSERT OR IGNORE INTO voucher_sequences (id, voucher_type, prefix) VALUES
@@ -971,9 +1102,11 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
         ('vs_' || hex(randomblob(16)), 'payment', 'PAY'),
         ('vs_' || hex(randomblob(16)), 'receipt', 'RCP'),
         ('vs_' || hex(randomblob(16)), 'journal', 'JV'),
+        ('vs_' || hex(randomblob(16)), 'contra', 'CV'),
         ('vs_' || hex(randomblob(16)), 'opening_balance', 'OB'),
         ('vs_' || hex(randomblob(16)), 'opening_stock', 'OS'),
-        ('vs_' || hex(randomblob(16)), 'stock_journal', 'STJ')",
+        ('vs_' || hex(randomblob(16)), 'stock_journal', 'STJ'),
+        ('vs_' || hex(randomblob(16)), 'stock_adjustment', 'SADJ')",
     )
     .execute(pool)
     .await?;
@@ -1016,6 +1149,73 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
         .execute(pool)
         .await;
 
+    // Migration: Financial year start (YYYY-MM-DD), used as the default opening-balance date
+    let _ = sqlx::query("ALTER TABLE company_profile ADD COLUMN financial_year_start TEXT")
+        .execute(pool)
+        .await;
+
+    // Migration: Reject sales that would take a product's stock below zero
+    let _ = sqlx::query(
+        "ALTER TABLE company_profile ADD COLUMN prevent_negative_stock INTEGER DEFAULT 0",
+    )
+    .execute(pool)
+    .await;
+
+    // Migration: Round invoice grand totals to the nearest whole unit
+    let _ = sqlx::query(
+        "ALTER TABLE company_profile ADD COLUMN round_invoice_totals INTEGER DEFAULT 0",
+    )
+    .execute(pool)
+    .await;
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN round_off_amount REAL DEFAULT 0")
+        .execute(pool)
+        .await;
+
+    // Full-text voucher search (skipped where SQLite lacks FTS5)
+    let search_index_created = sqlx::query(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS voucher_search USING fts5(
+            voucher_id UNINDEXED, voucher_no, narration, reference, party_name
+        )",
+    )
+    .execute(pool)
+    .await
+    .is_ok();
+    if search_index_created {
+        let search_index_triggers = [
+            "CREATE TRIGGER IF NOT EXISTS trg_voucher_search_insert AFTER INSERT ON vouchers
+             WHEN NEW.deleted_at IS NULL
+             BEGIN
+                INSERT INTO voucher_search (voucher_id, voucher_no, narration, reference, party_name)
+                VALUES (NEW.id, NEW.voucher_no, NEW.narration, NEW.reference,
+                        (SELECT account_name FROM chart_of_accounts WHERE id = NEW.party_id));
+             END",
+            "CREATE TRIGGER IF NOT EXISTS trg_voucher_search_update
+             AFTER UPDATE OF voucher_no, narration, reference, party_id, deleted_at ON vouchers
+             BEGIN
+                DELETE FROM voucher_search WHERE voucher_id = OLD.id;
+                INSERT INTO voucher_search (voucher_id, voucher_no, narration, reference, party_name)
+                SELECT NEW.id, NEW.voucher_no, NEW.narration, NEW.reference,
+                       (SELECT account_name FROM chart_of_accounts WHERE id = NEW.party_id)
+                WHERE NEW.deleted_at IS NULL;
+             END",
+            "CREATE TRIGGER IF NOT EXISTS trg_voucher_search_delete AFTER DELETE ON vouchers
+             BEGIN DELETE FROM voucher_search WHERE voucher_id = OLD.id; END",
+        ];
+        for trigger in search_index_triggers {
+            sqlx::query(trigger).execute(pool).await?;
+        }
+
+        // First run: index vouchers that predate the table
+        let indexed: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM voucher_search)")
+            .fetch_one(pool)
+            .await?;
+        if !indexed {
+            sqlx::query(crate::commands::search_index::INDEX_VOUCHERS_SQL)
+                .execute(pool)
+                .await?;
+        }
+    }
+
     // Voucher Settings
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS voucher_settings (
@@ -1174,6 +1374,10 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
     let _ = sqlx::query("ALTER TABLE customers ADD COLUMN currency TEXT").execute(pool).await;
     let _ = sqlx::query("ALTER TABLE suppliers ADD COLUMN currency TEXT").execute(pool).await;
 
+    // Migration: credit limit per party (0 = unlimited)
+    let _ = sqlx::query("ALTER TABLE customers ADD COLUMN credit_limit REAL DEFAULT 0").execute(pool).await;
+    let _ = sqlx::query("ALTER TABLE suppliers ADD COLUMN credit_limit REAL DEFAULT 0").execute(pool).await;
+
     // Map existing text country to country ID
     let _ = sqlx::query(
         "UPDATE customers 
@@ -1370,6 +1574,16 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
         .execute(pool)
         .await;
 
+    // Migration: Add reorder_level to products (0 = no reorder tracking)
+    let _ = sqlx::query("ALTER TABLE products ADD COLUMN reorder_level REAL DEFAULT 0")
+        .execute(pool)
+        .await;
+
+    // Migration: Add default_tax_rate to products (prefilled on invoice lines)
+    let _ = sqlx::query("ALTER TABLE products ADD COLUMN default_tax_rate REAL DEFAULT 0")
+        .execute(pool)
+        .await;
+
     // ==================== PRODUCT IMAGES MIGRATION ====================
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS product_images (
@@ -1390,8 +1604,290 @@ pub async fn init_schema(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Er
     .execute(pool)
     .await?;
 
+    // ==================== VOUCHER ATTACHMENTS ====================
+    // stored_path points at the app-managed copy under the app data dir
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS voucher_attachments (
+            id TEXT PRIMARY KEY,
+            voucher_id TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            stored_path TEXT NOT NULL,
+            mime_type TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (voucher_id) REFERENCES vouchers(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_voucher_attachments_voucher ON voucher_attachments(voucher_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    // ==================== VOUCHER AUDIT LOG ====================
+    // No foreign key: the trail must outlive the voucher it describes
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS voucher_audit_log (
+            id TEXT PRIMARY KEY,
+            voucher_id TEXT NOT NULL,
+            action TEXT NOT NULL,
+            actor TEXT,
+            changed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            snapshot_json TEXT
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_voucher_audit_log_voucher ON voucher_audit_log(voucher_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    // ==================== VOUCHER APPROVAL ====================
+    // Draft/pending vouchers park their postings here until approved
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN approved_by TEXT")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN approved_at DATETIME")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN rejection_reason TEXT")
+        .execute(pool)
+        .await;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS held_journal_entries (
+            id TEXT PRIMARY KEY,
+            voucher_id TEXT NOT NULL,
+            account_id TEXT NOT NULL,
+            debit REAL DEFAULT 0,
+            credit REAL DEFAULT 0,
+            is_manual INTEGER DEFAULT 0,
+            narration TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (voucher_id) REFERENCES vouchers(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS held_stock_movements (
+            id TEXT PRIMARY KEY,
+            voucher_id TEXT NOT NULL,
+            product_id TEXT NOT NULL,
+            movement_type TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            count INTEGER DEFAULT 0,
+            rate REAL NOT NULL,
+            amount REAL NOT NULL,
+            cost_rate REAL DEFAULT 0,
+            cost_amount REAL DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (voucher_id) REFERENCES vouchers(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_held_journal_voucher ON held_journal_entries(voucher_id)").execute(pool).await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_held_stock_voucher ON held_stock_movements(voucher_id)").execute(pool).await?;
+
+    // ==================== RECURRING VOUCHERS ====================
+    // template holds the serialized CreatePayment / CreateReceipt body
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS recurring_vouchers (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            voucher_type TEXT NOT NULL CHECK (voucher_type IN ('payment', 'receipt')),
+            template TEXT NOT NULL,
+            frequency TEXT NOT NULL CHECK (frequency IN ('weekly', 'monthly', 'yearly')),
+            next_run_date DATE NOT NULL,
+            end_date DATE,
+            is_active INTEGER DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // One row per materialized occurrence; the unique key keeps generation idempotent
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS recurring_voucher_runs (
+            id TEXT PRIMARY KEY,
+            recurring_voucher_id TEXT NOT NULL,
+            run_date DATE NOT NULL,
+            voucher_id TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (recurring_voucher_id, run_date),
+            FOREIGN KEY (recurring_voucher_id) REFERENCES recurring_vouchers(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // Freight, duty etc. capitalised into a purchase invoice's stock cost
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS purchase_additional_costs (
+            id TEXT PRIMARY KEY,
+            voucher_id TEXT NOT NULL,
+            description TEXT,
+            amount REAL NOT NULL,
+            account_id TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (voucher_id) REFERENCES vouchers(id) ON DELETE CASCADE,
+            FOREIGN KEY (account_id) REFERENCES chart_of_accounts(id)
+        )",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_purchase_additional_costs_voucher ON purchase_additional_costs(voucher_id)").execute(pool).await?;
+
+    // Journal templates: lines_json holds the serialized CreateJournalEntryLine list
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS journal_entry_templates (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            reference TEXT,
+            narration TEXT,
+            lines_json TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // Financial year closing: vouchers dated on or before a closed year end are locked
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN locked INTEGER DEFAULT 0")
+        .execute(pool)
+        .await;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS financial_year_closings (
+            id TEXT PRIMARY KEY,
+            fy_end_date DATE NOT NULL UNIQUE,
+            closing_voucher_id TEXT,
+            net_profit REAL DEFAULT 0,
+            carry_forward_year TEXT NOT NULL,
+            closed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (closing_voucher_id) REFERENCES vouchers(id)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // Rate/MRP changes made through product edits
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS product_price_history (
+            id TEXT PRIMARY KEY,
+            product_id TEXT NOT NULL,
+            field TEXT NOT NULL,
+            old_value REAL,
+            new_value REAL,
+            changed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            changed_by TEXT,
+            FOREIGN KEY (product_id) REFERENCES products(id),
+            FOREIGN KEY (changed_by) REFERENCES users(id)
+        )",
+    )
+    .execute(pool)
+    .await?;
+    let _ = sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_product_price_history_product ON product_price_history(product_id, changed_at)",
+    )
+    .execute(pool)
+    .await;
+
+    // Negotiated per-party product rates (customer or supplier)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS party_price_list (
+            id TEXT PRIMARY KEY,
+            party_id TEXT NOT NULL,
+            party_type TEXT NOT NULL,
+            product_id TEXT NOT NULL,
+            rate REAL NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(party_id, party_type, product_id),
+            FOREIGN KEY (product_id) REFERENCES products(id)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // Branches: optional; vouchers record the session's active branch in branch_id
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS branches (
+            id TEXT PRIMARY KEY,
+            code TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            address TEXT,
+            is_active INTEGER DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            deleted_at DATETIME
+        )",
+    )
+    .execute(pool)
+    .await?;
+    let _ = sqlx::query("ALTER TABLE vouchers ADD COLUMN branch_id TEXT REFERENCES branches(id)")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE company_profile ADD COLUMN upi_id TEXT")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_vouchers_branch ON vouchers(branch_id)")
+        .execute(pool)
+        .await;
+    // The branch is passed from the session on insert; the company-wide trigger is gone
+    sqlx::query("DROP TRIGGER IF EXISTS trg_voucher_branch_insert")
+        .execute(pool)
+        .await?;
+
+    // Purpose -> ledger mapping used by posting logic (see commands::system_accounts)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS system_accounts (
+            purpose TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (account_id) REFERENCES chart_of_accounts(id)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // Migration: coerce legacy opening_balance_type values to canonical Dr/Cr;
+    // reports compare against exactly 'Dr'/'Cr'. Unknown values fall back to Dr.
+    let _ = sqlx::query(
+        "UPDATE chart_of_accounts
+         SET opening_balance_type = CASE WHEN LOWER(TRIM(opening_balance_type)) = 'cr' THEN 'Cr' ELSE 'Dr' END
+         WHERE opening_balance_type IS NULL OR opening_balance_type NOT IN ('Dr', 'Cr')",
+    )
+    .execute(pool)
+    .await;
+
     crate::seeds::seed_initial_data(pool).await?;
     crate::seeds::seed_handlebars_templates(pool).await?;
 
     Ok(())
 }
+
+/// Fresh in-memory company database with the full schema and seed data.
+/// A single connection keeps every query on the same in-memory database.
+#[cfg(test)]
+pub(crate) async fn test_pool() -> SqlitePool {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .expect("open in-memory database");
+    init_schema(&pool).await.expect("create schema");
+    pool
+}
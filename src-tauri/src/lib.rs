@@ -47,6 +47,9 @@ pub fn run() {
             let session_store = commands::auth::SessionStore::new();
             app.manage(session_store);
 
+            // Cache for ledger opening balances (invalidated via ledger_cache_version)
+            app.manage(commands::reports::LedgerOpeningCache::new());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -81,15 +84,26 @@ pub fn run() {
             delete_product_brand,
             // Products
             get_products,
+            get_product_by_barcode,
+            get_product_defaults,
             get_all_product_unit_conversions,
             get_product_unit_conversions,
+            get_product_conversion,
             create_product,
             batch_create_products,
+            import_products_csv,
             update_product,
             update_multiple_product_rates,
+            get_product_price_history,
+            set_party_price,
+            get_party_prices,
+            delete_party_price,
+            get_effective_sales_rate,
             delete_product,
+            bulk_delete_products,
             get_deleted_products,
             restore_product,
+            bulk_restore_products,
             hard_delete_product,
             get_next_product_code,
             upload_product_image,
@@ -114,6 +128,7 @@ pub fn run() {
             restore_customer,
             hard_delete_customer,
             get_next_customer_code,
+            merge_customers,
             // Suppliers
             get_suppliers,
             create_supplier,
@@ -124,6 +139,7 @@ pub fn run() {
             restore_supplier,
             hard_delete_supplier,
             get_next_supplier_code,
+            merge_suppliers,
             // Chart of Accounts
             get_chart_of_accounts,
             create_chart_of_account,
@@ -144,9 +160,12 @@ pub fn run() {
             get_purchase_invoices,
             get_purchase_invoice,
             get_purchase_invoice_items,
+            get_purchase_additional_costs,
             create_purchase_invoice,
+            check_duplicate_purchase_reference,
             update_purchase_invoice,
             delete_purchase_invoice,
+            draft_reorder_purchase,
             // Purchase Returns
             get_purchase_returns,
             get_purchase_return,
@@ -173,6 +192,7 @@ pub fn run() {
             get_sales_returns,
             get_sales_return,
             get_sales_return_items,
+            get_returns_for_invoice,
             create_sales_return,
             update_sales_return,
             delete_sales_return,
@@ -183,6 +203,8 @@ pub fn run() {
             get_payment,
             get_payment_items,
             delete_payment,
+            get_deleted_payments,
+            restore_payment,
             // Receipts
             create_receipt,
             update_receipt,
@@ -190,13 +212,29 @@ pub fn run() {
             get_receipt,
             get_receipt_items,
             delete_receipt,
+            get_deleted_receipts,
+            restore_receipt,
+            // Recurring Vouchers
+            create_recurring_voucher,
+            list_recurring_vouchers,
+            delete_recurring_voucher,
+            generate_due_recurring,
+            // Journal Templates
+            save_journal_template,
+            list_journal_templates,
+            delete_journal_template,
             // Journal Entries
             create_journal_entry,
+            create_journal_entries_batch,
             get_journal_entries,
+            get_orphaned_journal_entries,
+            purge_orphaned_journal_entries,
             get_journal_entry,
             get_journal_entry_lines,
             update_journal_entry,
             delete_journal_entry,
+            get_deleted_journal_entries,
+            restore_journal_entry,
             // Opening Balance
             create_opening_balance,
             get_opening_balances,
@@ -206,20 +244,44 @@ pub fn run() {
             delete_opening_balance,
             // Reports
             get_trial_balance,
+            get_unbalanced_vouchers,
             get_ledger_report,
+            get_account_monthly_statement,
+            get_general_ledger,
+            export_trial_balance_csv,
+            export_profit_loss_csv,
+            get_sales_register,
+            get_purchase_register,
+            export_sales_register_csv,
+            export_purchase_register_csv,
+            export_chart_of_accounts_json,
+            get_system_accounts,
+            set_system_account,
             get_balance_sheet,
             get_profit_loss,
+            get_monthly_profit_loss,
             get_cash_flow,
             get_day_book,
             get_party_outstanding,
             get_party_invoice_details,
+            calculate_overdue_interest,
+            post_interest_journal,
+            get_party_statement,
+            get_aged_receivables,
+            get_aged_payables,
+            get_receivables_aging_summary,
+            get_payables_aging_summary,
             get_stock_report,
+            get_stock_valuation_summary,
+            get_inventory_reconciliation,
             get_stock_movements,
+            get_inventory_movement_summary,
             get_product_stock_qty,
             get_transaction_report,
             get_sales_return_report,
             get_product_profit_report,
             get_product_profit_invoices,
+            get_product_profitability,
             commands::parties::get_all_parties,
             // User Management (New)
             commands::auth::get_users,
@@ -229,21 +291,31 @@ pub fn run() {
             commands::auth::reset_user_password,
             // Dashboard
             get_dashboard_metrics,
+            get_cash_bank_balances,
             get_revenue_trend,
             get_top_products,
+            get_top_customers,
+            get_top_suppliers,
             get_cash_flow_summary,
             get_stock_alerts,
             get_recent_activity,
             get_product_groups_distribution,
             // Voucher Navigation
             list_vouchers,
+            search_vouchers,
+            rebuild_search_index,
             get_previous_voucher_id,
             get_next_voucher_id,
             get_voucher_by_id,
             // Company Profile
             get_company_profile,
+            get_current_financial_year,
             update_company_profile,
+            list_branches,
+            create_branch,
+            set_active_branch,
             get_countries,
+            get_states,
             get_currencies,
             // Invoice Templates
             render_invoice,
@@ -259,19 +331,45 @@ pub fn run() {
             get_payment_allocations,
             get_invoice_allocations,
             get_invoice_allocations_with_details,
+            update_allocation,
             delete_allocation,
             create_quick_payment,
             update_quick_payment,
+            recompute_payment_statuses,
+            net_off_party,
+            get_contra_adjustments,
             // Cash/Bank Invoice Splits
             get_cash_invoice_splits,
             adjust_cash_invoice_splits,
             // Allocations
             commands::entries::get_account_balance,
+            commands::entries::get_account_balances,
             commands::entries::get_pending_invoices,
+            // Bank Reconciliation
+            get_unreconciled_entries,
+            mark_entry_reconciled,
+            unmark_entry_reconciled,
+            get_reconciliation_summary,
+            // Voucher Attachments
+            add_voucher_attachment,
+            list_voucher_attachments,
+            delete_voucher_attachment,
+            // Voucher Audit Trail
+            get_voucher_audit_trail,
+            // Voucher Approval
+            submit_voucher_for_approval,
+            approve_voucher,
+            reject_voucher,
+            // Financial Year Closing
+            close_financial_year,
+            reopen_financial_year,
             // PDF Export
             generate_ledger_pdf,
+            generate_day_book_pdf,
+            generate_party_statement_pdf,
             get_downloads_path,
             save_invoice_pdf,
+            generate_invoice_pdf,
             // Employees
             create_employee,
             get_employees,
@@ -302,6 +400,9 @@ pub fn run() {
             reset_database_data,
             execute_raw_query,
             create_manual_backup,
+            backup_database,
+            restore_database,
+            restart_app,
             // Voucher Sequence Management
             list_voucher_sequences,
             update_voucher_sequence,
@@ -320,6 +421,10 @@ pub fn run() {
             commands::stock_journal::create_stock_journal,
             commands::stock_journal::update_stock_journal,
             commands::stock_journal::delete_stock_journal,
+            // Stock Adjustment
+            commands::stock_adjustments::get_stock_adjustments,
+            commands::stock_adjustments::get_stock_adjustment,
+            commands::stock_adjustments::create_stock_adjustment,
             // GST
             get_gst_tax_slabs,
             create_gst_tax_slab,
@@ -329,6 +434,7 @@ pub fn run() {
             save_gst_settings,
             get_gstr1_summary,
             get_gstr3b_summary,
+            get_tax_summary,
         ])
         .plugin(tauri_plugin_opener::init())
         .build(tauri::generate_context!())
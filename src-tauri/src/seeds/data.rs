@@ -204,6 +204,20 @@ pub async fn seed_initial_data(pool: &SqlitePool) -> Result<(), Box<dyn std::err
             "Operating Expenses",
             "Cost of services purchased from vendors",
         ),
+        (
+            "5012",
+            "Round Off",
+            "Expense",
+            "Operating Expenses",
+            "Rounding difference on invoice totals",
+        ),
+        (
+            "5013",
+            "Stock Adjustment",
+            "Expense",
+            "Cost of Sales",
+            "Stock shrinkage and surplus found in physical counts",
+        ),
     ];
 
     for (code, name, acc_type, group, desc) in coas {
@@ -268,6 +282,18 @@ pub async fn seed_initial_data(pool: &SqlitePool) -> Result<(), Box<dyn std::err
         .await?;
     }
 
+    // Map posting purposes to the seeded accounts
+    for (purpose, code, _) in crate::commands::system_accounts::SYSTEM_ACCOUNT_PURPOSES {
+        sqlx::query(
+            "INSERT OR IGNORE INTO system_accounts (purpose, account_id)
+             SELECT ?, id FROM chart_of_accounts WHERE account_code = ?",
+        )
+        .bind(*purpose)
+        .bind(*code)
+        .execute(pool)
+        .await?;
+    }
+
     // Insert default units
     let units = [("Piece", "Pcs", 1), ("Kilogram", "Kg", 0), ("Liter", "L", 0)];
 
@@ -696,6 +722,56 @@ pub async fn seed_initial_data(pool: &SqlitePool) -> Result<(), Box<dyn std::err
             .await?;
     }
 
+    // Insert states (India, with GST state codes)
+    let india_states = [
+        ("Jammu and Kashmir", "01"),
+        ("Himachal Pradesh", "02"),
+        ("Punjab", "03"),
+        ("Chandigarh", "04"),
+        ("Uttarakhand", "05"),
+        ("Haryana", "06"),
+        ("Delhi", "07"),
+        ("Rajasthan", "08"),
+        ("Uttar Pradesh", "09"),
+        ("Bihar", "10"),
+        ("Sikkim", "11"),
+        ("Arunachal Pradesh", "12"),
+        ("Nagaland", "13"),
+        ("Manipur", "14"),
+        ("Mizoram", "15"),
+        ("Tripura", "16"),
+        ("Meghalaya", "17"),
+        ("Assam", "18"),
+        ("West Bengal", "19"),
+        ("Jharkhand", "20"),
+        ("Odisha", "21"),
+        ("Chhattisgarh", "22"),
+        ("Madhya Pradesh", "23"),
+        ("Gujarat", "24"),
+        ("Dadra and Nagar Haveli and Daman and Diu", "26"),
+        ("Maharashtra", "27"),
+        ("Karnataka", "29"),
+        ("Goa", "30"),
+        ("Lakshadweep", "31"),
+        ("Kerala", "32"),
+        ("Tamil Nadu", "33"),
+        ("Puducherry", "34"),
+        ("Andaman and Nicobar Islands", "35"),
+        ("Telangana", "36"),
+        ("Andhra Pradesh", "37"),
+        ("Ladakh", "38"),
+        ("Other Territory", "97"),
+    ];
+
+    for (name, code) in india_states {
+        sqlx::query("INSERT OR IGNORE INTO states (id, country_code, name, code) VALUES (?, 'IN', ?, ?)")
+            .bind(Uuid::now_v7().to_string())
+            .bind(name)
+            .bind(code)
+            .execute(pool)
+            .await?;
+    }
+
     seed_default_admin(pool).await?;
 
     Ok(())
@@ -67,6 +67,15 @@ impl TemplateEngine {
         Ok(self.build_complete_html(&template.styles_css, &header_html, &body_html, &footer_html))
     }
 
+    /// Builds the Handlebars context. Besides the voucher fields, templates can use:
+    ///
+    /// - `{{company.*}}` — name, address, gstin, pan, logo, ...
+    /// - `{{bank.name}}`, `{{bank.branch}}`, `{{bank.account_no}}`, `{{bank.ifsc}}`,
+    ///   `{{bank.upi_id}}`; `{{#if bank.has_details}}` guards the block
+    /// - `{{upi_qr_string}}` — `upi://pay` link for the amount due in rupees (net payable
+    ///   when returns are set off, otherwise the grand total)
+    /// - `{{upi_qr_image}}` — the same link as a PNG data URI, for `<img src="...">`
+    /// - `{{#if has_upi}}` — false when no UPI id is set on the company profile
     fn prepare_template_data(
         &self,
         template: &InvoiceTemplate,
@@ -161,14 +170,46 @@ impl TemplateEngine {
         });
 
         // Add bank details
+        let upi_id = company
+            .upi_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty());
         voucher_data["bank"] = json!({
             "name": company.bank_name,
             "account_no": company.bank_account_no,
             "ifsc": company.bank_ifsc,
             "branch": company.bank_branch,
+            "upi_id": upi_id,
             "has_details": company.bank_name.is_some(),
         });
 
+        // UPI payment payload for the amount due; scanning it pre-fills the payee and amount.
+        // UPI settles in rupees, so foreign-currency invoices are converted at their rate.
+        let upi_qr_string = upi_id.map(|id| {
+            let exchange_rate = voucher_data
+                .get("exchange_rate")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1.0);
+            let amount = voucher_data
+                .get("net_payable")
+                .or_else(|| voucher_data.get("grand_total"))
+                .and_then(|v| v.as_f64())
+                .map(|amount| (amount * exchange_rate * 100.0).round() / 100.0)
+                .unwrap_or(0.0);
+            let voucher_no = voucher_data
+                .get("voucher_no")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            upi_payment_uri(id, &company.company_name, amount, voucher_no)
+        });
+        let upi_qr_image = upi_qr_string
+            .as_deref()
+            .and_then(crate::commands::tax_utils::irn_to_qr_base64);
+        voucher_data["has_upi"] = json!(upi_qr_string.is_some());
+        voucher_data["upi_qr_image"] = json!(upi_qr_image);
+        voucher_data["upi_qr_string"] = json!(upi_qr_string);
+
         // Add terms
         voucher_data["terms_and_conditions"] = json!(company.terms_and_conditions);
         voucher_data["has_terms"] = json!(company.terms_and_conditions.is_some());
@@ -242,6 +283,39 @@ impl TemplateEngine {
     }
 }
 
+/// `upi://pay` deep link as defined by the NPCI UPI linking spec. The amount is
+/// omitted when nothing is due so the payer can enter it.
+fn upi_payment_uri(upi_id: &str, payee_name: &str, amount: f64, note: &str) -> String {
+    let mut uri = format!(
+        "upi://pay?pa={}&pn={}",
+        upi_encode(upi_id),
+        upi_encode(payee_name)
+    );
+    if amount > 0.0 {
+        uri.push_str(&format!("&am={:.2}", amount));
+    }
+    uri.push_str("&cu=INR");
+    if !note.is_empty() {
+        uri.push_str(&format!("&tn={}", upi_encode(note)));
+    }
+    uri
+}
+
+/// Percent-encodes everything outside the URI unreserved set; `@` is kept so
+/// the VPA stays readable.
+fn upi_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'@' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
 // ============= HANDLEBARS HELPERS =============
 
 use handlebars::{
@@ -103,3 +103,133 @@ fn convert_to_words(num: u64) -> String {
     .trim()
     .to_string();
 }
+
+// ============= CSV =============
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One CSV line, fields quoted as needed and terminated with CRLF.
+pub fn csv_record(fields: &[String]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+pub fn write_csv(file_path: &str, records: &[Vec<String>]) -> Result<(), String> {
+    let content: String = records.iter().map(|r| csv_record(r)).collect();
+    std::fs::write(file_path, content).map_err(|e| format!("Failed to write {}: {}", file_path, e))
+}
+
+/// Splits CSV text into records. Handles quoted fields, doubled quotes and
+/// line breaks inside quotes; blank lines are dropped. Each record carries its
+/// starting line number.
+pub fn parse_csv(content: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                } else {
+                    record.clear();
+                }
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.trim().is_empty()) {
+        records.push((record_line, record));
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn csv_record_leaves_plain_fields_unquoted() {
+        assert_eq!(
+            csv_record(&fields(&["Cash", "100.00", ""])),
+            "Cash,100.00,\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_record_quotes_delimiters_quotes_and_line_breaks() {
+        assert_eq!(
+            csv_record(&fields(&["Sharma, Sons", "2\" pipe", "line\nbreak"])),
+            "\"Sharma, Sons\",\"2\"\" pipe\",\"line\nbreak\"\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_csv_reads_quoted_fields_and_numbers_records_by_line() {
+        let content =
+            "\u{feff}code,name\r\nP1,\"Bolt, 10mm\"\r\n\r\nP2,\"Say \"\"hi\"\"\nagain\"\nP3,";
+        assert_eq!(
+            parse_csv(content),
+            vec![
+                (1, fields(&["code", "name"])),
+                (2, fields(&["P1", "Bolt, 10mm"])),
+                (4, fields(&["P2", "Say \"hi\"\nagain"])),
+                (6, fields(&["P3", ""])),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_csv_reads_back_what_csv_record_writes() {
+        let records = vec![
+            fields(&["Sharma, Sons", "2\" pipe"]),
+            fields(&["line\nbreak", ""]),
+        ];
+        let content: String = records.iter().map(|r| csv_record(r)).collect();
+        let parsed: Vec<Vec<String>> = parse_csv(&content).into_iter().map(|(_, r)| r).collect();
+        assert_eq!(parsed, records);
+    }
+}
@@ -1,16 +1,102 @@
-use sqlx::{Sqlite, SqlitePool, Transaction};
+use sqlx::{Sqlite, SqliteExecutor, SqlitePool, Transaction};
 
 /// A single row from the voucher_sequences table
 #[derive(Debug, sqlx::FromRow)]
-struct VoucherSeqRow {
+pub(crate) struct VoucherSeqRow {
     prefix: String,
     suffix: String,
     separator: String,
     next_number: i64,
     padding: i64,
     include_financial_year: bool,
+    reset_yearly: bool,
+    last_issued_fy: Option<String>,
 }
 
+impl VoucherSeqRow {
+    /// The sequence as configured in the settings screen, for formatting numbers
+    /// before the change is saved.
+    pub(crate) fn from_settings(data: &crate::commands::settings::UpdateVoucherSequence) -> Self {
+        Self {
+            prefix: data.prefix.clone(),
+            suffix: data.suffix.clone().unwrap_or_default(),
+            separator: data.separator.clone(),
+            next_number: data.next_number,
+            padding: data.padding,
+            include_financial_year: data.include_financial_year,
+            reset_yearly: data.reset_yearly,
+            last_issued_fy: None,
+        }
+    }
+
+    /// Whether `financial_year` has its own counter in voucher_sequence_years
+    /// rather than the one on this row: only with a yearly reset, and only for
+    /// years other than the one this row last issued in.
+    fn counts_separately(&self, financial_year: &str) -> bool {
+        self.reset_yearly
+            && self
+                .last_issued_fy
+                .as_deref()
+                .is_some_and(|last| last != financial_year)
+    }
+
+    /// The counter to issue next in `financial_year`. With a yearly reset each
+    /// year counts from 1; a year seen before continues where it left off.
+    async fn next_number_for<'e, E: SqliteExecutor<'e>>(
+        &self,
+        executor: E,
+        voucher_type: &str,
+        financial_year: &str,
+    ) -> Result<i64, String> {
+        if !self.counts_separately(financial_year) {
+            return Ok(self.next_number);
+        }
+        let stored: Option<i64> = sqlx::query_scalar(
+            "SELECT next_number FROM voucher_sequence_years
+             WHERE voucher_type = ? AND financial_year = ?",
+        )
+        .bind(voucher_type)
+        .bind(financial_year)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(stored.unwrap_or(1))
+    }
+
+    /// `branch_code`, when a branch is active, goes in front of the prefix.
+    pub(crate) fn format(
+        &self,
+        number: i64,
+        financial_year: &str,
+        branch_code: Option<&str>,
+    ) -> String {
+        // A yearly reset repeats counters, so the FY must be part of the number
+        let include_fy = self.include_financial_year || self.reset_yearly;
+        let prefix = match branch_code {
+            Some(code) if self.prefix.is_empty() => code.to_string(),
+            Some(code) => format!("{}{}{}", code, self.separator, self.prefix),
+            None => self.prefix.clone(),
+        };
+        format_voucher_number(
+            &prefix,
+            &self.suffix,
+            &self.separator,
+            self.padding,
+            include_fy.then_some(financial_year),
+            number,
+        )
+    }
+}
+
+const SEQ_ROW_SELECT: &str =
+    "SELECT prefix, COALESCE(suffix, '') as suffix, COALESCE(separator, '-') as separator,
+            next_number, padding, COALESCE(include_financial_year, 0) as include_financial_year,
+            COALESCE(reset_yearly, 0) as reset_yearly, last_issued_fy
+     FROM voucher_sequences WHERE voucher_type = ?";
+
+/// Code of the branch a voucher is recorded under.
+const BRANCH_CODE_SELECT: &str = "SELECT code FROM branches WHERE id = ? AND deleted_at IS NULL";
+
 /// Row returned to the frontend for the settings UI
 #[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
 pub struct VoucherSequenceInfo {
@@ -24,22 +110,67 @@ pub struct VoucherSequenceInfo {
     pub reset_yearly: bool,
 }
 
-/// Build the financial-year string based on current date.
-/// Indian financial year: April–March.
-/// e.g. if today is March 2025 → "24-25"; if May 2025 → "25-26"
-pub fn current_financial_year() -> String {
-    use chrono::{Datelike, Utc, FixedOffset};
+/// The financial-year string of a "YYYY-MM-DD" voucher date (a time part is ignored).
+fn voucher_financial_year(voucher_date: &str, start_month: u32) -> Result<String, String> {
+    let date = chrono::NaiveDate::parse_from_str(
+        voucher_date.get(..10).unwrap_or(voucher_date),
+        "%Y-%m-%d",
+    )
+    .map_err(|_| format!("Invalid voucher date: {}", voucher_date))?;
+    Ok(financial_year_label(date, start_month))
+}
+
+/// Build the financial-year string based on current date, for a year starting
+/// in `start_month` (April for the Indian financial year).
+/// e.g. with April: if today is March 2025 → "24-25"; if May 2025 → "25-26"
+pub fn current_financial_year(start_month: u32) -> String {
+    use chrono::{FixedOffset, Utc};
     let now = Utc::now().with_timezone(&FixedOffset::east_opt(5 * 3600 + 1800).unwrap());
-    let year = now.year();
-    let month = now.month();
-    if month >= 4 {
-        format!("{}-{}", year % 100, (year + 1) % 100)
+    financial_year_label(now.date_naive(), start_month)
+}
+
+/// The financial-year string for `date`: "24-25" for a year spanning two
+/// calendar years, just "25" when the year starts in January.
+pub fn financial_year_label(date: chrono::NaiveDate, start_month: u32) -> String {
+    use chrono::Datelike;
+    let start = crate::commands::company::fiscal_year_start_for(date, start_month);
+    if start_month == 1 {
+        format!("{:02}", start.year() % 100)
     } else {
-        format!("{}-{}", (year - 1) % 100, year % 100)
+        format!("{}-{}", start.year() % 100, (start.year() + 1) % 100)
+    }
+}
+
+/// Joins prefix, optional FY, zero-padded counter and optional suffix with
+/// `separator`, skipping empty segments.
+pub fn format_voucher_number(
+    prefix: &str,
+    suffix: &str,
+    separator: &str,
+    padding: i64,
+    financial_year: Option<&str>,
+    number: i64,
+) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if !prefix.is_empty() {
+        parts.push(prefix.to_string());
+    }
+    if let Some(fy) = financial_year {
+        parts.push(fy.to_string());
+    }
+    parts.push(format!("{:0>width$}", number, width = padding as usize));
+
+    let base = parts.join(separator);
+    if suffix.is_empty() {
+        base
+    } else {
+        format!("{}{}{}", base, separator, suffix)
     }
 }
 
 /// Atomically fetches and increments the voucher number for the given type.
+/// The financial year in the number, and the counter a yearly reset uses, are
+/// those of `voucher_date`.
 /// Builds the formatted number from prefix, optional FY, padded counter, and optional suffix.
 /// All separated by the configured separator (default "-").
 ///
@@ -47,130 +178,177 @@ pub fn current_financial_year() -> String {
 ///   SI-0001            (prefix=SI, no FY, no suffix)
 ///   SI-24-25-0001      (prefix=SI, FY enabled, separator=-)
 ///   INV/25-26/0001/KBZ (prefix=INV, FY, suffix=KBZ, separator=/)
+///   KOC-SI-0001        (branch KOC, prefix=SI)
+///
+/// `branch_id` is the session's active branch (see `SessionStore::active_branch_id`).
 pub async fn get_next_voucher_number(
     pool: &SqlitePool,
     voucher_type: &str,
+    voucher_date: &str,
+    branch_id: Option<&str>,
 ) -> Result<String, String> {
     let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let voucher_no =
+        get_next_voucher_number_in_tx(&mut tx, voucher_type, voucher_date, branch_id).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(voucher_no)
+}
 
-    let seq = sqlx::query_as::<_, VoucherSeqRow>(
-        "SELECT prefix, COALESCE(suffix, '') as suffix, COALESCE(separator, '-') as separator,
-                next_number, padding, COALESCE(include_financial_year, 0) as include_financial_year
-         FROM voucher_sequences WHERE voucher_type = ?",
-    )
-    .bind(voucher_type)
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| format!("No voucher sequence found for type '{}': {}", voucher_type, e))?;
+pub async fn get_next_voucher_number_in_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    voucher_type: &str,
+    voucher_date: &str,
+    branch_id: Option<&str>,
+) -> Result<String, String> {
+    let seq = sqlx::query_as::<_, VoucherSeqRow>(SEQ_ROW_SELECT)
+        .bind(voucher_type)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| {
+            format!(
+                "No voucher sequence found for type '{}': {}",
+                voucher_type, e
+            )
+        })?;
 
-    // Build the padded number part
-    let number = format!("{:0>width$}", seq.next_number, width = seq.padding as usize);
-    let sep = &seq.separator;
+    let branch_code: Option<String> = match branch_id {
+        Some(id) => sqlx::query_scalar(BRANCH_CODE_SELECT)
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
 
-    // Assemble parts
-    let mut parts: Vec<String> = Vec::new();
-    if !seq.prefix.is_empty() {
-        parts.push(seq.prefix.clone());
-    }
-    if seq.include_financial_year {
-        parts.push(current_financial_year());
-    }
-    parts.push(number);
+    let start_month = crate::commands::company::financial_year_start_month(&mut **tx).await?;
+    let fy = voucher_financial_year(voucher_date, start_month)?;
+    let number = seq.next_number_for(&mut **tx, voucher_type, &fy).await?;
+    let voucher_no = seq.format(number, &fy, branch_code.as_deref());
 
-    let base = parts.join(sep);
+    // A voucher dated into an earlier year only advances that year's counter
+    let earlier_year = seq.counts_separately(&fy)
+        && seq
+            .last_issued_fy
+            .as_deref()
+            .is_some_and(|last| fy.as_str() < last);
+    if earlier_year {
+        save_year_counter(tx, voucher_type, &fy, number + 1).await?;
+        return Ok(voucher_no);
+    }
 
-    let voucher_no = if seq.suffix.is_empty() {
-        base
-    } else {
-        format!("{}{}{}", base, sep, seq.suffix)
-    };
+    if seq.counts_separately(&fy) {
+        // Moving on to a later year: park the outgoing year's counter
+        if let Some(last) = seq.last_issued_fy.as_deref() {
+            save_year_counter(tx, voucher_type, last, seq.next_number).await?;
+        }
+        sqlx::query(
+            "DELETE FROM voucher_sequence_years WHERE voucher_type = ? AND financial_year = ?",
+        )
+        .bind(voucher_type)
+        .bind(&fy)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
 
-    // Increment the sequence
     sqlx::query(
-        "UPDATE voucher_sequences SET next_number = next_number + 1 WHERE voucher_type = ?",
+        "UPDATE voucher_sequences SET next_number = ?, last_issued_fy = ? WHERE voucher_type = ?",
     )
+    .bind(number + 1)
+    .bind(&fy)
     .bind(voucher_type)
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await
     .map_err(|e| e.to_string())?;
 
-    tx.commit().await.map_err(|e| e.to_string())?;
     Ok(voucher_no)
 }
 
-pub async fn get_next_voucher_number_in_tx(
+async fn save_year_counter(
     tx: &mut Transaction<'_, Sqlite>,
     voucher_type: &str,
-) -> Result<String, String> {
-    let seq = sqlx::query_as::<_, VoucherSeqRow>(
-        "SELECT prefix, COALESCE(suffix, '') as suffix, COALESCE(separator, '-') as separator,
-                next_number, padding, COALESCE(include_financial_year, 0) as include_financial_year
-         FROM voucher_sequences WHERE voucher_type = ?",
+    financial_year: &str,
+    next_number: i64,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO voucher_sequence_years (voucher_type, financial_year, next_number)
+         VALUES (?, ?, ?)
+         ON CONFLICT (voucher_type, financial_year) DO UPDATE SET next_number = excluded.next_number",
     )
     .bind(voucher_type)
-    .fetch_one(&mut **tx)
+    .bind(financial_year)
+    .bind(next_number)
+    .execute(&mut **tx)
     .await
-    .map_err(|e| format!("No voucher sequence found for type '{}': {}", voucher_type, e))?;
-
-    let number = format!("{:0>width$}", seq.next_number, width = seq.padding as usize);
-    let sep = &seq.separator;
-
-    let mut parts: Vec<String> = Vec::new();
-    if !seq.prefix.is_empty() {
-        parts.push(seq.prefix.clone());
-    }
-    if seq.include_financial_year {
-        parts.push(current_financial_year());
-    }
-    parts.push(number);
-
-    let base = parts.join(sep);
-    let voucher_no = if seq.suffix.is_empty() {
-        base
-    } else {
-        format!("{}{}{}", base, sep, seq.suffix)
-    };
-
-    sqlx::query("UPDATE voucher_sequences SET next_number = next_number + 1 WHERE voucher_type = ?")
-        .bind(voucher_type)
-        .execute(&mut **tx)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    Ok(voucher_no)
+    .map_err(|e| e.to_string())?;
+    Ok(())
 }
 
 /// Preview what the next voucher number would look like WITHOUT incrementing the counter.
 pub async fn preview_voucher_number_for(
     pool: &SqlitePool,
     voucher_type: &str,
+    voucher_date: &str,
+    branch_id: Option<&str>,
 ) -> Result<String, String> {
-    let seq = sqlx::query_as::<_, VoucherSeqRow>(
-        "SELECT prefix, COALESCE(suffix, '') as suffix, COALESCE(separator, '-') as separator,
-                next_number, padding, COALESCE(include_financial_year, 0) as include_financial_year
-         FROM voucher_sequences WHERE voucher_type = ?",
-    )
-    .bind(voucher_type)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| format!("No voucher sequence found for type '{}': {}", voucher_type, e))?;
+    let seq = sqlx::query_as::<_, VoucherSeqRow>(SEQ_ROW_SELECT)
+        .bind(voucher_type)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            format!(
+                "No voucher sequence found for type '{}': {}",
+                voucher_type, e
+            )
+        })?;
 
-    let number = format!("{:0>width$}", seq.next_number, width = seq.padding as usize);
-    let sep = &seq.separator;
+    let branch_code: Option<String> = match branch_id {
+        Some(id) => sqlx::query_scalar(BRANCH_CODE_SELECT)
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
 
-    let mut parts: Vec<String> = Vec::new();
-    if !seq.prefix.is_empty() {
-        parts.push(seq.prefix.clone());
-    }
-    if seq.include_financial_year {
-        parts.push(current_financial_year());
+    let start_month = crate::commands::company::financial_year_start_month(pool).await?;
+    let fy = voucher_financial_year(voucher_date, start_month)?;
+    let number = seq.next_number_for(pool, voucher_type, &fy).await?;
+    Ok(seq.format(number, &fy, branch_code.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn issue(pool: &SqlitePool, voucher_date: &str) -> String {
+        get_next_voucher_number(pool, "journal", voucher_date, None)
+            .await
+            .unwrap()
     }
-    parts.push(number);
 
-    let base = parts.join(sep);
-    Ok(if seq.suffix.is_empty() {
-        base
-    } else {
-        format!("{}{}{}", base, sep, seq.suffix)
-    })
+    #[tokio::test]
+    async fn yearly_reset_numbers_each_voucher_in_its_own_year() {
+        let pool = test_pool().await;
+        sqlx::query(
+            "UPDATE voucher_sequences SET reset_yearly = 1, padding = 4 WHERE voucher_type = 'journal'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(issue(&pool, "2024-05-01").await, "JV-24-25-0001");
+        assert_eq!(issue(&pool, "2024-06-01").await, "JV-24-25-0002");
+        assert_eq!(issue(&pool, "2025-04-01").await, "JV-25-26-0001");
+        // Backdated into the previous year: that year's count carries on
+        assert_eq!(issue(&pool, "2025-03-31").await, "JV-24-25-0003");
+        assert_eq!(issue(&pool, "2025-05-01").await, "JV-25-26-0002");
+        assert_eq!(
+            preview_voucher_number_for(&pool, "journal", "2024-12-01", None)
+                .await
+                .unwrap(),
+            "JV-24-25-0004"
+        );
+    }
 }
@@ -41,6 +41,7 @@ interface StockMovement {
     voucher_no: string;
     voucher_type: string;
     movement_type: string;
+    movement_label: string;
     quantity: number;
     rate: number;
     amount: number;
@@ -340,12 +341,12 @@ export default function StockReportPage() {
                                                                                         <td className="p-2 text-xs">{movement.party_name || '-'}</td>
                                                                                         <td className="p-2 text-center">
                                                                                             <span
-                                                                                                className={`px-2 py-1 rounded text-xs font-bold ${movement.movement_type === 'IN'
+                                                                                                className={`px-2 py-1 rounded text-xs font-bold ${movement.movement_type.endsWith('IN')
                                                                                                     ? 'bg-green-100 text-green-700 dark:bg-green-950 dark:text-green-400'
                                                                                                     : 'bg-red-100 text-red-700 dark:bg-red-950 dark:text-red-400'
                                                                                                     }`}
                                                                                             >
-                                                                                                {movement.movement_type}
+                                                                                                {movement.movement_label}
                                                                                             </span>
                                                                                         </td>
                                                                                         <td className="p-2 text-right text-xs font-mono">
@@ -439,6 +439,18 @@ export default function CompanyProfilePage() {
                                     />
                                 </div>
                             </div>
+
+                            <div className="grid grid-cols-2 gap-4">
+                                <div className="space-y-2">
+                                    <Label htmlFor="upi_id">UPI ID</Label>
+                                    <Input
+                                        id="upi_id"
+                                        value={profile.upi_id ?? ''}
+                                        onChange={(e) => handleInputChange('upi_id', e.target.value)}
+                                        placeholder="e.g. business@okbank"
+                                    />
+                                </div>
+                            </div>
                         </CardContent>
                     </Card>
                 </TabsContent>
@@ -4,6 +4,7 @@ import { toast } from 'sonner';
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
 import { Button } from '@/components/ui/button';
 import { Checkbox } from '@/components/ui/checkbox';
+import { Input } from '@/components/ui/input';
 import { Label } from '@/components/ui/label';
 import { RadioGroup, RadioGroupItem } from '@/components/ui/radio-group';
 import { Switch } from '@/components/ui/switch';
@@ -16,6 +17,12 @@ import { RootState } from '@/store';
 
 type ResetMode = 'partial' | 'full';
 
+interface BackupResult {
+  success: boolean;
+  message: string;
+  path: string | null;
+}
+
 interface QueryResult {
   columns: string[];
   rows: (string | number | boolean | null)[][];
@@ -65,6 +72,11 @@ export default function DbSettingsPage() {
   const [queryPasswordDialogOpen, setQueryPasswordDialogOpen] = useState(false);
   const [queryVerifyingPassword, setQueryVerifyingPassword] = useState(false);
 
+  // Backup & Restore state
+  const [backupLoading, setBackupLoading] = useState(false);
+  const [restorePath, setRestorePath] = useState('');
+  const [restoreLoading, setRestoreLoading] = useState(false);
+
   // Get current user from Redux store
   const { user } = useSelector((state: RootState) => state.auth);
 
@@ -178,6 +190,60 @@ export default function DbSettingsPage() {
     }
   };
 
+  // ── Backup & Restore handlers ────────────────────────────────
+
+  const handleBackup = async () => {
+    setBackupLoading(true);
+    try {
+      const timestamp = new Date().toISOString().slice(0, 19).replace(/[-:T]/g, '');
+      const downloadsPath = await invoke<string>('get_downloads_path');
+      const targetPath = `${downloadsPath}/kolabiz_backup_${timestamp}.db`;
+
+      const size = await invoke<number>('backup_database', { targetPath });
+      toast.success(`Backup saved to ${targetPath} (${(size / 1024).toFixed(1)} KB)`);
+    } catch (error) {
+      console.error(error);
+      toast.error(typeof error === 'string' ? error : 'Failed to back up database');
+    } finally {
+      setBackupLoading(false);
+    }
+  };
+
+  const handleRestore = async () => {
+    const sourcePath = restorePath.trim();
+    if (!sourcePath || restoreLoading) return;
+
+    const confirmed = await confirm({
+      title: 'Restore database?',
+      description: 'All current data of this company will be replaced by the selected backup. A copy of the current database is kept next to it. The app must restart afterwards.',
+      confirmText: 'Yes, restore',
+      cancelText: 'Cancel',
+      variant: 'destructive',
+    });
+    if (!confirmed) return;
+
+    setRestoreLoading(true);
+    try {
+      const result = await invoke<BackupResult>('restore_database', { sourcePath });
+      setRestorePath('');
+
+      const restart = await confirm({
+        title: 'Restart required',
+        description: `${result.message}${result.path ? ` Previous data saved to ${result.path}.` : ''}`,
+        confirmText: 'Restart now',
+        cancelText: 'Later',
+      });
+      if (restart) {
+        await invoke('restart_app');
+      }
+    } catch (error) {
+      console.error(error);
+      toast.error(typeof error === 'string' ? error : 'Failed to restore database');
+    } finally {
+      setRestoreLoading(false);
+    }
+  };
+
   // ── Query Executor handlers ──────────────────────────────────
 
   const handleExecuteQueryClick = () => {
@@ -353,6 +419,41 @@ export default function DbSettingsPage() {
         </Button>
       </div>
 
+      {/* ── Backup & Restore ───────────────────────────────────── */}
+      <Card>
+        <CardHeader>
+          <CardTitle>Backup &amp; Restore</CardTitle>
+          <CardDescription>Save a copy of this company's database to Downloads, or replace it with a backup file.</CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between">
+            <Label>Back up the current database</Label>
+            <Button variant="outline" onClick={handleBackup} disabled={backupLoading}>
+              {backupLoading ? 'Backing up…' : 'Backup Now'}
+            </Button>
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="restore-path">Restore from backup file</Label>
+            <div className="flex gap-2">
+              <Input
+                id="restore-path"
+                value={restorePath}
+                onChange={(e) => setRestorePath(e.target.value)}
+                placeholder="C:\Users\you\Downloads\kolabiz_backup.db"
+                className="font-mono text-sm"
+              />
+              <Button
+                variant="destructive"
+                onClick={handleRestore}
+                disabled={!restorePath.trim() || restoreLoading}
+              >
+                {restoreLoading ? 'Restoring…' : 'Restore'}
+              </Button>
+            </div>
+          </div>
+        </CardContent>
+      </Card>
+
       {/* ── Query Executor ─────────────────────────────────────── */}
       <Card>
         <CardHeader>
@@ -1316,6 +1316,7 @@ export interface CompanyProfileState {
     bank_account_no: string;
     bank_ifsc: string;
     bank_branch: string;
+    upi_id: string;
     terms_and_conditions: string;
     base_currency: string;
   };
@@ -1345,6 +1346,7 @@ const companyProfileInitialState: CompanyProfileState = {
     bank_account_no: '',
     bank_ifsc: '',
     bank_branch: '',
+    upi_id: '',
     terms_and_conditions: '',
     base_currency: 'INR',
   },